@@ -0,0 +1,58 @@
+// Criterion benches for the CPU-bound text processing. The backend is a binary crate, so the
+// modules under test are compiled in directly (their unit tests' imports included, without
+// the tests themselves, since the bench has no test harness).
+#![allow(dead_code, unused_imports)]
+
+#[path = "../src/analysis.rs"]
+mod analysis;
+#[path = "../src/announcements.rs"]
+mod announcements;
+#[path = "../src/bench.rs"]
+mod bench;
+#[path = "../src/privacy.rs"]
+mod privacy;
+#[path = "../src/script.rs"]
+mod script;
+#[path = "../src/urls.rs"]
+mod urls;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn html_stripping(c: &mut Criterion) {
+    let mut rng = bench::Rng::new(1);
+    let mut group = c.benchmark_group("strip_html");
+    for words in [60, 500] {
+        let html = bench::comment_html(&mut rng, words);
+        group.bench_with_input(BenchmarkId::from_parameter(words), &html, |b, html| b.iter(|| analysis::strip_html(black_box(html))));
+    }
+    group.finish();
+
+    let article = bench::article_html(&mut rng, 40);
+    c.bench_function("article_extract", |b| b.iter(|| analysis::article_extract(black_box(&article))));
+}
+
+fn comment_analysis(c: &mut Criterion) {
+    let mut rng = bench::Rng::new(2);
+    let comments: Vec<String> = (0..200).map(|_| analysis::strip_html(&bench::comment_html(&mut rng, 80))).collect();
+    let refs: Vec<&str> = comments.iter().map(String::as_str).collect();
+
+    c.bench_function("keywords/200_comments", |b| b.iter(|| analysis::keywords("Synthetic story title", black_box(&refs), 8)));
+    c.bench_function("sentiment/200_comments", |b| b.iter(|| analysis::sentiment(black_box(&refs))));
+
+    let authors: Vec<String> = (0..200).map(|_| bench::username(&mut rng)).collect();
+    privacy::register(authors.iter().map(String::as_str));
+    let joined = comments.join("\n\n");
+    c.bench_function("anonymize/200_comments", |b| b.iter(|| privacy::anonymize(black_box(&joined))));
+}
+
+fn script_chunking(c: &mut Criterion) {
+    let mut rng = bench::Rng::new(3);
+    let long_script = bench::script(&mut rng, 12, 600);
+
+    c.bench_function("enforce_length", |b| b.iter(|| script::enforce_length(black_box(&long_script), 2000)));
+    c.bench_function("estimate_segments", |b| b.iter(|| script::estimate(black_box(&long_script), 150.0)));
+    c.bench_function("split_script", |b| b.iter(|| announcements::split_script(black_box(&long_script))));
+}
+
+criterion_group!(benches, html_stripping, comment_analysis, script_chunking);
+criterion_main!(benches);
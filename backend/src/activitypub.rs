@@ -0,0 +1,427 @@
+use crate::archive::{self, Episode};
+use axum::http::HeaderMap;
+use base64::Engine;
+use rsa::pkcs1v15::{Signature, SigningKey, VerifyingKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePublicKey, LineEnding};
+use rsa::signature::{SignatureEncoding, Signer, Verifier};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use std::env;
+use tracing::{error, info};
+
+// ActivityPub actor for the show (optional): with ACTIVITYPUB_USERNAME and ACTIVITYPUB_PRIVATE_KEY
+// (RSA, PKCS#8 PEM) set, the show is @<username>@<PUBLIC_BASE_URL host>, found through WebFinger,
+// and Mastodon and other fediverse accounts can follow it. Follows are accepted automatically.
+// Each episode is published once, when its audio lands in the feeds (the same moment WebSub hubs
+// are pinged), as a public Note with the title, a summary, the topics as hashtags, a link to the
+// embeddable player and the audio attached; it's delivered to every follower's (shared) inbox.
+// Inbox activities must carry a valid HTTP signature (rsa-sha256) by the actor they claim to be
+// from, and outgoing deliveries are signed the same way with the show's key.
+
+pub const CONTENT_TYPE: &str = "application/activity+json";
+const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+pub const OUTBOX_ITEMS: u32 = 20;
+// Signed requests older or newer than this are refused as replays
+const MAX_CLOCK_SKEW_SECS: i64 = 12 * 3600;
+
+pub struct Actor {
+    pub username: String,
+    key: RsaPrivateKey,
+}
+
+// None while ActivityPub isn't configured
+pub fn settings() -> Result<Option<Actor>, String> {
+    let username = env::var("ACTIVITYPUB_USERNAME").ok().map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    let pem = env::var("ACTIVITYPUB_PRIVATE_KEY").ok().filter(|k| !k.trim().is_empty());
+    let (username, pem) = match (username, pem) {
+        (Some(username), Some(pem)) => (username, pem),
+        (None, None) => return Ok(None),
+        _ => return Err("ACTIVITYPUB_USERNAME and ACTIVITYPUB_PRIVATE_KEY must be set together".to_string()),
+    };
+    if !username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err("ACTIVITYPUB_USERNAME may only contain letters, digits and '_'".to_string());
+    }
+    // Env files often carry the PEM on one line with literal \n
+    let key = RsaPrivateKey::from_pkcs8_pem(&pem.replace("\\n", "\n"))
+        .map_err(|_| "ACTIVITYPUB_PRIVATE_KEY is not an RSA private key in PKCS#8 PEM".to_string())?;
+    Ok(Some(Actor { username, key }))
+}
+
+pub fn enabled() -> bool {
+    crate::archive::enabled() && matches!(settings(), Ok(Some(_)))
+}
+
+fn base_url() -> String {
+    crate::alerts::public_base_url()
+}
+
+// host[:port] of PUBLIC_BASE_URL, the domain of the show's handle
+fn domain() -> String {
+    url::Url::parse(&base_url())
+        .ok()
+        .and_then(|url| url.host_str().map(|host| url.port().map_or(host.to_string(), |port| format!("{}:{}", host, port))))
+        .unwrap_or_default()
+}
+
+pub fn actor_id() -> String {
+    format!("{}/ap/actor", base_url())
+}
+
+fn key_id() -> String {
+    format!("{}#main-key", actor_id())
+}
+
+fn followers_id() -> String {
+    format!("{}/ap/followers", base_url())
+}
+
+pub fn note_id(episode_id: &str) -> String {
+    format!("{}/ap/notes/{}", base_url(), episode_id)
+}
+
+// JRD for acct:<username>@<domain> or the actor's URL
+pub fn webfinger(actor: &Actor, resource: &str) -> Option<serde_json::Value> {
+    let subject = format!("acct:{}@{}", actor.username, domain());
+    if !resource.eq_ignore_ascii_case(&subject) && resource != actor_id() {
+        return None;
+    }
+    Some(serde_json::json!({
+        "subject": subject,
+        "aliases": [actor_id()],
+        "links": [{ "rel": "self", "type": CONTENT_TYPE, "href": actor_id() }],
+    }))
+}
+
+pub fn actor_document(actor: &Actor) -> Result<serde_json::Value, String> {
+    let public_key = RsaPublicKey::from(&actor.key).to_public_key_pem(LineEnding::LF).map_err(|e| e.to_string())?;
+    let (summary, image) = crate::feed::show_profile();
+    let mut document = serde_json::json!({
+        "@context": ["https://www.w3.org/ns/activitystreams", "https://w3id.org/security/v1"],
+        "id": actor_id(),
+        "type": "Service",
+        "preferredUsername": actor.username,
+        "name": crate::feed::show_title(),
+        "summary": format!("<p>{}</p>", crate::seo::escape_xml(&summary)),
+        "url": crate::seo::site_url(),
+        "inbox": format!("{}/ap/inbox", base_url()),
+        "outbox": format!("{}/ap/outbox", base_url()),
+        "followers": followers_id(),
+        "manuallyApprovesFollowers": false,
+        "discoverable": true,
+        "publicKey": { "id": key_id(), "owner": actor_id(), "publicKeyPem": public_key },
+    });
+    if let Some(image) = image {
+        document["icon"] = serde_json::json!({ "type": "Image", "url": image });
+    }
+    Ok(document)
+}
+
+// None until the episode's audio is rendered
+pub fn note(episode: &Episode) -> Option<serde_json::Value> {
+    let audio = episode.audio.as_ref()?;
+    let link = crate::embed::page_url(&episode.id);
+    let summary = episode.script.as_deref().map(crate::syndication::summary).unwrap_or_default();
+    let hashtags: Vec<String> = episode.topics.iter().map(|topic| format!("#{}", topic.replace('-', ""))).collect();
+    let mut content = format!("<p><strong>{}</strong></p>", crate::seo::escape_xml(&episode.title));
+    if !summary.is_empty() {
+        content.push_str(&format!("<p>{}</p>", crate::seo::escape_xml(&summary)));
+    }
+    content.push_str(&format!("<p><a href=\"{0}\">{0}</a></p>", crate::seo::escape_xml(&link)));
+    if !hashtags.is_empty() {
+        content.push_str(&format!("<p>{}</p>", hashtags.join(" ")));
+    }
+    Some(serde_json::json!({
+        "id": note_id(&episode.id),
+        "type": "Note",
+        "attributedTo": actor_id(),
+        "published": episode.published_at().to_rfc3339(),
+        "url": link,
+        "to": [PUBLIC],
+        "cc": [followers_id()],
+        "content": content,
+        "attachment": [{
+            "type": "Document",
+            "mediaType": "audio/mpeg",
+            "url": audio.url,
+            "name": episode.title,
+        }],
+        "tag": hashtags.iter().map(|tag| serde_json::json!({ "type": "Hashtag", "name": tag })).collect::<Vec<_>>(),
+    }))
+}
+
+fn create(episode: &Episode) -> Option<serde_json::Value> {
+    let note = note(episode)?;
+    Some(serde_json::json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": format!("{}#create", note_id(&episode.id)),
+        "type": "Create",
+        "actor": actor_id(),
+        "published": note["published"],
+        "to": [PUBLIC],
+        "cc": [followers_id()],
+        "object": note,
+    }))
+}
+
+// The newest OUTBOX_ITEMS published episodes
+pub fn outbox(episodes: &[Episode], total: u64) -> serde_json::Value {
+    let items: Vec<serde_json::Value> = episodes.iter().filter_map(create).take(OUTBOX_ITEMS as usize).collect();
+    serde_json::json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": format!("{}/ap/outbox", base_url()),
+        "type": "OrderedCollection",
+        "totalItems": total,
+        "orderedItems": items,
+    })
+}
+
+// Only the count: who follows the show isn't published
+pub fn followers(total: i64) -> serde_json::Value {
+    serde_json::json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": followers_id(),
+        "type": "OrderedCollection",
+        "totalItems": total,
+    })
+}
+
+// --- HTTP signatures ---
+
+#[derive(Debug, PartialEq)]
+struct SignatureHeader {
+    key_id: String,
+    headers: Vec<String>,
+    signature: Vec<u8>,
+}
+
+// keyId="...",algorithm="rsa-sha256",headers="(request-target) host date digest",signature="..."
+fn parse_signature(header: &str) -> Option<SignatureHeader> {
+    let mut key_id = None;
+    let mut headers = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        let (name, value) = part.trim().split_once('=')?;
+        let value = value.trim().trim_matches('"');
+        match name.trim() {
+            "keyId" => key_id = Some(value.to_string()),
+            "headers" => headers = Some(value.split_whitespace().map(str::to_lowercase).collect()),
+            "signature" => signature = base64::engine::general_purpose::STANDARD.decode(value).ok(),
+            "algorithm" if !matches!(value, "rsa-sha256" | "hs2019") => return None,
+            _ => {}
+        }
+    }
+    // Without a headers parameter only Date is signed
+    Some(SignatureHeader { key_id: key_id?, headers: headers.unwrap_or_else(|| vec!["date".to_string()]), signature: signature? })
+}
+
+fn signing_string(names: &[String], request_target: &str, header: impl Fn(&str) -> Option<String>) -> Option<String> {
+    names
+        .iter()
+        .map(|name| match name.as_str() {
+            "(request-target)" => Some(format!("(request-target): {}", request_target)),
+            name => header(name).map(|value| format!("{}: {}", name, value)),
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|lines| lines.join("\n"))
+}
+
+fn digest(body: &[u8]) -> String {
+    format!("SHA-256={}", base64::engine::general_purpose::STANDARD.encode(Sha256::digest(body)))
+}
+
+fn http_date() -> String {
+    chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+// Headers for a request to `url` signed with the show's key; POSTs also sign the body's digest
+fn signed_headers(actor: &Actor, method: &str, url: &reqwest::Url, body: Option<&[u8]>) -> Vec<(&'static str, String)> {
+    let host = url.port().map_or(url.host_str().unwrap_or_default().to_string(), |port| format!("{}:{}", url.host_str().unwrap_or_default(), port));
+    let target = format!("{} {}{}", method, url.path(), url.query().map(|q| format!("?{}", q)).unwrap_or_default());
+    let mut headers = vec![("host", host), ("date", http_date())];
+    if let Some(body) = body {
+        headers.push(("digest", digest(body)));
+    }
+    let names: Vec<String> = std::iter::once("(request-target)".to_string()).chain(headers.iter().map(|(name, _)| name.to_string())).collect();
+    let text = signing_string(&names, &target, |name| headers.iter().find(|(n, _)| *n == name).map(|(_, v)| v.clone())).unwrap_or_default();
+    let signature = SigningKey::<Sha256>::new(actor.key.clone()).sign(text.as_bytes());
+    headers.push((
+        "signature",
+        format!(
+            "keyId=\"{}\",algorithm=\"rsa-sha256\",headers=\"{}\",signature=\"{}\"",
+            key_id(),
+            names.join(" "),
+            base64::engine::general_purpose::STANDARD.encode(signature.to_bytes())
+        ),
+    ));
+    headers
+}
+
+async fn fetch_object(actor: &Actor, url: &str) -> Result<serde_json::Value, String> {
+    let url = crate::fetch::parse_url(url)?;
+    let mut request = crate::fetch::safe_client().get(url.clone()).header("accept", CONTENT_TYPE);
+    // Servers in authorized-fetch mode want even reads signed
+    for (name, value) in signed_headers(actor, "get", &url, None) {
+        if name != "host" {
+            request = request.header(name, value);
+        }
+    }
+    let response = request.send().await.map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} answered {}", url, response.status()));
+    }
+    response.json().await.map_err(|e| format!("Invalid JSON from {}: {}", url, e))
+}
+
+// The signing actor's document, once the request's signature, digest and date check out
+pub async fn verify(actor: &Actor, request_target: &str, headers: &HeaderMap, body: &[u8]) -> Result<serde_json::Value, String> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(String::from);
+    let signature = header("signature").and_then(|value| parse_signature(&value)).ok_or("Missing or malformed Signature header")?;
+    for required in ["(request-target)", "date", "digest"] {
+        if !signature.headers.iter().any(|name| name == required) {
+            return Err(format!("The signature doesn't cover {}", required));
+        }
+    }
+    if header("digest").is_none_or(|value| value != digest(body)) {
+        return Err("Digest doesn't match the body".to_string());
+    }
+    let date = header("date").and_then(|value| chrono::DateTime::parse_from_rfc2822(&value).ok()).ok_or("Missing or malformed Date header")?;
+    if (chrono::Utc::now() - date.with_timezone(&chrono::Utc)).num_seconds().abs() > MAX_CLOCK_SKEW_SECS {
+        return Err("Date is too far from now".to_string());
+    }
+    let text = signing_string(&signature.headers, request_target, header).ok_or("A signed header is missing")?;
+
+    let key_url = signature.key_id.split('#').next().unwrap_or_default().to_string();
+    let owner = fetch_object(actor, &key_url).await?;
+    let keys = match &owner["publicKey"] {
+        serde_json::Value::Array(keys) => keys.clone(),
+        key => vec![key.clone()],
+    };
+    let pem = keys
+        .iter()
+        .find(|key| key["id"] == signature.key_id.as_str())
+        .and_then(|key| key["publicKeyPem"].as_str())
+        .ok_or_else(|| format!("{} has no key {}", key_url, signature.key_id))?;
+    let key = RsaPublicKey::from_public_key_pem(pem).map_err(|_| format!("{} isn't an RSA public key", signature.key_id))?;
+    let parsed = Signature::try_from(signature.signature.as_slice()).map_err(|_| "Malformed signature".to_string())?;
+    VerifyingKey::<Sha256>::new(key).verify(text.as_bytes(), &parsed).map_err(|_| "Signature doesn't verify".to_string())?;
+    Ok(owner)
+}
+
+// --- Inbox ---
+
+fn object_id(value: &serde_json::Value) -> Option<&str> {
+    value.as_str().or_else(|| value["id"].as_str())
+}
+
+// Follows are recorded and accepted, Undo of a follow or Delete of the account removes the
+// follower; everything else is ignored. `signer` is the verified sending actor.
+pub async fn receive(actor: &Actor, activity: serde_json::Value, signer: serde_json::Value) -> Result<(), String> {
+    let signer_id = signer["id"].as_str().ok_or("The signing actor has no id")?;
+    if activity["actor"].as_str() != Some(signer_id) {
+        return Err(format!("Activity by {} signed by {}", activity["actor"], signer_id));
+    }
+    match activity["type"].as_str() {
+        Some("Follow") if object_id(&activity["object"]) == Some(actor_id().as_str()) => {
+            let inbox = signer["inbox"].as_str().ok_or("The follower has no inbox")?;
+            crate::fetch::parse_url(inbox)?;
+            let shared_inbox = signer["endpoints"]["sharedInbox"].as_str().filter(|url| crate::fetch::parse_url(url).is_ok());
+            archive::add_follower(signer_id, inbox, shared_inbox).await?;
+            info!("{} follows the show on ActivityPub", signer_id);
+            let accept = serde_json::json!({
+                "@context": "https://www.w3.org/ns/activitystreams",
+                "id": format!("{}#accept-{}", actor_id(), uuid::Uuid::new_v4()),
+                "type": "Accept",
+                "actor": actor_id(),
+                "object": activity,
+            });
+            deliver(actor, inbox, &accept).await
+        }
+        Some("Undo") if activity["object"]["type"] == "Follow" => {
+            if archive::remove_follower(signer_id).await? {
+                info!("{} unfollowed the show on ActivityPub", signer_id);
+            }
+            Ok(())
+        }
+        Some("Delete") if object_id(&activity["object"]) == Some(signer_id) => {
+            archive::remove_follower(signer_id).await.map(|_| ())
+        }
+        _ => Ok(()),
+    }
+}
+
+// --- Delivery ---
+
+async fn deliver(actor: &Actor, inbox: &str, activity: &serde_json::Value) -> Result<(), String> {
+    let url = crate::fetch::parse_url(inbox)?;
+    let body = serde_json::to_vec(activity).map_err(|e| e.to_string())?;
+    let mut request = crate::fetch::safe_client().post(url.clone()).header("content-type", CONTENT_TYPE);
+    for (name, value) in signed_headers(actor, "post", &url, Some(&body)) {
+        if name != "host" {
+            request = request.header(name, value);
+        }
+    }
+    let response = request.body(body).send().await.map_err(|e| format!("Failed to deliver to {}: {}", inbox, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} answered {}", inbox, response.status()));
+    }
+    Ok(())
+}
+
+// Publishes the episode to every follower in the background, once per episode
+pub fn spawn_publish(episode_id: String) {
+    let Ok(Some(actor)) = settings() else { return };
+    if !archive::enabled() {
+        return;
+    }
+    tokio::spawn(async move {
+        let activity = match archive::get(&episode_id).await {
+            Ok(Some(episode)) => create(&episode),
+            Ok(None) => None,
+            Err(e) => return error!("Failed to load episode {} for ActivityPub: {}", episode_id, e),
+        };
+        let Some(activity) = activity else { return };
+        match archive::claim_note(&episode_id).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => return error!("Failed to record the ActivityPub note of episode {}: {}", episode_id, e),
+        }
+        let inboxes = match archive::follower_inboxes().await {
+            Ok(inboxes) => inboxes,
+            Err(e) => return error!("Failed to list ActivityPub followers: {}", e),
+        };
+        for inbox in &inboxes {
+            if let Err(e) = deliver(&actor, inbox, &activity).await {
+                error!("ActivityPub delivery of episode {}: {}", episode_id, e);
+            }
+        }
+        info!("Published episode {} on ActivityPub to {} inbox(es)", episode_id, inboxes.len());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_headers_are_parsed() {
+        let header = "keyId=\"https://mastodon.example/users/ann#main-key\",algorithm=\"rsa-sha256\",headers=\"(request-target) host date digest\",signature=\"AQID\"";
+        assert_eq!(
+            parse_signature(header),
+            Some(SignatureHeader {
+                key_id: "https://mastodon.example/users/ann#main-key".to_string(),
+                headers: vec!["(request-target)".to_string(), "host".to_string(), "date".to_string(), "digest".to_string()],
+                signature: vec![1, 2, 3],
+            })
+        );
+        assert_eq!(parse_signature("keyId=\"k\",algorithm=\"hmac-sha256\",signature=\"AQID\""), None);
+        assert_eq!(parse_signature("algorithm=\"rsa-sha256\",signature=\"AQID\""), None);
+    }
+
+    #[test]
+    fn signing_strings_follow_the_signed_header_order() {
+        let names: Vec<String> = ["(request-target)", "host", "date"].iter().map(|name| name.to_string()).collect();
+        let text = signing_string(&names, "post /ap/inbox", |name| Some(format!("<{}>", name))).unwrap();
+        assert_eq!(text, "(request-target): post /ap/inbox\nhost: <host>\ndate: <date>");
+        assert_eq!(signing_string(&names, "post /ap/inbox", |_| None), None);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::jobs::{Job, JobStatus};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use tracing::{error, info};
+
+// Operator alerts when background jobs keep failing.
+//   ALERT_FAILURE_THRESHOLD  - consecutive failures per job kind before alerting (default 3)
+//   ALERT_WEBHOOK_URL        - receives a JSON POST per alert
+//   SMTP_HOST, SMTP_PORT, SMTP_USERNAME, SMTP_PASSWORD, ALERT_EMAIL_FROM, ALERT_EMAIL_TO
+//                            - send alert emails (STARTTLS)
+//   PUBLIC_BASE_URL          - used to link to the job detail endpoint
+
+static CONSECUTIVE_FAILURES: OnceLock<Mutex<HashMap<String, u32>>> = OnceLock::new();
+
+fn consecutive_failures() -> &'static Mutex<HashMap<String, u32>> {
+    CONSECUTIVE_FAILURES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn failure_threshold() -> u32 {
+    env::var("ALERT_FAILURE_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&v| v > 0)
+        .unwrap_or(3)
+}
+
+pub fn public_base_url() -> String {
+    env::var("PUBLIC_BASE_URL")
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| format!("http://localhost:{}", env::var("PORT").unwrap_or_else(|_| "3001".to_string())))
+}
+
+pub async fn record_job_outcome(job: &Job) {
+    let failures = {
+        let mut counts = consecutive_failures().lock().unwrap();
+        match job.status {
+            JobStatus::Succeeded => {
+                counts.remove(&job.kind);
+                return;
+            }
+            JobStatus::Dead => {
+                let count = counts.entry(job.kind.clone()).or_insert(0);
+                *count += 1;
+                *count
+            }
+            _ => return,
+        }
+    };
+
+    // Alert when the threshold is reached, then again every `threshold` failures
+    let threshold = failure_threshold();
+    if failures % threshold == 0 {
+        send_alert(job, failures).await;
+    }
+}
+
+async fn send_alert(job: &Job, failures: u32) {
+    let job_url = format!("{}/api/jobs/{}", public_base_url(), job.id);
+    let stage = job.stage.as_deref().unwrap_or("unknown");
+    let upstream_error = job.error.as_deref().unwrap_or("unknown error");
+
+    let subject = format!("[hackernews-podcast] {} jobs failed {} times in a row", job.kind, failures);
+    let body = format!(
+        "The last {} {} jobs failed.\n\nLatest job: {}\nFailed stage: {}\nError: {}\nDetails: {}\n",
+        failures, job.kind, job.id, stage, upstream_error, job_url
+    );
+
+    info!("Sending failure alert for {} jobs ({} consecutive failures)", job.kind, failures);
+
+    if let Ok(webhook_url) = env::var("ALERT_WEBHOOK_URL") {
+        let result = reqwest::Client::new()
+            .post(&webhook_url)
+            .json(&serde_json::json!({
+                "text": subject,
+                "job_kind": job.kind,
+                "job_id": job.id,
+                "consecutive_failures": failures,
+                "stage": stage,
+                "error": upstream_error,
+                "job_url": job_url
+            }))
+            .send()
+            .await;
+        if let Err(e) = result.and_then(|r| r.error_for_status()) {
+            error!("Failed to deliver alert webhook: {}", e);
+        }
+    }
+
+    if let Err(e) = send_email(&subject, body).await {
+        error!("Failed to send alert email: {}", e);
+    }
+}
+
+async fn send_email(subject: &str, body: String) -> Result<(), anyhow::Error> {
+    let host = match env::var("SMTP_HOST") {
+        Ok(host) if !host.is_empty() => host,
+        _ => return Ok(()),
+    };
+    let from: Mailbox = env::var("ALERT_EMAIL_FROM")?.parse()?;
+    let recipients = env::var("ALERT_EMAIL_TO")?;
+
+    let mut message = Message::builder().from(from).subject(subject);
+    for recipient in recipients.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        message = message.to(recipient.parse()?);
+    }
+    let message = message.body(body)?;
+
+    let mut transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)?;
+    if let Ok(port) = env::var("SMTP_PORT") {
+        transport = transport.port(port.parse()?);
+    }
+    if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+        transport = transport.credentials(Credentials::new(username, password));
+    }
+
+    transport.build().send(message).await?;
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+// Lightweight text analysis for story pages: article extraction, keywords and comment sentiment.
+// Heuristic on purpose; it runs inline on every /api/stories/:id/full request.
+
+const MAX_EXTRACT_CHARS: usize = 2000;
+
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be", "because",
+    "been", "before", "being", "but", "by", "can", "could", "did", "do", "does", "doing", "don't", "down", "each",
+    "even", "few", "for", "from", "get", "got", "had", "has", "have", "having", "he", "her", "here", "him", "his",
+    "how", "i", "i'm", "if", "in", "into", "is", "it", "it's", "its", "just", "like", "more", "most", "much", "my",
+    "no", "not", "now", "of", "on", "one", "only", "or", "other", "our", "out", "over", "own", "people", "really",
+    "same", "she", "should", "so", "some", "such", "than", "that", "that's", "the", "their", "them", "then", "there",
+    "these", "they", "thing", "things", "think", "this", "those", "through", "to", "too", "under", "up", "use",
+    "used", "using", "very", "was", "way", "we", "well", "were", "what", "when", "where", "which", "while", "who",
+    "why", "will", "with", "would", "you", "your", "show", "ask", "hn",
+];
+
+const POSITIVE: &[&str] = &[
+    "amazing", "awesome", "beautiful", "best", "better", "brilliant", "clean", "cool", "easy", "elegant", "excellent",
+    "fantastic", "fast", "fun", "good", "great", "helpful", "impressive", "interesting", "love", "loved", "nice",
+    "perfect", "powerful", "recommend", "reliable", "simple", "solid", "thanks", "useful", "wonderful",
+];
+
+const NEGATIVE: &[&str] = &[
+    "annoying", "awful", "bad", "broken", "buggy", "complicated", "confusing", "disappointing", "fail", "failed",
+    "hate", "horrible", "mess", "painful", "poor", "problem", "slow", "terrible", "ugly", "unusable", "useless",
+    "worse", "worst", "wrong",
+];
+
+// Strip tags and decode the handful of entities HN and most articles use
+pub fn strip_html(html: &str) -> String {
+    let tags = regex::Regex::new(r"(?s)<[^>]*>").unwrap();
+    let html = html.replace("<p>", "\n\n");
+    let text = tags.replace_all(&html, "");
+    text.replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&quot;", "\"")
+        .replace("&#x2F;", "/")
+        .replace("&gt;", ">")
+        .replace("&lt;", "<")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+// Readable body text: paragraphs inside <article>/<main> when present, else the whole page
+pub fn article_extract(html: &str) -> Option<String> {
+    let noise = regex::Regex::new(r"(?is)<(script|style|nav|header|footer|aside)[^>]*>.*?</(script|style|nav|header|footer|aside)>").unwrap();
+    let html = noise.replace_all(html, " ");
+    let container = regex::Regex::new(r"(?is)<(article|main)[^>]*>(.*?)</(article|main)>").unwrap();
+    let body = container.captures(&html).and_then(|c| c.get(2)).map(|m| m.as_str()).unwrap_or(&html);
+
+    let paragraph = regex::Regex::new(r"(?is)<p[^>]*>(.*?)</p>").unwrap();
+    let mut extract = String::new();
+    for p in paragraph.captures_iter(body) {
+        let text = strip_html(&p[1]).split_whitespace().collect::<Vec<_>>().join(" ");
+        // Skip bylines, captions and cookie notices
+        if text.split_whitespace().count() < 8 {
+            continue;
+        }
+        if !extract.is_empty() {
+            extract.push_str("\n\n");
+        }
+        extract.push_str(&text);
+        if extract.chars().count() >= MAX_EXTRACT_CHARS {
+            let cut = extract.char_indices().nth(MAX_EXTRACT_CHARS).map(|(i, _)| i).unwrap_or(extract.len());
+            extract.truncate(cut);
+            extract.push('…');
+            break;
+        }
+    }
+    (!extract.is_empty()).then_some(extract)
+}
+
+// Publications that paywall (nearly) everything
+const PAYWALLED_DOMAINS: &[&str] = &[
+    "wsj.com", "ft.com", "nytimes.com", "bloomberg.com", "economist.com", "theinformation.com", "washingtonpost.com",
+    "newyorker.com", "theatlantic.com", "barrons.com", "businessinsider.com", "hbr.org", "thetimes.co.uk", "telegraph.co.uk",
+];
+
+const PAYWALL_PHRASES: &[&str] = &[
+    "subscribe to continue reading",
+    "subscribe to read",
+    "this article is for subscribers",
+    "subscribers only",
+    "to continue reading, subscribe",
+    "create a free account to continue",
+    "sign in to continue reading",
+    "already a subscriber?",
+];
+
+// Why the article looks paywalled, if it does: schema.org markup, paywall markup/phrases, or a
+// known paywalled publication whose page yielded little readable text
+pub fn detect_paywall(url: &str, html: &str, extract: Option<&str>) -> Option<&'static str> {
+    let compact: String = html.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+    if compact.contains("\"isaccessibleforfree\":false") || compact.contains("\"isaccessibleforfree\":\"false\"") {
+        return Some("structured_data");
+    }
+
+    let lower = html.to_lowercase();
+    let markup = regex::Regex::new(r#"(class|id)=["'][^"']*(paywall|subscriber-only|premium-content|meteredcontent)"#).unwrap();
+    if markup.is_match(&lower) {
+        return Some("paywall_markup");
+    }
+    if PAYWALL_PHRASES.iter().any(|phrase| lower.contains(phrase)) {
+        return Some("subscribe_prompt");
+    }
+
+    let thin = extract.map(|e| e.chars().count() < 500).unwrap_or(true);
+    let host = crate::urls::canonical_host(url).unwrap_or_default();
+    let known = PAYWALLED_DOMAINS.iter().any(|d| host == *d || host.ends_with(&format!(".{}", d)));
+    (known && thin).then_some("known_publication")
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '-' || c == '+' || c == '#'))
+        .map(|w| w.trim_matches(|c| c == '\'' || c == '-').to_lowercase())
+        .filter(|w| !w.is_empty())
+}
+
+pub fn is_stopword(word: &str) -> bool {
+    STOPWORDS.contains(&word.to_lowercase().as_str())
+}
+
+// Most frequent non-stopwords; title words count extra since they name the topic
+pub fn keywords(title: &str, texts: &[&str], limit: usize) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut add = |text: &str, weight: usize| {
+        for word in words(text) {
+            if word.len() > 2 && !STOPWORDS.contains(&word.as_str()) && !word.chars().all(|c| c.is_ascii_digit()) {
+                *counts.entry(word).or_default() += weight;
+            }
+        }
+    };
+    add(title, 5);
+    for text in texts {
+        add(text, 1);
+    }
+
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.into_iter().take(limit).map(|(word, _)| word).collect()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Sentiment {
+    // Mean per-comment polarity in [-1, 1]
+    pub score: f64,
+    pub label: &'static str,
+    pub positive: usize,
+    pub negative: usize,
+    pub neutral: usize,
+}
+
+pub fn sentiment(texts: &[&str]) -> Sentiment {
+    let mut polarities = Vec::new();
+    let (mut positive, mut negative, mut neutral) = (0, 0, 0);
+    for text in texts {
+        let (mut pos, mut neg) = (0i32, 0i32);
+        for word in words(text) {
+            if POSITIVE.contains(&word.as_str()) {
+                pos += 1;
+            } else if NEGATIVE.contains(&word.as_str()) {
+                neg += 1;
+            }
+        }
+        match pos.cmp(&neg) {
+            std::cmp::Ordering::Greater => positive += 1,
+            std::cmp::Ordering::Less => negative += 1,
+            std::cmp::Ordering::Equal => neutral += 1,
+        }
+        if pos + neg > 0 {
+            polarities.push((pos - neg) as f64 / (pos + neg) as f64);
+        } else {
+            polarities.push(0.0);
+        }
+    }
+
+    let score = if polarities.is_empty() { 0.0 } else { polarities.iter().sum::<f64>() / polarities.len() as f64 };
+    let label = if score > 0.15 {
+        "positive"
+    } else if score < -0.15 {
+        "negative"
+    } else {
+        "mixed"
+    };
+    Sentiment { score, label, positive, negative, neutral }
+}
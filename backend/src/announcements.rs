@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use tracing::error;
+
+// Sponsor / community announcement segments spliced into episodes at audio assembly time.
+// Configured through a JSON file pointed to by ANNOUNCEMENTS_PATH, e.g.
+// [{ "title": "Sponsor: Acme", "placement": "after_intro", "text": "This episode is brought to you by..." },
+//  { "title": "Community", "placement": "mid_roll", "audio_path": "./announcements/meetup.wav" }]
+#[derive(Debug, Clone, Deserialize)]
+pub struct Announcement {
+    pub title: String,
+    pub placement: Placement,
+    // Either text to synthesize with the episode voice...
+    pub text: Option<String>,
+    // ...or a pre-recorded WAV file (24kHz, mono, 16-bit PCM)
+    pub audio_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    AfterIntro,
+    MidRoll,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chapter {
+    pub start_secs: f64,
+    pub title: String,
+    pub kind: ChapterKind,
+    // A pre-recorded announcement rather than the host's voice
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub prerecorded: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChapterKind {
+    Content,
+    Announcement,
+}
+
+// Script split at the announcement insertion points
+#[derive(Debug)]
+pub struct ScriptParts {
+    pub intro: String,
+    pub first_half: String,
+    pub second_half: String,
+}
+
+pub fn load_announcements() -> Vec<Announcement> {
+    let path = match env::var("ANNOUNCEMENTS_PATH") {
+        Ok(path) if !path.is_empty() => path,
+        _ => return vec![],
+    };
+
+    let raw = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            error!("Failed to read announcements file {}: {}", path, e);
+            return vec![];
+        }
+    };
+
+    match serde_json::from_str::<Vec<Announcement>>(&raw) {
+        Ok(announcements) => announcements
+            .into_iter()
+            .filter(|a| {
+                let valid = a.text.as_ref().is_some_and(|t| !t.trim().is_empty()) || a.audio_path.is_some();
+                if !valid {
+                    error!("Skipping announcement '{}': neither text nor audio_path is set", a.title);
+                }
+                valid
+            })
+            .collect(),
+        Err(e) => {
+            error!("Failed to parse announcements file {}: {}", path, e);
+            vec![]
+        }
+    }
+}
+
+// Split a script into intro / first half / second half. The intro ends at the first
+// [TRANSITION] cue (falling back to the first paragraph), and the remaining body is split
+// at the paragraph break closest to its midpoint.
+pub fn split_script(text: &str) -> ScriptParts {
+    let intro_end = text
+        .to_ascii_uppercase()
+        .find("[TRANSITION]")
+        .or_else(|| text.find("\n\n"))
+        .unwrap_or(0);
+
+    let (intro, body) = text.split_at(intro_end);
+
+    let midpoint = body.len() / 2;
+    let mid_split = body
+        .match_indices("\n\n")
+        .map(|(i, _)| i)
+        .filter(|&i| i > 0)
+        .min_by_key(|&i| i.abs_diff(midpoint))
+        .unwrap_or(body.len());
+
+    let (first_half, second_half) = body.split_at(mid_split);
+
+    ScriptParts {
+        intro: intro.trim().to_string(),
+        first_half: first_half.trim().to_string(),
+        second_half: second_half.trim().to_string(),
+    }
+}
+
+// Read a pre-recorded announcement and return its raw PCM samples
+pub fn read_wav_pcm(path: &str, sample_rate: u32, channels: u16, bits_per_sample: u16) -> Result<Vec<u8>, anyhow::Error> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        anyhow::bail!("{} is not a WAV file", path);
+    }
+
+    let mut offset = 12;
+    let mut format_ok = false;
+    while offset + 8 <= bytes.len() {
+        let chunk_id = &bytes[offset..offset + 4];
+        let chunk_size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into()?) as usize;
+        let body_start = offset + 8;
+        let body_end = std::cmp::min(body_start + chunk_size, bytes.len());
+
+        if chunk_id == b"fmt " && body_end - body_start >= 16 {
+            let fmt = &bytes[body_start..body_end];
+            let file_channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+            let file_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+            let file_bits = u16::from_le_bytes([fmt[14], fmt[15]]);
+            if file_channels != channels || file_rate != sample_rate || file_bits != bits_per_sample {
+                anyhow::bail!(
+                    "{} must be {}Hz, {} channel(s), {}-bit PCM (got {}Hz, {} channel(s), {}-bit)",
+                    path, sample_rate, channels, bits_per_sample, file_rate, file_channels, file_bits
+                );
+            }
+            format_ok = true;
+        } else if chunk_id == b"data" {
+            if !format_ok {
+                anyhow::bail!("{} has no fmt chunk before its data chunk", path);
+            }
+            return Ok(bytes[body_start..body_end].to_vec());
+        }
+
+        // Chunks are padded to an even number of bytes
+        offset = body_start + chunk_size + (chunk_size % 2);
+    }
+
+    anyhow::bail!("{} has no data chunk", path)
+}
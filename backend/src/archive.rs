@@ -0,0 +1,1959 @@
+use crate::jobs::{Job, JobStatus, Priority};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::Row;
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+// Episode archive: every generated script is stored in SQLite (DATABASE_URL, default
+// sqlite://episodes.db, created if missing) so it can be listed and re-opened later through
+// /api/episodes, along with the topics of the stories behind it and the front-page radar's
+// keywords and hits. DATABASE_URL="" turns the archive off; the endpoints then answer 503.
+
+// Applied in order; the database's user_version records how many have run
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS episodes (
+        id TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        slug TEXT NOT NULL,
+        season INTEGER,
+        persona TEXT NOT NULL,
+        script TEXT NOT NULL,
+        story_ids TEXT NOT NULL DEFAULT '[]',
+        chat_id TEXT,
+        job_id TEXT,
+        created_at TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS episodes_created_at ON episodes (created_at);",
+    "ALTER TABLE episodes ADD COLUMN topics TEXT NOT NULL DEFAULT '[]';
+    CREATE TABLE story_topics (
+        story_id INTEGER NOT NULL,
+        topic TEXT NOT NULL,
+        source TEXT NOT NULL,
+        classified_at TEXT NOT NULL,
+        PRIMARY KEY (story_id, topic)
+    );
+    -- Stories the classifier found nothing for, so they aren't classified again
+    CREATE TABLE unclassified_stories (story_id INTEGER PRIMARY KEY, classified_at TEXT NOT NULL);",
+    "CREATE TABLE radar_keywords (keyword TEXT PRIMARY KEY, created_at TEXT NOT NULL);
+    -- Front-page stories that matched a watched keyword, recorded once per story
+    CREATE TABLE radar_hits (
+        story_id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        keywords TEXT NOT NULL,
+        rank INTEGER NOT NULL,
+        score INTEGER,
+        job_id TEXT,
+        matched_at TEXT NOT NULL
+    );
+    CREATE INDEX radar_hits_matched_at ON radar_hits (matched_at);",
+    // Rendered audio (see episode_audio.rs)
+    "ALTER TABLE episodes ADD COLUMN audio_file TEXT;
+    ALTER TABLE episodes ADD COLUMN audio_bytes INTEGER;
+    ALTER TABLE episodes ADD COLUMN audio_duration_secs REAL;
+    ALTER TABLE episodes ADD COLUMN audio_rendered_at TEXT;",
+    "ALTER TABLE episodes ADD COLUMN status TEXT NOT NULL DEFAULT 'complete';
+    ALTER TABLE episodes ADD COLUMN show_notes TEXT;",
+    // Existing episodes are numbered in the order they were created
+    "ALTER TABLE episodes ADD COLUMN episode_number INTEGER;
+    UPDATE episodes SET episode_number = (
+        SELECT COUNT(*) FROM episodes AS earlier
+        WHERE earlier.created_at < episodes.created_at OR (earlier.created_at = episodes.created_at AND earlier.id <= episodes.id)
+    );
+    CREATE UNIQUE INDEX episodes_episode_number ON episodes (episode_number);
+    -- Last number handed out, kept apart from the episodes so a deleted episode's number is never reused
+    CREATE TABLE episode_sequence (id INTEGER PRIMARY KEY CHECK (id = 1), last_number INTEGER NOT NULL);
+    INSERT INTO episode_sequence (id, last_number) SELECT 1, COALESCE(MAX(episode_number), 0) FROM episodes;",
+    // Loudness/clipping QA report of the rendered audio, as JSON
+    "ALTER TABLE episodes ADD COLUMN audio_qa TEXT;",
+    // Chapter markers of the rendered audio, as JSON
+    "ALTER TABLE episodes ADD COLUMN audio_chapters TEXT;",
+    // Lower-bitrate encodings next to the main file, as JSON
+    "ALTER TABLE episodes ADD COLUMN audio_renditions TEXT;",
+    // HLS variants of the rendered audio, as JSON
+    "ALTER TABLE episodes ADD COLUMN audio_hls TEXT;",
+    // Whisper word and sentence timestamps of the rendered audio, as JSON
+    "ALTER TABLE episodes ADD COLUMN transcript_timings TEXT;",
+    // Earlier and refined scripts; the episode's own script is always the latest version
+    "CREATE TABLE episode_versions (
+        episode_id TEXT NOT NULL,
+        version INTEGER NOT NULL,
+        script TEXT NOT NULL,
+        instruction TEXT,
+        created_at TEXT NOT NULL,
+        PRIMARY KEY (episode_id, version)
+    );",
+    // Listener libraries, keyed by an opaque user id (see main.rs)
+    "CREATE TABLE user_bookmarks (user_id TEXT NOT NULL, episode_id TEXT NOT NULL, created_at TEXT NOT NULL, PRIMARY KEY (user_id, episode_id));
+    CREATE TABLE user_playlists (
+        user_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        episode_ids TEXT NOT NULL DEFAULT '[]',
+        updated_at TEXT NOT NULL,
+        PRIMARY KEY (user_id, name)
+    );
+    CREATE TABLE user_subscriptions (user_id TEXT NOT NULL, topic TEXT NOT NULL, created_at TEXT NOT NULL, PRIMARY KEY (user_id, topic));",
+    // Admin and destructive actions; triggers keep it append-only
+    "CREATE TABLE audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at TEXT NOT NULL,
+        actor TEXT NOT NULL,
+        action TEXT NOT NULL,
+        target TEXT NOT NULL,
+        before TEXT,
+        after TEXT
+    );
+    CREATE INDEX audit_log_action ON audit_log (action);
+    CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+    CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;",
+    // API keys and what each has used per month; see usage.rs
+    "CREATE TABLE api_keys (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        secret_hash TEXT NOT NULL UNIQUE,
+        generation_quota INTEGER,
+        tts_quota INTEGER,
+        created_at TEXT NOT NULL,
+        revoked_at TEXT
+    );
+    CREATE TABLE api_usage (
+        key_id TEXT NOT NULL,
+        month TEXT NOT NULL,
+        metric TEXT NOT NULL,
+        amount INTEGER NOT NULL,
+        PRIMARY KEY (key_id, month, metric)
+    );",
+    // The API key whose request rendered the audio, for storage and bandwidth usage
+    "ALTER TABLE episodes ADD COLUMN audio_key_id TEXT;",
+    "ALTER TABLE api_keys ADD COLUMN sandbox INTEGER NOT NULL DEFAULT 0;",
+    // Front-page snapshots by unix milliseconds, for /api/stories?as_of=
+    "CREATE TABLE frontpage_snapshots (taken_at INTEGER PRIMARY KEY, stories TEXT NOT NULL);",
+    // Embargo in unix seconds; release_pending until the release has been announced
+    "ALTER TABLE episodes ADD COLUMN publish_at INTEGER;
+    ALTER TABLE episodes ADD COLUMN release_pending INTEGER NOT NULL DEFAULT 0;",
+    // Digest schedules; see schedules.rs
+    "CREATE TABLE schedules (
+        name TEXT PRIMARY KEY,
+        cron TEXT NOT NULL,
+        timezone TEXT NOT NULL,
+        enabled INTEGER NOT NULL,
+        updated_at TEXT NOT NULL
+    );",
+    // Per-schedule sources, persona, prompt, voice and feed as JSON
+    "ALTER TABLE schedules ADD COLUMN settings TEXT NOT NULL DEFAULT '{}';",
+    // The shared job queue; see jobs.rs and roles.rs. `job` is the job as JSON, `runner` the
+    // process running it (NULL until claimed), `local` set for jobs started with the caller's own
+    // keys, and `decision` an approval made by another process
+    "CREATE TABLE jobs (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        priority INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        runner TEXT,
+        local INTEGER NOT NULL,
+        decision TEXT,
+        job TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE INDEX jobs_by_status ON jobs (status, priority, created_at);",
+    // Schedule runs already queued, by unix seconds, so each period runs once across replicas
+    "CREATE TABLE schedule_runs (name TEXT NOT NULL, run_at INTEGER NOT NULL, PRIMARY KEY (name, run_at));",
+    // Process heartbeats by unix seconds, to tell which jobs' runners are gone
+    "CREATE TABLE processes (id TEXT PRIMARY KEY, role TEXT NOT NULL, seen_at INTEGER NOT NULL);",
+    // Audio file names and the content-addressed object each refers to; see artifacts.rs
+    "CREATE TABLE artifacts (name TEXT PRIMARY KEY, hash TEXT NOT NULL, bytes INTEGER NOT NULL);
+    CREATE INDEX artifacts_by_hash ON artifacts (hash);",
+    // The episode a follow-up updates
+    "ALTER TABLE episodes ADD COLUMN follow_up_of TEXT;
+    CREATE INDEX episodes_by_follow_up_of ON episodes (follow_up_of);",
+    // Listener ratings; a listener's later rating of an episode replaces their earlier one
+    "CREATE TABLE episode_feedback (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        episode_id TEXT NOT NULL,
+        user_id TEXT,
+        rating INTEGER NOT NULL,
+        comment TEXT,
+        created_at TEXT NOT NULL
+    );
+    CREATE INDEX episode_feedback_by_episode ON episode_feedback (episode_id);
+    CREATE UNIQUE INDEX episode_feedback_by_user ON episode_feedback (user_id, episode_id) WHERE user_id IS NOT NULL;",
+    // ActivityPub: accounts following the show, and the episodes already published as notes
+    "CREATE TABLE activitypub_followers (actor TEXT PRIMARY KEY, inbox TEXT NOT NULL, shared_inbox TEXT, followed_at TEXT NOT NULL);
+    CREATE TABLE activitypub_notes (episode_id TEXT PRIMARY KEY, published_at TEXT NOT NULL);",
+    // Segments listeners skipped or finished, with the topics they were attributed to; a
+    // listener's later event for a segment replaces their earlier one
+    "CREATE TABLE playback_events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        episode_id TEXT NOT NULL,
+        chapter INTEGER NOT NULL,
+        chapter_title TEXT NOT NULL,
+        event TEXT NOT NULL,
+        topics TEXT NOT NULL DEFAULT '[]',
+        user_id TEXT,
+        created_at TEXT NOT NULL
+    );
+    CREATE INDEX playback_events_by_episode ON playback_events (episode_id);
+    CREATE INDEX playback_events_by_time ON playback_events (created_at);
+    CREATE UNIQUE INDEX playback_events_by_user ON playback_events (user_id, episode_id, chapter) WHERE user_id IS NOT NULL;",
+];
+
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+// Degraded episodes were published with parts missing (a story's segment or audio failed); the
+// show notes say which
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EpisodeStatus {
+    #[default]
+    Complete,
+    Degraded,
+}
+
+impl EpisodeStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            EpisodeStatus::Complete => "complete",
+            EpisodeStatus::Degraded => "degraded",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Episode {
+    pub id: String,
+    pub title: String,
+    pub slug: String,
+    // Assigned in order when the episode is archived, starting at 1
+    pub episode_number: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub season: Option<u32>,
+    pub persona: String,
+    pub status: EpisodeStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_notes: Option<String>,
+    // Left out of list responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+    pub story_ids: Vec<u32>,
+    pub topics: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<EpisodeAudio>,
+    // Embargo: kept out of the feeds until then
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<DateTime<Utc>>,
+    // Follow-ups update an earlier episode with the discussion since
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follow_up_of: Option<String>,
+    // Follow-ups of this episode, oldest first; filled in for GET /api/episodes/:id only
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub follow_ups: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Episode {
+    pub fn embargoed(&self) -> bool {
+        self.publish_at.is_some_and(|at| at > Utc::now())
+    }
+
+    // When listeners first saw it: the embargo if there was one
+    pub fn published_at(&self) -> DateTime<Utc> {
+        self.publish_at.map_or(self.created_at, |at| at.max(self.created_at))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EpisodeAudio {
+    // File name in EPISODE_AUDIO_DIR
+    pub file: String,
+    pub url: String,
+    pub bytes: u64,
+    pub duration_secs: f64,
+    pub rendered_at: DateTime<Utc>,
+    // None for audio rendered before QA reports were kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qa: Option<crate::audio::QaReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<crate::announcements::Chapter>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub renditions: Vec<crate::episode_audio::Rendition>,
+    // None when segmenting failed or the audio predates HLS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hls: Option<crate::episode_audio::Hls>,
+}
+
+impl EpisodeAudio {
+    // The main file is the high-quality rendition
+    pub fn rendition(&self, quality: crate::episode_audio::Quality) -> Option<crate::episode_audio::Rendition> {
+        use crate::episode_audio::{Quality, Rendition};
+        match quality {
+            Quality::High => Some(Rendition {
+                quality,
+                file: self.file.clone(),
+                url: self.url.clone(),
+                bytes: self.bytes,
+                mime_type: quality.mime_type().to_string(),
+                bits_per_second: quality.bits_per_second(),
+            }),
+            _ => self.renditions.iter().find(|rendition| rendition.quality == quality).cloned(),
+        }
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.file.as_str()).chain(self.renditions.iter().map(|rendition| rendition.file.as_str()))
+    }
+}
+
+pub struct NewEpisode<'a> {
+    pub title: &'a str,
+    pub slug: &'a str,
+    pub season: Option<u32>,
+    pub persona: &'a str,
+    pub script: &'a str,
+    pub story_ids: &'a [u32],
+    pub topics: &'a [String],
+    pub chat_id: Option<&'a str>,
+    pub job_id: Option<&'a str>,
+    pub status: EpisodeStatus,
+    pub show_notes: Option<&'a str>,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub follow_up_of: Option<&'a str>,
+}
+
+static POOL: OnceLock<SqlitePool> = OnceLock::new();
+
+pub fn database_url() -> Option<String> {
+    match env::var("DATABASE_URL") {
+        Ok(url) if url.is_empty() => None,
+        Ok(url) => Some(url),
+        Err(_) => Some("sqlite://episodes.db".to_string()),
+    }
+}
+
+pub fn enabled() -> bool {
+    POOL.get().is_some()
+}
+
+// Opens the database and creates the schema; called once at startup
+pub async fn init() -> Result<(), String> {
+    let Some(url) = database_url() else { return Ok(()) };
+    let options = SqliteConnectOptions::from_str(&url)
+        .map_err(|e| format!("DATABASE_URL is not a valid SQLite URL: {}", e))?
+        .create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(4)
+        .connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", url, e))?;
+    migrate(&pool).await?;
+    let _ = POOL.set(pool);
+    Ok(())
+}
+
+// All on one connection: another pooled connection that loaded the schema halfway through would
+// keep preparing statements against the old columns. Each step takes the write lock before
+// reading the version, so replicas starting together apply every migration once.
+async fn migrate(pool: &SqlitePool) -> Result<(), String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    loop {
+        sqlx::raw_sql("BEGIN IMMEDIATE").execute(&mut *conn).await.map_err(|e| e.to_string())?;
+        let step = migrate_step(&mut conn).await;
+        let end = if matches!(step, Ok(true)) { "COMMIT" } else { "ROLLBACK" };
+        sqlx::raw_sql(end).execute(&mut *conn).await.map_err(|e| e.to_string())?;
+        if !step? {
+            return Ok(());
+        }
+    }
+}
+
+// Applies the next migration; false when there is none left
+async fn migrate_step(conn: &mut sqlx::SqliteConnection) -> Result<bool, String> {
+    let applied: i64 = sqlx::query_scalar("PRAGMA user_version").fetch_one(&mut *conn).await.map_err(|e| e.to_string())?;
+    let version = applied.max(0) as usize;
+    let Some(migration) = MIGRATIONS.get(version) else { return Ok(false) };
+    sqlx::raw_sql(migration).execute(&mut *conn).await.map_err(|e| format!("Archive migration {} failed: {}", version + 1, e))?;
+    sqlx::raw_sql(&format!("PRAGMA user_version = {}", version + 1)).execute(&mut *conn).await.map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+fn pool() -> Result<&'static SqlitePool, String> {
+    POOL.get().ok_or_else(|| "The episode archive is disabled (DATABASE_URL is empty)".to_string())
+}
+
+fn episode(row: &SqliteRow, with_script: bool) -> Result<Episode, sqlx::Error> {
+    let story_ids: String = row.try_get("story_ids")?;
+    let topics: String = row.try_get("topics")?;
+    Ok(Episode {
+        id: row.try_get("id")?,
+        title: row.try_get("title")?,
+        slug: row.try_get("slug")?,
+        episode_number: row.try_get::<Option<i64>, _>("episode_number")?.unwrap_or_default() as u32,
+        season: row.try_get::<Option<i64>, _>("season")?.map(|s| s as u32),
+        persona: row.try_get("persona")?,
+        status: match row.try_get::<String, _>("status")?.as_str() {
+            "degraded" => EpisodeStatus::Degraded,
+            _ => EpisodeStatus::Complete,
+        },
+        show_notes: row.try_get("show_notes")?,
+        script: if with_script { Some(row.try_get("script")?) } else { None },
+        story_ids: serde_json::from_str(&story_ids).unwrap_or_default(),
+        topics: serde_json::from_str(&topics).unwrap_or_default(),
+        chat_id: row.try_get("chat_id")?,
+        job_id: row.try_get("job_id")?,
+        audio: match row.try_get::<Option<String>, _>("audio_file")? {
+            Some(file) => Some(EpisodeAudio {
+                url: crate::episode_audio::url(&file),
+                file,
+                bytes: row.try_get::<Option<i64>, _>("audio_bytes")?.unwrap_or_default() as u64,
+                duration_secs: row.try_get::<Option<f64>, _>("audio_duration_secs")?.unwrap_or_default(),
+                rendered_at: row.try_get::<Option<DateTime<Utc>>, _>("audio_rendered_at")?.unwrap_or_default(),
+                qa: row.try_get::<Option<String>, _>("audio_qa")?.and_then(|qa| serde_json::from_str(&qa).ok()),
+                chapters: row
+                    .try_get::<Option<String>, _>("audio_chapters")?
+                    .and_then(|chapters| serde_json::from_str(&chapters).ok())
+                    .unwrap_or_default(),
+                renditions: row
+                    .try_get::<Option<String>, _>("audio_renditions")?
+                    .and_then(|renditions| serde_json::from_str(&renditions).ok())
+                    .unwrap_or_default(),
+                hls: row.try_get::<Option<String>, _>("audio_hls")?.and_then(|hls| serde_json::from_str(&hls).ok()),
+            }),
+            None => None,
+        },
+        publish_at: row.try_get::<Option<i64>, _>("publish_at")?.and_then(|secs| DateTime::from_timestamp(secs, 0)),
+        follow_up_of: row.try_get("follow_up_of")?,
+        follow_ups: Vec::new(),
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+pub async fn save(new: NewEpisode<'_>) -> Result<Episode, String> {
+    let mut tx = pool()?.begin().await.map_err(|e| e.to_string())?;
+    let episode_number: i64 = sqlx::query_scalar("UPDATE episode_sequence SET last_number = last_number + 1 RETURNING last_number")
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    let episode = Episode {
+        id: uuid::Uuid::new_v4().to_string(),
+        title: new.title.to_string(),
+        slug: new.slug.to_string(),
+        episode_number: episode_number as u32,
+        season: new.season,
+        persona: new.persona.to_string(),
+        status: new.status,
+        show_notes: new.show_notes.map(String::from),
+        script: Some(new.script.to_string()),
+        story_ids: new.story_ids.to_vec(),
+        topics: new.topics.to_vec(),
+        chat_id: new.chat_id.map(String::from),
+        job_id: new.job_id.map(String::from),
+        audio: None,
+        publish_at: new.publish_at,
+        follow_up_of: new.follow_up_of.map(String::from),
+        follow_ups: Vec::new(),
+        created_at: Utc::now(),
+    };
+    sqlx::query(
+        "INSERT INTO episodes (id, title, slug, episode_number, season, persona, script, story_ids, topics, chat_id, job_id, status, show_notes,
+                               publish_at, release_pending, follow_up_of, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&episode.id)
+    .bind(&episode.title)
+    .bind(&episode.slug)
+    .bind(episode_number)
+    .bind(episode.season.map(i64::from))
+    .bind(&episode.persona)
+    .bind(new.script)
+    .bind(serde_json::to_string(&episode.story_ids).unwrap_or_else(|_| "[]".to_string()))
+    .bind(serde_json::to_string(&episode.topics).unwrap_or_else(|_| "[]".to_string()))
+    .bind(&episode.chat_id)
+    .bind(&episode.job_id)
+    .bind(episode.status.as_str())
+    .bind(&episode.show_notes)
+    .bind(episode.publish_at.map(|at| at.timestamp()))
+    .bind(episode.embargoed())
+    .bind(&episode.follow_up_of)
+    .bind(episode.created_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(episode)
+}
+
+const TOPIC_FILTER: &str = "(?1 IS NULL OR EXISTS (SELECT 1 FROM json_each(episodes.topics) WHERE value = ?1))";
+// Past any embargo
+const RELEASED_FILTER: &str = "(publish_at IS NULL OR publish_at <= CAST(strftime('%s', 'now') AS INTEGER))";
+
+// Newest first, without scripts, optionally only those tagged `topic`; also returns the total count
+pub async fn list(limit: u32, offset: u32, topic: Option<&str>) -> Result<(Vec<Episode>, u64), String> {
+    let pool = pool()?;
+    let rows = sqlx::query(&format!("SELECT * FROM episodes WHERE {} ORDER BY created_at DESC, id LIMIT ?2 OFFSET ?3", TOPIC_FILTER))
+        .bind(topic)
+        .bind(i64::from(limit.min(MAX_PAGE_SIZE)))
+        .bind(i64::from(offset))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM episodes WHERE {}", TOPIC_FILTER))
+        .bind(topic)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let episodes = rows.iter().map(|row| episode(row, false)).collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+    Ok((episodes, total as u64))
+}
+
+// Newest first, with scripts, only those with rendered audio, optionally only those tagged
+// `topic`; with `qa_passed`, audio that failed QA is left out (audio rendered before QA reports
+// were kept counts as passed), and never under embargo. Also returns how many such episodes there are.
+pub async fn with_audio(limit: u32, offset: u32, qa_passed: bool, topic: Option<&str>) -> Result<(Vec<Episode>, u64), String> {
+    let filter = format!(
+        "audio_file IS NOT NULL AND (NOT ?2 OR audio_qa IS NULL OR json_extract(audio_qa, '$.passed')) AND {} AND {}",
+        TOPIC_FILTER,
+        RELEASED_FILTER
+    );
+    let pool = pool()?;
+    let rows = sqlx::query(&format!("SELECT * FROM episodes WHERE {} ORDER BY created_at DESC, id LIMIT ?3 OFFSET ?4", filter))
+        .bind(topic)
+        .bind(qa_passed)
+        .bind(i64::from(limit.min(MAX_PAGE_SIZE)))
+        .bind(i64::from(offset))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM episodes WHERE {}", filter))
+        .bind(topic)
+        .bind(qa_passed)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let episodes = rows.iter().map(|row| episode(row, true)).collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+    Ok((episodes, total as u64))
+}
+
+pub async fn get(id: &str) -> Result<Option<Episode>, String> {
+    let row = sqlx::query("SELECT * FROM episodes WHERE id = ?").bind(id).fetch_optional(pool()?).await.map_err(|e| e.to_string())?;
+    row.map(|row| episode(&row, true)).transpose().map_err(|e| e.to_string())
+}
+
+// None when there is no such episode. Moving the embargo into the future holds the release back
+// again; clearing it or moving it into the past releases a pending episode on the next check.
+pub async fn set_publish_at(id: &str, publish_at: Option<DateTime<Utc>>) -> Result<Option<Episode>, String> {
+    sqlx::query("UPDATE episodes SET publish_at = ?, release_pending = release_pending OR ? WHERE id = ?")
+        .bind(publish_at.map(|at| at.timestamp()))
+        .bind(publish_at.is_some_and(|at| at > Utc::now()))
+        .bind(id)
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    get(id).await
+}
+
+// Ids of the episode's follow-ups, oldest first
+pub async fn follow_ups(id: &str) -> Result<Vec<String>, String> {
+    sqlx::query_scalar("SELECT id FROM episodes WHERE follow_up_of = ? ORDER BY created_at, id")
+        .bind(id)
+        .fetch_all(pool()?)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Embargoed episodes whose time has come, with scripts
+pub async fn due_releases() -> Result<Vec<Episode>, String> {
+    let rows = sqlx::query(&format!("SELECT * FROM episodes WHERE release_pending AND {} ORDER BY publish_at", RELEASED_FILTER))
+        .fetch_all(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    rows.iter().map(|row| episode(row, true)).collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// False when another check already released it
+pub async fn mark_released(id: &str) -> Result<bool, String> {
+    let result = sqlx::query("UPDATE episodes SET release_pending = 0 WHERE id = ? AND release_pending")
+        .bind(id)
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Marks the episode degraded and appends `note` to its show notes; false when there was no such
+// episode
+pub async fn mark_degraded(id: &str, note: &str) -> Result<bool, String> {
+    let result = sqlx::query(
+        "UPDATE episodes SET status = 'degraded',
+             show_notes = CASE WHEN show_notes IS NULL OR show_notes = '' THEN ?1 ELSE show_notes || char(10) || char(10) || ?1 END
+         WHERE id = ?2",
+    )
+    .bind(note)
+    .bind(id)
+    .execute(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// False when there was no such episode
+pub async fn set_audio(id: &str, audio: &EpisodeAudio, key_id: Option<&str>) -> Result<bool, String> {
+    let result = sqlx::query(
+        "UPDATE episodes
+         SET audio_file = ?, audio_bytes = ?, audio_duration_secs = ?, audio_rendered_at = ?, audio_qa = ?, audio_chapters = ?,
+             audio_renditions = ?, audio_hls = ?, transcript_timings = NULL, audio_key_id = ?
+         WHERE id = ?",
+    )
+    .bind(&audio.file)
+    .bind(audio.bytes as i64)
+    .bind(audio.duration_secs)
+    .bind(audio.rendered_at)
+    .bind(audio.qa.as_ref().and_then(|qa| serde_json::to_string(qa).ok()))
+    .bind(serde_json::to_string(&audio.chapters).ok())
+    .bind(serde_json::to_string(&audio.renditions).ok())
+    .bind(audio.hls.as_ref().and_then(|hls| serde_json::to_string(hls).ok()))
+    .bind(key_id)
+    .bind(id)
+    .execute(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Timestamps of the audio rendered at `rendered_at`; false when the episode is gone or its audio has
+// been rendered again since
+pub async fn set_transcript_timings(id: &str, rendered_at: DateTime<Utc>, timings: &crate::transcribe::Transcript) -> Result<bool, String> {
+    let result = sqlx::query("UPDATE episodes SET transcript_timings = ? WHERE id = ? AND audio_rendered_at = ?")
+        .bind(serde_json::to_string(timings).map_err(|e| e.to_string())?)
+        .bind(id)
+        .bind(rendered_at)
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// None when the episode doesn't exist, Some(None) when its audio hasn't been aligned
+pub async fn transcript_timings(id: &str) -> Result<Option<Option<crate::transcribe::Transcript>>, String> {
+    let row: Option<Option<String>> = sqlx::query_scalar("SELECT transcript_timings FROM episodes WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.map(|timings| timings.and_then(|timings| serde_json::from_str(&timings).ok())))
+}
+
+// False when there was no such episode
+pub async fn delete(id: &str) -> Result<bool, String> {
+    let pool = pool()?;
+    let result = sqlx::query("DELETE FROM episodes WHERE id = ?").bind(id).execute(pool).await.map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM episode_versions WHERE episode_id = ?").bind(id).execute(pool).await.map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM user_bookmarks WHERE episode_id = ?").bind(id).execute(pool).await.map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM episode_feedback WHERE episode_id = ?").bind(id).execute(pool).await.map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM playback_events WHERE episode_id = ?").bind(id).execute(pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// --- Script versions ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptVersion {
+    pub version: u32,
+    pub script: String,
+    // The refinement request that produced it; None for the generated script
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Oldest first; None when there was no such episode. An episode that was never refined has its
+// script as version 1
+pub async fn versions(id: &str) -> Result<Option<Vec<ScriptVersion>>, String> {
+    let Some(episode) = get(id).await? else { return Ok(None) };
+    let rows = sqlx::query("SELECT * FROM episode_versions WHERE episode_id = ? ORDER BY version")
+        .bind(id)
+        .fetch_all(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    let versions = rows
+        .iter()
+        .map(|row| {
+            Ok(ScriptVersion {
+                version: row.try_get::<i64, _>("version")? as u32,
+                script: row.try_get("script")?,
+                instruction: row.try_get("instruction")?,
+                created_at: row.try_get("created_at")?,
+            })
+        })
+        .collect::<Result<Vec<_>, sqlx::Error>>()
+        .map_err(|e| e.to_string())?;
+    if versions.is_empty() {
+        let original = ScriptVersion { version: 1, script: episode.script.unwrap_or_default(), instruction: None, created_at: episode.created_at };
+        return Ok(Some(vec![original]));
+    }
+    Ok(Some(versions))
+}
+
+// Saves `script` as the next version and makes it the episode's script, recording the generated
+// script as version 1 on the first refinement; None when there was no such episode
+pub async fn add_version(id: &str, script: &str, instruction: &str) -> Result<Option<ScriptVersion>, String> {
+    let mut tx = pool()?.begin().await.map_err(|e| e.to_string())?;
+    let Some(row) = sqlx::query("SELECT script, created_at FROM episodes WHERE id = ?").bind(id).fetch_optional(&mut *tx).await.map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+    let latest: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM episode_versions WHERE episode_id = ?")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    if latest.is_none() {
+        sqlx::query("INSERT INTO episode_versions (episode_id, version, script, instruction, created_at) VALUES (?, 1, ?, NULL, ?)")
+            .bind(id)
+            .bind(row.try_get::<String, _>("script").map_err(|e| e.to_string())?)
+            .bind(row.try_get::<DateTime<Utc>, _>("created_at").map_err(|e| e.to_string())?)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let version = ScriptVersion {
+        version: latest.unwrap_or(1) as u32 + 1,
+        script: script.to_string(),
+        instruction: Some(instruction.to_string()),
+        created_at: Utc::now(),
+    };
+    sqlx::query("INSERT INTO episode_versions (episode_id, version, script, instruction, created_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(id)
+        .bind(version.version as i64)
+        .bind(&version.script)
+        .bind(&version.instruction)
+        .bind(version.created_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    sqlx::query("UPDATE episodes SET script = ? WHERE id = ?").bind(script).bind(id).execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(Some(version))
+}
+
+// --- User libraries ---
+
+#[derive(Debug, Clone, Copy)]
+pub enum LibraryItem {
+    Bookmark,
+    Playlist,
+    Subscription,
+}
+
+impl LibraryItem {
+    // Every table holding data tied to a user, for export and deletion
+    pub const ALL: [LibraryItem; 3] = [LibraryItem::Bookmark, LibraryItem::Playlist, LibraryItem::Subscription];
+
+    fn table(self) -> &'static str {
+        match self {
+            LibraryItem::Bookmark => "user_bookmarks",
+            LibraryItem::Playlist => "user_playlists",
+            LibraryItem::Subscription => "user_subscriptions",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            LibraryItem::Bookmark => "episode_id",
+            LibraryItem::Playlist => "name",
+            LibraryItem::Subscription => "topic",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Bookmark {
+    pub episode_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Playlist {
+    pub name: String,
+    pub episode_ids: Vec<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Subscription {
+    pub topic: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Library {
+    pub bookmarks: Vec<Bookmark>,
+    pub playlists: Vec<Playlist>,
+    pub subscriptions: Vec<Subscription>,
+}
+
+// False when the episode was already bookmarked
+pub async fn add_bookmark(user_id: &str, episode_id: &str) -> Result<bool, String> {
+    let result = sqlx::query("INSERT OR IGNORE INTO user_bookmarks (user_id, episode_id, created_at) VALUES (?, ?, ?)")
+        .bind(user_id)
+        .bind(episode_id)
+        .bind(Utc::now())
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Creates or replaces the playlist
+pub async fn save_playlist(user_id: &str, name: &str, episode_ids: &[String]) -> Result<(), String> {
+    sqlx::query("INSERT OR REPLACE INTO user_playlists (user_id, name, episode_ids, updated_at) VALUES (?, ?, ?, ?)")
+        .bind(user_id)
+        .bind(name)
+        .bind(serde_json::to_string(episode_ids).map_err(|e| e.to_string())?)
+        .bind(Utc::now())
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// False when the user was already subscribed
+pub async fn add_subscription(user_id: &str, topic: &str) -> Result<bool, String> {
+    let result = sqlx::query("INSERT OR IGNORE INTO user_subscriptions (user_id, topic, created_at) VALUES (?, ?, ?)")
+        .bind(user_id)
+        .bind(topic)
+        .bind(Utc::now())
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// False when there was no such item
+pub async fn remove_library_item(user_id: &str, item: LibraryItem, key: &str) -> Result<bool, String> {
+    let result = sqlx::query(&format!("DELETE FROM {} WHERE user_id = ? AND {} = ?", item.table(), item.key()))
+        .bind(user_id)
+        .bind(key)
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn library(user_id: &str) -> Result<Library, String> {
+    let pool = pool()?;
+    let bookmarks = sqlx::query("SELECT episode_id, created_at FROM user_bookmarks WHERE user_id = ? ORDER BY created_at")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|row| Ok(Bookmark { episode_id: row.try_get("episode_id")?, created_at: row.try_get("created_at")? }))
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| e.to_string())?;
+    let playlists = sqlx::query("SELECT name, episode_ids, updated_at FROM user_playlists WHERE user_id = ? ORDER BY name")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|row| {
+            let episode_ids: String = row.try_get("episode_ids")?;
+            Ok(Playlist {
+                name: row.try_get("name")?,
+                episode_ids: serde_json::from_str(&episode_ids).unwrap_or_default(),
+                updated_at: row.try_get("updated_at")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| e.to_string())?;
+    let subscriptions = sqlx::query("SELECT topic, created_at FROM user_subscriptions WHERE user_id = ? ORDER BY topic")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|row| Ok(Subscription { topic: row.try_get("topic")?, created_at: row.try_get("created_at")? }))
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| e.to_string())?;
+    Ok(Library { bookmarks, playlists, subscriptions })
+}
+
+// Erases everything tied to the user in one transaction; rows removed per table
+pub async fn delete_user_data(user_id: &str) -> Result<Vec<(&'static str, u64)>, String> {
+    let mut tx = pool()?.begin().await.map_err(|e| e.to_string())?;
+    let mut removed = Vec::new();
+    for item in LibraryItem::ALL {
+        let result = sqlx::query(&format!("DELETE FROM {} WHERE user_id = ?", item.table()))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        removed.push((item.table(), result.rows_affected()));
+    }
+    let result = sqlx::query("DELETE FROM episode_feedback WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    removed.push(("episode_feedback", result.rows_affected()));
+    let result = sqlx::query("DELETE FROM playback_events WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    removed.push(("playback_events", result.rows_affected()));
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(removed)
+}
+
+// --- Listener feedback ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Feedback {
+    pub id: i64,
+    pub episode_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    // 1 to 5
+    pub rating: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FeedbackSummary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode_id: Option<String>,
+    pub count: u64,
+    // None without ratings
+    pub average: Option<f64>,
+    // Ratings given per star, 1 to 5
+    pub distribution: [u64; 5],
+    pub comments: u64,
+}
+
+impl FeedbackSummary {
+    // `n` ratings of `rating` stars, with or without a comment
+    fn add(&mut self, rating: u8, commented: bool, n: u64) {
+        self.distribution[usize::from(rating.clamp(1, 5)) - 1] += n;
+        self.count += n;
+        if commented {
+            self.comments += n;
+        }
+        let total: u64 = self.distribution.iter().zip(1u64..).map(|(n, stars)| n * stars).sum();
+        self.average = Some((total as f64 / self.count as f64 * 100.0).round() / 100.0);
+    }
+}
+
+fn feedback_row(row: &SqliteRow) -> Result<Feedback, sqlx::Error> {
+    Ok(Feedback {
+        id: row.try_get("id")?,
+        episode_id: row.try_get("episode_id")?,
+        user_id: row.try_get("user_id")?,
+        rating: row.try_get("rating")?,
+        comment: row.try_get("comment")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+// Replaces the listener's earlier rating of the episode, if any; anonymous ratings always add
+pub async fn add_feedback(episode_id: &str, user_id: Option<&str>, rating: u8, comment: Option<&str>) -> Result<i64, String> {
+    let mut tx = pool()?.begin().await.map_err(|e| e.to_string())?;
+    if let Some(user_id) = user_id {
+        sqlx::query("DELETE FROM episode_feedback WHERE user_id = ? AND episode_id = ?")
+            .bind(user_id)
+            .bind(episode_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let id = sqlx::query("INSERT INTO episode_feedback (episode_id, user_id, rating, comment, created_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(episode_id)
+        .bind(user_id)
+        .bind(rating)
+        .bind(comment)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?
+        .last_insert_rowid();
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+// Newest first. `before_id` pages back from an entry; the other filters are optional
+pub async fn feedback(
+    limit: u32,
+    before_id: Option<i64>,
+    episode_id: Option<&str>,
+    user_id: Option<&str>,
+    commented_since: Option<DateTime<Utc>>,
+) -> Result<Vec<Feedback>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM episode_feedback WHERE (?1 IS NULL OR id < ?1) AND (?2 IS NULL OR episode_id = ?2) \
+         AND (?3 IS NULL OR user_id = ?3) AND (?4 IS NULL OR (comment IS NOT NULL AND created_at >= ?4)) ORDER BY id DESC LIMIT ?5",
+    )
+    .bind(before_id)
+    .bind(episode_id)
+    .bind(user_id)
+    .bind(commented_since)
+    .bind(limit)
+    .fetch_all(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    rows.iter().map(feedback_row).collect::<Result<_, sqlx::Error>>().map_err(|e| e.to_string())
+}
+
+// Ratings per episode (given since `since`, if set), most rated first, plus the total over all of them
+pub async fn feedback_summaries(since: Option<DateTime<Utc>>, episode_id: Option<&str>) -> Result<(FeedbackSummary, Vec<FeedbackSummary>), String> {
+    let rows = sqlx::query(
+        "SELECT episode_id, rating, comment IS NOT NULL AS commented, COUNT(*) AS n FROM episode_feedback \
+         WHERE (?1 IS NULL OR created_at >= ?1) AND (?2 IS NULL OR episode_id = ?2) GROUP BY episode_id, rating, commented",
+    )
+    .bind(since)
+    .bind(episode_id)
+    .fetch_all(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    let mut total = FeedbackSummary::default();
+    let mut episodes: HashMap<String, FeedbackSummary> = HashMap::new();
+    for row in &rows {
+        let episode_id: String = row.try_get("episode_id").map_err(|e| e.to_string())?;
+        let rating: u8 = row.try_get("rating").map_err(|e| e.to_string())?;
+        let commented: bool = row.try_get("commented").map_err(|e| e.to_string())?;
+        let n: i64 = row.try_get("n").map_err(|e| e.to_string())?;
+        let summary = episodes
+            .entry(episode_id.clone())
+            .or_insert_with(|| FeedbackSummary { episode_id: Some(episode_id), ..Default::default() });
+        summary.add(rating, commented, n as u64);
+        total.add(rating, commented, n as u64);
+    }
+    let mut episodes: Vec<FeedbackSummary> = episodes.into_values().collect();
+    episodes.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.episode_id.cmp(&b.episode_id)));
+    Ok((total, episodes))
+}
+
+// --- Playback analytics ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackEvent {
+    pub id: i64,
+    pub episode_id: String,
+    // Index into the episode's chapters
+    pub chapter: u32,
+    pub chapter_title: String,
+    // skip or complete
+    pub event: String,
+    pub topics: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Skips and completions of one topic's segments, and the ratings of episodes on it
+#[derive(Debug, Clone, Default)]
+pub struct TopicActivity {
+    pub completes: u64,
+    pub skips: u64,
+    pub ratings: FeedbackSummary,
+}
+
+// Replaces the listener's earlier event for the segment, if any; anonymous events always add
+pub async fn add_playback_event(
+    episode_id: &str,
+    chapter: u32,
+    chapter_title: &str,
+    event: &str,
+    topics: &[String],
+    user_id: Option<&str>,
+) -> Result<i64, String> {
+    let mut tx = pool()?.begin().await.map_err(|e| e.to_string())?;
+    if let Some(user_id) = user_id {
+        sqlx::query("DELETE FROM playback_events WHERE user_id = ? AND episode_id = ? AND chapter = ?")
+            .bind(user_id)
+            .bind(episode_id)
+            .bind(chapter)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let id = sqlx::query(
+        "INSERT INTO playback_events (episode_id, chapter, chapter_title, event, topics, user_id, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(episode_id)
+    .bind(chapter)
+    .bind(chapter_title)
+    .bind(event)
+    .bind(serde_json::to_string(topics).unwrap_or_else(|_| "[]".to_string()))
+    .bind(user_id)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .last_insert_rowid();
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+// A listener's events, newest first
+pub async fn playback_events(user_id: &str) -> Result<Vec<PlaybackEvent>, String> {
+    let rows = sqlx::query("SELECT * FROM playback_events WHERE user_id = ? ORDER BY id DESC")
+        .bind(user_id)
+        .fetch_all(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    rows.iter()
+        .map(|row| {
+            let topics: String = row.try_get("topics")?;
+            Ok(PlaybackEvent {
+                id: row.try_get("id")?,
+                episode_id: row.try_get("episode_id")?,
+                chapter: row.try_get("chapter")?,
+                chapter_title: row.try_get("chapter_title")?,
+                event: row.try_get("event")?,
+                topics: serde_json::from_str(&topics).unwrap_or_default(),
+                user_id: row.try_get("user_id")?,
+                created_at: row.try_get("created_at")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| e.to_string())
+}
+
+// Per topic: segment events, and ratings of the episodes filed under it, both since `since`
+pub async fn topic_activity(since: Option<DateTime<Utc>>) -> Result<HashMap<String, TopicActivity>, String> {
+    let pool = pool()?;
+    let mut topics: HashMap<String, TopicActivity> = HashMap::new();
+    let rows = sqlx::query(
+        "SELECT json_each.value AS topic, event, COUNT(*) AS n FROM playback_events, json_each(playback_events.topics) \
+         WHERE ?1 IS NULL OR created_at >= ?1 GROUP BY topic, event",
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    for row in &rows {
+        let topic: String = row.try_get("topic").map_err(|e| e.to_string())?;
+        let event: String = row.try_get("event").map_err(|e| e.to_string())?;
+        let n: i64 = row.try_get("n").map_err(|e| e.to_string())?;
+        let activity = topics.entry(topic).or_default();
+        match event.as_str() {
+            "complete" => activity.completes += n as u64,
+            _ => activity.skips += n as u64,
+        }
+    }
+    let rows = sqlx::query(
+        "SELECT json_each.value AS topic, episode_feedback.rating AS rating, COUNT(*) AS n FROM episode_feedback \
+         JOIN episodes ON episodes.id = episode_feedback.episode_id JOIN json_each(episodes.topics) \
+         WHERE ?1 IS NULL OR episode_feedback.created_at >= ?1 GROUP BY topic, rating",
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    for row in &rows {
+        let topic: String = row.try_get("topic").map_err(|e| e.to_string())?;
+        let rating: u8 = row.try_get("rating").map_err(|e| e.to_string())?;
+        let n: i64 = row.try_get("n").map_err(|e| e.to_string())?;
+        topics.entry(topic).or_default().ratings.add(rating, false, n as u64);
+    }
+    Ok(topics)
+}
+
+// --- ActivityPub ---
+
+// Following again just refreshes the inboxes
+pub async fn add_follower(actor: &str, inbox: &str, shared_inbox: Option<&str>) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO activitypub_followers (actor, inbox, shared_inbox, followed_at) VALUES (?, ?, ?, ?) \
+         ON CONFLICT (actor) DO UPDATE SET inbox = excluded.inbox, shared_inbox = excluded.shared_inbox",
+    )
+    .bind(actor)
+    .bind(inbox)
+    .bind(shared_inbox)
+    .bind(Utc::now())
+    .execute(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// False when the actor wasn't following
+pub async fn remove_follower(actor: &str) -> Result<bool, String> {
+    let result = sqlx::query("DELETE FROM activitypub_followers WHERE actor = ?")
+        .bind(actor)
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn follower_count() -> Result<i64, String> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM activitypub_followers").fetch_one(pool()?).await.map_err(|e| e.to_string())
+}
+
+// One delivery per server where followers share an inbox
+pub async fn follower_inboxes() -> Result<Vec<String>, String> {
+    sqlx::query_scalar("SELECT DISTINCT COALESCE(shared_inbox, inbox) FROM activitypub_followers ORDER BY 1")
+        .fetch_all(pool()?)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// True the first time only, so a re-rendered episode isn't announced twice
+pub async fn claim_note(episode_id: &str) -> Result<bool, String> {
+    let result = sqlx::query("INSERT OR IGNORE INTO activitypub_notes (episode_id, published_at) VALUES (?, ?)")
+        .bind(episode_id)
+        .bind(Utc::now())
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// --- Audit log ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub at: DateTime<Utc>,
+    pub actor: String,
+    // e.g. "episode.delete"
+    pub action: String,
+    pub target: String,
+    // Snapshots of the target around the action, where it has state
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<serde_json::Value>,
+}
+
+pub async fn record_audit(
+    actor: &str,
+    action: &str,
+    target: &str,
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+) -> Result<(), String> {
+    sqlx::query("INSERT INTO audit_log (at, actor, action, target, before, after) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(Utc::now())
+        .bind(actor)
+        .bind(action)
+        .bind(target)
+        .bind(before.map(|v| v.to_string()))
+        .bind(after.map(|v| v.to_string()))
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Newest first; `before_id` pages back from an entry, `action` keeps one kind of action
+pub async fn audit_log(limit: u32, before_id: Option<i64>, action: Option<&str>) -> Result<Vec<AuditEntry>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM audit_log WHERE (?1 IS NULL OR id < ?1) AND (?2 IS NULL OR action = ?2) ORDER BY id DESC LIMIT ?3",
+    )
+    .bind(before_id)
+    .bind(action)
+    .bind(limit)
+    .fetch_all(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    let snapshot = |value: Option<String>| value.and_then(|v| serde_json::from_str(&v).ok());
+    rows.iter()
+        .map(|row| {
+            Ok(AuditEntry {
+                id: row.try_get("id")?,
+                at: row.try_get("at")?,
+                actor: row.try_get("actor")?,
+                action: row.try_get("action")?,
+                target: row.try_get("target")?,
+                before: snapshot(row.try_get("before")?),
+                after: snapshot(row.try_get("after")?),
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| e.to_string())
+}
+
+// --- API keys ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    // Monthly allowances; None is unlimited
+    pub generation_quota: Option<u64>,
+    pub tts_quota: Option<u64>,
+    // Gets the mock pipeline and isn't metered; see sandbox.rs
+    pub sandbox: bool,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+fn api_key_row(row: &SqliteRow) -> Result<ApiKey, sqlx::Error> {
+    Ok(ApiKey {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        generation_quota: row.try_get::<Option<i64>, _>("generation_quota")?.map(|q| q.max(0) as u64),
+        tts_quota: row.try_get::<Option<i64>, _>("tts_quota")?.map(|q| q.max(0) as u64),
+        sandbox: row.try_get("sandbox")?,
+        created_at: row.try_get("created_at")?,
+        revoked_at: row.try_get("revoked_at")?,
+    })
+}
+
+pub async fn create_api_key(
+    name: &str,
+    secret_hash: &str,
+    generation_quota: Option<u64>,
+    tts_quota: Option<u64>,
+    sandbox: bool,
+) -> Result<ApiKey, String> {
+    let key = ApiKey {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        generation_quota,
+        tts_quota,
+        sandbox,
+        created_at: Utc::now(),
+        revoked_at: None,
+    };
+    sqlx::query("INSERT INTO api_keys (id, name, secret_hash, generation_quota, tts_quota, sandbox, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)")
+        .bind(&key.id)
+        .bind(&key.name)
+        .bind(secret_hash)
+        .bind(generation_quota.map(|q| q as i64))
+        .bind(tts_quota.map(|q| q as i64))
+        .bind(sandbox)
+        .bind(key.created_at)
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+pub async fn api_keys() -> Result<Vec<ApiKey>, String> {
+    let rows = sqlx::query("SELECT * FROM api_keys ORDER BY created_at").fetch_all(pool()?).await.map_err(|e| e.to_string())?;
+    rows.iter().map(api_key_row).collect::<Result<_, sqlx::Error>>().map_err(|e| e.to_string())
+}
+
+pub async fn api_key(id: &str) -> Result<Option<ApiKey>, String> {
+    let row = sqlx::query("SELECT * FROM api_keys WHERE id = ?").bind(id).fetch_optional(pool()?).await.map_err(|e| e.to_string())?;
+    row.as_ref().map(api_key_row).transpose().map_err(|e| e.to_string())
+}
+
+// Revoked keys don't match
+pub async fn api_key_by_secret(secret_hash: &str) -> Result<Option<ApiKey>, String> {
+    let row = sqlx::query("SELECT * FROM api_keys WHERE secret_hash = ? AND revoked_at IS NULL")
+        .bind(secret_hash)
+        .fetch_optional(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    row.as_ref().map(api_key_row).transpose().map_err(|e| e.to_string())
+}
+
+// None when there is no such key; revoking twice keeps the first time
+pub async fn revoke_api_key(id: &str) -> Result<Option<ApiKey>, String> {
+    sqlx::query("UPDATE api_keys SET revoked_at = COALESCE(revoked_at, ?) WHERE id = ?")
+        .bind(Utc::now())
+        .bind(id)
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    api_key(id).await
+}
+
+// Adds to the month's total in one statement, so concurrent requests can't both slip under the
+// quota; false (and nothing added) when it would go over
+pub async fn add_usage(key_id: &str, month: &str, metric: &str, amount: u64, quota: Option<u64>) -> Result<bool, String> {
+    let result = sqlx::query(
+        "INSERT INTO api_usage (key_id, month, metric, amount) SELECT ?1, ?2, ?3, ?4 WHERE ?5 IS NULL OR ?4 <= ?5
+         ON CONFLICT (key_id, month, metric) DO UPDATE SET amount = amount + excluded.amount
+         WHERE ?5 IS NULL OR amount + excluded.amount <= ?5",
+    )
+    .bind(key_id)
+    .bind(month)
+    .bind(metric)
+    .bind(amount as i64)
+    .bind(quota.map(|q| q as i64))
+    .execute(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Totals per metric for one key and month ("2024-05")
+pub async fn usage(key_id: &str, month: &str) -> Result<Vec<(String, u64)>, String> {
+    let rows = sqlx::query("SELECT metric, amount FROM api_usage WHERE key_id = ? AND month = ?")
+        .bind(key_id)
+        .bind(month)
+        .fetch_all(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    rows.iter()
+        .map(|row| Ok((row.try_get("metric")?, row.try_get::<i64, _>("amount")?.max(0) as u64)))
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| e.to_string())
+}
+
+// Every key's totals for a month: (key id, metric, amount)
+pub async fn month_usage(month: &str) -> Result<Vec<(String, String, u64)>, String> {
+    let rows = sqlx::query("SELECT key_id, metric, amount FROM api_usage WHERE month = ? ORDER BY key_id")
+        .bind(month)
+        .fetch_all(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    rows.iter()
+        .map(|row| Ok((row.try_get("key_id")?, row.try_get("metric")?, row.try_get::<i64, _>("amount")?.max(0) as u64)))
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| e.to_string())
+}
+
+// The key that rendered the audio behind a path under EPISODE_AUDIO_DIR: a main file, a rendition
+// or an HLS segment or playlist ("hls/<dir>/...")
+pub async fn audio_key(path: &str) -> Result<Option<String>, String> {
+    let hls_dir = path.strip_prefix("hls/").and_then(|rest| rest.split('/').next());
+    let key: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT audio_key_id FROM episodes
+         WHERE audio_key_id IS NOT NULL AND (
+             audio_file = ?1
+             OR json_extract(audio_hls, '$.dir') = ?2
+             OR EXISTS (SELECT 1 FROM json_each(audio_renditions) WHERE json_extract(value, '$.file') = ?1)
+         )
+         LIMIT 1",
+    )
+    .bind(path)
+    .bind(hls_dir)
+    .fetch_optional(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(key.flatten())
+}
+
+// --- Front-page snapshots ---
+
+pub async fn save_frontpage(snapshot: &crate::frontpage::Snapshot) -> Result<(), String> {
+    sqlx::query("INSERT OR REPLACE INTO frontpage_snapshots (taken_at, stories) VALUES (?, ?)")
+        .bind(snapshot.taken_at.timestamp_millis())
+        .bind(serde_json::to_string(&snapshot.stories).map_err(|e| e.to_string())?)
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Latest snapshot taken at or before `at`
+pub async fn frontpage_at(at: DateTime<Utc>) -> Result<Option<crate::frontpage::Snapshot>, String> {
+    let row = sqlx::query("SELECT taken_at, stories FROM frontpage_snapshots WHERE taken_at <= ? ORDER BY taken_at DESC LIMIT 1")
+        .bind(at.timestamp_millis())
+        .fetch_optional(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(row) = row else { return Ok(None) };
+    let taken_at: i64 = row.try_get("taken_at").map_err(|e| e.to_string())?;
+    let stories: String = row.try_get("stories").map_err(|e| e.to_string())?;
+    Ok(Some(crate::frontpage::Snapshot {
+        taken_at: DateTime::from_timestamp_millis(taken_at).unwrap_or_default(),
+        stories: serde_json::from_str(&stories).map_err(|e| e.to_string())?,
+    }))
+}
+
+// --- Schedules ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Schedule {
+    pub name: String,
+    pub cron: String,
+    // IANA name, e.g. Europe/Berlin
+    pub timezone: String,
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub settings: crate::schedules::Settings,
+    // None when the schedule comes from the environment
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+fn schedule_row(row: &SqliteRow) -> Result<Schedule, sqlx::Error> {
+    let settings: String = row.try_get("settings")?;
+    Ok(Schedule {
+        name: row.try_get("name")?,
+        cron: row.try_get("cron")?,
+        timezone: row.try_get("timezone")?,
+        enabled: row.try_get("enabled")?,
+        settings: serde_json::from_str(&settings).unwrap_or_default(),
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+pub async fn schedules() -> Result<Vec<Schedule>, String> {
+    let rows = sqlx::query("SELECT * FROM schedules ORDER BY name").fetch_all(pool()?).await.map_err(|e| e.to_string())?;
+    rows.iter().map(schedule_row).collect::<Result<_, sqlx::Error>>().map_err(|e| e.to_string())
+}
+
+pub async fn save_schedule(schedule: &Schedule) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO schedules (name, cron, timezone, enabled, settings, updated_at) VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT (name) DO UPDATE SET cron = excluded.cron, timezone = excluded.timezone, enabled = excluded.enabled,
+             settings = excluded.settings, updated_at = excluded.updated_at",
+    )
+    .bind(&schedule.name)
+    .bind(&schedule.cron)
+    .bind(&schedule.timezone)
+    .bind(schedule.enabled)
+    .bind(serde_json::to_string(&schedule.settings).unwrap_or_else(|_| "{}".to_string()))
+    .bind(schedule.updated_at.unwrap_or_else(Utc::now))
+    .execute(pool()?)
+    .await
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+// False when there was no such schedule
+pub async fn delete_schedule(name: &str) -> Result<bool, String> {
+    let result = sqlx::query("DELETE FROM schedules WHERE name = ?").bind(name).execute(pool()?).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Claims a schedule's run for this process; false when another replica already queued it.
+// Claims older than `keep_days` are dropped along the way.
+pub async fn claim_schedule_run(name: &str, run_at: DateTime<Utc>, keep_days: i64) -> Result<bool, String> {
+    let pool = pool()?;
+    let result = sqlx::query("INSERT OR IGNORE INTO schedule_runs (name, run_at) VALUES (?, ?)")
+        .bind(name)
+        .bind(run_at.timestamp())
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM schedule_runs WHERE run_at < ?")
+        .bind((Utc::now() - chrono::Duration::days(keep_days)).timestamp())
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// --- Artifacts ---
+
+// Points `name` at an object; returns the object it referred to before
+pub async fn set_artifact(name: &str, hash: &str, bytes: u64) -> Result<Option<String>, String> {
+    let pool = pool()?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let previous: Option<String> =
+        sqlx::query_scalar("SELECT hash FROM artifacts WHERE name = ?").bind(name).fetch_optional(&mut *tx).await.map_err(|e| e.to_string())?;
+    sqlx::query("INSERT INTO artifacts (name, hash, bytes) VALUES (?, ?, ?) ON CONFLICT (name) DO UPDATE SET hash = excluded.hash, bytes = excluded.bytes")
+        .bind(name)
+        .bind(hash)
+        .bind(bytes as i64)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(previous)
+}
+
+// The object `name` referred to, if it was tracked
+pub async fn remove_artifact(name: &str) -> Result<Option<String>, String> {
+    sqlx::query_scalar("DELETE FROM artifacts WHERE name = ? RETURNING hash").bind(name).fetch_optional(pool()?).await.map_err(|e| e.to_string())
+}
+
+// Drops every name starting with `prefix`; returns the objects they referred to
+pub async fn remove_artifacts_under(prefix: &str) -> Result<Vec<String>, String> {
+    let mut hashes: Vec<String> = sqlx::query_scalar("DELETE FROM artifacts WHERE substr(name, 1, length(?1)) = ?1 RETURNING hash")
+        .bind(prefix)
+        .fetch_all(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    hashes.sort();
+    hashes.dedup();
+    Ok(hashes)
+}
+
+pub async fn artifact_referenced(hash: &str) -> Result<bool, String> {
+    let found: Option<i64> =
+        sqlx::query_scalar("SELECT 1 FROM artifacts WHERE hash = ? LIMIT 1").bind(hash).fetch_optional(pool()?).await.map_err(|e| e.to_string())?;
+    Ok(found.is_some())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactStats {
+    // Served file names
+    pub files: i64,
+    // Distinct objects behind them
+    pub objects: i64,
+    pub referenced_bytes: i64,
+    pub stored_bytes: i64,
+}
+
+pub async fn artifact_stats() -> Result<ArtifactStats, String> {
+    let row = sqlx::query(
+        "SELECT COUNT(*) AS files, COALESCE(SUM(bytes), 0) AS referenced_bytes,
+             (SELECT COUNT(*) FROM (SELECT DISTINCT hash FROM artifacts)) AS objects,
+             (SELECT COALESCE(SUM(bytes), 0) FROM (SELECT hash, MAX(bytes) AS bytes FROM artifacts GROUP BY hash)) AS stored_bytes
+         FROM artifacts",
+    )
+    .fetch_one(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    let get = |column: &str| row.try_get::<i64, _>(column).map_err(|e| e.to_string());
+    Ok(ArtifactStats { files: get("files")?, objects: get("objects")?, referenced_bytes: get("referenced_bytes")?, stored_bytes: get("stored_bytes")? })
+}
+
+// --- Jobs ---
+
+fn job_row(row: &SqliteRow) -> Result<Job, String> {
+    let json: String = row.try_get("job").map_err(|e| e.to_string())?;
+    let mut job: Job = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    job.runner = row.try_get("runner").map_err(|e| e.to_string())?;
+    job.local = row.try_get("local").map_err(|e| e.to_string())?;
+    Ok(job)
+}
+
+fn job_json(job: &Job) -> Result<String, String> {
+    serde_json::to_string(job).map_err(|e| e.to_string())
+}
+
+// Caller keys are never written: they're skipped when the job is serialized
+pub async fn save_job(job: &Job) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO jobs (id, kind, priority, status, runner, local, job, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (id) DO UPDATE SET status = excluded.status, runner = excluded.runner, job = excluded.job,
+             updated_at = excluded.updated_at",
+    )
+    .bind(&job.id)
+    .bind(&job.kind)
+    .bind(job.priority as i64)
+    .bind(job.status.name())
+    .bind(&job.runner)
+    .bind(job.local)
+    .bind(job_json(job)?)
+    .bind(job.created_at)
+    .bind(job.updated_at)
+    .execute(pool()?)
+    .await
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+pub async fn job(id: &str) -> Result<Option<Job>, String> {
+    let row = sqlx::query("SELECT * FROM jobs WHERE id = ?").bind(id).fetch_optional(pool()?).await.map_err(|e| e.to_string())?;
+    row.as_ref().map(job_row).transpose()
+}
+
+pub async fn jobs_with_status(status: &str) -> Result<Vec<Job>, String> {
+    let rows = sqlx::query("SELECT * FROM jobs WHERE status = ?").bind(status).fetch_all(pool()?).await.map_err(|e| e.to_string())?;
+    rows.iter().map(job_row).collect()
+}
+
+// Atomically makes `runner` the runner of the most urgent, oldest unclaimed job in `lanes`
+pub async fn claim_job(runner: &str, lanes: &[Priority]) -> Result<Option<Job>, String> {
+    let lanes: Vec<i64> = lanes.iter().map(|p| *p as i64).collect();
+    let row = sqlx::query(
+        "UPDATE jobs SET runner = ? WHERE id = (
+             SELECT id FROM jobs WHERE status = 'queued' AND runner IS NULL AND NOT local
+                 AND priority IN (SELECT value FROM json_each(?))
+             ORDER BY priority DESC, created_at LIMIT 1
+         ) AND runner IS NULL
+         RETURNING *",
+    )
+    .bind(runner)
+    .bind(serde_json::to_string(&lanes).unwrap_or_else(|_| "[]".to_string()))
+    .fetch_optional(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    row.as_ref().map(job_row).transpose()
+}
+
+// False when the job was no longer dead-lettered, e.g. another retry got there first
+pub async fn requeue_job(job: &Job) -> Result<bool, String> {
+    let result = sqlx::query(
+        "UPDATE jobs SET status = ?, runner = ?, decision = NULL, job = ?, updated_at = ? WHERE id = ? AND status = 'dead'",
+    )
+    .bind(job.status.name())
+    .bind(&job.runner)
+    .bind(job_json(job)?)
+    .bind(job.updated_at)
+    .bind(&job.id)
+    .execute(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Records an approval for the process holding the job; false when it isn't waiting for one
+pub async fn decide_job(id: &str, approve: bool) -> Result<bool, String> {
+    let result = sqlx::query("UPDATE jobs SET decision = ? WHERE id = ? AND status = 'awaiting_approval' AND decision IS NULL")
+        .bind(if approve { "approved" } else { "rejected" })
+        .bind(id)
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// The decision recorded for a held job, cleared as it's read; Some(true) when approved
+pub async fn take_job_decision(id: &str) -> Result<Option<bool>, String> {
+    let decision: Option<String> =
+        sqlx::query_scalar("UPDATE jobs SET decision = NULL WHERE id = ? AND decision IS NOT NULL RETURNING decision")
+            .bind(id)
+            .fetch_optional(pool()?)
+            .await
+            .map_err(|e| e.to_string())?;
+    Ok(decision.map(|decision| decision == "approved"))
+}
+
+// Drops the oldest finished jobs beyond `keep`, succeeded and skipped ones before dead ones
+pub async fn prune_jobs(keep: usize) -> Result<(), String> {
+    sqlx::query(
+        "DELETE FROM jobs WHERE id IN (
+             SELECT id FROM jobs WHERE status IN ('succeeded', 'skipped', 'dead', 'interrupted')
+             ORDER BY status IN ('dead', 'interrupted') DESC, updated_at DESC LIMIT -1 OFFSET ?
+         )",
+    )
+    .bind(keep as i64)
+    .execute(pool()?)
+    .await
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+// Overwrites a job unless it has moved on from the runner and status it was read with
+pub async fn replace_job(job: &Job, runner: Option<&str>, status: JobStatus) -> Result<bool, String> {
+    let result = sqlx::query("UPDATE jobs SET status = ?, runner = ?, job = ?, updated_at = ? WHERE id = ? AND runner IS ? AND status = ?")
+        .bind(job.status.name())
+        .bind(&job.runner)
+        .bind(job_json(job)?)
+        .bind(job.updated_at)
+        .bind(&job.id)
+        .bind(runner)
+        .bind(status.name())
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Records that a process is alive; processes silent for a day are forgotten
+pub async fn touch_process(id: &str, role: &str) -> Result<(), String> {
+    let pool = pool()?;
+    let now = Utc::now();
+    sqlx::query("INSERT INTO processes (id, role, seen_at) VALUES (?, ?, ?) ON CONFLICT (id) DO UPDATE SET seen_at = excluded.seen_at")
+        .bind(id)
+        .bind(role)
+        .bind(now.timestamp())
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM processes WHERE seen_at < ?")
+        .bind((now - chrono::Duration::days(1)).timestamp())
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// Unfinished jobs whose runner hasn't been seen since `alive_since`
+pub async fn orphaned_jobs(alive_since: DateTime<Utc>) -> Result<Vec<Job>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM jobs WHERE status IN ('queued', 'running', 'awaiting_approval') AND runner IS NOT NULL
+             AND runner NOT IN (SELECT id FROM processes WHERE seen_at >= ?)",
+    )
+    .bind(alive_since.timestamp())
+    .fetch_all(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    rows.iter().map(job_row).collect()
+}
+
+// --- Story topics ---
+
+// None when the story hasn't been classified yet (Some(empty) when nothing fit)
+pub async fn story_topics(story_id: u32) -> Result<Option<Vec<String>>, String> {
+    let pool = pool()?;
+    let topics: Vec<String> = sqlx::query_scalar("SELECT topic FROM story_topics WHERE story_id = ? ORDER BY rowid")
+        .bind(i64::from(story_id))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !topics.is_empty() {
+        return Ok(Some(topics));
+    }
+    let unclassified: Option<i64> = sqlx::query_scalar("SELECT story_id FROM unclassified_stories WHERE story_id = ?")
+        .bind(i64::from(story_id))
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(unclassified.map(|_| Vec::new()))
+}
+
+pub async fn save_story_topics(story_id: u32, topics: &[String], source: &str) -> Result<(), String> {
+    let mut tx = pool()?.begin().await.map_err(|e| e.to_string())?;
+    let now = Utc::now();
+    if topics.is_empty() {
+        sqlx::query("INSERT OR REPLACE INTO unclassified_stories (story_id, classified_at) VALUES (?, ?)")
+            .bind(i64::from(story_id))
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    for topic in topics {
+        sqlx::query("INSERT OR REPLACE INTO story_topics (story_id, topic, source, classified_at) VALUES (?, ?, ?, ?)")
+            .bind(i64::from(story_id))
+            .bind(topic)
+            .bind(source)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicStats {
+    pub topic: String,
+    pub episodes: u64,
+    pub stories: u64,
+}
+
+// Episode and classified-story counts per topic, for every topic that has either
+pub async fn topic_stats() -> Result<Vec<TopicStats>, String> {
+    let rows = sqlx::query(
+        "SELECT topic, SUM(episodes) AS episodes, SUM(stories) AS stories FROM (
+             SELECT json_each.value AS topic, COUNT(*) AS episodes, 0 AS stories
+             FROM episodes, json_each(episodes.topics) GROUP BY json_each.value
+             UNION ALL
+             SELECT topic, 0, COUNT(*) FROM story_topics GROUP BY topic
+         ) GROUP BY topic",
+    )
+    .fetch_all(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    rows.iter()
+        .map(|row| {
+            Ok(TopicStats {
+                topic: row.try_get("topic")?,
+                episodes: row.try_get::<i64, _>("episodes")? as u64,
+                stories: row.try_get::<i64, _>("stories")? as u64,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| e.to_string())
+}
+
+// --- Radar ---
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RadarKeyword {
+    pub keyword: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RadarHit {
+    pub story_id: u32,
+    pub title: String,
+    pub keywords: Vec<String>,
+    // Front-page position when the story was first matched
+    pub rank: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    pub matched_at: DateTime<Utc>,
+}
+
+pub async fn radar_keywords() -> Result<Vec<RadarKeyword>, String> {
+    let rows = sqlx::query("SELECT keyword, created_at FROM radar_keywords ORDER BY keyword")
+        .fetch_all(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    rows.iter()
+        .map(|row| Ok(RadarKeyword { keyword: row.try_get("keyword")?, created_at: row.try_get("created_at")? }))
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| e.to_string())
+}
+
+// False when the keyword was already watched
+pub async fn add_radar_keyword(keyword: &str) -> Result<bool, String> {
+    let result = sqlx::query("INSERT OR IGNORE INTO radar_keywords (keyword, created_at) VALUES (?, ?)")
+        .bind(keyword)
+        .bind(Utc::now())
+        .execute(pool()?)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// False when the keyword wasn't watched
+pub async fn remove_radar_keyword(keyword: &str) -> Result<bool, String> {
+    let result = sqlx::query("DELETE FROM radar_keywords WHERE keyword = ?").bind(keyword).execute(pool()?).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+// False when the story was already on the radar
+pub async fn record_radar_hit(hit: &RadarHit) -> Result<bool, String> {
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO radar_hits (story_id, title, keywords, rank, score, job_id, matched_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(i64::from(hit.story_id))
+    .bind(&hit.title)
+    .bind(serde_json::to_string(&hit.keywords).unwrap_or_else(|_| "[]".to_string()))
+    .bind(i64::from(hit.rank))
+    .bind(hit.score.map(i64::from))
+    .bind(&hit.job_id)
+    .bind(hit.matched_at)
+    .execute(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn set_radar_hit_job(story_id: u32, job_id: &str) -> Result<(), String> {
+    sqlx::query("UPDATE radar_hits SET job_id = ? WHERE story_id = ?")
+        .bind(job_id)
+        .bind(i64::from(story_id))
+        .execute(pool()?)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// Newest first, optionally only hits on `keyword`
+pub async fn radar_hits(limit: u32, keyword: Option<&str>) -> Result<Vec<RadarHit>, String> {
+    let rows = sqlx::query(
+        "SELECT * FROM radar_hits
+         WHERE ?1 IS NULL OR EXISTS (SELECT 1 FROM json_each(radar_hits.keywords) WHERE value = ?1)
+         ORDER BY matched_at DESC, story_id LIMIT ?2",
+    )
+    .bind(keyword)
+    .bind(i64::from(limit.min(MAX_PAGE_SIZE)))
+    .fetch_all(pool()?)
+    .await
+    .map_err(|e| e.to_string())?;
+    rows.iter()
+        .map(|row| {
+            let keywords: String = row.try_get("keywords")?;
+            Ok(RadarHit {
+                story_id: row.try_get::<i64, _>("story_id")? as u32,
+                title: row.try_get("title")?,
+                keywords: serde_json::from_str(&keywords).unwrap_or_default(),
+                rank: row.try_get::<i64, _>("rank")? as u32,
+                score: row.try_get::<Option<i64>, _>("score")?.map(|s| s as u32),
+                job_id: row.try_get("job_id")?,
+                matched_at: row.try_get("matched_at")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,156 @@
+use crate::archive;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+// Content-addressed artifact store: every rendered audio file (main MP3, renditions and HLS
+// segments) is kept once in EPISODE_AUDIO_DIR/objects/<aa>/<sha256>, and the name it is served
+// under is a hard link to that object. The archive's artifacts table records which object each
+// name refers to, so an object is deleted once nothing refers to it any more. Rendering an
+// identical script again (or re-running a digest) therefore adds names, not megabytes.
+//
+// Without the archive files are written as they are.
+
+const OBJECTS_DIR: &str = "objects";
+
+pub fn hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+// Relative to EPISODE_AUDIO_DIR; fanned out by the first byte so no directory gets huge
+fn object_path(hash: &str) -> PathBuf {
+    Path::new(OBJECTS_DIR).join(&hash[..2]).join(hash)
+}
+
+// Stores `bytes` under `name` (relative to EPISODE_AUDIO_DIR), replacing whatever it held. The
+// name is swapped in by rename, so the file being served is never half-written.
+pub async fn put(name: &str, bytes: &[u8]) -> Result<(), String> {
+    let root = crate::episode_audio::dir();
+    let path = root.join(name);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let partial = root.join(format!("{}.partial", name));
+    if !archive::enabled() {
+        tokio::fs::write(&partial, bytes).await.map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+        return tokio::fs::rename(&partial, &path).await.map_err(|e| format!("Failed to move audio into {}: {}", path.display(), e));
+    }
+
+    let hash = hash(bytes);
+    // Referenced first, so a concurrent release of the last other reference keeps the object
+    let previous = archive::set_artifact(name, &hash, bytes.len() as u64).await?;
+    let object = root.join(object_path(&hash));
+    write_object(&object, bytes).await?;
+    link(&object, &partial).await?;
+    tokio::fs::rename(&partial, &path).await.map_err(|e| format!("Failed to move audio into {}: {}", path.display(), e))?;
+    if let Some(previous) = previous.filter(|previous| *previous != hash) {
+        release_object(&previous).await;
+    }
+    Ok(())
+}
+
+// Moves every file under `dir` (relative to EPISODE_AUDIO_DIR) into the store, e.g. the segments
+// ffmpeg wrote for HLS
+pub async fn adopt_dir(dir: &str) -> Result<(), String> {
+    if !archive::enabled() {
+        return Ok(());
+    }
+    let root = crate::episode_audio::dir();
+    // Listed up front: storing a file writes next to it
+    let mut files = Vec::new();
+    let mut entries = tokio::fs::read_dir(root.join(dir)).await.map_err(|e| format!("Failed to read {}: {}", dir, e))?;
+    while let Some(entry) = entries.next_entry().await.map_err(|e| format!("Failed to read {}: {}", dir, e))? {
+        if entry.file_type().await.is_ok_and(|kind| kind.is_file()) {
+            files.push(format!("{}/{}", dir, entry.file_name().to_string_lossy()));
+        }
+    }
+    for name in files {
+        let bytes = tokio::fs::read(root.join(&name)).await.map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        put(&name, &bytes).await?;
+    }
+    Ok(())
+}
+
+// Removes the file served as `name` and drops its reference
+pub async fn remove(name: &str) {
+    let path = crate::episode_audio::dir().join(name);
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            error!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+    if !archive::enabled() {
+        return;
+    }
+    match archive::remove_artifact(name).await {
+        Ok(Some(hash)) => release_object(&hash).await,
+        Ok(None) => {}
+        Err(e) => error!("Failed to release {}: {}", name, e),
+    }
+}
+
+// Drops the references of every file under `dir`, before the directory itself is removed
+pub async fn release_dir(dir: &str) {
+    if !archive::enabled() {
+        return;
+    }
+    match archive::remove_artifacts_under(&format!("{}/", dir)).await {
+        Ok(hashes) => {
+            for hash in hashes {
+                release_object(&hash).await;
+            }
+        }
+        Err(e) => error!("Failed to release {}: {}", dir, e),
+    }
+}
+
+async fn release_object(hash: &str) {
+    match archive::artifact_referenced(hash).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let path = crate::episode_audio::dir().join(object_path(hash));
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    error!("Failed to remove {}: {}", path.display(), e);
+                }
+            }
+        }
+        Err(e) => error!("Failed to check references to object {}: {}", hash, e),
+    }
+}
+
+// Identical content is written once
+async fn write_object(object: &Path, bytes: &[u8]) -> Result<(), String> {
+    if tokio::fs::try_exists(object).await.unwrap_or(false) {
+        return Ok(());
+    }
+    if let Some(parent) = object.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let partial = object.with_extension("partial");
+    tokio::fs::write(&partial, bytes).await.map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+    tokio::fs::rename(&partial, object).await.map_err(|e| format!("Failed to store object {}: {}", object.display(), e))
+}
+
+// A copy where the filesystem has no hard links
+pub async fn link(object: &Path, path: &Path) -> Result<(), String> {
+    let _ = tokio::fs::remove_file(path).await;
+    if tokio::fs::hard_link(object, path).await.is_ok() {
+        return Ok(());
+    }
+    tokio::fs::copy(object, path).await.map(|_| ()).map_err(|e| format!("Failed to link {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objects_are_named_by_content() {
+        let a = hash(b"episode audio");
+        assert_eq!(a, hash(b"episode audio"));
+        assert_ne!(a, hash(b"other audio"));
+        assert_eq!(object_path(&a), Path::new("objects").join(&a[..2]).join(&a));
+    }
+}
+
@@ -0,0 +1,494 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+
+// PCM helpers for the 16-bit little-endian mono audio produced by TTS
+
+pub fn pcm_to_samples(pcm: &[u8]) -> Vec<f64> {
+    pcm.chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f64 / 32768.0)
+        .collect()
+}
+
+fn to_db(amplitude: f64) -> Option<f64> {
+    if amplitude > 0.0 {
+        Some(20.0 * amplitude.log10())
+    } else {
+        None
+    }
+}
+
+// Number of bytes of 16-bit mono PCM for a duration
+fn pcm_len_for(sample_rate: u32, secs: f64) -> usize {
+    (sample_rate as f64 * secs) as usize * 2
+}
+
+pub fn silence(sample_rate: u32, secs: f64) -> Vec<u8> {
+    vec![0; pcm_len_for(sample_rate, secs)]
+}
+
+// --- Silence trimming ---
+
+// AUDIO_TRIM_SILENCE enables trimming by default; AUDIO_MAX_GAP_SECS caps pauses (default 0.75s)
+pub fn trim_enabled_by_default() -> bool {
+    env::var("AUDIO_TRIM_SILENCE").map(|v| v == "true" || v == "1").unwrap_or(false)
+}
+
+pub fn max_gap_secs() -> f64 {
+    env::var("AUDIO_MAX_GAP_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&v: &f64| v >= 0.0)
+        .unwrap_or(0.75)
+}
+
+// Drop leading/trailing silence and shorten internal pauses to at most `max_gap_secs`,
+// keeping the start and end of each long pause so speech decays naturally.
+pub fn trim_silence(pcm: &[u8], sample_rate: u32, max_gap_secs: f64) -> Vec<u8> {
+    let samples = pcm_to_samples(pcm);
+    let (window, flags) = silent_windows(&samples, sample_rate);
+    let window_bytes = window * 2;
+    let max_gap_windows = (max_gap_secs * 100.0) as usize;
+
+    let first = match flags.iter().position(|&silent| !silent) {
+        Some(first) => first,
+        None => return Vec::new(),
+    };
+    let last = flags.iter().rposition(|&silent| !silent).unwrap_or(first);
+
+    let window_range = |i: usize| {
+        let start = i * window_bytes;
+        start..std::cmp::min(start + window_bytes, pcm.len())
+    };
+
+    let mut output = Vec::with_capacity(pcm.len());
+    let mut i = first;
+    while i <= last {
+        if !flags[i] {
+            output.extend_from_slice(&pcm[window_range(i)]);
+            i += 1;
+            continue;
+        }
+
+        let run_end = (i..=last).find(|&j| !flags[j]).unwrap_or(last + 1);
+        let run = run_end - i;
+        if run <= max_gap_windows {
+            for j in i..run_end {
+                output.extend_from_slice(&pcm[window_range(j)]);
+            }
+        } else {
+            let head = max_gap_windows / 2;
+            let tail = max_gap_windows - head;
+            for j in (i..i + head).chain(run_end - tail..run_end) {
+                output.extend_from_slice(&pcm[window_range(j)]);
+            }
+        }
+        i = run_end;
+    }
+
+    output
+}
+
+// --- Playback speed renditions ---
+
+// Speeds offered for clients that can't change playback rate themselves
+pub const SUPPORTED_SPEEDS: [f64; 3] = [1.0, 1.25, 1.5];
+
+fn samples_to_pcm(samples: &[f64]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|s| ((s.clamp(-1.0, 1.0) * 32767.0).round() as i16).to_le_bytes())
+        .collect()
+}
+
+// Time-stretch without changing pitch (WSOLA: windowed overlap-add where each input frame is
+// shifted within a small tolerance to best line up with the previous frame's continuation).
+pub fn time_stretch(pcm: &[u8], sample_rate: u32, speed: f64) -> Vec<u8> {
+    if (speed - 1.0).abs() < f64::EPSILON {
+        return pcm.to_vec();
+    }
+
+    let input = pcm_to_samples(pcm);
+    let frame = (sample_rate as usize * 20) / 1000;
+    let synthesis_hop = frame / 2;
+    let analysis_hop = synthesis_hop as f64 * speed;
+    let tolerance = (sample_rate as usize * 5) / 1000;
+    if input.len() < frame + 2 * tolerance {
+        return pcm.to_vec();
+    }
+
+    let window: Vec<f64> = (0..frame)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / frame as f64).cos())
+        .collect();
+    let last_start = input.len() - frame;
+    let frames = (last_start as f64 / analysis_hop) as usize + 1;
+    let mut output = vec![0.0; frames * synthesis_hop + frame];
+    let mut previous = 0usize;
+
+    for k in 0..frames {
+        let position = if k == 0 {
+            0
+        } else {
+            let nominal = (k as f64 * analysis_hop) as usize;
+            let natural = std::cmp::min(previous + synthesis_hop, last_start);
+            best_alignment(&input, natural, nominal, tolerance, frame, last_start)
+        };
+
+        let out_start = k * synthesis_hop;
+        for i in 0..frame {
+            output[out_start + i] += input[position + i] * window[i];
+        }
+        previous = position;
+    }
+
+    output.truncate((input.len() as f64 / speed) as usize);
+    samples_to_pcm(&output)
+}
+
+// Offset within nominal±tolerance whose frame correlates best with the natural continuation
+fn best_alignment(input: &[f64], natural: usize, nominal: usize, tolerance: usize, frame: usize, last_start: usize) -> usize {
+    let low = nominal.saturating_sub(tolerance);
+    let high = std::cmp::min(nominal + tolerance, last_start);
+    let correlation = |candidate: usize| -> f64 {
+        (0..frame).step_by(2).map(|i| input[natural + i] * input[candidate + i]).sum()
+    };
+    let best_in = |candidates: &mut dyn Iterator<Item = usize>| {
+        candidates
+            .map(|c| (c, correlation(c)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(c, _)| c)
+    };
+
+    // Coarse search, then refine around the best coarse candidate
+    let coarse = best_in(&mut (low..=high).step_by(4)).unwrap_or(std::cmp::min(nominal, last_start));
+    let fine_low = std::cmp::max(coarse.saturating_sub(3), low);
+    let fine_high = std::cmp::min(coarse + 3, high);
+    best_in(&mut (fine_low..=fine_high)).unwrap_or(coarse)
+}
+
+// --- Resampling ---
+
+// Linear-interpolation resampler; only used to bring local TTS output (typically 16/22.05 kHz)
+// up to the pipeline rate, where it's transparent for speech
+pub fn resample(pcm: &[u8], from_rate: u32, to_rate: u32) -> Vec<u8> {
+    if from_rate == to_rate || from_rate == 0 {
+        return pcm.to_vec();
+    }
+    let input = pcm_to_samples(pcm);
+    if input.is_empty() {
+        return vec![];
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let len = (input.len() as f64 / ratio) as usize;
+    let output: Vec<f64> = (0..len)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            let index = position as usize;
+            let fraction = position - index as f64;
+            let next = input.get(index + 1).copied().unwrap_or(input[index]);
+            input[index] + (next - input[index]) * fraction
+        })
+        .collect();
+    samples_to_pcm(&output)
+}
+
+// --- Loudness / clipping QA ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QaReport {
+    // ITU-R BS.1770 integrated loudness (None for silent audio)
+    pub integrated_lufs: Option<f64>,
+    pub true_peak_dbtp: Option<f64>,
+    pub sample_peak_dbfs: Option<f64>,
+    pub leading_silence_secs: f64,
+    pub trailing_silence_secs: f64,
+    pub longest_gap_secs: f64,
+    pub long_gap_count: usize,
+    pub violations: Vec<String>,
+    pub passed: bool,
+}
+
+// Configurable through AUDIO_QA_MIN_LUFS, AUDIO_QA_MAX_LUFS, AUDIO_QA_MAX_TRUE_PEAK_DBTP and AUDIO_QA_MAX_GAP_SECS
+#[derive(Debug, Clone)]
+pub struct QaThresholds {
+    pub min_lufs: f64,
+    pub max_lufs: f64,
+    pub max_true_peak_dbtp: f64,
+    pub max_gap_secs: f64,
+}
+
+impl QaThresholds {
+    pub fn from_env() -> Self {
+        let read = |key: &str, default: f64| env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            min_lufs: read("AUDIO_QA_MIN_LUFS", -20.0),
+            max_lufs: read("AUDIO_QA_MAX_LUFS", -12.0),
+            max_true_peak_dbtp: read("AUDIO_QA_MAX_TRUE_PEAK_DBTP", -1.0),
+            max_gap_secs: read("AUDIO_QA_MAX_GAP_SECS", 3.0),
+        }
+    }
+}
+
+// AUDIO_QA_BLOCK_PUBLISH=false keeps episodes whose audio fails QA in the feed (default: held back)
+pub fn qa_blocks_publish() -> bool {
+    env::var("AUDIO_QA_BLOCK_PUBLISH").map(|v| v != "false" && v != "0").unwrap_or(true)
+}
+
+// Anything quieter than this over a 10ms window counts as silence
+const SILENCE_DBFS: f64 = -50.0;
+
+pub fn analyze(pcm: &[u8], sample_rate: u32, thresholds: &QaThresholds) -> QaReport {
+    let samples = pcm_to_samples(pcm);
+    let integrated_lufs = integrated_loudness(&samples, sample_rate);
+    let sample_peak = samples.iter().fold(0.0f64, |peak, s| peak.max(s.abs()));
+    let true_peak = true_peak(&samples, sample_peak);
+    let silence = silence_stats(&samples, sample_rate, thresholds.max_gap_secs);
+
+    let mut violations = Vec::new();
+    match integrated_lufs {
+        Some(lufs) if lufs < thresholds.min_lufs => {
+            violations.push(format!("Integrated loudness {:.1} LUFS is below {:.1} LUFS", lufs, thresholds.min_lufs))
+        }
+        Some(lufs) if lufs > thresholds.max_lufs => {
+            violations.push(format!("Integrated loudness {:.1} LUFS is above {:.1} LUFS", lufs, thresholds.max_lufs))
+        }
+        None => violations.push("Audio is silent".to_string()),
+        _ => {}
+    }
+    if let Some(peak) = to_db(true_peak) {
+        if peak > thresholds.max_true_peak_dbtp {
+            violations.push(format!("True peak {:.1} dBTP exceeds {:.1} dBTP", peak, thresholds.max_true_peak_dbtp));
+        }
+    }
+    if silence.longest_gap_secs > thresholds.max_gap_secs {
+        violations.push(format!(
+            "{} silence gap(s) longer than {:.1}s (longest {:.1}s)",
+            silence.long_gap_count, thresholds.max_gap_secs, silence.longest_gap_secs
+        ));
+    }
+
+    QaReport {
+        integrated_lufs,
+        true_peak_dbtp: to_db(true_peak),
+        sample_peak_dbfs: to_db(sample_peak),
+        leading_silence_secs: silence.leading_secs,
+        trailing_silence_secs: silence.trailing_secs,
+        longest_gap_secs: silence.longest_gap_secs,
+        long_gap_count: silence.long_gap_count,
+        passed: violations.is_empty(),
+        violations,
+    }
+}
+
+// Second-order IIR section (direct form I)
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    fn apply(&self, input: &[f64]) -> Vec<f64> {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        input
+            .iter()
+            .map(|&x| {
+                let y = self.b[0] * x + self.b[1] * x1 + self.b[2] * x2 - self.a[0] * y1 - self.a[1] * y2;
+                x2 = x1;
+                x1 = x;
+                y2 = y1;
+                y1 = y;
+                y
+            })
+            .collect()
+    }
+}
+
+// BS.1770 K-weighting (high shelf + high pass), derived for an arbitrary sample rate
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    [shelf, high_pass]
+}
+
+fn integrated_loudness(samples: &[f64], sample_rate: u32) -> Option<f64> {
+    let [shelf, high_pass] = k_weighting(sample_rate);
+    let weighted = high_pass.apply(&shelf.apply(samples));
+
+    // 400ms gating blocks with 75% overlap
+    let block = (sample_rate as usize * 400) / 1000;
+    let step = block / 4;
+    if block == 0 || weighted.len() < block {
+        return None;
+    }
+
+    let block_power: Vec<f64> = (0..=(weighted.len() - block) / step)
+        .map(|i| weighted[i * step..i * step + block].iter().map(|s| s * s).sum::<f64>() / block as f64)
+        .collect();
+    let loudness = |power: f64| -0.691 + 10.0 * power.log10();
+
+    // Absolute gate at -70 LUFS, then relative gate 10 LU below the absolute-gated loudness
+    let above_absolute: Vec<f64> = block_power.iter().copied().filter(|&p| p > 0.0 && loudness(p) > -70.0).collect();
+    if above_absolute.is_empty() {
+        return None;
+    }
+    let relative_gate = loudness(above_absolute.iter().sum::<f64>() / above_absolute.len() as f64) - 10.0;
+    let gated: Vec<f64> = above_absolute.into_iter().filter(|&p| loudness(p) > relative_gate).collect();
+    if gated.is_empty() {
+        return None;
+    }
+
+    Some(loudness(gated.iter().sum::<f64>() / gated.len() as f64))
+}
+
+// Estimate inter-sample peaks with 4x windowed-sinc oversampling around the loudest samples
+fn true_peak(samples: &[f64], sample_peak: f64) -> f64 {
+    const TAPS: isize = 16;
+    const OVERSAMPLE: usize = 4;
+
+    let candidate_level = sample_peak * 0.7;
+    let mut peak = sample_peak;
+
+    for n in 0..samples.len().saturating_sub(1) {
+        if samples[n].abs().max(samples[n + 1].abs()) < candidate_level {
+            continue;
+        }
+        for phase in 1..OVERSAMPLE {
+            let t = phase as f64 / OVERSAMPLE as f64;
+            let mut value = 0.0;
+            for k in -TAPS + 1..=TAPS {
+                let index = n as isize + k;
+                if index < 0 || index as usize >= samples.len() {
+                    continue;
+                }
+                let x = t - k as f64;
+                let sinc = (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x);
+                // Hann window over the filter span
+                let window = 0.5 + 0.5 * (std::f64::consts::PI * x / TAPS as f64).cos();
+                value += samples[index as usize] * sinc * window;
+            }
+            peak = peak.max(value.abs());
+        }
+    }
+
+    peak
+}
+
+struct SilenceStats {
+    leading_secs: f64,
+    trailing_secs: f64,
+    longest_gap_secs: f64,
+    long_gap_count: usize,
+}
+
+// Per-10ms-window silence flags
+fn silent_windows(samples: &[f64], sample_rate: u32) -> (usize, Vec<bool>) {
+    let window = (sample_rate as usize / 100).max(1);
+    let flags = samples
+        .chunks(window)
+        .map(|chunk| {
+            let rms = (chunk.iter().map(|s| s * s).sum::<f64>() / chunk.len() as f64).sqrt();
+            to_db(rms).is_none_or(|db| db < SILENCE_DBFS)
+        })
+        .collect();
+    (window, flags)
+}
+
+fn silence_stats(samples: &[f64], sample_rate: u32, max_gap_secs: f64) -> SilenceStats {
+    let (window, flags) = silent_windows(samples, sample_rate);
+    let window_secs = window as f64 / sample_rate as f64;
+
+    let leading = flags.iter().take_while(|&&silent| silent).count();
+    let trailing = if leading == flags.len() {
+        0
+    } else {
+        flags.iter().rev().take_while(|&&silent| silent).count()
+    };
+
+    let mut longest = 0usize;
+    let mut long_gaps = 0usize;
+    let mut run = 0usize;
+    for &silent in &flags[leading..flags.len() - trailing] {
+        if silent {
+            run += 1;
+        } else {
+            longest = longest.max(run);
+            if run as f64 * window_secs > max_gap_secs {
+                long_gaps += 1;
+            }
+            run = 0;
+        }
+    }
+
+    SilenceStats {
+        leading_secs: leading as f64 * window_secs,
+        trailing_secs: trailing as f64 * window_secs,
+        longest_gap_secs: longest as f64 * window_secs,
+        long_gap_count: long_gaps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 24_000;
+
+    fn tone(secs: f64) -> Vec<u8> {
+        let samples: Vec<f64> = (0..(RATE as f64 * secs) as usize)
+            .map(|i| 0.5 * (2.0 * std::f64::consts::PI * 440.0 * i as f64 / RATE as f64).sin())
+            .collect();
+        samples_to_pcm(&samples)
+    }
+
+    #[test]
+    fn trim_silence_drops_edges_and_caps_gaps() {
+        let pcm = [silence(RATE, 0.5), tone(0.2), silence(RATE, 2.0), tone(0.2), silence(RATE, 0.5)].concat();
+        let trimmed = trim_silence(&pcm, RATE, 0.5);
+        // Two 0.2s tones around a gap cut down to 0.5s: 90 windows of 240 samples
+        assert_eq!(trimmed.len(), 90 * 240 * 2);
+    }
+
+    #[test]
+    fn trim_silence_keeps_short_gaps() {
+        let pcm = [tone(0.2), silence(RATE, 0.3), tone(0.2)].concat();
+        assert_eq!(trim_silence(&pcm, RATE, 0.5), pcm);
+    }
+
+    #[test]
+    fn trim_silence_of_pure_silence_is_empty() {
+        assert!(trim_silence(&silence(RATE, 1.0), RATE, 0.5).is_empty());
+    }
+
+    #[test]
+    fn time_stretch_shortens_by_the_speed() {
+        let pcm = tone(1.0);
+        assert_eq!(time_stretch(&pcm, RATE, 1.5).len(), 16_000 * 2);
+        assert_eq!(time_stretch(&pcm, RATE, 1.25).len(), 19_200 * 2);
+    }
+
+    #[test]
+    fn time_stretch_leaves_normal_speed_and_tiny_clips_alone() {
+        let pcm = tone(1.0);
+        assert_eq!(time_stretch(&pcm, RATE, 1.0), pcm);
+        let clip = tone(0.01);
+        assert_eq!(time_stretch(&clip, RATE, 1.5), clip);
+    }
+}
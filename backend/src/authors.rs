@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// Cached HN user profiles for enriching comment responses (?enrich_authors=true). Karma and
+// account age change slowly, so an hour-long cache keeps popular threads from refetching users.
+
+const CACHE_TTL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorProfile {
+    pub karma: Option<i64>,
+    // Unix timestamp of account creation
+    pub created: Option<u64>,
+    pub account_age_days: Option<u64>,
+}
+
+impl AuthorProfile {
+    pub fn new(karma: Option<i64>, created: Option<u64>) -> Self {
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        let account_age_days = created.map(|c| now.saturating_sub(c) / 86_400);
+        Self { karma, created, account_age_days }
+    }
+}
+
+type CachedProfiles = HashMap<String, (AuthorProfile, Instant)>;
+
+static PROFILES: OnceLock<Mutex<CachedProfiles>> = OnceLock::new();
+
+fn profiles() -> &'static Mutex<CachedProfiles> {
+    PROFILES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn get(username: &str) -> Option<AuthorProfile> {
+    let profiles = profiles().lock().unwrap();
+    profiles
+        .get(username)
+        .filter(|(_, fetched_at)| fetched_at.elapsed() < CACHE_TTL)
+        .map(|(profile, _)| profile.clone())
+}
+
+pub fn put(username: &str, profile: AuthorProfile) {
+    let mut profiles = profiles().lock().unwrap();
+    profiles.retain(|_, (_, fetched_at)| fetched_at.elapsed() < CACHE_TTL);
+    profiles.insert(username.to_string(), (profile, Instant::now()));
+}
+
+// Drop profiles HN reports as changed; returns how many were cached
+pub fn invalidate(usernames: &[String]) -> usize {
+    let mut profiles = profiles().lock().unwrap();
+    usernames.iter().filter(|name| profiles.remove(name.as_str()).is_some()).count()
+}
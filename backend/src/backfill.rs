@@ -0,0 +1,256 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tracing::{error, info};
+
+// Back catalog for a new show: POST /admin/backfill {from, to} walks the dates in between and
+// generates a digest of each day's front page (looked up through HN Algolia's front_page tag).
+// Days run one at a time in the backfill job lane, BACKFILL_DELAY_SECS apart (default 10), and
+// each digest covers the day's BACKFILL_STORIES_PER_DAY top stories (default 10). With
+// "dry_run": true only the stories are looked up, a second apart. Progress is kept in memory
+// (GET /admin/backfill/:id) and published as backfill_progress events.
+
+pub const MAX_DAYS: i64 = 366;
+const MAX_RUNS: usize = 50;
+const DRY_RUN_DELAY: Duration = Duration::from_secs(1);
+
+pub fn stories_per_day() -> usize {
+    env::var("BACKFILL_STORIES_PER_DAY").ok().and_then(|v| v.parse().ok()).filter(|&n| n > 0).unwrap_or(10)
+}
+
+fn delay() -> Duration {
+    Duration::from_secs(env::var("BACKFILL_DELAY_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(10))
+}
+
+pub fn digest_title(date: NaiveDate) -> String {
+    format!("HackerNews on {}", date.format("%B %-d, %Y"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Completed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DayStatus {
+    Pending,
+    Generating,
+    // Dry runs stop once the stories are known
+    Planned,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Day {
+    pub date: NaiveDate,
+    pub status: DayStatus,
+    pub title: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stories: Vec<crate::RelatedStory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Run {
+    pub id: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub dry_run: bool,
+    pub status: RunStatus,
+    // Days finished so far, whatever their outcome
+    pub done: usize,
+    pub total: usize,
+    pub days: Vec<Day>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+static RUNS: OnceLock<Mutex<HashMap<String, Run>>> = OnceLock::new();
+
+fn runs() -> &'static Mutex<HashMap<String, Run>> {
+    RUNS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn get(id: &str) -> Option<Run> {
+    runs().lock().unwrap().get(id).cloned()
+}
+
+// Newest first
+pub fn list() -> Vec<Run> {
+    let mut runs: Vec<Run> = runs().lock().unwrap().values().cloned().collect();
+    runs.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+    runs
+}
+
+pub fn running() -> Option<Run> {
+    runs().lock().unwrap().values().find(|r| r.status == RunStatus::Running).cloned()
+}
+
+fn update(id: &str, f: impl FnOnce(&mut Run)) {
+    if let Some(run) = runs().lock().unwrap().get_mut(id) {
+        f(run);
+        run.updated_at = Utc::now();
+    }
+}
+
+fn update_day(id: &str, index: usize, f: impl FnOnce(&mut Day)) {
+    update(id, |run| {
+        if let Some(day) = run.days.get_mut(index) {
+            f(day);
+        }
+        run.done = run.days.iter().filter(|d| matches!(d.status, DayStatus::Planned | DayStatus::Succeeded | DayStatus::Failed)).count();
+    });
+    if let Some(run) = get(id) {
+        crate::events::publish("backfill_progress", serde_json::json!({
+            "backfill_id": run.id,
+            "status": run.status,
+            "done": run.done,
+            "total": run.total,
+            "day": run.days.get(index),
+        }));
+    }
+}
+
+// Registers the run and works through it in the background
+pub fn start(from: NaiveDate, to: NaiveDate, dry_run: bool) -> Run {
+    let days: Vec<Day> = from
+        .iter_days()
+        .take_while(|date| *date <= to)
+        .map(|date| Day {
+            date,
+            status: DayStatus::Pending,
+            title: digest_title(date),
+            stories: Vec::new(),
+            job_id: None,
+            episode_id: None,
+            error: None,
+        })
+        .collect();
+    let now = Utc::now();
+    let run = Run {
+        id: uuid::Uuid::new_v4().to_string(),
+        from,
+        to,
+        dry_run,
+        status: RunStatus::Running,
+        done: 0,
+        total: days.len(),
+        days,
+        created_at: now,
+        updated_at: now,
+    };
+    {
+        let mut runs = runs().lock().unwrap();
+        if runs.len() >= MAX_RUNS {
+            let oldest = runs.values().filter(|r| r.status != RunStatus::Running).min_by_key(|r| r.created_at).map(|r| r.id.clone());
+            if let Some(oldest) = oldest {
+                runs.remove(&oldest);
+            }
+        }
+        runs.insert(run.id.clone(), run.clone());
+    }
+    info!("Backfill {} started: {} day(s) from {} to {}{}", run.id, run.total, from, to, if dry_run { " (dry run)" } else { "" });
+    tokio::spawn(crate::credentials::scope(crate::credentials::current(), execute(run.id.clone())));
+    run
+}
+
+// False when the run had already finished
+pub fn cancel(id: &str) -> bool {
+    let mut cancelled = false;
+    update(id, |run| {
+        if run.status == RunStatus::Running {
+            run.status = RunStatus::Cancelled;
+            cancelled = true;
+        }
+    });
+    cancelled
+}
+
+fn cancelled(id: &str) -> bool {
+    get(id).is_none_or(|run| run.status == RunStatus::Cancelled)
+}
+
+async fn execute(id: String) {
+    let Some(run) = get(&id) else { return };
+    for (index, day) in run.days.iter().enumerate() {
+        if index > 0 {
+            tokio::time::sleep(if run.dry_run { DRY_RUN_DELAY } else { delay() }).await;
+        }
+        // A cancelled run finishes the day in progress but starts no new one
+        if cancelled(&id) {
+            info!("Backfill {} cancelled after {} day(s)", id, index);
+            return;
+        }
+        if run.dry_run {
+            plan_day(&id, index, day.date).await;
+        } else {
+            generate_day(&id, index, day.date).await;
+        }
+    }
+    update(&id, |run| {
+        if run.status == RunStatus::Running {
+            run.status = RunStatus::Completed;
+        }
+    });
+    info!("Backfill {} finished", id);
+}
+
+async fn plan_day(id: &str, index: usize, date: NaiveDate) {
+    let result = crate::get_hn_client().get_front_page(date, stories_per_day()).await;
+    update_day(id, index, |day| match result {
+        Ok(stories) if stories.is_empty() => {
+            day.status = DayStatus::Failed;
+            day.error = Some(format!("No front-page stories found for {}", date));
+        }
+        Ok(stories) => {
+            day.status = DayStatus::Planned;
+            day.stories = stories;
+        }
+        Err(e) => {
+            day.status = DayStatus::Failed;
+            day.error = Some(format!("Failed to look up the front page for {}: {}", date, e));
+        }
+    });
+}
+
+// The job retries on its own; the day waits for its final outcome, which keeps one day in flight
+async fn generate_day(id: &str, index: usize, date: NaiveDate) {
+    let (job, handle) = crate::spawn_generation_job(crate::GenerationJobRequest {
+        date: Some(date),
+        priority: Some(crate::jobs::Priority::Backfill),
+        ..Default::default()
+    });
+    update_day(id, index, |day| {
+        day.status = DayStatus::Generating;
+        day.job_id = Some(job.id.clone());
+    });
+    let outcome = handle.await;
+    update_day(id, index, |day| match outcome {
+        Ok(Ok(result)) => {
+            day.status = DayStatus::Succeeded;
+            day.episode_id = result.get("episode_id").and_then(|e| e.as_str()).map(String::from);
+        }
+        Ok(Err(e)) => {
+            day.status = DayStatus::Failed;
+            day.error = Some(e);
+        }
+        Err(e) => {
+            error!("Backfill job {} panicked: {}", job.id, e);
+            day.status = DayStatus::Failed;
+            day.error = Some("The generation task panicked".to_string());
+        }
+    });
+}
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+// Synthetic load for measuring the CPU-bound parts of the pipeline (only built with the `bench`
+// feature). Stories, comments, articles and the "upstream" script are generated locally from a
+// seed, so runs are repeatable and never touch HN, Alchemyst or TTS. Also used by benches/.
+
+const MAX_STORIES: usize = 200;
+const MAX_COMMENTS_PER_STORY: usize = 2000;
+const MAX_COMMENT_WORDS: usize = 1000;
+
+const WORDS: &[&str] = &[
+    "rust", "compiler", "database", "latency", "startup", "kernel", "browser", "security", "model", "cache",
+    "great", "terrible", "useful", "broken", "interesting", "slow", "fast", "simple", "the", "and", "with",
+    "because", "really", "think", "open", "source", "release", "memory", "thread", "async", "network",
+];
+
+// xorshift64*; good enough for filler text and no extra dependency
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn sentence(&mut self, words: usize) -> String {
+        let mut sentence: Vec<&str> = (0..words.max(1)).map(|_| WORDS[self.below(WORDS.len())]).collect();
+        let first = sentence[0].to_string();
+        let capitalized = first[..1].to_uppercase() + &first[1..];
+        sentence[0] = &capitalized;
+        format!("{}.", sentence.join(" "))
+    }
+}
+
+pub fn username(rng: &mut Rng) -> String {
+    format!("user{}", rng.below(100_000))
+}
+
+// HN-style comment HTML: <p> paragraphs, entities and the occasional link
+pub fn comment_html(rng: &mut Rng, words: usize) -> String {
+    let mut html = String::new();
+    let mut remaining = words.max(1);
+    while remaining > 0 {
+        let n = remaining.min(8 + rng.below(12));
+        remaining -= n;
+        if !html.is_empty() {
+            html.push_str("<p>");
+        }
+        html.push_str(&rng.sentence(n).replace("the", "the &quot;").replace("and", "&amp;"));
+        if rng.below(5) == 0 {
+            html.push_str(&format!(" <a href=\"https:&#x2F;&#x2F;example.com&#x2F;{}\">link</a>", rng.below(1000)));
+        }
+    }
+    html
+}
+
+pub fn article_html(rng: &mut Rng, paragraphs: usize) -> String {
+    let body: String = (0..paragraphs)
+        .map(|_| {
+            let words = 20 + rng.below(30);
+            format!("<p>{}</p>\n", rng.sentence(words))
+        })
+        .collect();
+    format!(
+        "<html><head><title>Synthetic</title><script>var x = 1;</script></head><body><nav>Home</nav><article>{}</article><footer>Footer</footer></body></html>",
+        body
+    )
+}
+
+// Stands in for the upstream LLM: a script with segment headings, transitions and links
+pub fn script(rng: &mut Rng, segments: usize, words_per_segment: usize) -> String {
+    let mut script = format!("Welcome to the show. {}\n\n[TRANSITION]\n\n", rng.sentence(20));
+    for i in 0..segments {
+        script.push_str(&format!("## Segment {}\n\n", i + 1));
+        let mut remaining = words_per_segment;
+        while remaining > 0 {
+            let n = remaining.min(15);
+            remaining -= n;
+            script.push_str(&rng.sentence(n));
+            script.push(' ');
+        }
+        script.push_str(&format!("More at https://example.com/story/{}.\n\n", rng.below(1000)));
+    }
+    script
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct BenchRequest {
+    pub stories: usize,
+    pub comments_per_story: usize,
+    pub comment_words: usize,
+    pub article_paragraphs: usize,
+    pub seed: u64,
+}
+
+impl Default for BenchRequest {
+    fn default() -> Self {
+        Self { stories: 10, comments_per_story: 50, comment_words: 60, article_paragraphs: 20, seed: 1 }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StageTiming {
+    pub stage: &'static str,
+    pub total_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub stories: usize,
+    pub comments: usize,
+    pub input_bytes: usize,
+    pub total_ms: f64,
+    pub stages: Vec<StageTiming>,
+}
+
+struct Stages(Vec<(&'static str, Duration)>);
+
+impl Stages {
+    fn time<T>(&mut self, stage: &'static str, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        match self.0.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += started.elapsed(),
+            None => self.0.push((stage, started.elapsed())),
+        }
+        result
+    }
+}
+
+// Runs each story through the same steps the real handlers use, with a synthetic upstream script
+pub fn run_pipeline(request: &BenchRequest) -> BenchReport {
+    let stories = request.stories.min(MAX_STORIES);
+    let comments_per_story = request.comments_per_story.min(MAX_COMMENTS_PER_STORY);
+    let comment_words = request.comment_words.min(MAX_COMMENT_WORDS);
+
+    let mut rng = Rng::new(request.seed);
+    let mut stages = Stages(Vec::new());
+    let mut input_bytes = 0;
+    let started = Instant::now();
+
+    for _ in 0..stories {
+        let title = rng.sentence(8);
+        let authors: Vec<String> = (0..comments_per_story).map(|_| username(&mut rng)).collect();
+        let comments: Vec<String> = (0..comments_per_story).map(|_| comment_html(&mut rng, comment_words)).collect();
+        let article = article_html(&mut rng, request.article_paragraphs);
+        input_bytes += comments.iter().map(String::len).sum::<usize>() + article.len();
+
+        let texts: Vec<String> = stages.time("strip_html", || comments.iter().map(|c| crate::analysis::strip_html(c)).collect());
+        let texts: Vec<String> = stages.time("anonymize", || {
+            crate::privacy::register(authors.iter().map(String::as_str));
+            texts.iter().map(|t| crate::privacy::anonymize(t)).collect()
+        });
+        let refs: Vec<&str> = texts.iter().map(String::as_str).collect();
+        stages.time("keywords", || crate::analysis::keywords(&title, &refs, 8));
+        stages.time("sentiment", || crate::analysis::sentiment(&refs));
+        stages.time("article_extract", || crate::analysis::article_extract(&article));
+
+        let script = stages.time("mock_upstream", || script(&mut rng, 5, 150));
+        let (script, _) = stages.time("enforce_length", || crate::script::enforce_length(&script, 6000));
+        stages.time("estimate", || crate::script::estimate(&script, 150.0));
+        stages.time("split_script", || crate::announcements::split_script(&script));
+    }
+
+    BenchReport {
+        stories,
+        comments: stories * comments_per_story,
+        input_bytes,
+        total_ms: started.elapsed().as_secs_f64() * 1000.0,
+        stages: stages
+            .0
+            .into_iter()
+            .map(|(stage, total)| StageTiming { stage, total_ms: total.as_secs_f64() * 1000.0 })
+            .collect(),
+    }
+}
@@ -0,0 +1,128 @@
+use crate::jobs::{Job, JobStatus};
+use hmac::{Hmac, Mac};
+use std::env;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+// Job-level webhooks: a `callback_url` on /hooks/generate receives the finished job (result or
+// dead-letter details) as a POST signed like inbound hooks, `X-Hub-Signature-256: sha256=<hex
+// hmac of body>` with HOOKS_SECRET. Failed deliveries are retried with exponential backoff
+// (CALLBACK_MAX_ATTEMPTS, default 5; 2s, 4s, 8s, ...). Client errors other than 408/429 are final.
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+fn max_attempts() -> u32 {
+    env::var("CALLBACK_MAX_ATTEMPTS").ok().and_then(|v| v.trim().parse().ok()).filter(|n| *n > 0).unwrap_or(5)
+}
+
+pub fn validate_url(url: &str) -> Result<(), String> {
+    match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.host().is_some() => Ok(()),
+        _ => Err(format!("Invalid callback_url '{}': expected an http(s) URL", url)),
+    }
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// Delivers in the background so callers awaiting the job aren't held up by slow receivers
+pub fn spawn_delivery(url: String, job: Job) {
+    tokio::spawn(async move { deliver(&url, &job).await });
+}
+
+async fn deliver(url: &str, job: &Job) {
+    let event = match job.status {
+        JobStatus::Succeeded => "job.succeeded",
+        JobStatus::Skipped => "job.skipped",
+        JobStatus::Interrupted => "job.interrupted",
+        _ => "job.dead",
+    };
+    let body = serde_json::to_vec(&serde_json::json!({ "event": event, "job": job })).unwrap_or_default();
+    let signature = sign(&env::var("HOOKS_SECRET").unwrap_or_default(), &body);
+    let delivery_id = uuid::Uuid::new_v4().to_string();
+    let client = reqwest::Client::new();
+
+    let attempts = max_attempts();
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 1..=attempts {
+        let result = client
+            .post(url)
+            .timeout(REQUEST_TIMEOUT)
+            .header("Content-Type", "application/json")
+            .header("X-Hub-Signature-256", &signature)
+            .header("X-Hook-Event", event)
+            .header("X-Hook-Delivery", &delivery_id)
+            .body(body.clone())
+            .send()
+            .await;
+
+        let retryable = match result {
+            Ok(response) if response.status().is_success() => {
+                info!("Delivered {} callback for job {} (attempt {})", event, job.id, attempt);
+                return;
+            }
+            Ok(response) => {
+                let status = response.status();
+                warn!("Callback for job {} returned {} (attempt {}/{})", job.id, status, attempt, attempts);
+                !status.is_client_error() || status.as_u16() == 408 || status.as_u16() == 429
+            }
+            Err(e) => {
+                warn!("Callback for job {} failed (attempt {}/{}): {}", job.id, attempt, attempts, e);
+                true
+            }
+        };
+        if !retryable || attempt == attempts {
+            break;
+        }
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+    error!("Giving up on {} callback for job {} to {}", event, job.id, crate::redact::mask(url));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, HeaderValue};
+
+    fn headers(signature: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Hub-Signature-256", HeaderValue::from_str(signature).unwrap());
+        headers
+    }
+
+    #[test]
+    fn sign_matches_the_github_format() {
+        // HMAC-SHA256("key", "The quick brown fox jumps over the lazy dog")
+        assert_eq!(
+            sign("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn signed_callbacks_pass_hook_verification() {
+        let body = br#"{"event":"job.succeeded"}"#;
+        assert!(crate::verify_hook_signature("secret", &headers(&sign("secret", body)), body));
+    }
+
+    #[test]
+    fn hook_verification_rejects_bad_signatures() {
+        let body = br#"{"event":"job.succeeded"}"#;
+        assert!(!crate::verify_hook_signature("other", &headers(&sign("secret", body)), body));
+        assert!(!crate::verify_hook_signature("secret", &headers(&sign("secret", b"tampered")), body));
+        assert!(!crate::verify_hook_signature("secret", &headers("sha256=not-hex"), body));
+        assert!(!crate::verify_hook_signature("secret", &HeaderMap::new(), body));
+    }
+
+    #[test]
+    fn callback_urls_must_be_http() {
+        assert!(validate_url("https://example.com/hook").is_ok());
+        assert!(validate_url("ftp://example.com/hook").is_err());
+        assert!(validate_url("not a url").is_err());
+    }
+}
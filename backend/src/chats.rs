@@ -0,0 +1,121 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+// Upstream chat sessions keyed by the Alchemyst chatId, so follow-up turns can continue them.
+// Sessions expire after a day without activity, and a session started with a caller's own
+// Alchemyst key (BYOK) is only visible to requests carrying that same key.
+
+const SESSION_TTL_HOURS: i64 = 24;
+const MAX_SESSIONS: usize = 1_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: &str, content: impl Into<String>) -> Self {
+        Self { role: role.to_string(), content: content.into() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatSession {
+    pub chat_id: String,
+    pub persona: String,
+    pub scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    // SHA-256 of the caller's Alchemyst key; None for sessions on the server's key
+    #[serde(skip)]
+    owner: Option<String>,
+}
+
+impl ChatSession {
+    fn expired(&self, now: DateTime<Utc>) -> bool {
+        now - self.updated_at > chrono::Duration::hours(SESSION_TTL_HOURS)
+    }
+}
+
+fn current_owner() -> Option<String> {
+    crate::credentials::supplied("ALCHEMYST_API_KEY").map(|key| hex::encode(Sha256::digest(key.as_bytes())))
+}
+
+static SESSIONS: OnceLock<Mutex<HashMap<String, ChatSession>>> = OnceLock::new();
+
+fn sessions() -> &'static Mutex<HashMap<String, ChatSession>> {
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Store (or replace) the full conversation for a chat
+pub fn record(chat_id: &str, persona: &str, scope: &str, title: Option<String>, messages: Vec<ChatMessage>) {
+    let now = Utc::now();
+    let owner = current_owner();
+    let mut sessions = sessions().lock().unwrap();
+    let created_at = sessions.get(chat_id).filter(|s| s.owner == owner).map(|s| s.created_at).unwrap_or(now);
+    if !sessions.contains_key(chat_id) && sessions.len() >= MAX_SESSIONS {
+        sessions.retain(|_, session| !session.expired(now));
+        // Still full: make room by dropping the least recently used session
+        if sessions.len() >= MAX_SESSIONS {
+            if let Some(oldest) = sessions.values().min_by_key(|s| s.updated_at).map(|s| s.chat_id.clone()) {
+                sessions.remove(&oldest);
+            }
+        }
+    }
+    sessions.insert(
+        chat_id.to_string(),
+        ChatSession {
+            chat_id: chat_id.to_string(),
+            persona: persona.to_string(),
+            scope: scope.to_string(),
+            title,
+            messages,
+            created_at,
+            updated_at: now,
+            owner,
+        },
+    );
+}
+
+pub fn append(chat_id: &str, messages: Vec<ChatMessage>) {
+    let owner = current_owner();
+    if let Some(session) = sessions().lock().unwrap().get_mut(chat_id).filter(|s| s.owner == owner) {
+        session.messages.extend(messages);
+        session.updated_at = Utc::now();
+    }
+}
+
+// None when the session doesn't exist, has expired or belongs to another caller's key
+pub fn get(chat_id: &str) -> Option<ChatSession> {
+    let now = Utc::now();
+    let mut sessions = sessions().lock().unwrap();
+    if sessions.get(chat_id).is_some_and(|s| s.expired(now)) {
+        sessions.remove(chat_id);
+        return None;
+    }
+    let owner = current_owner();
+    sessions.get(chat_id).filter(|s| s.owner == owner).cloned()
+}
+
+// Platform API returns { result: { response: { content } } } or { result: { content } }
+pub fn extract_content(value: &serde_json::Value) -> Option<&serde_json::Value> {
+    let result = value.get("result")?;
+    result
+        .get("response")
+        .and_then(|r| r.get("content"))
+        .or_else(|| result.get("content"))
+}
+
+pub fn content_text(content: &serde_json::Value) -> String {
+    match content.as_str() {
+        Some(text) => text.to_string(),
+        None => content.to_string(),
+    }
+}
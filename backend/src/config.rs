@@ -0,0 +1,485 @@
+use serde::Serialize;
+use std::env;
+
+// Runtime settings shared by handlers and exposed (minus secrets) at GET /api/config
+
+pub const API_VERSION: &str = "v1";
+
+// Number of top stories returned by /api/stories
+pub const TOP_STORIES_LIMIT: usize = 50;
+
+// Prebuilt voices offered by the Gemini TTS models
+pub const GEMINI_VOICES: [&str; 30] = [
+    "Zephyr", "Puck", "Charon", "Kore", "Fenrir", "Leda", "Orus", "Aoede", "Callirrhoe", "Autonoe",
+    "Enceladus", "Iapetus", "Umbriel", "Algieba", "Despina", "Erinome", "Algenib", "Rasalgethi", "Laomedeia", "Achernar",
+    "Alnilam", "Schedar", "Gacrux", "Pulcherrima", "Achird", "Zubenelgenubi", "Vindemiatrix", "Sadachbia", "Sadaltager", "Sulafat",
+];
+
+pub fn alchemyst_api_url() -> String {
+    env::var("ALCHEMYST_API_URL").unwrap_or_else(|_| "https://platform-backend.getalchemystai.com".to_string())
+}
+
+fn is_set(key: &str) -> bool {
+    env::var(key).map(|v| !v.is_empty()).unwrap_or(false)
+}
+
+// Comma-separated PERSONAS; the first entry is the default
+pub fn personas() -> Vec<String> {
+    let personas: Vec<String> = env::var("PERSONAS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if personas.is_empty() {
+        vec!["maya".to_string()]
+    } else {
+        personas
+    }
+}
+
+pub fn default_persona() -> String {
+    personas().remove(0)
+}
+
+// Used by automated jobs (webhooks, Telegram) when the requested persona isn't known
+pub fn fallback_persona() -> Option<String> {
+    env::var("FALLBACK_PERSONA").ok().map(|p| p.trim().to_string()).filter(|p| !p.is_empty())
+}
+
+pub fn default_tts_voice() -> String {
+    env::var("TTS_VOICE")
+        .ok()
+        .filter(|v| GEMINI_VOICES.contains(&v.as_str()))
+        .unwrap_or_else(|| "Kore".to_string())
+}
+
+fn port() -> String {
+    env::var("PORT").unwrap_or_else(|_| "3001".to_string())
+}
+
+// Comma-separated BIND_ADDRS (e.g. "0.0.0.0:3001,[::]:3001"); defaults to 0.0.0.0:$PORT unless
+// only a Unix socket is configured
+pub fn bind_addrs() -> Vec<std::net::SocketAddr> {
+    match env::var("BIND_ADDRS") {
+        Ok(addrs) if !addrs.trim().is_empty() => addrs
+            .split(',')
+            .filter_map(|addr| addr.trim().parse().ok())
+            .collect(),
+        _ if unix_socket_path().is_some() => vec![],
+        _ => format!("0.0.0.0:{}", port()).parse().into_iter().collect(),
+    }
+}
+
+pub fn unix_socket_path() -> Option<String> {
+    env::var("UNIX_SOCKET_PATH").ok().filter(|p| !p.is_empty())
+}
+
+// Certificate chain and private key (PEM) for the built-in HTTPS listener
+pub fn tls_paths() -> Option<(String, String)> {
+    match (env::var("TLS_CERT_PATH"), env::var("TLS_KEY_PATH")) {
+        (Ok(cert), Ok(key)) if !cert.is_empty() && !key.is_empty() => Some((cert, key)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub api_version: &'static str,
+    pub service_version: &'static str,
+    pub personas: Vec<String>,
+    pub default_persona: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_persona: Option<String>,
+    pub voices: Vec<&'static str>,
+    pub default_voice: String,
+    pub features: Features,
+    pub limits: Limits,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Features {
+    pub podcast_generation: bool,
+    pub tts: bool,
+    pub tts_provider: &'static str,
+    // Provider behind the streaming generation endpoint
+    pub llm_provider: &'static str,
+    pub transcription: bool,
+    pub voice_cloning: bool,
+    pub announcements: bool,
+    pub webhooks: bool,
+    pub telegram: bool,
+    pub failure_alerts: bool,
+    pub episode_archive: bool,
+    // Watched-keyword alerts for the front page; see radar.rs
+    pub radar: bool,
+    // allow, skip or approve; see topics.rs
+    pub sensitive_topics: &'static str,
+    // Enabled episode publish targets; see syndication.rs
+    pub syndication: Vec<&'static str>,
+    pub activitypub: bool,
+    // Digest selection biased towards well engaged topics; see engagement.rs
+    pub engagement_bias: bool,
+    // off, optional or required; see credentials.rs
+    pub byok_mode: &'static str,
+    pub story_feeds: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Limits {
+    pub top_stories: usize,
+    // How long HN items are served from memory; 0 when caching is off
+    pub hn_cache_ttl_secs: u64,
+    pub playback_speeds: Vec<f64>,
+    pub max_silence_gap_secs: f64,
+    pub default_tools: Vec<String>,
+    pub allowed_tools: Vec<String>,
+    pub comment_rank_weights: crate::ranking::Weights,
+}
+
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        api_version: API_VERSION,
+        service_version: env!("CARGO_PKG_VERSION"),
+        personas: personas(),
+        default_persona: default_persona(),
+        fallback_persona: fallback_persona(),
+        voices: GEMINI_VOICES.to_vec(),
+        default_voice: default_tts_voice(),
+        features: Features {
+            podcast_generation: is_set("ALCHEMYST_API_KEY"),
+            tts: match crate::tts::provider_name().as_str() {
+                "piper" => crate::tts::piper_model().is_some(),
+                "elevenlabs" => is_set("ELEVENLABS_API_KEY"),
+                "openai" => is_set("OPENAI_API_KEY"),
+                _ => is_set("GEMINI_API_KEY"),
+            },
+            tts_provider: crate::tts::synthesizer().name(),
+            llm_provider: crate::upstream::adapter().name(),
+            transcription: crate::transcribe::is_configured(),
+            voice_cloning: crate::voices::cloning_available(),
+            announcements: is_set("ANNOUNCEMENTS_PATH"),
+            webhooks: is_set("HOOKS_SECRET"),
+            telegram: is_set("TELEGRAM_BOT_TOKEN"),
+            failure_alerts: is_set("ALERT_WEBHOOK_URL") || is_set("SMTP_HOST"),
+            episode_archive: crate::archive::enabled(),
+            radar: crate::archive::enabled() && crate::frontpage::snapshots_enabled(),
+            sensitive_topics: crate::topics::sensitive_policy().name(),
+            syndication: crate::syndication::targets().iter().map(|t| t.name()).collect(),
+            activitypub: crate::activitypub::enabled(),
+            engagement_bias: crate::engagement::bias_enabled() && crate::archive::enabled(),
+            byok_mode: crate::credentials::mode().name(),
+            story_feeds: vec!["top"],
+        },
+        limits: Limits {
+            top_stories: TOP_STORIES_LIMIT,
+            hn_cache_ttl_secs: crate::hn_cache::ttl().map_or(0, |ttl| ttl.as_secs()),
+            playback_speeds: crate::audio::SUPPORTED_SPEEDS.to_vec(),
+            max_silence_gap_secs: crate::audio::max_gap_secs(),
+            default_tools: default_tools(),
+            allowed_tools: allowed_tools(),
+            comment_rank_weights: crate::ranking::default_weights(),
+        },
+    }
+}
+
+// --- Upstream tools ---
+
+// Retrieval tools the Alchemyst platform can use while generating: (request name, upstream flag)
+pub const GENERATION_TOOLS: [(&str, &str); 3] = [
+    ("web_search", "webSearch"),
+    ("organization_search", "organizationSearch"),
+    ("deep_research", "deepResearch"),
+];
+
+fn tool_list(key: &str, default: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+// GENERATION_TOOLS: tools enabled unless a request says otherwise (default: organization_search)
+pub fn default_tools() -> Vec<String> {
+    tool_list("GENERATION_TOOLS", "organization_search")
+}
+
+// GENERATION_ALLOWED_TOOLS: tools a request may turn on (default: all of them)
+pub fn allowed_tools() -> Vec<String> {
+    tool_list("GENERATION_ALLOWED_TOOLS", "web_search,organization_search,deep_research")
+}
+
+// Upstream `tools` object for per-request toggles such as {"deep_research": true}; Err names an
+// unknown tool or one the deployment doesn't allow
+pub fn upstream_tools(requested: &std::collections::HashMap<String, bool>) -> Result<serde_json::Value, String> {
+    let allowed = allowed_tools();
+    for (name, enabled) in requested {
+        if !GENERATION_TOOLS.iter().any(|(tool, _)| tool == name) {
+            let known: Vec<&str> = GENERATION_TOOLS.iter().map(|(tool, _)| *tool).collect();
+            return Err(format!("Unknown tool '{}'. Known tools: {}", name, known.join(", ")));
+        }
+        if *enabled && !allowed.contains(name) {
+            return Err(format!("Tool '{}' is not enabled on this server. Allowed tools: {}", name, allowed.join(", ")));
+        }
+    }
+
+    let defaults = default_tools();
+    let mut tools = serde_json::json!({ "researchIcps": false });
+    for (name, flag) in GENERATION_TOOLS {
+        let enabled = requested.get(name).copied().unwrap_or_else(|| defaults.iter().any(|d| d == name));
+        tools[flag] = serde_json::Value::Bool(enabled);
+    }
+    Ok(tools)
+}
+
+// --- Startup validation ---
+
+// Problems found in the environment at boot; errors abort startup, warnings only disable features
+#[derive(Debug, Default)]
+pub struct ConfigReport {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+fn check_number<T: std::str::FromStr + PartialOrd + Default>(key: &str, errors: &mut Vec<String>) {
+    if let Ok(value) = env::var(key) {
+        match value.trim().parse::<T>() {
+            Ok(n) if n > T::default() => {}
+            _ => errors.push(format!("{} must be a positive number, got '{}'", key, value)),
+        }
+    }
+}
+
+fn check_url(key: &str, errors: &mut Vec<String>) {
+    if let Ok(value) = env::var(key) {
+        match reqwest::Url::parse(&value) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+            _ => errors.push(format!("{} must be an http(s) URL, got '{}'", key, value)),
+        }
+    }
+}
+
+pub fn validate() -> ConfigReport {
+    let mut report = ConfigReport::default();
+    let errors = &mut report.errors;
+
+    if let Ok(port) = env::var("PORT") {
+        if port.parse::<u16>().map(|p| p == 0).unwrap_or(true) {
+            errors.push(format!("PORT must be a port number between 1 and 65535, got '{}'", port));
+        }
+    }
+
+    if let Ok(addrs) = env::var("BIND_ADDRS") {
+        for addr in addrs.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+            if addr.parse::<std::net::SocketAddr>().is_err() {
+                errors.push(format!("BIND_ADDRS entry '{}' is not an address like 0.0.0.0:3001 or [::]:3001", addr));
+            }
+        }
+    }
+    if let Some(dir) = unix_socket_path().as_deref().map(std::path::Path::new).and_then(|p| p.parent()) {
+        if !dir.as_os_str().is_empty() && !dir.is_dir() {
+            errors.push(format!("UNIX_SOCKET_PATH directory {} doesn't exist", dir.display()));
+        }
+    }
+
+    check_url("ALCHEMYST_API_URL", errors);
+    check_url("PUBLIC_BASE_URL", errors);
+    check_url("SITE_URL", errors);
+    check_url("ALERT_WEBHOOK_URL", errors);
+    check_url("RADAR_WEBHOOK_URL", errors);
+    check_url("OPENAI_BASE_URL", errors);
+    check_url("OLLAMA_URL", errors);
+
+    // With BYOK the provider keys below may come from each request instead
+    let byok = crate::credentials::mode() != crate::credentials::Mode::Off;
+    let alchemyst_key = env::var("ALCHEMYST_API_KEY").unwrap_or_default();
+    if alchemyst_key.is_empty() {
+        if !byok {
+            report.warnings.push("ALCHEMYST_API_KEY is not set; context and podcast generation are disabled".to_string());
+        }
+    } else if alchemyst_key.trim() != alchemyst_key || alchemyst_key.contains(char::is_whitespace) {
+        errors.push("ALCHEMYST_API_KEY contains whitespace (check for stray quotes or newlines)".to_string());
+    }
+
+    // Google API keys are 39 characters starting with "AIza"
+    let tts_provider = crate::tts::provider_name();
+    match env::var("GEMINI_API_KEY") {
+        Ok(key) if !key.is_empty() => {
+            let looks_valid = key.starts_with("AIza") && key.len() == 39;
+            if !looks_valid {
+                errors.push("GEMINI_API_KEY doesn't look like a Google API key (expected 39 characters starting with 'AIza')".to_string());
+            }
+        }
+        _ if tts_provider == "gemini" && !byok => report.warnings.push("GEMINI_API_KEY is not set; text-to-speech is disabled".to_string()),
+        _ => {}
+    }
+
+    match tts_provider.as_str() {
+        "gemini" => {}
+        "elevenlabs" if !is_set("ELEVENLABS_API_KEY") && !byok => {
+            errors.push("ELEVENLABS_API_KEY is required when TTS_PROVIDER=elevenlabs".to_string());
+        }
+        "elevenlabs" => {}
+        "openai" if !is_set("OPENAI_API_KEY") && !byok => {
+            errors.push("OPENAI_API_KEY is required when TTS_PROVIDER=openai".to_string());
+        }
+        "openai" => {}
+        "piper" => match crate::tts::piper_model() {
+            Some(model) if !std::path::Path::new(&model).is_file() => {
+                errors.push(format!("PIPER_MODEL {} doesn't exist", model));
+            }
+            Some(_) => {}
+            None => errors.push("PIPER_MODEL is required when TTS_PROVIDER=piper".to_string()),
+        },
+        other => errors.push(format!("TTS_PROVIDER must be gemini, piper, elevenlabs or openai, got '{}'", other)),
+    }
+
+    let known_tools: Vec<&str> = GENERATION_TOOLS.iter().map(|(tool, _)| *tool).collect();
+    let allowed = allowed_tools();
+    for tool in allowed.iter().chain(default_tools().iter()) {
+        if !known_tools.contains(&tool.as_str()) {
+            errors.push(format!("Unknown generation tool '{}' (known: {})", tool, known_tools.join(", ")));
+        }
+    }
+    for tool in default_tools() {
+        if !allowed.contains(&tool) {
+            errors.push(format!("GENERATION_TOOLS enables '{}', which GENERATION_ALLOWED_TOOLS doesn't allow", tool));
+        }
+    }
+
+    match crate::upstream::provider_name().as_str() {
+        "alchemyst" | "ollama" => {}
+        "openai" if !is_set("OPENAI_API_KEY") && !byok => errors.push("OPENAI_API_KEY is required when LLM_PROVIDER=openai".to_string()),
+        "openai" => {}
+        other => errors.push(format!("LLM_PROVIDER must be alchemyst, openai or ollama, got '{}'", other)),
+    }
+
+    // Bot tokens look like "123456:ABC-DEF..."
+    if let Ok(token) = env::var("TELEGRAM_BOT_TOKEN") {
+        let valid = token
+            .split_once(':')
+            .is_some_and(|(id, secret)| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) && secret.len() >= 30);
+        if !token.is_empty() && !valid {
+            errors.push("TELEGRAM_BOT_TOKEN doesn't look like a bot token (expected '<bot id>:<secret>')".to_string());
+        }
+    }
+
+    if env::var("RADAR_TELEGRAM_CHAT_ID").is_ok_and(|c| !c.is_empty()) && !is_set("TELEGRAM_BOT_TOKEN") {
+        errors.push("TELEGRAM_BOT_TOKEN is required when RADAR_TELEGRAM_CHAT_ID is set".to_string());
+    }
+
+    if let Err(e) = crate::syndication::nostr_key() {
+        errors.push(e);
+    }
+    if let Err(e) = crate::activitypub::settings() {
+        errors.push(e);
+    }
+    for relay in crate::syndication::nostr_relays() {
+        if !matches!(reqwest::Url::parse(&relay), Ok(url) if url.scheme() == "ws" || url.scheme() == "wss") {
+            errors.push(format!("NOSTR_RELAYS entry '{}' must be a ws:// or wss:// URL", relay));
+        }
+    }
+
+    if let Ok(voice) = env::var("TTS_VOICE") {
+        if !GEMINI_VOICES.contains(&voice.as_str()) {
+            errors.push(format!("TTS_VOICE '{}' is not a Gemini voice (see GET /api/config for the list)", voice));
+        }
+    }
+
+    if let Some(fallback) = fallback_persona() {
+        let known = crate::personas::names();
+        if !known.iter().any(|p| p.eq_ignore_ascii_case(&fallback)) {
+            errors.push(format!("FALLBACK_PERSONA '{}' is not a known persona ({})", fallback, known.join(", ")));
+        }
+    }
+
+    if let Ok(mode) = env::var("BYOK_MODE") {
+        if !["off", "optional", "required"].contains(&mode.to_lowercase().as_str()) {
+            errors.push(format!("BYOK_MODE must be off, optional or required, got '{}'", mode));
+        }
+    }
+
+    if let Ok(policy) = env::var("SENSITIVE_TOPICS") {
+        if crate::topics::SensitivePolicy::parse(&policy).is_none() {
+            errors.push(format!("SENSITIVE_TOPICS must be allow, skip or approve, got '{}'", policy));
+        }
+    }
+
+    if let Ok(provider) = env::var("WHISPER_PROVIDER") {
+        if provider != "openai" && provider != "local" {
+            errors.push(format!("WHISPER_PROVIDER must be openai or local, got '{}'", provider));
+        }
+    }
+
+    check_number::<u32>("SCRIPT_MAX_TOKENS", errors);
+    check_number::<u32>("ALERT_FAILURE_THRESHOLD", errors);
+    check_number::<u16>("SMTP_PORT", errors);
+    check_number::<f64>("AUDIO_MAX_GAP_SECS", errors);
+    check_number::<usize>("FETCH_MAX_PER_DOMAIN", errors);
+    check_number::<usize>("JOBS_MAX_CONCURRENT", errors);
+    if let Err(e) = crate::ranking::configured_weights() {
+        errors.push(format!("COMMENT_RANK_WEIGHTS: {}", e));
+    }
+    check_number::<u32>("JOBS_MAX_ATTEMPTS", errors);
+    check_number::<u32>("CALLBACK_MAX_ATTEMPTS", errors);
+    for priority in crate::jobs::Priority::ALL {
+        check_number::<usize>(&priority.limit_key(), errors);
+    }
+
+    if env::var("SMTP_HOST").is_ok_and(|h| !h.is_empty()) {
+        for key in ["ALERT_EMAIL_FROM", "ALERT_EMAIL_TO"] {
+            if env::var(key).map(|v| v.is_empty()).unwrap_or(true) {
+                errors.push(format!("{} is required when SMTP_HOST is set", key));
+            }
+        }
+    }
+
+    if let Ok(mode) = env::var("LINK_VERIFICATION") {
+        if !["off", "flag", "remove"].contains(&mode.as_str()) {
+            errors.push(format!("LINK_VERIFICATION must be one of off, flag, remove; got '{}'", mode));
+        }
+    }
+
+    if is_set("TLS_CERT_PATH") != is_set("TLS_KEY_PATH") {
+        errors.push("TLS_CERT_PATH and TLS_KEY_PATH must be set together".to_string());
+    }
+    if let Some((cert, key)) = tls_paths() {
+        for (name, path) in [("TLS_CERT_PATH", cert), ("TLS_KEY_PATH", key)] {
+            if let Err(e) = std::fs::File::open(&path) {
+                errors.push(format!("{} {} can't be read: {}", name, path, e));
+            }
+        }
+    }
+
+    if let Ok(path) = env::var("ANNOUNCEMENTS_PATH") {
+        if !path.is_empty() {
+            match std::fs::read_to_string(&path) {
+                Ok(raw) => {
+                    if let Err(e) = serde_json::from_str::<Vec<crate::announcements::Announcement>>(&raw) {
+                        errors.push(format!("ANNOUNCEMENTS_PATH {} is not a valid announcements file: {}", path, e));
+                    }
+                }
+                Err(e) => errors.push(format!("ANNOUNCEMENTS_PATH {} can't be read: {}", path, e)),
+            }
+        }
+    }
+
+    report
+}
+
+// Optional (CONFIG_CHECK_REACHABILITY=true): make sure the Alchemyst API answers at all
+pub async fn check_reachability(report: &mut ConfigReport) {
+    if !env::var("CONFIG_CHECK_REACHABILITY").is_ok_and(|v| v == "true" || v == "1") {
+        return;
+    }
+    let url = alchemyst_api_url();
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .expect("Failed to build HTTP client");
+    if let Err(e) = client.get(&url).send().await {
+        report.errors.push(format!("ALCHEMYST_API_URL {} is not reachable: {}", url, e));
+    }
+}
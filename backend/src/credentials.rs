@@ -0,0 +1,129 @@
+use axum::extract::Request;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+
+// Bring-your-own-key: with BYOK_MODE=optional, callers may send their own provider keys in the
+// headers below and are billed instead of the server's keys; with BYOK_MODE=required the
+// server's keys are never used for these providers. Default "off" rejects the headers. Keys
+// live only for the request (and any job it starts) and are masked in logs like configured secrets.
+
+pub const HEADERS: [(&str, &str); 4] = [
+    ("x-alchemyst-api-key", "ALCHEMYST_API_KEY"),
+    ("x-openai-api-key", "OPENAI_API_KEY"),
+    ("x-gemini-api-key", "GEMINI_API_KEY"),
+    ("x-elevenlabs-api-key", "ELEVENLABS_API_KEY"),
+];
+
+const MIN_KEY_LEN: usize = 8;
+const MAX_KEY_LEN: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Off,
+    Optional,
+    Required,
+}
+
+impl Mode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Off => "off",
+            Mode::Optional => "optional",
+            Mode::Required => "required",
+        }
+    }
+}
+
+pub fn mode() -> Mode {
+    match env::var("BYOK_MODE").unwrap_or_default().to_lowercase().as_str() {
+        "optional" => Mode::Optional,
+        "required" => Mode::Required,
+        _ => Mode::Off,
+    }
+}
+
+// Provider keys supplied with the current request, by environment variable name
+#[derive(Clone, Default)]
+pub struct Credentials(HashMap<&'static str, String>);
+
+impl Credentials {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// Names only, so a logged job or request never shows a key
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+tokio::task_local! {
+    static REQUEST: Credentials;
+}
+
+pub fn current() -> Credentials {
+    REQUEST.try_with(Credentials::clone).unwrap_or_default()
+}
+
+// Carries the caller's keys into work spawned on its behalf (streams, background jobs)
+pub async fn scope<F: Future>(credentials: Credentials, f: F) -> F::Output {
+    REQUEST.scope(credentials, f).await
+}
+
+// The key the caller sent for `key`, if any
+pub fn supplied(key: &str) -> Option<String> {
+    REQUEST.try_with(|c| c.0.get(key).cloned()).ok().flatten()
+}
+
+// Request key first, then the server's own unless BYOK is required
+pub fn get(key: &str) -> Option<String> {
+    let supplied = supplied(key);
+    if supplied.is_some() || mode() == Mode::Required && HEADERS.iter().any(|(_, k)| *k == key) {
+        return supplied;
+    }
+    env::var(key).ok().filter(|v| !v.is_empty())
+}
+
+pub fn require(key: &str) -> Result<String, String> {
+    get(key).ok_or_else(|| match HEADERS.iter().find(|(_, k)| *k == key) {
+        Some((header, _)) if mode() == Mode::Required => format!("{} is required for this request", header),
+        _ => format!("{} is not configured", key),
+    })
+}
+
+// Values to mask in logs for the current request
+pub fn supplied_values() -> Vec<String> {
+    REQUEST.try_with(|c| c.0.values().cloned().collect()).unwrap_or_default()
+}
+
+fn valid_key(value: &str) -> bool {
+    (MIN_KEY_LEN..=MAX_KEY_LEN).contains(&value.len()) && value.bytes().all(|b| b.is_ascii_graphic())
+}
+
+pub async fn layer(request: Request, next: Next) -> Response {
+    let mut credentials = Credentials::default();
+    for (header, key) in HEADERS {
+        let Some(value) = request.headers().get(header) else { continue };
+        if mode() == Mode::Off {
+            return reject(format!("{} is not accepted: per-request credentials are disabled (BYOK_MODE)", header));
+        }
+        // Never echo the value back
+        match value.to_str() {
+            Ok(value) if valid_key(value.trim()) => {
+                credentials.0.insert(key, value.trim().to_string());
+            }
+            _ => return reject(format!("Invalid {} header", header)),
+        }
+    }
+    REQUEST.scope(credentials, next.run(request)).await
+}
+
+fn reject(error: String) -> Response {
+    (StatusCode::BAD_REQUEST, axum::Json(serde_json::json!({ "error": error }))).into_response()
+}
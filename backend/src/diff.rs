@@ -0,0 +1,183 @@
+use serde::Serialize;
+
+// Diffs between script versions: a longest-common-subsequence diff over lines (or any other
+// units), which is plenty for scripts of a few hundred lines. `compare` gives editors the
+// structured view: `## ` segments added or removed, and the sentences that changed inside the
+// segments both versions share.
+
+const OPENING: &str = "Opening";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+    Equal,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Line {
+    pub op: Op,
+    pub text: String,
+}
+
+// Edit script from `old` to `new`; removals come before additions at the same position
+pub fn diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    // lengths[i][j]: LCS length of old[i..] and new[j..]
+    let mut lengths = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] { lengths[i + 1][j + 1] + 1 } else { lengths[i + 1][j].max(lengths[i][j + 1]) };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            ops.push((Op::Equal, old[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            ops.push((Op::Removed, old[i]));
+            i += 1;
+        } else {
+            ops.push((Op::Added, new[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[i..].iter().map(|line| (Op::Removed, *line)));
+    ops.extend(new[j..].iter().map(|line| (Op::Added, *line)));
+    ops
+}
+
+pub fn lines(old: &str, new: &str) -> Vec<Line> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    diff(&old, &new).into_iter().map(|(op, text)| Line { op, text: text.to_string() }).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SegmentChange {
+    pub title: String,
+    // Added and removed sentences only, in script order
+    pub sentences: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Comparison {
+    pub added_segments: Vec<String>,
+    pub removed_segments: Vec<String>,
+    pub changed_segments: Vec<SegmentChange>,
+    pub unchanged_segments: usize,
+}
+
+// (title, body) per `## ` section; text before the first heading is the "Opening"
+fn segments(script: &str) -> Vec<(&str, String)> {
+    let mut segments = vec![(OPENING, String::new())];
+    for line in script.lines() {
+        match line.trim_start().strip_prefix("## ") {
+            Some(title) => segments.push((title.trim(), String::new())),
+            None => {
+                let body = &mut segments.last_mut().unwrap().1;
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+    }
+    segments.retain(|(title, body)| *title != OPENING || !body.trim().is_empty());
+    segments
+}
+
+// Ends at ., ! or ? followed by whitespace, or at a line break
+fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    for line in text.lines() {
+        let mut start = 0;
+        let mut chars = line.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let boundary = matches!(c, '.' | '!' | '?') && chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+            if boundary {
+                sentences.push(line[start..i + c.len_utf8()].trim());
+                start = i + c.len_utf8();
+            }
+        }
+        sentences.push(line[start..].trim());
+    }
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
+pub fn compare(old: &str, new: &str) -> Comparison {
+    let (old, new) = (segments(old), segments(new));
+    let body = |segments: &[(&str, String)], title: &str| segments.iter().find(|(t, _)| *t == title).map(|(_, body)| body.clone());
+    let mut comparison = Comparison { added_segments: Vec::new(), removed_segments: Vec::new(), changed_segments: Vec::new(), unchanged_segments: 0 };
+    for (title, _) in &old {
+        if body(&new, title).is_none() {
+            comparison.removed_segments.push(title.to_string());
+        }
+    }
+    for (title, new_body) in &new {
+        let Some(old_body) = body(&old, title) else {
+            comparison.added_segments.push(title.to_string());
+            continue;
+        };
+        let changed: Vec<Line> = diff(&sentences(&old_body), &sentences(new_body))
+            .into_iter()
+            .filter(|(op, _)| *op != Op::Equal)
+            .map(|(op, text)| Line { op, text: text.to_string() })
+            .collect();
+        if changed.is_empty() {
+            comparison.unchanged_segments += 1;
+        } else {
+            comparison.changed_segments.push(SegmentChange { title: title.to_string(), sentences: changed });
+        }
+    }
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_common_lines_and_marks_changes() {
+        let ops = lines("intro\nold story\noutro", "intro\nnew story\noutro\nbonus");
+        let summary: Vec<(Op, &str)> = ops.iter().map(|line| (line.op, line.text.as_str())).collect();
+        assert_eq!(
+            summary,
+            [
+                (Op::Equal, "intro"),
+                (Op::Removed, "old story"),
+                (Op::Added, "new story"),
+                (Op::Equal, "outro"),
+                (Op::Added, "bonus"),
+            ]
+        );
+    }
+
+    #[test]
+    fn compares_segments_and_sentences() {
+        let old = "Welcome in.\n## Rust 2.0\nIt shipped. People cheered!\n## Old news\nNothing new.\n";
+        let new = "Welcome in.\n## Rust 2.0\nIt shipped. Some grumbled? People cheered!\n## Fresh\nA new story.\n";
+        let comparison = compare(old, new);
+        assert_eq!(comparison.added_segments, ["Fresh"]);
+        assert_eq!(comparison.removed_segments, ["Old news"]);
+        assert_eq!(comparison.unchanged_segments, 1);
+        assert_eq!(
+            comparison.changed_segments,
+            [SegmentChange { title: "Rust 2.0".to_string(), sentences: vec![Line { op: Op::Added, text: "Some grumbled?".to_string() }] }]
+        );
+    }
+
+    #[test]
+    fn splits_sentences_at_terminators_and_lines() {
+        assert_eq!(sentences("One. Two! v1.2 is out?\nNo stop"), ["One.", "Two!", "v1.2 is out?", "No stop"]);
+    }
+
+    #[test]
+    fn identical_scripts_have_no_changes() {
+        assert!(lines("a\nb", "a\nb").iter().all(|line| line.op == Op::Equal));
+        assert_eq!(lines("", "a").len(), 1);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::archive::{self, Episode};
+use std::env;
+use std::time::Duration;
+use tracing::{error, info};
+
+// Scheduled publishing: an episode with a `publish_at` in the future stays out of the feeds until
+// then (see archive::with_audio), so a digest generated overnight can drop in the morning. This
+// worker checks every EMBARGO_CHECK_INTERVAL_SECS (default 30) and announces each episode whose
+// time has come like a freshly finished one: an `episode_published` event, the syndication
+// targets and a WebSub ping.
+
+fn interval() -> Duration {
+    let secs = env::var("EMBARGO_CHECK_INTERVAL_SECS").ok().and_then(|v| v.trim().parse().ok()).unwrap_or(30u64);
+    Duration::from_secs(secs.max(1))
+}
+
+pub fn spawn_release_worker() {
+    if !archive::enabled() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval());
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            release_due().await;
+        }
+    });
+}
+
+async fn release_due() {
+    let due = match archive::due_releases().await {
+        Ok(due) => due,
+        Err(e) => {
+            error!("Failed to look up embargoed episodes: {}", e);
+            return;
+        }
+    };
+    for episode in due {
+        match archive::mark_released(&episode.id).await {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                error!("Failed to release episode {}: {}", episode.id, e);
+                continue;
+            }
+        }
+        info!("Releasing embargoed episode {} (\"{}\")", episode.id, episode.title);
+        release(&episode).await;
+    }
+}
+
+async fn release(episode: &Episode) {
+    let result = serde_json::json!({
+        "episode_id": episode.id,
+        "title": episode.title,
+        "slug": episode.slug,
+        "season": episode.season,
+        "chat_id": episode.chat_id,
+        "podcast_script": episode.script,
+    });
+    crate::events::publish("episode_published", serde_json::json!({
+        "job_id": episode.job_id,
+        "episode_id": episode.id,
+        "title": episode.title,
+        "slug": episode.slug,
+        "season": episode.season,
+        "chat_id": episode.chat_id,
+    }));
+    crate::syndication::publish_episode(episode.job_id.as_deref().unwrap_or(&episode.id), &result).await;
+    // Audio held back by QA isn't in the feeds either
+    let in_feeds = episode
+        .audio
+        .as_ref()
+        .is_some_and(|audio| audio.qa.as_ref().is_none_or(|qa| qa.passed) || !crate::audio::qa_blocks_publish());
+    if in_feeds {
+        crate::websub::spawn_ping(crate::feed::subscription_urls(&episode.topics));
+        crate::activitypub::spawn_publish(episode.id.clone());
+    }
+}
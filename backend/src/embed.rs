@@ -0,0 +1,145 @@
+use crate::announcements::Chapter;
+use crate::archive::Episode;
+use crate::seo::escape_xml;
+
+// Embeddable player (GET /embed/:episode_id): one self-contained HTML page per episode, with the
+// audio element, chapters that seek when clicked and the transcript, and no external scripts,
+// styles or fonts, so it can sit in an iframe on any site. Pages advertise an oEmbed endpoint
+// (GET /oembed?url=) that returns the iframe for a page URL, so CMSs turn a pasted link into a player.
+
+pub const WIDTH: u32 = 600;
+pub const HEIGHT: u32 = 400;
+
+pub const STYLE: &str = "body{margin:0;font:15px/1.5 system-ui,sans-serif;color:#1a1a1a;background:#fff}\
+main{padding:12px 16px}h1{font-size:17px;margin:0 0 8px}audio{width:100%}\
+ol{padding-left:0;list-style:none;margin:8px 0}li button{all:unset;cursor:pointer;display:block;width:100%;padding:2px 0}\
+li button:hover,li.current button{color:#ff6600}time{display:inline-block;width:4em;color:#666;font-variant-numeric:tabular-nums}\
+details{margin-top:8px}summary{cursor:pointer}.transcript h2{font-size:15px;margin:12px 0 4px}\
+footer{font-size:12px;color:#666;margin-top:8px}footer a{color:inherit}";
+
+// Seeks on chapter clicks and highlights the chapter being played
+pub const SCRIPT: &str = "const a=document.querySelector('audio'),c=[...document.querySelectorAll('li[data-start]')];\
+c.forEach(l=>l.querySelector('button').onclick=()=>{a.currentTime=+l.dataset.start;a.play()});\
+a.ontimeupdate=()=>{let n=null;c.forEach(l=>{if(+l.dataset.start<=a.currentTime)n=l});c.forEach(l=>l.classList.toggle('current',l===n))};";
+
+pub fn page_url(episode_id: &str) -> String {
+    format!("{}/embed/{}", crate::alerts::public_base_url(), episode_id)
+}
+
+// The episode id of an embed page URL (as served by this backend, query ignored)
+pub fn episode_id(url: &str) -> Option<&str> {
+    let prefix = format!("{}/embed/", crate::alerts::public_base_url());
+    let id = url.strip_prefix(&prefix)?.split(['?', '#']).next()?;
+    (!id.is_empty() && !id.contains('/')).then_some(id)
+}
+
+pub fn timestamp(secs: f64) -> String {
+    let secs = secs.max(0.0).round() as u64;
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+    }
+}
+
+// Script headings become transcript headings, everything else paragraphs
+pub fn transcript(script: &str) -> String {
+    script
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.strip_prefix("## ") {
+            Some(heading) => format!("<h2>{}</h2>", escape_xml(heading.trim())),
+            None => format!("<p>{}</p>", escape_xml(line.trim_start_matches('#').trim())),
+        })
+        .collect()
+}
+
+// Chapter list that SCRIPT makes seek; empty without chapters
+pub fn chapters(chapters: &[Chapter]) -> String {
+    if chapters.is_empty() {
+        return String::new();
+    }
+    let mut html = String::from("<ol class=\"chapters\">\n");
+    for chapter in chapters {
+        html.push_str(&format!(
+            "<li data-start=\"{}\"><button type=\"button\"><time>{}</time>{}</button></li>\n",
+            chapter.start_secs,
+            timestamp(chapter.start_secs),
+            escape_xml(&chapter.title)
+        ));
+    }
+    html.push_str("</ol>\n");
+    html
+}
+
+// None until the episode's audio is rendered
+pub fn page(episode: &Episode) -> Option<String> {
+    let audio = episode.audio.as_ref()?;
+    let title = escape_xml(&episode.title);
+    let oembed_query = url::form_urlencoded::Serializer::new(String::new()).append_pair("url", &page_url(&episode.id)).append_pair("format", "json").finish();
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n",
+        title
+    );
+    html.push_str(&format!(
+        "<link rel=\"alternate\" type=\"application/json+oembed\" href=\"{}\" title=\"{}\">\n",
+        escape_xml(&format!("{}/oembed?{}", crate::alerts::public_base_url(), oembed_query)),
+        title
+    ));
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n<main>\n<h1>{}</h1>\n", STYLE, title));
+    html.push_str(&format!("<audio controls preload=\"metadata\" src=\"{}\"></audio>\n", escape_xml(&audio.url)));
+    html.push_str(&chapters(&audio.chapters));
+    if let Some(script) = episode.script.as_deref().filter(|script| !script.trim().is_empty()) {
+        html.push_str(&format!("<details class=\"transcript\">\n<summary>Transcript</summary>\n{}\n</details>\n", transcript(script)));
+    }
+    html.push_str(&format!(
+        "<footer><a href=\"{}\" target=\"_blank\" rel=\"noopener\">{}</a></footer>\n</main>\n<script>{}</script>\n</body>\n</html>\n",
+        escape_xml(&crate::seo::site_url()),
+        escape_xml(&crate::feed::show_title()),
+        SCRIPT
+    ));
+    Some(html)
+}
+
+// oEmbed 1.0 "rich" response; the iframe keeps within maxwidth/maxheight when the consumer sets them
+pub fn oembed(episode: &Episode, max_width: Option<u32>, max_height: Option<u32>) -> serde_json::Value {
+    let width = max_width.map_or(WIDTH, |max| max.min(WIDTH));
+    let height = max_height.map_or(HEIGHT, |max| max.min(HEIGHT));
+    serde_json::json!({
+        "version": "1.0",
+        "type": "rich",
+        "title": episode.title,
+        "provider_name": crate::feed::show_title(),
+        "provider_url": crate::seo::site_url(),
+        "width": width,
+        "height": height,
+        "html": format!(
+            "<iframe src=\"{}\" width=\"{}\" height=\"{}\" title=\"{}\" frameborder=\"0\" allow=\"autoplay\" loading=\"lazy\"></iframe>",
+            escape_xml(&page_url(&episode.id)),
+            width,
+            height,
+            escape_xml(&episode.title)
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_urls_name_the_episode() {
+        let url = page_url("3f2b");
+        assert_eq!(episode_id(&url), Some("3f2b"));
+        assert_eq!(episode_id(&format!("{}?autoplay=1", url)), Some("3f2b"));
+        assert_eq!(episode_id(&format!("{}/x", url)), None);
+        assert_eq!(episode_id("https://example.com/embed/3f2b"), None);
+    }
+
+    #[test]
+    fn transcripts_keep_headings_and_escape_text() {
+        assert_eq!(transcript("## Rust <3\n\nHost: a & b\n"), "<h2>Rust &lt;3</h2><p>Host: a &amp; b</p>");
+        assert_eq!(timestamp(65.4), "1:05");
+        assert_eq!(timestamp(3725.0), "1:02:05");
+    }
+}
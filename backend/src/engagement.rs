@@ -0,0 +1,152 @@
+use crate::announcements::{Chapter, ChapterKind};
+use crate::archive::{self, TopicActivity};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+
+// Topic engagement: players report the story segments listeners skip or finish
+// (POST /api/episodes/:id/playback), each attributed to the topics of its chapter title, or the
+// episode's when the title fits none. Per topic, the share of segments finished and the average
+// rating of its episodes make a 0-1 score (GET /admin/engagement), both over the last
+// ENGAGEMENT_DAYS (default 90). With ENGAGEMENT_BIAS=true digest story selection leans on it:
+// a story moves up to twice as far up or down the candidate list by its best topic's score.
+
+// Neutral observations each rate starts from, so a topic with a handful of events stays near 0.5
+const PRIOR: f64 = 5.0;
+const NEUTRAL: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    Skip,
+    Complete,
+}
+
+impl Event {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Event::Skip => "skip",
+            Event::Complete => "complete",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TopicEngagement {
+    pub topic: String,
+    pub completes: u64,
+    pub skips: u64,
+    pub ratings: u64,
+    // None without ratings
+    pub average_rating: Option<f64>,
+    pub score: f64,
+}
+
+pub fn bias_enabled() -> bool {
+    env::var("ENGAGEMENT_BIAS").is_ok_and(|v| v == "true" || v == "1")
+}
+
+pub fn days() -> i64 {
+    env::var("ENGAGEMENT_DAYS").ok().and_then(|v| v.trim().parse().ok()).filter(|days| *days > 0).unwrap_or(90)
+}
+
+// The story segment playing at `position_secs`, with its index; None for announcements
+pub fn chapter_at(chapters: &[Chapter], position_secs: f64) -> Option<(usize, &Chapter)> {
+    let (index, chapter) = chapters.iter().enumerate().take_while(|(_, chapter)| chapter.start_secs <= position_secs).last()?;
+    matches!(chapter.kind, ChapterKind::Content).then_some((index, chapter))
+}
+
+// Topics a segment counts towards: its title's, else the episode's
+pub fn segment_topics(chapter_title: &str, episode_topics: &[String]) -> Vec<String> {
+    let topics = crate::topics::classify_rules(chapter_title, "");
+    if topics.is_empty() {
+        episode_topics.to_vec()
+    } else {
+        topics.into_iter().map(String::from).collect()
+    }
+}
+
+// Mean of the finished share and the rating (1-5 as 0-1), each smoothed towards NEUTRAL
+fn score(activity: &TopicActivity) -> f64 {
+    let listened = (activity.completes as f64 + PRIOR * NEUTRAL) / ((activity.completes + activity.skips) as f64 + PRIOR);
+    let stars: u64 = activity.ratings.distribution.iter().zip(0u64..).map(|(n, stars)| n * stars).sum();
+    let liked = (stars as f64 / 4.0 + PRIOR * NEUTRAL) / (activity.ratings.count as f64 + PRIOR);
+    ((listened + liked) / 2.0 * 1000.0).round() / 1000.0
+}
+
+fn summarize(activity: HashMap<String, TopicActivity>) -> Vec<TopicEngagement> {
+    let mut topics: Vec<TopicEngagement> = activity
+        .into_iter()
+        .map(|(topic, activity)| TopicEngagement {
+            score: score(&activity),
+            topic,
+            completes: activity.completes,
+            skips: activity.skips,
+            ratings: activity.ratings.count,
+            average_rating: activity.ratings.average,
+        })
+        .collect();
+    topics.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.topic.cmp(&b.topic)));
+    topics
+}
+
+// Best engaged first, over the last `days` (ENGAGEMENT_DAYS by default)
+pub async fn scores(days: Option<i64>) -> Result<Vec<TopicEngagement>, String> {
+    let since = Utc::now() - Duration::days(days.unwrap_or_else(self::days));
+    Ok(summarize(archive::topic_activity(Some(since)).await?))
+}
+
+// `items` in list order, reordered so better engaged topics come sooner: an item's place is
+// scaled by 2^(1 - 2 * score) of its best scored topic, so unscored items keep theirs
+pub fn rerank<T>(items: Vec<T>, scores: &HashMap<String, f64>, topics_of: impl Fn(&T) -> Vec<&'static str>) -> Vec<T> {
+    let mut keyed: Vec<(f64, T)> = items
+        .into_iter()
+        .enumerate()
+        .map(|(position, item)| {
+            let score = topics_of(&item).iter().filter_map(|topic| scores.get(*topic).copied()).reduce(f64::max).unwrap_or(NEUTRAL);
+            ((position + 1) as f64 * 2f64.powf(1.0 - 2.0 * score), item)
+        })
+        .collect();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    keyed.into_iter().map(|(_, item)| item).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(start_secs: f64, kind: ChapterKind) -> Chapter {
+        Chapter { start_secs, title: format!("at {}", start_secs), kind, prerecorded: false }
+    }
+
+    #[test]
+    fn positions_map_to_story_segments() {
+        let chapters = [chapter(0.0, ChapterKind::Content), chapter(30.0, ChapterKind::Announcement), chapter(45.0, ChapterKind::Content)];
+        assert_eq!(chapter_at(&chapters, 12.0).map(|(index, _)| index), Some(0));
+        assert_eq!(chapter_at(&chapters, 31.0).map(|(index, _)| index), None);
+        assert_eq!(chapter_at(&chapters, 600.0).map(|(index, _)| index), Some(2));
+        assert_eq!(chapter_at(&chapters, -1.0).map(|(index, _)| index), None);
+        assert_eq!(segment_topics("Untitled", &["ai".to_string()]), vec!["ai".to_string()]);
+    }
+
+    #[test]
+    fn skipped_and_poorly_rated_topics_score_low() {
+        let mut liked = TopicActivity { completes: 20, skips: 2, ..Default::default() };
+        liked.ratings.distribution = [0, 0, 0, 2, 8];
+        liked.ratings.count = 10;
+        let skipped = TopicActivity { completes: 1, skips: 15, ..Default::default() };
+        assert_eq!(score(&TopicActivity::default()), NEUTRAL);
+        assert!(score(&liked) > 0.8);
+        assert!(score(&skipped) < 0.4);
+
+        let scores = HashMap::from([("rust".to_string(), 0.9), ("crypto".to_string(), 0.1)]);
+        let topics_of = |title: &&str| match *title {
+            "rust" => vec!["rust"],
+            "crypto" => vec!["crypto"],
+            _ => Vec::new(),
+        };
+        assert_eq!(rerank(vec!["crypto", "rust", "other"], &scores, topics_of), vec!["rust", "crypto", "other"]);
+        assert_eq!(rerank(vec!["other", "crypto"], &HashMap::new(), topics_of), vec!["other", "crypto"]);
+    }
+}
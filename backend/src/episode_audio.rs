@@ -0,0 +1,417 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tracing::{error, info};
+
+// Rendered episodes: POST /api/podcast/:id/audio synthesizes an archived script, encodes it to MP3
+// with ffmpeg (FFMPEG_BINARY, default "ffmpeg"; MP3_BITRATE, default 128k) and saves it in
+// EPISODE_AUDIO_DIR (default "episode_audio"), which is served at /audio/<file>. Download URLs
+// start with PUBLIC_BASE_URL. The file starts with an ID3v2.4 tag holding the title and CHAP/CTOC
+// chapter frames, which Overcast, Pocket Casts and Apple Podcasts show as chapter navigation.
+//
+// Each render also produces lighter renditions for listeners on poor connections: low (48 kbps
+// Opus) and medium (MP3_MEDIUM_BITRATE, default 64k MP3) next to the high-quality main file. They
+// are picked with ?quality= on the feeds and GET /api/episodes/:id/audio, and the RSS feed lists
+// them as podcast:alternateEnclosure. For seeking in long episodes the web player streams HLS: AAC
+// segments of every quality in EPISODE_AUDIO_DIR/hls/<episode>/ behind a master playlist at
+// GET /api/episodes/:id/hls/playlist.m3u8.
+//
+// Files are kept in the content-addressed store (see artifacts.rs), so identical audio is stored
+// once; encodings are bit-exact, so the same script, title and voice encode to the same bytes.
+
+pub fn dir() -> PathBuf {
+    PathBuf::from(env::var("EPISODE_AUDIO_DIR").ok().filter(|d| !d.is_empty()).unwrap_or_else(|| "episode_audio".to_string()))
+}
+
+pub fn url(file: &str) -> String {
+    format!("{}/audio/{}", crate::alerts::public_base_url(), file)
+}
+
+// Stable per episode, so re-rendering replaces the previous file; episode numbers are unique, so
+// no two episodes share a name
+pub fn file_name(slug: &str, season: Option<u32>, episode_number: u32) -> String {
+    format!("{}.mp3", crate::episodes::episode_file_stem(slug, season, Some(episode_number)))
+}
+
+pub fn rendition_file_name(slug: &str, season: Option<u32>, episode_number: u32, quality: Quality) -> String {
+    format!("{}-{}.{}", crate::episodes::episode_file_stem(slug, season, Some(episode_number)), quality.name(), quality.extension())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quality {
+    Low,
+    Medium,
+    High,
+}
+
+impl Quality {
+    // The main file is High; these are rendered alongside it
+    pub const RENDITIONS: [Quality; 2] = [Quality::Low, Quality::Medium];
+
+    // ?quality=
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "low" => Some(Quality::Low),
+            "medium" => Some(Quality::Medium),
+            "high" => Some(Quality::High),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Quality::Low => "low",
+            Quality::Medium => "medium",
+            Quality::High => "high",
+        }
+    }
+
+    fn bitrate(self) -> String {
+        match self {
+            Quality::Low => "48k".to_string(),
+            Quality::Medium => env::var("MP3_MEDIUM_BITRATE").unwrap_or_else(|_| "64k".to_string()),
+            Quality::High => env::var("MP3_BITRATE").unwrap_or_else(|_| "128k".to_string()),
+        }
+    }
+
+    // "64k" -> 64, for podcast:alternateEnclosure's bitrate in bits per second
+    pub fn bits_per_second(self) -> u64 {
+        self.bitrate().trim_end_matches(['k', 'K']).parse::<u64>().map_or(0, |kbps| kbps * 1000)
+    }
+
+    fn codec(self) -> &'static str {
+        match self {
+            Quality::Low => "libopus",
+            _ => "libmp3lame",
+        }
+    }
+
+    fn container(self) -> &'static str {
+        match self {
+            Quality::Low => "ogg",
+            _ => "mp3",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Quality::Low => "opus",
+            _ => "mp3",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Quality::Low => "audio/opus",
+            _ => "audio/mpeg",
+        }
+    }
+}
+
+// A lighter encoding of the episode next to the main file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rendition {
+    pub quality: Quality,
+    pub file: String,
+    pub url: String,
+    pub bytes: u64,
+    pub mime_type: String,
+    pub bits_per_second: u64,
+}
+
+// 16-bit mono PCM in, the quality's codec out
+pub async fn encode(pcm: &[u8], sample_rate: u32, quality: Quality) -> Result<Vec<u8>, String> {
+    let mut output = vec!["-codec:a".to_string(), quality.codec().to_string(), "-b:a".to_string(), quality.bitrate()];
+    output.extend(BITEXACT.iter().map(|arg| arg.to_string()));
+    // Our own tag with the chapters goes in front of MP3s instead
+    if quality.container() == "mp3" {
+        output.extend(["-id3v2_version".to_string(), "0".to_string()]);
+    }
+    output.extend(["-f".to_string(), quality.container().to_string(), "pipe:1".to_string()]);
+    let encoded = ffmpeg(pcm, sample_rate, &output).await?;
+    if encoded.is_empty() {
+        return Err("The audio encoder produced no output".to_string());
+    }
+    info!("Encoded {} bytes of PCM into {} bytes of {} audio", pcm.len(), encoded.len(), quality.name());
+    Ok(encoded)
+}
+
+// No random stream serials or encoder version tags, so identical input gives identical files
+const BITEXACT: [&str; 4] = ["-fflags", "+bitexact", "-flags:a", "+bitexact"];
+
+// Feeds the PCM to ffmpeg on stdin; `output` holds the encoder and output arguments. Returns stdout
+async fn ffmpeg(pcm: &[u8], sample_rate: u32, output: &[String]) -> Result<Vec<u8>, String> {
+    let binary = env::var("FFMPEG_BINARY").unwrap_or_else(|_| "ffmpeg".to_string());
+
+    let mut child = tokio::process::Command::new(&binary)
+        .args(["-hide_banner", "-loglevel", "error", "-f", "s16le", "-ar"])
+        .arg(sample_rate.to_string())
+        .args(["-ac", "1", "-i", "pipe:0"])
+        .args(output)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            error!("Failed to start {}: {}", binary, e);
+            format!("Failed to start the audio encoder ({})", binary)
+        })?;
+
+    let mut stdin = child.stdin.take().ok_or_else(|| "Failed to open ffmpeg stdin".to_string())?;
+    let input = pcm.to_vec();
+    let writer = tokio::spawn(async move {
+        let result = stdin.write_all(&input).await;
+        drop(stdin);
+        result
+    });
+
+    let output = child.wait_with_output().await.map_err(|e| {
+        error!("ffmpeg failed: {}", e);
+        "The audio encoder failed".to_string()
+    })?;
+    if let Ok(Err(e)) = writer.await {
+        error!("Failed to write PCM to ffmpeg: {}", e);
+    }
+    if !output.status.success() {
+        error!("ffmpeg exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr));
+        return Err(format!("The audio encoder exited with {}", output.status));
+    }
+    Ok(output.stdout)
+}
+
+// --- HLS ---
+
+// Segment length; short enough that a seek in an hour-long digest starts playing at once
+const HLS_SEGMENT_SECS: u32 = 6;
+
+// One bitrate ladder rung: its media playlist and segments live in the episode's HLS directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HlsVariant {
+    pub quality: Quality,
+    // Media playlist file in the HLS directory
+    pub playlist: String,
+    pub bits_per_second: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hls {
+    // Directory under EPISODE_AUDIO_DIR/hls, served at /audio/hls/<dir>/
+    pub dir: String,
+    pub variants: Vec<HlsVariant>,
+}
+
+impl Hls {
+    // Master playlist for GET /api/episodes/:id/hls/playlist.m3u8, highest bitrate first
+    pub fn master_playlist(&self) -> String {
+        let mut playlist = String::from("#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-INDEPENDENT-SEGMENTS\n");
+        for variant in &self.variants {
+            playlist.push_str(&format!(
+                "#EXT-X-STREAM-INF:BANDWIDTH={},CODECS=\"mp4a.40.2\"\n{}\n",
+                variant.bits_per_second,
+                url(&format!("hls/{}/{}", self.dir, variant.playlist))
+            ));
+        }
+        playlist
+    }
+}
+
+// AAC in MPEG-TS segments for every quality, written to a scratch directory that replaces the
+// episode's previous one when all variants are done
+pub async fn encode_hls(pcm: &[u8], sample_rate: u32, name: &str) -> Result<Hls, String> {
+    let root = dir().join("hls");
+    let partial = root.join(format!("{}.partial", name));
+    let _ = tokio::fs::remove_dir_all(&partial).await;
+    tokio::fs::create_dir_all(&partial).await.map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+
+    let mut variants = Vec::new();
+    for quality in [Quality::High, Quality::Medium, Quality::Low] {
+        let playlist = format!("{}.m3u8", quality.name());
+        let output = [
+            "-codec:a".to_string(),
+            "aac".to_string(),
+            "-b:a".to_string(),
+            quality.bitrate(),
+            BITEXACT[0].to_string(),
+            BITEXACT[1].to_string(),
+            BITEXACT[2].to_string(),
+            BITEXACT[3].to_string(),
+            "-f".to_string(),
+            "hls".to_string(),
+            "-hls_time".to_string(),
+            HLS_SEGMENT_SECS.to_string(),
+            "-hls_playlist_type".to_string(),
+            "vod".to_string(),
+            "-hls_segment_filename".to_string(),
+            partial.join(format!("{}-%04d.ts", quality.name())).to_string_lossy().into_owned(),
+            partial.join(&playlist).to_string_lossy().into_owned(),
+        ];
+        if let Err(e) = ffmpeg(pcm, sample_rate, &output).await {
+            let _ = tokio::fs::remove_dir_all(&partial).await;
+            return Err(e);
+        }
+        variants.push(HlsVariant { quality, playlist, bits_per_second: quality.bits_per_second() });
+    }
+
+    let target = root.join(name);
+    remove_hls(name).await;
+    tokio::fs::rename(&partial, &target).await.map_err(|e| format!("Failed to move HLS output into {}: {}", target.display(), e))?;
+    // Still served from the directory when this fails, just not deduplicated
+    if let Err(e) = crate::artifacts::adopt_dir(&format!("hls/{}", name)).await {
+        error!("Failed to store the HLS segments of {}: {}", name, e);
+    }
+    info!("Segmented {} bytes of PCM into {} HLS variants in {}", pcm.len(), variants.len(), target.display());
+    Ok(Hls { dir: name.to_string(), variants })
+}
+
+pub async fn remove_hls(name: &str) {
+    crate::artifacts::release_dir(&format!("hls/{}", name)).await;
+    let path = dir().join("hls").join(name);
+    if let Err(e) = tokio::fs::remove_dir_all(&path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            error!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+pub async fn save(file: &str, bytes: &[u8]) -> Result<(), String> {
+    crate::artifacts::put(file, bytes).await
+}
+
+// --- ID3 chapters ---
+
+// CTOC stores its entry count in one byte
+const MAX_CHAPTERS: usize = 255;
+
+// 7 bits per byte, as ID3v2.4 sizes are
+fn syncsafe(n: usize) -> [u8; 4] {
+    [(n >> 21) as u8 & 0x7F, (n >> 14) as u8 & 0x7F, (n >> 7) as u8 & 0x7F, n as u8 & 0x7F]
+}
+
+fn frame(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut frame = id.to_vec();
+    frame.extend_from_slice(&syncsafe(body.len()));
+    frame.extend_from_slice(&[0, 0]);
+    frame.extend_from_slice(body);
+    frame
+}
+
+// UTF-8 text frame
+fn text_frame(id: &[u8; 4], text: &str) -> Vec<u8> {
+    let mut body = vec![3];
+    body.extend_from_slice(text.as_bytes());
+    frame(id, &body)
+}
+
+fn millis(secs: f64) -> [u8; 4] {
+    ((secs.max(0.0) * 1000.0).round() as u32).to_be_bytes()
+}
+
+// ID3v2.4 tag with the title, a CTOC listing the chapters in order and one CHAP per chapter, each
+// ending where the next starts
+pub fn id3_tag(title: &str, chapters: &[crate::announcements::Chapter], duration_secs: f64) -> Vec<u8> {
+    let chapters = &chapters[..chapters.len().min(MAX_CHAPTERS)];
+    let mut frames = text_frame(b"TIT2", title);
+    if !chapters.is_empty() {
+        let mut toc = b"toc\0".to_vec();
+        // Top-level and ordered
+        toc.push(0x03);
+        toc.push(chapters.len() as u8);
+        for index in 0..chapters.len() {
+            toc.extend_from_slice(format!("chp{}\0", index).as_bytes());
+        }
+        frames.extend(frame(b"CTOC", &toc));
+    }
+    for (index, chapter) in chapters.iter().enumerate() {
+        let end = chapters.get(index + 1).map_or(duration_secs, |next| next.start_secs);
+        let mut chap = format!("chp{}\0", index).into_bytes();
+        chap.extend_from_slice(&millis(chapter.start_secs));
+        chap.extend_from_slice(&millis(end));
+        // No byte offsets; players seek by time
+        chap.extend_from_slice(&[0xFF; 8]);
+        chap.extend(text_frame(b"TIT2", &chapter.title));
+        frames.extend(frame(b"CHAP", &chap));
+    }
+    let mut tag = b"ID3\x04\x00\x00".to_vec();
+    tag.extend_from_slice(&syncsafe(frames.len()));
+    tag.extend(frames);
+    tag
+}
+
+pub async fn remove(file: &str) {
+    crate::artifacts::remove(file).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::announcements::{Chapter, ChapterKind};
+
+    fn chapter(start_secs: f64, title: &str) -> Chapter {
+        Chapter { start_secs, title: title.to_string(), kind: ChapterKind::Content, prerecorded: false }
+    }
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack.windows(needle.len()).position(|window| window == needle)
+    }
+
+    #[test]
+    fn renditions_are_named_after_the_main_file() {
+        assert_eq!(rendition_file_name("ai-news", None, 7, Quality::Low), format!("{}-low.opus", file_name("ai-news", None, 7).trim_end_matches(".mp3")));
+        assert_eq!(Quality::Low.bits_per_second(), 48_000);
+        assert_eq!(Quality::parse("medium"), Some(Quality::Medium));
+        assert_eq!(Quality::parse("lossless"), None);
+    }
+
+    #[test]
+    fn master_playlist_lists_every_variant() {
+        let hls = Hls {
+            dir: "s01e07-news".to_string(),
+            variants: vec![
+                HlsVariant { quality: Quality::High, playlist: "high.m3u8".to_string(), bits_per_second: 128_000 },
+                HlsVariant { quality: Quality::Low, playlist: "low.m3u8".to_string(), bits_per_second: 48_000 },
+            ],
+        };
+        let playlist = hls.master_playlist();
+        assert!(playlist.starts_with("#EXTM3U\n"));
+        assert!(playlist.contains("#EXT-X-STREAM-INF:BANDWIDTH=128000,CODECS=\"mp4a.40.2\"\n"));
+        assert!(playlist.contains("/audio/hls/s01e07-news/high.m3u8\n"));
+        assert!(playlist.trim_end().ends_with("/audio/hls/s01e07-news/low.m3u8"));
+    }
+
+    #[test]
+    fn syncsafe_sizes_use_seven_bits_per_byte() {
+        assert_eq!(syncsafe(127), [0, 0, 0, 127]);
+        assert_eq!(syncsafe(128), [0, 0, 1, 0]);
+        assert_eq!(syncsafe(300_000), [0, 0x12, 0x27, 0x60]);
+    }
+
+    #[test]
+    fn tag_lists_chapters_with_their_times() {
+        let tag = id3_tag("Episode", &[chapter(0.0, "Intro"), chapter(62.5, "Story"), chapter(300.0, "Outro")], 360.0);
+        assert_eq!(&tag[..6], b"ID3\x04\x00\x00");
+        assert_eq!(tag[6..10], syncsafe(tag.len() - 10));
+
+        let toc = find(&tag, b"CTOC").unwrap();
+        assert_eq!(&tag[toc + 10..toc + 16], b"toc\0\x03\x03");
+        assert!(find(&tag[toc..], b"chp0\0chp1\0chp2\0").is_some());
+
+        // Element IDs in the CTOC are followed by the next ID, in a CHAP by the start time
+        let second = find(&tag, b"chp1\0\x00").unwrap();
+        assert_eq!(tag[second + 5..second + 9], 62_500u32.to_be_bytes());
+        assert_eq!(tag[second + 9..second + 13], 300_000u32.to_be_bytes());
+        let last = find(&tag, b"chp2\0\x00").unwrap();
+        assert_eq!(tag[last + 9..last + 13], 360_000u32.to_be_bytes());
+        assert!(find(&tag, b"\x03Story").is_some());
+    }
+
+    #[test]
+    fn tag_without_chapters_only_has_the_title() {
+        let tag = id3_tag("Episode", &[], 10.0);
+        assert!(find(&tag, b"CTOC").is_none());
+        assert!(find(&tag, b"CHAP").is_none());
+        assert!(find(&tag, b"\x03Episode").is_some());
+    }
+}
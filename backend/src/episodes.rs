@@ -0,0 +1,79 @@
+// Episode identity helpers: slugs, seasons and episode numbers used for
+// file names, GUIDs and API paths.
+
+const MAX_SLUG_LEN: usize = 80;
+
+// URL-safe slug: lowercase ASCII alphanumerics separated by single dashes
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    let mut pending_dash = false;
+
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            if pending_dash && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_dash = false;
+            slug.push(c.to_ascii_lowercase());
+        } else if c == '\'' {
+            // "What's new" -> "whats-new"
+            continue;
+        } else {
+            pending_dash = true;
+        }
+    }
+
+    if slug.len() > MAX_SLUG_LEN {
+        // Cut on a word boundary where possible
+        let cut = slug[..MAX_SLUG_LEN].rfind('-').unwrap_or(MAX_SLUG_LEN);
+        slug.truncate(cut);
+    }
+
+    if slug.is_empty() {
+        "episode".to_string()
+    } else {
+        slug
+    }
+}
+
+// File stem like "s02e14-rust-in-the-kernel", "e14-rust-in-the-kernel" or just the slug
+pub fn episode_file_stem(slug: &str, season: Option<u32>, number: Option<u32>) -> String {
+    match (season, number) {
+        (Some(season), Some(number)) => format!("s{:02}e{:02}-{}", season, number, slug),
+        (None, Some(number)) => format!("e{:02}-{}", number, slug),
+        (Some(season), None) => format!("s{:02}-{}", season, slug),
+        (None, None) => slug.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_collapses_punctuation() {
+        assert_eq!(slugify("Rust in the Kernel: What's Next?"), "rust-in-the-kernel-whats-next");
+        assert_eq!(slugify("  --C++ & Go--  "), "c-go");
+    }
+
+    #[test]
+    fn slugify_falls_back_for_empty_titles() {
+        assert_eq!(slugify(""), "episode");
+        assert_eq!(slugify("日本語"), "episode");
+    }
+
+    #[test]
+    fn slugify_cuts_long_titles_on_a_word_boundary() {
+        let slug = slugify(&"word ".repeat(40));
+        assert!(slug.len() <= MAX_SLUG_LEN);
+        assert!(slug.ends_with("word"));
+    }
+
+    #[test]
+    fn file_stems() {
+        assert_eq!(episode_file_stem("title", Some(2), Some(14)), "s02e14-title");
+        assert_eq!(episode_file_stem("title", None, Some(3)), "e03-title");
+        assert_eq!(episode_file_stem("title", Some(1), None), "s01-title");
+        assert_eq!(episode_file_stem("title", None, None), "title");
+    }
+}
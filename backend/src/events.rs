@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::broadcast;
+
+// Server-wide event bus behind GET /api/events, so frontends learn about finished jobs and new
+// episodes without polling. Events carry increasing IDs and the most recent ones are kept so a
+// reconnecting client (Last-Event-ID) can catch up; anything older than the buffer is lost.
+
+const CHANNEL_CAPACITY: usize = 256;
+const REPLAY_BUFFER: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerEvent {
+    pub id: u64,
+    // e.g. "job_progress", "job_finished", "episode_published"
+    pub kind: &'static str,
+    pub data: serde_json::Value,
+    pub at: DateTime<Utc>,
+}
+
+static CHANNEL: OnceLock<broadcast::Sender<ServerEvent>> = OnceLock::new();
+
+fn channel() -> &'static broadcast::Sender<ServerEvent> {
+    CHANNEL.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+// Recent events plus the last assigned ID; one lock so IDs and buffer order always agree
+static RECENT: OnceLock<Mutex<(u64, VecDeque<ServerEvent>)>> = OnceLock::new();
+
+fn recent() -> &'static Mutex<(u64, VecDeque<ServerEvent>)> {
+    RECENT.get_or_init(|| Mutex::new((0, VecDeque::new())))
+}
+
+pub fn publish(kind: &'static str, data: serde_json::Value) {
+    let mut recent = recent().lock().unwrap();
+    recent.0 += 1;
+    let event = ServerEvent { id: recent.0, kind, data, at: Utc::now() };
+    if recent.1.len() == REPLAY_BUFFER {
+        recent.1.pop_front();
+    }
+    recent.1.push_back(event.clone());
+    // Sending only fails when nobody is listening
+    let _ = channel().send(event);
+}
+
+// Buffered events after `last_id`, oldest first
+pub fn since(last_id: u64) -> Vec<ServerEvent> {
+    recent().lock().unwrap().1.iter().filter(|e| e.id > last_id).cloned().collect()
+}
+
+pub fn subscribe() -> broadcast::Receiver<ServerEvent> {
+    channel().subscribe()
+}
@@ -0,0 +1,712 @@
+use crate::archive::{Episode, EpisodeAudio};
+use crate::episode_audio::{Quality, Rendition};
+use crate::seo::escape_xml;
+use chrono::Utc;
+use sha1::{Digest, Sha1};
+use std::env;
+
+// Podcast feeds of the archived episodes with rendered audio, newest first: RSS (GET /feed.xml) with
+// the iTunes tags podcast apps expect, and the same data as Atom (GET /feed.atom) and JSON Feed 1.1
+// (GET /feed.json, audio in `attachments`). Channel details come from PODCAST_TITLE, PODCAST_DESCRIPTION,
+// PODCAST_AUTHOR, PODCAST_OWNER_EMAIL, PODCAST_IMAGE_URL (at least 1400x1400 for Apple Podcasts),
+// PODCAST_LANGUAGE (default en), PODCAST_CATEGORY (default Technology) and PODCAST_EXPLICIT.
+//
+// ?quality=low|medium picks the enclosure rendition (see episode_audio.rs); Atom and JSON Feed list the
+// others as extra enclosures/attachments. Every format links the WebSub hubs (see websub.rs). The RSS feed also carries Podcasting 2.0 tags: the show's podcast:guid (PODCAST_GUID, or the
+// UUIDv5 of the feed URL as the namespace prescribes) and, per episode, the host as
+// podcast:person plus podcast:transcript and podcast:chapters pointing at
+// /api/episodes/:id/transcript and /api/episodes/:id/chapters.
+// Items with renditions list all of them as podcast:alternateEnclosure.
+//
+// Large back-catalogs are paged as RFC 5005 archived feeds: /feed.xml holds the newest
+// FEED_PAGE_SIZE episodes (default and maximum 100) and links with rel="prev-archive" to
+// /feed/archive/<n> (?format=atom or ?format=json; JSON Feed points to it with next_url). Archive pages are numbered from the oldest episodes and each holds a full
+// page, so a page's contents only change when an episode in it is deleted or loses its audio.
+
+pub fn page_size() -> u32 {
+    env::var("FEED_PAGE_SIZE")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|n| (1..=crate::archive::MAX_PAGE_SIZE).contains(n))
+        .unwrap_or(crate::archive::MAX_PAGE_SIZE)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Rss,
+    Atom,
+    Json,
+}
+
+impl Format {
+    // ?format= of the archive pages
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "rss" => Some(Format::Rss),
+            "atom" => Some(Format::Atom),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Rss => "application/rss+xml; charset=utf-8",
+            Format::Atom => "application/atom+xml; charset=utf-8",
+            Format::Json => "application/feed+json; charset=utf-8",
+        }
+    }
+
+    fn link_type(self) -> &'static str {
+        match self {
+            Format::Rss => "application/rss+xml",
+            Format::Atom => "application/atom+xml",
+            Format::Json => "application/feed+json",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Rss => "rss",
+            Format::Atom => "atom",
+            Format::Json => "json",
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            Format::Rss => "/feed.xml",
+            Format::Atom => "/feed.atom",
+            Format::Json => "/feed.json",
+        }
+    }
+}
+
+// Which document of the paged feed is being rendered
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub format: Format,
+    // None for the subscription feed
+    pub archive: Option<u32>,
+    pub archive_pages: u32,
+    // Per-topic feed (?topic=)
+    pub topic: Option<String>,
+    // Enclosure rendition (?quality=); None for the main MP3
+    pub quality: Option<Quality>,
+}
+
+impl Page {
+    // Full pages of older episodes behind the subscription feed
+    pub fn new(format: Format, archive: Option<u32>, topic: Option<String>, total: u64, page_size: u32) -> Self {
+        let archive_pages = if total > u64::from(page_size) { (total / u64::from(page_size)) as u32 } else { 0 };
+        Page { format, archive, archive_pages, topic, quality: None }
+    }
+
+    pub fn exists(&self) -> bool {
+        self.archive.is_none_or(|n| (1..=self.archive_pages).contains(&n))
+    }
+
+    // Newest-first offset and limit of the page's episodes
+    pub fn window(&self, total: u64, page_size: u32) -> (u32, u32) {
+        match self.archive {
+            None => (0, page_size),
+            Some(n) => (total.saturating_sub(u64::from(n) * u64::from(page_size)) as u32, page_size),
+        }
+    }
+
+    // The subscription feed or an archive page of this feed
+    fn url(&self, archive: Option<u32>) -> String {
+        let mut query = Vec::new();
+        let path = match archive {
+            None => self.format.path().to_string(),
+            Some(n) => {
+                if self.format != Format::Rss {
+                    query.push(format!("format={}", self.format.name()));
+                }
+                format!("/feed/archive/{}", n)
+            }
+        };
+        // Topics come from a fixed vocabulary of URL-safe names
+        if let Some(topic) = &self.topic {
+            query.push(format!("topic={}", topic));
+        }
+        if let Some(quality) = self.quality {
+            query.push(format!("quality={}", quality.name()));
+        }
+        let query = if query.is_empty() { String::new() } else { format!("?{}", query.join("&")) };
+        format!("{}{}{}", crate::alerts::public_base_url(), path, query)
+    }
+
+    fn self_url(&self) -> String {
+        self.url(self.archive)
+    }
+
+    // RFC 5005 links: rel="current" on archive pages, then prev-archive and next-archive
+    fn links(&self) -> Vec<(&'static str, String)> {
+        let mut links = Vec::new();
+        if self.archive.is_some() {
+            links.push(("current", self.url(None)));
+        }
+        let prev = match self.archive {
+            None => (self.archive_pages > 0).then_some(self.archive_pages),
+            Some(n) => (n > 1).then(|| n - 1),
+        };
+        links.extend(prev.map(|n| ("prev-archive", self.url(Some(n)))));
+        let next = self.archive.filter(|n| *n < self.archive_pages).map(|n| n + 1);
+        links.extend(next.map(|n| ("next-archive", self.url(Some(n)))));
+        links
+    }
+}
+
+// Subscription feeds an episode with these topics appears in, in every format
+pub fn subscription_urls(topics: &[String]) -> Vec<String> {
+    let topics = std::iter::once(None).chain(topics.iter().map(|topic| Some(topic.clone())));
+    topics
+        .flat_map(|topic| {
+            [Format::Rss, Format::Atom, Format::Json].map(|format| Page::new(format, None, topic.clone(), 0, 1).url(None))
+        })
+        .collect()
+}
+
+// Show details shared by every format
+struct Channel {
+    title: String,
+    summary: String,
+    author: String,
+    explicit: bool,
+    language: String,
+    category: String,
+    owner_email: Option<String>,
+    image: Option<String>,
+}
+
+impl Channel {
+    fn from_env() -> Self {
+        let title = setting("PODCAST_TITLE").unwrap_or_else(|| "HackerNews Podcast".to_string());
+        Channel {
+            summary: setting("PODCAST_DESCRIPTION").unwrap_or_else(|| "The stories and discussions on HackerNews, as a podcast.".to_string()),
+            author: setting("PODCAST_AUTHOR").unwrap_or_else(|| title.clone()),
+            explicit: setting("PODCAST_EXPLICIT").is_some_and(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes")),
+            language: setting("PODCAST_LANGUAGE").unwrap_or_else(|| "en".to_string()),
+            category: setting("PODCAST_CATEGORY").unwrap_or_else(|| "Technology".to_string()),
+            owner_email: setting("PODCAST_OWNER_EMAIL"),
+            image: setting("PODCAST_IMAGE_URL"),
+            title,
+        }
+    }
+}
+
+// PODCAST_TITLE, for pages that name the show
+pub fn show_title() -> String {
+    Channel::from_env().title
+}
+
+// PODCAST_DESCRIPTION and PODCAST_IMAGE_URL, for profiles of the show elsewhere
+pub fn show_profile() -> (String, Option<String>) {
+    let channel = Channel::from_env();
+    (channel.summary, channel.image)
+}
+
+fn setting(name: &str) -> Option<String> {
+    env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+// iTunes wants HH:MM:SS
+fn duration(secs: f64) -> String {
+    let secs = secs.round().max(0.0) as u64;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+// The page's rendition first, falling back to the main file for audio rendered before renditions
+// existed, then the other renditions
+fn renditions(audio: &EpisodeAudio, page: &Page) -> Vec<Rendition> {
+    let wanted = page.quality.unwrap_or(Quality::High);
+    let mut renditions: Vec<Rendition> = [Quality::High, Quality::Medium, Quality::Low]
+        .into_iter()
+        .filter_map(|quality| audio.rendition(quality))
+        .collect();
+    if let Some(index) = renditions.iter().position(|rendition| rendition.quality == wanted) {
+        let preferred = renditions.remove(index);
+        renditions.insert(0, preferred);
+    }
+    renditions
+}
+
+fn description(episode: &Episode) -> String {
+    match (&episode.show_notes, &episode.script) {
+        (Some(notes), _) if !notes.trim().is_empty() => notes.clone(),
+        (_, Some(script)) => crate::seo::description(script),
+        _ => episode.title.clone(),
+    }
+}
+
+pub fn render(episodes: &[Episode], page: &Page) -> String {
+    let mut channel = Channel::from_env();
+    if let Some(topic) = &page.topic {
+        channel.title = format!("{}: {}", channel.title, topic);
+    }
+    match page.format {
+        Format::Rss => render_rss(&channel, episodes, page),
+        Format::Atom => render_atom(&channel, episodes, page),
+        Format::Json => render_json(&channel, episodes, page),
+    }
+}
+
+fn render_rss(channel: &Channel, episodes: &[Episode], page: &Page) -> String {
+    let explicit = channel.explicit;
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\" xmlns:itunes=\"http://www.itunes.com/dtds/podcast-1.0.dtd\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:fh=\"http://purl.org/syndication/history/1.0\" xmlns:podcast=\"https://podcastindex.org/namespace/1.0\">\n<channel>\n",
+    );
+    xml.push_str(&format!("  <title>{}</title>\n", escape_xml(&channel.title)));
+    xml.push_str(&format!("  <link>{}</link>\n", escape_xml(&crate::seo::site_url())));
+    xml.push_str(&format!("  <atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n", escape_xml(&page.self_url())));
+    if page.archive.is_some() {
+        xml.push_str("  <fh:archive/>\n");
+    }
+    for (rel, href) in page.links() {
+        xml.push_str(&format!("  <atom:link href=\"{}\" rel=\"{}\" type=\"application/rss+xml\"/>\n", escape_xml(&href), rel));
+    }
+    for hub in crate::websub::hubs() {
+        xml.push_str(&format!("  <atom:link href=\"{}\" rel=\"hub\"/>\n", escape_xml(&hub)));
+    }
+    xml.push_str(&format!("  <description>{}</description>\n", escape_xml(&channel.summary)));
+    xml.push_str(&format!("  <language>{}</language>\n", escape_xml(&channel.language)));
+    xml.push_str(&format!("  <lastBuildDate>{}</lastBuildDate>\n", Utc::now().to_rfc2822()));
+    xml.push_str(&format!("  <itunes:author>{}</itunes:author>\n", escape_xml(&channel.author)));
+    xml.push_str(&format!("  <itunes:summary>{}</itunes:summary>\n", escape_xml(&channel.summary)));
+    if let Some(email) = &channel.owner_email {
+        xml.push_str(&format!(
+            "  <itunes:owner>\n    <itunes:name>{}</itunes:name>\n    <itunes:email>{}</itunes:email>\n  </itunes:owner>\n",
+            escape_xml(&channel.author),
+            escape_xml(email)
+        ));
+    }
+    if let Some(image) = &channel.image {
+        xml.push_str(&format!("  <itunes:image href=\"{}\"/>\n", escape_xml(image)));
+    }
+    xml.push_str(&format!("  <itunes:category text=\"{}\"/>\n", escape_xml(&channel.category)));
+    xml.push_str(&format!("  <itunes:explicit>{}</itunes:explicit>\n", explicit));
+    xml.push_str("  <itunes:type>episodic</itunes:type>\n");
+    xml.push_str(&format!("  <podcast:guid>{}</podcast:guid>\n", escape_xml(&podcast_guid())));
+
+    for episode in episodes {
+        let Some(audio) = &episode.audio else { continue };
+        xml.push_str("  <item>\n");
+        xml.push_str(&format!("    <title>{}</title>\n", escape_xml(&episode.title)));
+        // Built from the episode's number and slug, which never change once archived
+        let guid = crate::episodes::episode_file_stem(&episode.slug, episode.season, Some(episode.episode_number));
+        xml.push_str(&format!("    <guid isPermaLink=\"false\">{}</guid>\n", escape_xml(&guid)));
+        xml.push_str(&format!("    <pubDate>{}</pubDate>\n", episode.published_at().to_rfc2822()));
+        xml.push_str(&format!("    <description>{}</description>\n", escape_xml(&description(episode))));
+        let renditions = renditions(audio, page);
+        let enclosure = &renditions[0];
+        xml.push_str(&format!(
+            "    <enclosure url=\"{}\" length=\"{}\" type=\"{}\"/>\n",
+            escape_xml(&enclosure.url),
+            enclosure.bytes,
+            enclosure.mime_type
+        ));
+        xml.push_str(&format!("    <itunes:duration>{}</itunes:duration>\n", duration(audio.duration_secs)));
+        if let Some(season) = episode.season {
+            xml.push_str(&format!("    <itunes:season>{}</itunes:season>\n", season));
+        }
+        xml.push_str(&format!("    <itunes:episode>{}</itunes:episode>\n", episode.episode_number));
+        xml.push_str("    <itunes:episodeType>full</itunes:episodeType>\n");
+        xml.push_str(&format!("    <itunes:explicit>{}</itunes:explicit>\n", explicit));
+        let api = format!("{}/api/episodes/{}", crate::alerts::public_base_url(), episode.id);
+        if episode.script.as_deref().is_some_and(|script| !script.trim().is_empty()) {
+            xml.push_str(&format!("    <podcast:transcript url=\"{}/transcript\" type=\"text/plain\"/>\n", escape_xml(&api)));
+        }
+        if !audio.chapters.is_empty() {
+            xml.push_str(&format!("    <podcast:chapters url=\"{}/chapters\" type=\"application/json+chapters\"/>\n", escape_xml(&api)));
+        }
+        xml.push_str(&format!("    <podcast:person role=\"host\">{}</podcast:person>\n", escape_xml(&episode.persona)));
+        if renditions.len() > 1 {
+            for (index, rendition) in renditions.iter().enumerate() {
+                xml.push_str(&format!(
+                    "    <podcast:alternateEnclosure type=\"{}\" length=\"{}\" bitrate=\"{}\" title=\"{}\"{}>\n      <podcast:source uri=\"{}\"/>\n    </podcast:alternateEnclosure>\n",
+                    rendition.mime_type,
+                    rendition.bytes,
+                    rendition.bits_per_second,
+                    rendition.quality.name(),
+                    if index == 0 { " default=\"true\"" } else { "" },
+                    escape_xml(&rendition.url)
+                ));
+            }
+        }
+        xml.push_str("  </item>\n");
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+fn podcast_guid() -> String {
+    setting("PODCAST_GUID").unwrap_or_else(|| guid_for_feed_url(&format!("{}/feed.xml", crate::alerts::public_base_url())))
+}
+
+// UUIDv5 in the podcast namespace of the URL without its scheme and trailing slashes
+fn guid_for_feed_url(url: &str) -> String {
+    const NAMESPACE: &str = "ead4c236-bf58-58c6-a2c6-a6b28d128cb6";
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest).trim_end_matches('/');
+    let namespace = uuid::Uuid::parse_str(NAMESPACE).unwrap_or_default();
+    let digest = Sha1::new().chain_update(namespace.as_bytes()).chain_update(url.as_bytes()).finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_sha1_bytes(bytes).into_uuid().to_string()
+}
+
+// Podcasting 2.0 JSON chapters (application/json+chapters)
+pub fn chapters_json(chapters: &[crate::announcements::Chapter]) -> serde_json::Value {
+    let chapters: Vec<serde_json::Value> = chapters
+        .iter()
+        .map(|chapter| serde_json::json!({ "startTime": chapter.start_secs, "title": chapter.title }))
+        .collect();
+    serde_json::json!({ "version": "1.2.0", "chapters": chapters })
+}
+
+// Atom 1.0 with the audio as an enclosure link; entry ids are the episodes' UUIDs
+fn render_atom(channel: &Channel, episodes: &[Episode], page: &Page) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\" xmlns:fh=\"http://purl.org/syndication/history/1.0\">\n",
+    );
+    // One id for the whole feed, archive pages included
+    xml.push_str(&format!("  <id>{}</id>\n", escape_xml(&page.url(None))));
+    xml.push_str(&format!("  <title>{}</title>\n", escape_xml(&channel.title)));
+    xml.push_str(&format!("  <subtitle>{}</subtitle>\n", escape_xml(&channel.summary)));
+    let updated = episodes.iter().map(|e| e.published_at()).max().unwrap_or_else(Utc::now);
+    xml.push_str(&format!("  <updated>{}</updated>\n", updated.to_rfc3339()));
+    xml.push_str(&format!("  <author>\n    <name>{}</name>\n", escape_xml(&channel.author)));
+    if let Some(email) = &channel.owner_email {
+        xml.push_str(&format!("    <email>{}</email>\n", escape_xml(email)));
+    }
+    xml.push_str("  </author>\n");
+    xml.push_str(&format!("  <link rel=\"self\" type=\"application/atom+xml\" href=\"{}\"/>\n", escape_xml(&page.self_url())));
+    xml.push_str(&format!("  <link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>\n", escape_xml(&crate::seo::site_url())));
+    if page.archive.is_some() {
+        xml.push_str("  <fh:archive/>\n");
+    }
+    for (rel, href) in page.links() {
+        xml.push_str(&format!("  <link rel=\"{}\" type=\"{}\" href=\"{}\"/>\n", rel, page.format.link_type(), escape_xml(&href)));
+    }
+    for hub in crate::websub::hubs() {
+        xml.push_str(&format!("  <link rel=\"hub\" href=\"{}\"/>\n", escape_xml(&hub)));
+    }
+    if let Some(image) = &channel.image {
+        xml.push_str(&format!("  <logo>{}</logo>\n", escape_xml(image)));
+    }
+    xml.push_str(&format!("  <category term=\"{}\"/>\n", escape_xml(&channel.category)));
+
+    for episode in episodes {
+        let Some(audio) = &episode.audio else { continue };
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <id>urn:uuid:{}</id>\n", escape_xml(&episode.id)));
+        xml.push_str(&format!("    <title>{}</title>\n", escape_xml(&episode.title)));
+        xml.push_str(&format!("    <published>{}</published>\n", episode.published_at().to_rfc3339()));
+        xml.push_str(&format!("    <updated>{}</updated>\n", episode.published_at().max(audio.rendered_at).to_rfc3339()));
+        xml.push_str(&format!("    <summary>{}</summary>\n", escape_xml(&description(episode))));
+        for rendition in renditions(audio, page) {
+            xml.push_str(&format!(
+                "    <link rel=\"enclosure\" type=\"{}\" length=\"{}\" href=\"{}\"/>\n",
+                rendition.mime_type,
+                rendition.bytes,
+                escape_xml(&rendition.url)
+            ));
+        }
+        // A follow-up points at the episode it updates
+        if let Some(original) = &episode.follow_up_of {
+            xml.push_str(&format!("    <link rel=\"related\" href=\"urn:uuid:{}\"/>\n", escape_xml(original)));
+        }
+        for topic in &episode.topics {
+            xml.push_str(&format!("    <category term=\"{}\"/>\n", escape_xml(topic)));
+        }
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+// JSON Feed 1.1; older episodes are reached through next_url, which follows prev-archive
+fn render_json(channel: &Channel, episodes: &[Episode], page: &Page) -> String {
+    let items: Vec<serde_json::Value> = episodes
+        .iter()
+        .filter_map(|episode| {
+            let audio = episode.audio.as_ref()?;
+            let mut item = serde_json::json!({
+                "id": episode.id,
+                "title": episode.title,
+                "content_text": description(episode),
+                "date_published": episode.published_at().to_rfc3339(),
+                "date_modified": episode.published_at().max(audio.rendered_at).to_rfc3339(),
+                "tags": episode.topics,
+                // Alternate encodings of the same audio, preferred first
+                "attachments": renditions(audio, page).into_iter().map(|rendition| serde_json::json!({
+                    "url": rendition.url,
+                    "mime_type": rendition.mime_type,
+                    "title": rendition.quality.name(),
+                    "size_in_bytes": rendition.bytes,
+                    "duration_in_seconds": audio.duration_secs.round(),
+                })).collect::<Vec<_>>(),
+            });
+            // JSON Feed extension keys start with an underscore
+            if let Some(original) = &episode.follow_up_of {
+                item["_hn_podcast"] = serde_json::json!({ "follow_up_of": original });
+            }
+            Some(item)
+        })
+        .collect();
+    let next_url = page.links().into_iter().find(|(rel, _)| *rel == "prev-archive").map(|(_, href)| href);
+
+    let mut feed = serde_json::json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": channel.title,
+        "home_page_url": crate::seo::site_url(),
+        "feed_url": page.self_url(),
+        "description": channel.summary,
+        "authors": [{ "name": channel.author }],
+        "language": channel.language,
+        "items": items,
+    });
+    if let Some(next_url) = next_url {
+        feed["next_url"] = next_url.into();
+    }
+    if let Some(image) = &channel.image {
+        feed["icon"] = image.clone().into();
+    }
+    let hubs: Vec<serde_json::Value> = crate::websub::hubs().into_iter().map(|url| serde_json::json!({ "type": "WebSub", "url": url })).collect();
+    if !hubs.is_empty() {
+        feed["hubs"] = hubs.into();
+    }
+    serde_json::to_string_pretty(&feed).unwrap_or_default()
+}
+
+// OPML outline of the show's RSS feeds, for importing them into a podcast app or reader in one
+// step: the main feed and one per topic in `topics`
+pub fn opml(topics: &[&str]) -> String {
+    let channel = Channel::from_env();
+    let outline = |text: &str, topic: Option<&str>| {
+        let page = Page::new(Format::Rss, None, topic.map(String::from), 0, page_size());
+        format!(
+            "<outline type=\"rss\" text=\"{}\" title=\"{}\" xmlUrl=\"{}\" htmlUrl=\"{}\"/>",
+            escape_xml(text),
+            escape_xml(text),
+            escape_xml(&page.url(None)),
+            escape_xml(&crate::seo::site_url())
+        )
+    };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n");
+    xml.push_str(&format!("  <head>\n    <title>{} feeds</title>\n", escape_xml(&channel.title)));
+    xml.push_str(&format!("    <dateCreated>{}</dateCreated>\n  </head>\n  <body>\n", Utc::now().to_rfc2822()));
+    xml.push_str(&format!("    {}\n", outline(&channel.title, None)));
+    if !topics.is_empty() {
+        xml.push_str("    <outline text=\"Topics\">\n");
+        for topic in topics {
+            xml.push_str(&format!("      {}\n", outline(&format!("{}: {}", channel.title, topic), Some(topic))));
+        }
+        xml.push_str("    </outline>\n");
+    }
+    xml.push_str("  </body>\n</opml>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::{EpisodeAudio, EpisodeStatus};
+
+    const CURRENT: Page = Page { format: Format::Rss, archive: None, archive_pages: 0, topic: None, quality: None };
+
+    fn episode(title: &str, audio: bool) -> Episode {
+        Episode {
+            id: "3f2b".to_string(),
+            title: title.to_string(),
+            slug: crate::episodes::slugify(title),
+            episode_number: 7,
+            season: Some(1),
+            persona: "maya".to_string(),
+            status: EpisodeStatus::Complete,
+            show_notes: Some("Notes with \"quotes\" & <tags>".to_string()),
+            script: None,
+            story_ids: vec![1],
+            topics: Vec::new(),
+            chat_id: None,
+            job_id: None,
+            audio: audio.then(|| EpisodeAudio {
+                file: "s01e07.mp3".to_string(),
+                url: "https://example.com/audio/s01e07.mp3?a=1&b=2".to_string(),
+                bytes: 1234,
+                duration_secs: 3725.4,
+                rendered_at: Utc::now(),
+                qa: None,
+                chapters: Vec::new(),
+                renditions: Vec::new(),
+                hls: None,
+            }),
+            publish_at: None,
+            follow_up_of: None,
+            follow_ups: Vec::new(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn escapes_episode_fields() {
+        let xml = render(&[episode("Tom & Jerry <Live>", true)], &CURRENT);
+        assert!(xml.contains("<title>Tom &amp; Jerry &lt;Live&gt;</title>"));
+        assert!(xml.contains("<description>Notes with &quot;quotes&quot; &amp; &lt;tags&gt;</description>"));
+        assert!(xml.contains("url=\"https://example.com/audio/s01e07.mp3?a=1&amp;b=2\" length=\"1234\""));
+        assert!(!xml.contains("Tom & Jerry"));
+    }
+
+    #[test]
+    fn items_carry_numbering_and_duration() {
+        let xml = render(&[episode("Rust in the Kernel", true)], &CURRENT);
+        assert!(xml.contains("<guid isPermaLink=\"false\">s01e07-rust-in-the-kernel</guid>"));
+        assert!(xml.contains("<itunes:season>1</itunes:season>"));
+        assert!(xml.contains("<itunes:episode>7</itunes:episode>"));
+        assert!(xml.contains("<itunes:duration>01:02:05</itunes:duration>"));
+    }
+
+    #[test]
+    fn skips_episodes_without_audio() {
+        let xml = render(&[episode("Unrendered", false)], &CURRENT);
+        assert!(!xml.contains("<item>"));
+    }
+
+    #[test]
+    fn archive_pages_are_full_pages_counted_from_the_oldest() {
+        assert_eq!(Page::new(Format::Rss, None, None, 100, 100).archive_pages, 0);
+        let page = Page::new(Format::Rss, Some(1), None, 250, 100);
+        assert_eq!(page.archive_pages, 2);
+        // The oldest hundred: newest-first offset 150
+        assert_eq!(page.window(250, 100), (150, 100));
+        assert_eq!(Page::new(Format::Rss, Some(2), None, 250, 100).window(250, 100), (50, 100));
+        assert!(!Page::new(Format::Rss, Some(3), None, 250, 100).exists());
+        assert!(!Page::new(Format::Rss, Some(0), None, 250, 100).exists());
+    }
+
+    #[test]
+    fn pages_link_to_their_neighbours() {
+        let current = render(&[], &Page::new(Format::Rss, None, None, 250, 100));
+        assert!(current.contains("/feed/archive/2\" rel=\"prev-archive\""));
+        assert!(!current.contains("<fh:archive/>"));
+
+        let first = render(&[], &Page::new(Format::Rss, Some(1), None, 250, 100));
+        assert!(first.contains("<fh:archive/>"));
+        assert!(first.contains("rel=\"current\""));
+        assert!(first.contains("/feed/archive/2\" rel=\"next-archive\""));
+        assert!(!first.contains("prev-archive"));
+    }
+
+    #[test]
+    fn atom_entries_use_episode_ids_and_enclosure_links() {
+        let page = Page { format: Format::Atom, archive: Some(1), archive_pages: 2, topic: None, quality: None };
+        let xml = render(&[episode("Tom & Jerry", true)], &page);
+        assert!(xml.contains("<feed xmlns=\"http://www.w3.org/2005/Atom\""));
+        assert!(xml.contains("<id>urn:uuid:3f2b</id>"));
+        assert!(xml.contains("<title>Tom &amp; Jerry</title>"));
+        assert!(xml.contains("<link rel=\"enclosure\" type=\"audio/mpeg\" length=\"1234\""));
+        assert!(xml.contains("/feed/archive/2?format=atom\"/>"));
+        assert!(xml.contains("<fh:archive/>"));
+    }
+
+    #[test]
+    fn json_feed_lists_audio_as_attachments() {
+        let page = Page { format: Format::Json, archive: None, archive_pages: 3, topic: None, quality: None };
+        let feed: serde_json::Value = serde_json::from_str(&render(&[episode("Rust", true), episode("Draft", false)], &page)).unwrap();
+        assert_eq!(feed["version"], "https://jsonfeed.org/version/1.1");
+        assert!(feed["next_url"].as_str().unwrap().ends_with("/feed/archive/3?format=json"));
+        let items = feed["items"].as_array().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["attachments"][0]["mime_type"], "audio/mpeg");
+        assert_eq!(items[0]["attachments"][0]["size_in_bytes"], 1234);
+    }
+
+    #[test]
+    fn follow_ups_link_to_the_original() {
+        let mut follow_up = episode("Follow-up: Rust", true);
+        follow_up.follow_up_of = Some("a1b2".to_string());
+        let page = Page { format: Format::Atom, archive: None, archive_pages: 1, topic: None, quality: None };
+        assert!(render(&[follow_up.clone()], &page).contains("<link rel=\"related\" href=\"urn:uuid:a1b2\"/>"));
+        let page = Page { format: Format::Json, ..page };
+        let feed: serde_json::Value = serde_json::from_str(&render(&[follow_up, episode("Rust", true)], &page)).unwrap();
+        assert_eq!(feed["items"][0]["_hn_podcast"]["follow_up_of"], "a1b2");
+        assert!(feed["items"][1].get("_hn_podcast").is_none());
+    }
+
+    #[test]
+    fn topic_feeds_carry_the_topic_through_their_links() {
+        let page = Page { format: Format::Atom, archive: Some(2), archive_pages: 3, topic: Some("ai".to_string()), quality: None };
+        let xml = render(&[], &page);
+        assert!(xml.contains("/feed/archive/1?format=atom&amp;topic=ai\"/>"));
+        assert!(xml.contains("/feed.atom?topic=ai\"/>"));
+
+        let opml = opml(&["ai", "security"]);
+        assert!(opml.contains("xmlUrl=\"http"));
+        assert!(opml.contains("/feed.xml?topic=security\""));
+    }
+
+    #[test]
+    fn podcast_guid_follows_the_namespace_spec() {
+        // The example from the Podcasting 2.0 namespace documentation
+        assert_eq!(guid_for_feed_url("https://mp3s.nashownotes.com/pc20rss.xml/"), "917393e3-1b1e-5cef-ace4-edaa54e1f810");
+    }
+
+    #[test]
+    fn items_link_transcripts_and_chapters() {
+        let mut with_chapters = episode("Rust", true);
+        with_chapters.script = Some("## Rust\nHello".to_string());
+        if let Some(audio) = with_chapters.audio.as_mut() {
+            audio.chapters = vec![crate::announcements::Chapter {
+                start_secs: 0.0,
+                title: "Rust".to_string(),
+                kind: crate::announcements::ChapterKind::Content,
+                prerecorded: false,
+            }];
+        }
+        let xml = render(&[with_chapters], &CURRENT);
+        assert!(xml.contains("/api/episodes/3f2b/transcript\" type=\"text/plain\"/>"));
+        assert!(xml.contains("/api/episodes/3f2b/chapters\" type=\"application/json+chapters\"/>"));
+        assert!(xml.contains("<podcast:person role=\"host\">maya</podcast:person>"));
+
+        let xml = render(&[episode("Rust", true)], &CURRENT);
+        assert!(!xml.contains("podcast:transcript"));
+        assert!(!xml.contains("podcast:chapters"));
+    }
+
+    #[test]
+    fn episodes_are_announced_for_every_feed_they_appear_in() {
+        let urls = subscription_urls(&["ai".to_string()]);
+        assert_eq!(urls.len(), 6);
+        assert!(urls[0].ends_with("/feed.xml"));
+        assert!(urls[5].ends_with("/feed.json?topic=ai"));
+    }
+
+    #[test]
+    fn renditions_are_alternate_enclosures_and_selectable() {
+        let mut episode = episode("Renditions", true);
+        if let Some(audio) = episode.audio.as_mut() {
+            audio.renditions.push(Rendition {
+                quality: Quality::Low,
+                file: "s01e07-low.opus".to_string(),
+                url: "https://example.com/audio/s01e07-low.opus".to_string(),
+                bytes: 400,
+                mime_type: "audio/opus".to_string(),
+                bits_per_second: 48_000,
+            });
+        }
+        let xml = render(std::slice::from_ref(&episode), &CURRENT);
+        assert!(xml.contains("<enclosure url=\"https://example.com/audio/s01e07.mp3?a=1&amp;b=2\""));
+        assert!(xml.contains("<podcast:alternateEnclosure type=\"audio/opus\" length=\"400\" bitrate=\"48000\" title=\"low\">"));
+        assert!(xml.contains("title=\"high\" default=\"true\">"));
+
+        let page = Page { quality: Some(Quality::Low), ..CURRENT };
+        let xml = render(std::slice::from_ref(&episode), &page);
+        assert!(xml.contains("<enclosure url=\"https://example.com/audio/s01e07-low.opus\" length=\"400\" type=\"audio/opus\"/>"));
+        assert!(xml.contains("/feed.xml?quality=low\" rel=\"self\""));
+
+        // No medium rendition: the main file stands in
+        let page = Page { quality: Some(Quality::Medium), ..CURRENT };
+        assert!(render(&[episode], &page).contains("<enclosure url=\"https://example.com/audio/s01e07.mp3?a=1&amp;b=2\""));
+    }
+}
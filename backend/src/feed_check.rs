@@ -0,0 +1,335 @@
+use futures::stream::{self, StreamExt};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+// Self-check of the generated RSS feed (GET /admin/feed/validate) against what Apple Podcasts and
+// Spotify reject on submission: required channel and item tags, duplicate GUIDs, enclosures that
+// don't answer or don't support byte ranges, and cover art outside 1400x1400 to 3000x3000.
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// Enclosure probes in flight at once
+const CONCURRENCY: usize = 8;
+const MIN_IMAGE_SIDE: u32 = 1400;
+const MAX_IMAGE_SIDE: u32 = 3000;
+// Enough to reach a JPEG's frame header behind large EXIF blocks
+const MAX_IMAGE_BYTES: usize = 512 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    // Directories reject the feed or the episode
+    Error,
+    // Accepted, but listeners or directories will notice
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    pub check: &'static str,
+    // GUID (or title) of the episode, for item checks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    // No errors; warnings don't block a submission
+    pub valid: bool,
+    pub items: usize,
+    pub issues: Vec<Issue>,
+}
+
+fn issue(severity: Severity, check: &'static str, item: Option<&str>, message: impl Into<String>) -> Issue {
+    Issue { severity, check, item: item.map(String::from), message: message.into() }
+}
+
+// --- XML scanning ---
+
+// The feed is our own output, so a few patterns are enough to read it back
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+fn text(xml: &str, name: &str) -> Option<String> {
+    let re = Regex::new(&format!(r"(?s)<{}(?:\s[^>]*)?>(.*?)</{}>", regex::escape(name), regex::escape(name))).ok()?;
+    re.captures(xml).map(|c| unescape(c[1].trim())).filter(|t| !t.is_empty())
+}
+
+fn open_tag<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let re = Regex::new(&format!(r"<{}(?:\s[^>]*)?/?>", regex::escape(name))).ok()?;
+    re.find(xml).map(|m| m.as_str())
+}
+
+fn attr(tag: &str, name: &str) -> Option<String> {
+    let re = Regex::new(&format!(r#"\s{}="([^"]*)""#, regex::escape(name))).ok()?;
+    re.captures(tag).map(|c| unescape(&c[1])).filter(|v| !v.is_empty())
+}
+
+fn items(xml: &str) -> Vec<&str> {
+    xml.split("<item>").skip(1).map(|item| item.split("</item>").next().unwrap_or(item)).collect()
+}
+
+// --- Static checks ---
+
+// Channel tags of the subscription feed
+pub fn check_channel(xml: &str) -> Vec<Issue> {
+    let channel = xml.split("<item>").next().unwrap_or(xml);
+    let mut issues = Vec::new();
+    for name in ["title", "description", "language", "itunes:author", "itunes:explicit"] {
+        if text(channel, name).is_none() {
+            issues.push(issue(Severity::Error, "required_tag", None, format!("Channel is missing <{}>", name)));
+        }
+    }
+    if open_tag(channel, "itunes:category").and_then(|tag| attr(tag, "text")).is_none() {
+        issues.push(issue(Severity::Error, "required_tag", None, "Channel is missing <itunes:category> (set PODCAST_CATEGORY)"));
+    }
+    if open_tag(channel, "itunes:image").and_then(|tag| attr(tag, "href")).is_none() {
+        issues.push(issue(Severity::Error, "required_tag", None, "Channel is missing <itunes:image> (set PODCAST_IMAGE_URL)"));
+    }
+    // Apple and Spotify send the ownership verification code to this address
+    if text(channel, "itunes:email").is_none() {
+        issues.push(issue(
+            Severity::Warning,
+            "required_tag",
+            None,
+            "Channel has no <itunes:owner> email, so directories can't verify ownership (set PODCAST_OWNER_EMAIL)",
+        ));
+    }
+    issues
+}
+
+// Item tags and GUID uniqueness across every page of the feed
+pub fn check_items(documents: &[String]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for item in documents.iter().flat_map(|xml| items(xml)) {
+        let guid = text(item, "guid");
+        let label = guid.clone().or_else(|| text(item, "title"));
+        let label = label.as_deref();
+        if text(item, "title").is_none() {
+            issues.push(issue(Severity::Error, "required_tag", label, "Episode is missing <title>"));
+        }
+        match &guid {
+            Some(guid) => *seen.entry(guid.clone()).or_default() += 1,
+            None => issues.push(issue(Severity::Error, "guid", label, "Episode is missing <guid>")),
+        }
+        match open_tag(item, "enclosure") {
+            None => issues.push(issue(Severity::Error, "enclosure", label, "Episode is missing <enclosure>")),
+            Some(tag) => {
+                if attr(tag, "url").is_none() {
+                    issues.push(issue(Severity::Error, "enclosure", label, "Enclosure has no url"));
+                }
+                if attr(tag, "length").and_then(|l| l.parse::<u64>().ok()).is_none_or(|l| l == 0) {
+                    issues.push(issue(Severity::Warning, "enclosure", label, "Enclosure length is missing or 0"));
+                }
+                if attr(tag, "type").is_none_or(|t| !t.starts_with("audio/")) {
+                    issues.push(issue(Severity::Error, "enclosure", label, "Enclosure type is not an audio type"));
+                }
+            }
+        }
+    }
+    let mut duplicates: Vec<(String, usize)> = seen.into_iter().filter(|(_, count)| *count > 1).collect();
+    duplicates.sort();
+    for (guid, count) in duplicates {
+        issues.push(issue(Severity::Error, "guid", Some(&guid), format!("GUID is used by {} episodes", count)));
+    }
+    issues
+}
+
+// --- Remote checks ---
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default()
+}
+
+// Directories stream and seek with range requests, so a one-byte range has to work
+async fn check_enclosure(client: &reqwest::Client, guid: Option<String>, url: String) -> Vec<Issue> {
+    let item = guid.as_deref();
+    let mut issues = Vec::new();
+    if !url.starts_with("https://") {
+        issues.push(issue(Severity::Warning, "enclosure", item, format!("Enclosure {} is not served over HTTPS", url)));
+    }
+    match client.get(&url).header(reqwest::header::RANGE, "bytes=0-0").send().await {
+        Err(e) => issues.push(issue(Severity::Error, "enclosure", item, format!("Enclosure {} is unreachable: {}", url, e))),
+        Ok(response) if !response.status().is_success() => issues.push(issue(
+            Severity::Error,
+            "enclosure",
+            item,
+            format!("Enclosure {} answered {}", url, response.status()),
+        )),
+        Ok(response) if response.status() != reqwest::StatusCode::PARTIAL_CONTENT => issues.push(issue(
+            Severity::Warning,
+            "enclosure",
+            item,
+            format!("Enclosure {} ignores byte-range requests", url),
+        )),
+        Ok(_) => {}
+    }
+    issues
+}
+
+// Width and height from a PNG IHDR or a JPEG start-of-frame marker
+fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") && bytes.len() >= 24 {
+        let width = u32::from_be_bytes(bytes[16..20].try_into().ok()?);
+        let height = u32::from_be_bytes(bytes[20..24].try_into().ok()?);
+        return Some((width, height));
+    }
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut i = 2;
+    while i + 9 < bytes.len() {
+        if bytes[i] != 0xFF {
+            return None;
+        }
+        let marker = bytes[i + 1];
+        let length = usize::from(u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]));
+        // SOF0..SOF15, except DHT (C4), JPG (C8) and DAC (CC)
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let height = u32::from(u16::from_be_bytes([bytes[i + 5], bytes[i + 6]]));
+            let width = u32::from(u16::from_be_bytes([bytes[i + 7], bytes[i + 8]]));
+            return Some((width, height));
+        }
+        i += 2 + length;
+    }
+    None
+}
+
+fn check_image_dimensions(url: &str, dimensions: Option<(u32, u32)>) -> Vec<Issue> {
+    let Some((width, height)) = dimensions else {
+        return vec![issue(Severity::Error, "image", None, format!("Cover art {} is not a readable JPEG or PNG", url))];
+    };
+    let mut issues = Vec::new();
+    if width != height {
+        issues.push(issue(Severity::Error, "image", None, format!("Cover art is {}x{}; it must be square", width, height)));
+    }
+    if !(MIN_IMAGE_SIDE..=MAX_IMAGE_SIDE).contains(&width.max(height)) || width.min(height) < MIN_IMAGE_SIDE {
+        issues.push(issue(
+            Severity::Error,
+            "image",
+            None,
+            format!("Cover art is {}x{}; it must be between {2}x{2} and {3}x{3}", width, height, MIN_IMAGE_SIDE, MAX_IMAGE_SIDE),
+        ));
+    }
+    issues
+}
+
+async fn check_image(client: &reqwest::Client, url: &str) -> Vec<Issue> {
+    let response = match client.get(url).send().await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => return vec![issue(Severity::Error, "image", None, format!("Cover art {} answered {}", url, response.status()))],
+        Err(e) => return vec![issue(Severity::Error, "image", None, format!("Cover art {} is unreachable: {}", url, e))],
+    };
+    let mut bytes = Vec::new();
+    let mut body = response.bytes_stream();
+    while let Some(Ok(chunk)) = body.next().await {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() >= MAX_IMAGE_BYTES {
+            break;
+        }
+    }
+    check_image_dimensions(url, image_dimensions(&bytes))
+}
+
+// documents[0] is the subscription feed, followed by its archive pages
+pub async fn validate(documents: &[String]) -> Report {
+    let mut issues = documents.first().map(|xml| check_channel(xml)).unwrap_or_default();
+    issues.extend(check_items(documents));
+
+    let client = client();
+    let channel = documents.first().map(|xml| xml.split("<item>").next().unwrap_or(xml)).unwrap_or_default();
+    if let Some(image) = open_tag(channel, "itunes:image").and_then(|tag| attr(tag, "href")) {
+        issues.extend(check_image(&client, &image).await);
+    }
+
+    let enclosures: Vec<(Option<String>, String)> = documents
+        .iter()
+        .flat_map(|xml| items(xml))
+        .filter_map(|item| Some((text(item, "guid"), attr(open_tag(item, "enclosure")?, "url")?)))
+        .collect();
+    let count = documents.iter().map(|xml| items(xml).len()).sum();
+    let probes: Vec<Vec<Issue>> = stream::iter(enclosures)
+        .map(|(guid, url)| check_enclosure(&client, guid, url))
+        .buffered(CONCURRENCY)
+        .collect()
+        .await;
+    issues.extend(probes.into_iter().flatten());
+
+    let valid = issues.iter().all(|issue| issue.severity != Severity::Error);
+    Report { valid, items: count, issues }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANNEL: &str = "<rss><channel>\n<title>Show</title>\n<description>About</description>\n<language>en</language>\n<itunes:author>Host</itunes:author>\n<itunes:owner><itunes:name>Host</itunes:name><itunes:email>host@example.com</itunes:email></itunes:owner>\n<itunes:image href=\"https://example.com/cover.png\"/>\n<itunes:category text=\"Technology\"/>\n<itunes:explicit>false</itunes:explicit>\n";
+
+    fn item(guid: &str) -> String {
+        format!(
+            "<item><title>Episode {0}</title><guid isPermaLink=\"false\">{0}</guid><enclosure url=\"https://example.com/{0}.mp3\" length=\"100\" type=\"audio/mpeg\"/></item>\n",
+            guid
+        )
+    }
+
+    #[test]
+    fn complete_channel_passes() {
+        assert!(check_channel(&format!("{}{}</channel></rss>", CHANNEL, item("a"))).is_empty());
+    }
+
+    #[test]
+    fn reports_missing_channel_tags() {
+        let xml = CHANNEL.replace("<itunes:image href=\"https://example.com/cover.png\"/>\n", "").replace("<language>en</language>", "");
+        let messages: Vec<String> = check_channel(&xml).into_iter().map(|issue| issue.message).collect();
+        assert_eq!(messages.len(), 2);
+        assert!(messages.iter().any(|m| m.contains("<language>")));
+        assert!(messages.iter().any(|m| m.contains("<itunes:image>")));
+    }
+
+    #[test]
+    fn duplicate_guids_are_found_across_pages() {
+        let current = format!("{}{}{}", CHANNEL, item("a"), item("b"));
+        let archive = format!("{}{}", CHANNEL, item("a"));
+        let issues = check_items(&[current, archive]);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].check, "guid");
+        assert_eq!(issues[0].item.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn items_need_an_audio_enclosure() {
+        let xml = "<item><title>T</title><guid>g</guid><enclosure url=\"https://example.com/g.txt\" length=\"0\" type=\"text/plain\"/></item>";
+        let issues = check_items(&[xml.to_string()]);
+        assert!(issues.iter().any(|i| i.severity == Severity::Error && i.message.contains("audio type")));
+        assert!(issues.iter().any(|i| i.severity == Severity::Warning && i.message.contains("length")));
+    }
+
+    #[test]
+    fn reads_png_and_jpeg_dimensions() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend_from_slice(&1400u32.to_be_bytes());
+        png.extend_from_slice(&1400u32.to_be_bytes());
+        assert_eq!(image_dimensions(&png), Some((1400, 1400)));
+
+        // SOI, an APP0 segment, then SOF0 with height 3000 and width 2000
+        let jpeg = [
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x0B, 0xB8, 0x07, 0xD0, 0x03,
+        ];
+        assert_eq!(image_dimensions(&jpeg), Some((2000, 3000)));
+        assert_eq!(image_dimensions(b"GIF89a"), None);
+    }
+
+    #[test]
+    fn cover_art_must_be_square_and_in_range() {
+        assert!(check_image_dimensions("c", Some((3000, 3000))).is_empty());
+        assert_eq!(check_image_dimensions("c", Some((1000, 1000))).len(), 1);
+        assert_eq!(check_image_dimensions("c", Some((2000, 3000))).len(), 1);
+        assert_eq!(check_image_dimensions("c", Some((1400, 3200))).len(), 2);
+    }
+}
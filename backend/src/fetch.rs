@@ -0,0 +1,117 @@
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Resolve, Resolving};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+// SSRF-safe HTTP client for fetching user- or LLM-supplied URLs: only http(s), and every
+// resolved address (including after redirects) must be a public unicast address.
+
+const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36";
+const MAX_REDIRECTS: usize = 5;
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || a == 0
+        // Carrier-grade NAT 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments 192.0.0.0/24
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking 198.18.0.0/15
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved 240.0.0.0/4
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_public_ipv4(v4);
+    }
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local fc00::/7
+        || (first & 0xfe00) == 0xfc00
+        // Link-local fe80::/10
+        || (first & 0xffc0) == 0xfe80
+        // Documentation 2001:db8::/32
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+}
+
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_ipv4(v4),
+        IpAddr::V6(v6) => is_public_ipv6(v6),
+    }
+}
+
+// Resolver that drops non-public addresses so a hostname can't point the fetcher inwards
+struct PublicOnlyResolver;
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} does not resolve to a public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Box<dyn Iterator<Item = SocketAddr> + Send>)
+        })
+    }
+}
+
+// Scheme and IP-literal checks; IP literals never reach the resolver, so they're checked here
+pub fn validate_url(url: &reqwest::Url) -> Result<(), String> {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!("Unsupported URL scheme '{}'", url.scheme()));
+    }
+    match url.host() {
+        Some(url::Host::Ipv4(ip)) if !is_public_ipv4(ip) => Err(format!("{} is not a public address", ip)),
+        Some(url::Host::Ipv6(ip)) if !is_public_ipv6(ip) => Err(format!("{} is not a public address", ip)),
+        Some(url::Host::Domain(domain)) if domain.eq_ignore_ascii_case("localhost") => Err("localhost is not allowed".to_string()),
+        None => Err("URL has no host".to_string()),
+        _ => Ok(()),
+    }
+}
+
+pub fn parse_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+    validate_url(&parsed)?;
+    Ok(parsed)
+}
+
+static SAFE_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+pub fn safe_client() -> &'static reqwest::Client {
+    SAFE_CLIENT.get_or_init(|| {
+        let redirect_policy = reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if let Err(e) = validate_url(attempt.url()) {
+                attempt.error(e)
+            } else {
+                attempt.follow()
+            }
+        });
+
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .user_agent(USER_AGENT)
+            .redirect(redirect_policy)
+            .dns_resolver(Arc::new(PublicOnlyResolver))
+            .build()
+            .expect("Failed to build SSRF-safe HTTP client")
+    })
+}
@@ -0,0 +1,41 @@
+use serde::Serialize;
+
+// Sparse fieldsets for list endpoints: `?fields=id,title,score` keeps only those keys on each
+// item, so clients that render a title and score don't download comment IDs and story text.
+
+#[derive(Debug, Serialize)]
+pub struct Envelope<T> {
+    pub data: T,
+    pub meta: serde_json::Value,
+}
+
+// None when no selection was requested; unknown names are an error listing the valid ones
+pub fn parse(requested: Option<&str>, valid: &[&str]) -> Result<Option<Vec<String>>, String> {
+    let Some(requested) = requested.filter(|r| !r.trim().is_empty()) else { return Ok(None) };
+    let mut fields = Vec::new();
+    for field in requested.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        if !valid.contains(&field) {
+            return Err(format!("Unknown field '{}'. Valid fields: {}", field, valid.join(", ")));
+        }
+        if !fields.iter().any(|f| f == field) {
+            fields.push(field.to_string());
+        }
+    }
+    Ok(Some(fields))
+}
+
+pub fn select<T: Serialize>(items: &[T], fields: Option<&[String]>) -> Vec<serde_json::Value> {
+    items
+        .iter()
+        .map(|item| {
+            let value = serde_json::to_value(item).unwrap_or_default();
+            match (fields, value) {
+                (Some(fields), serde_json::Value::Object(mut map)) => {
+                    map.retain(|key, _| fields.iter().any(|f| f == key));
+                    serde_json::Value::Object(map)
+                }
+                (_, value) => value,
+            }
+        })
+        .collect()
+}
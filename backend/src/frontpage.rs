@@ -0,0 +1,199 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tracing::{error, info};
+
+// Periodic snapshots of the HN front page (top 30 by rank) so GET /api/frontpage/diff can show
+// which stories entered, left, rose or fell between two points in time. Interval from
+// FRONTPAGE_SNAPSHOT_INTERVAL_SECS (default 600s, 0 disables); the last MAX_SNAPSHOTS are kept.
+// Each snapshot is also checked against the radar's watched keywords (see radar.rs), and kept in
+// the archive when it's enabled so /api/stories?as_of= can reconstruct past front pages.
+
+const FRONTPAGE_SIZE: usize = 30;
+const MAX_SNAPSHOTS: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u32,
+    // 1-based position on the front page
+    pub rank: usize,
+    pub title: Option<String>,
+    pub score: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    pub taken_at: DateTime<Utc>,
+    pub stories: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Movement {
+    pub id: u32,
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_rank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_rank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score_change: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diff {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub entered: Vec<Movement>,
+    pub left: Vec<Movement>,
+    // Biggest moves first
+    pub rose: Vec<Movement>,
+    pub fell: Vec<Movement>,
+}
+
+static SNAPSHOTS: OnceLock<Mutex<VecDeque<Snapshot>>> = OnceLock::new();
+
+fn snapshots() -> &'static Mutex<VecDeque<Snapshot>> {
+    SNAPSHOTS.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+fn record(snapshot: Snapshot) {
+    let mut snapshots = snapshots().lock().unwrap();
+    if snapshots.len() == MAX_SNAPSHOTS {
+        snapshots.pop_front();
+    }
+    snapshots.push_back(snapshot);
+}
+
+pub fn taken_at() -> Vec<DateTime<Utc>> {
+    snapshots().lock().unwrap().iter().map(|s| s.taken_at).collect()
+}
+
+// Latest snapshot taken at or before `at` (the latest overall when None)
+pub fn at_or_before(at: Option<DateTime<Utc>>) -> Option<Snapshot> {
+    let snapshots = snapshots().lock().unwrap();
+    snapshots.iter().rev().find(|s| at.is_none_or(|at| s.taken_at <= at)).cloned()
+}
+
+// Like at_or_before, but from the archive when it's enabled, which outlives restarts and the
+// in-memory limit
+pub async fn as_of(at: DateTime<Utc>) -> Result<Option<Snapshot>, String> {
+    if crate::archive::enabled() {
+        crate::archive::frontpage_at(at).await
+    } else {
+        Ok(at_or_before(Some(at)))
+    }
+}
+
+// The snapshot just before `snapshot`, the default baseline for a diff
+pub fn previous(snapshot: &Snapshot) -> Option<Snapshot> {
+    snapshots().lock().unwrap().iter().rev().find(|s| s.taken_at < snapshot.taken_at).cloned()
+}
+
+pub fn diff(from: &Snapshot, to: &Snapshot) -> Diff {
+    let find = |snapshot: &Snapshot, id: u32| snapshot.stories.iter().find(|e| e.id == id).cloned();
+    let score_change = |a: Option<u32>, b: Option<u32>| Some(i64::from(b?) - i64::from(a?));
+
+    let mut diff = Diff { from: from.taken_at, to: to.taken_at, entered: vec![], left: vec![], rose: vec![], fell: vec![] };
+    for entry in &to.stories {
+        match find(from, entry.id) {
+            None => diff.entered.push(Movement {
+                id: entry.id,
+                title: entry.title.clone(),
+                from_rank: None,
+                to_rank: Some(entry.rank),
+                score_change: None,
+            }),
+            Some(before) if before.rank != entry.rank => {
+                let movement = Movement {
+                    id: entry.id,
+                    title: entry.title.clone(),
+                    from_rank: Some(before.rank),
+                    to_rank: Some(entry.rank),
+                    score_change: score_change(before.score, entry.score),
+                };
+                if entry.rank < before.rank {
+                    diff.rose.push(movement);
+                } else {
+                    diff.fell.push(movement);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    for entry in &from.stories {
+        if find(to, entry.id).is_none() {
+            diff.left.push(Movement {
+                id: entry.id,
+                title: entry.title.clone(),
+                from_rank: Some(entry.rank),
+                to_rank: None,
+                score_change: None,
+            });
+        }
+    }
+
+    let distance = |m: &Movement| m.from_rank.unwrap_or(0).abs_diff(m.to_rank.unwrap_or(0));
+    diff.rose.sort_by_key(|m| std::cmp::Reverse(distance(m)));
+    diff.fell.sort_by_key(|m| std::cmp::Reverse(distance(m)));
+    diff
+}
+
+async fn take_snapshot() -> Result<Snapshot, anyhow::Error> {
+    let client = crate::get_hn_client();
+    let ids = client.get_top_stories().await?;
+    let ids = &ids[..ids.len().min(FRONTPAGE_SIZE)];
+    let batch = client.get_stories_batch(ids).await;
+
+    // Ranks follow the topstories order, including positions whose item couldn't be fetched
+    let stories = ids
+        .iter()
+        .enumerate()
+        .map(|(i, &id)| {
+            let story = batch.items.iter().find(|s| s.id == id);
+            Entry { id, rank: i + 1, title: story.and_then(|s| s.title.clone()), score: story.and_then(|s| s.score) }
+        })
+        .collect();
+    Ok(Snapshot { taken_at: Utc::now(), stories })
+}
+
+fn interval() -> Option<Duration> {
+    let secs = env::var("FRONTPAGE_SNAPSHOT_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(600u64);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+pub fn snapshots_enabled() -> bool {
+    interval().is_some()
+}
+
+pub fn spawn_snapshot_worker() {
+    let Some(interval) = interval() else {
+        info!("Front-page snapshots disabled");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match take_snapshot().await {
+                Ok(snapshot) => {
+                    crate::radar::check(&snapshot).await;
+                    if crate::archive::enabled() {
+                        if let Err(e) = crate::archive::save_frontpage(&snapshot).await {
+                            error!("Failed to archive the front-page snapshot: {}", e);
+                        }
+                    }
+                    record(snapshot);
+                }
+                Err(e) => error!("Failed to snapshot the front page: {}", e),
+            }
+        }
+    });
+}
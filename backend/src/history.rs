@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use tracing::error;
+
+// Raw upstream generation exchanges for background jobs (request body, raw response chunks and
+// the final script), served at GET /api/jobs/:id/raw for debugging and building regression
+// fixtures. Kept in memory for recent jobs and written to GENERATION_HISTORY_DIR/<job_id>.json
+// when that is set, so they survive restarts and can be replayed offline.
+
+const MAX_IN_MEMORY: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub job_id: String,
+    pub provider: String,
+    pub url: String,
+    pub request: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    // Response body as received; a non-streaming response is a single chunk
+    pub chunks: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+static RECENT: OnceLock<Mutex<VecDeque<Exchange>>> = OnceLock::new();
+
+fn recent() -> &'static Mutex<VecDeque<Exchange>> {
+    RECENT.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+fn history_dir() -> Option<std::path::PathBuf> {
+    env::var("GENERATION_HISTORY_DIR").ok().filter(|d| !d.is_empty()).map(std::path::PathBuf::from)
+}
+
+// Job IDs are UUIDs we generate, but never let one escape the directory
+fn file_for(job_id: &str) -> Option<std::path::PathBuf> {
+    let safe = !job_id.is_empty() && job_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    history_dir().filter(|_| safe).map(|dir| dir.join(format!("{}.json", job_id)))
+}
+
+fn persist(exchange: &Exchange) {
+    let Some(path) = file_for(&exchange.job_id) else { return };
+    let result = std::fs::create_dir_all(path.parent().unwrap_or(std::path::Path::new(".")))
+        .and_then(|_| std::fs::write(&path, serde_json::to_vec_pretty(exchange).unwrap_or_default()));
+    if let Err(e) = result {
+        error!("Failed to write generation history {}: {}", path.display(), e);
+    }
+}
+
+pub fn record(exchange: Exchange) {
+    persist(&exchange);
+    let mut recent = recent().lock().unwrap();
+    recent.retain(|e| e.job_id != exchange.job_id);
+    if recent.len() == MAX_IN_MEMORY {
+        recent.pop_front();
+    }
+    recent.push_back(exchange);
+}
+
+// Attach the post-processed script once generation succeeds
+pub fn set_output(job_id: &str, output: &str) {
+    if let Some(mut exchange) = get(job_id) {
+        exchange.output = Some(output.to_string());
+        record(exchange);
+    }
+}
+
+pub fn get(job_id: &str) -> Option<Exchange> {
+    if let Some(exchange) = recent().lock().unwrap().iter().find(|e| e.job_id == job_id) {
+        return Some(exchange.clone());
+    }
+    let raw = std::fs::read(file_for(job_id)?).ok()?;
+    serde_json::from_slice(&raw).ok()
+}
@@ -0,0 +1,88 @@
+use axum::extract::Request;
+use axum::http::{header, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// In-memory cache of HN API responses (items and the top-stories list) inside HNClient, so page
+// loads within HN_CACHE_TTL_SECS (default 60, 0 disables it) are served from memory instead of
+// refetching every item from Firebase. The updates worker evicts items HN reports as changed, and
+// ?refresh=true on the /api/stories endpoints skips cached entries for that request.
+
+const MAX_ENTRIES: usize = 20_000;
+
+pub fn ttl() -> Option<Duration> {
+    let secs = env::var("HN_CACHE_TTL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(60u64);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+tokio::task_local! {
+    static REFRESH: bool;
+}
+
+// True inside a ?refresh=true request; fresh responses are still stored for later requests
+fn refreshing() -> bool {
+    REFRESH.try_with(|refresh| *refresh).unwrap_or(false)
+}
+
+// Keyed by API path ("topstories", "item/123")
+#[derive(Default)]
+pub struct Cache {
+    entries: Mutex<HashMap<String, (serde_json::Value, Instant)>>,
+}
+
+impl Cache {
+    pub fn get(&self, path: &str) -> Option<serde_json::Value> {
+        let ttl = ttl()?;
+        if refreshing() {
+            return None;
+        }
+        let entries = self.entries.lock().unwrap();
+        entries.get(path).filter(|(_, fetched_at)| fetched_at.elapsed() < ttl).map(|(value, _)| value.clone())
+    }
+
+    pub fn put(&self, path: &str, value: serde_json::Value) {
+        let Some(ttl) = ttl() else { return };
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, (_, fetched_at)| fetched_at.elapsed() < ttl);
+            // Still full of live entries: skip rather than grow without bound
+            if entries.len() >= MAX_ENTRIES {
+                return;
+            }
+        }
+        entries.insert(path.to_string(), (value, Instant::now()));
+    }
+
+    // Drop items HN reports as changed; returns how many were cached
+    pub fn invalidate_items(&self, ids: &[u32]) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        ids.iter().filter(|id| entries.remove(&format!("item/{}", id)).is_some()).count()
+    }
+}
+
+// ?refresh=true bypasses the cache, and successful /api/stories responses say how long clients
+// may reuse them
+pub async fn layer(request: Request, next: Next) -> Response {
+    let stories = request.uri().path().starts_with("/api/stories");
+    let refresh = stories
+        && request
+            .uri()
+            .query()
+            .is_some_and(|query| url::form_urlencoded::parse(query.as_bytes()).any(|(k, v)| k == "refresh" && v == "true"));
+
+    let mut response = REFRESH.scope(refresh, next.run(request)).await;
+    if stories && response.status().is_success() && !response.headers().contains_key(header::CACHE_CONTROL) {
+        let value = match ttl() {
+            Some(ttl) if !refresh => format!("public, max-age={}", ttl.as_secs()),
+            _ => "no-cache".to_string(),
+        };
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.headers_mut().insert(header::CACHE_CONTROL, value);
+        }
+    }
+    response
+}
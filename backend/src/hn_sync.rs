@@ -0,0 +1,44 @@
+use std::env;
+use std::time::Duration;
+use tracing::{error, info};
+
+// Polls HN's /v0/updates feed and evicts cached items and profiles that changed, so caches don't
+// have to wait out their TTL. Interval from HN_UPDATES_INTERVAL_SECS (default 60s, 0 disables the worker).
+
+fn interval() -> Option<Duration> {
+    let secs = env::var("HN_UPDATES_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60u64);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+pub fn spawn_updates_worker() {
+    let Some(interval) = interval() else {
+        info!("HN updates worker disabled");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match crate::get_hn_client().get_updates().await {
+                Ok(updates) => {
+                    let evicted = crate::authors::invalidate(&updates.profiles)
+                        + crate::get_hn_client().cache.invalidate_items(&updates.items);
+                    if evicted > 0 {
+                        info!(
+                            "HN updates: {} changed items, {} changed profiles ({} evicted from cache)",
+                            updates.items.len(),
+                            updates.profiles.len(),
+                            evicted
+                        );
+                    }
+                }
+                Err(e) => error!("Failed to fetch HN updates: {}", e),
+            }
+        }
+    });
+}
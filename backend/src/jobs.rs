@@ -0,0 +1,860 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info, warn};
+
+// In-memory registry of background generation jobs. A failed run is retried up to
+// JOBS_MAX_ATTEMPTS times (default 2), JOBS_RETRY_DELAY_SECS apart (default 30); after that the
+// job is dead-lettered with every attempt's error and its request, so it can be re-driven. Jobs
+// about sensitive stories may be held for approval or skipped (see topics.rs).
+//
+// With the archive on, every job is also kept in its jobs table, which is how API and worker
+// processes share the queue (see roles.rs): the registry holds the jobs this process created or
+// runs, and lookups fall back to the table for the rest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub kind: String,
+    pub priority: Priority,
+    pub status: JobStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    // Pipeline step currently running (or the one that failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+    // What the current stage found, e.g. comment filter stats
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage_details: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub attempts: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<Failure>,
+    // Sensitive categories the story was flagged for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sensitive: Vec<String>,
+    // Original trigger payload, replayed by POST /admin/jobs/:id/retry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request: Option<serde_json::Value>,
+    // Keys the caller brought (BYOK), reused by every attempt and admin retry; memory only, and
+    // dropped once the job can't run again
+    #[serde(skip)]
+    pub credentials: crate::credentials::Credentials,
+    // The process running the job; None while it waits for a worker to claim it
+    #[serde(skip)]
+    pub runner: Option<String>,
+    // Started with the caller's own keys, so only the process that accepted it can run it
+    #[serde(skip)]
+    pub local: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Failure {
+    pub attempt: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+    pub error: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    // Out of attempts; waits for triage in GET /admin/jobs/dead
+    Dead,
+    // Flagged as sensitive; waits in GET /admin/jobs/pending
+    AwaitingApproval,
+    // Not generated: flagged as sensitive and skipped by policy, or rejected
+    Skipped,
+    // Its process stopped mid-run and it couldn't be re-queued (see recover())
+    Interrupted,
+}
+
+impl JobStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Dead => "dead",
+            JobStatus::AwaitingApproval => "awaiting_approval",
+            JobStatus::Skipped => "skipped",
+            JobStatus::Interrupted => "interrupted",
+        }
+    }
+
+    fn finished(self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Dead | JobStatus::Skipped | JobStatus::Interrupted)
+    }
+}
+
+// Lanes in descending order of urgency: someone waiting on "Generate now", a periodic digest,
+// and bulk backfill/archival work
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Backfill,
+    Scheduled,
+    Interactive,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Interactive, Priority::Scheduled, Priority::Backfill];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Interactive => "interactive",
+            Priority::Scheduled => "scheduled",
+            Priority::Backfill => "backfill",
+        }
+    }
+
+    // JOBS_MAX_<LANE>; interactive work may use every slot by default
+    pub fn limit_key(self) -> String {
+        format!("JOBS_MAX_{}", self.name().to_uppercase())
+    }
+
+    fn max_running(self) -> usize {
+        let default = match self {
+            Priority::Interactive => max_concurrent(),
+            Priority::Scheduled => 2,
+            Priority::Backfill => 1,
+        };
+        env::var(self.limit_key()).ok().and_then(|v| v.trim().parse().ok()).filter(|n| *n > 0).unwrap_or(default)
+    }
+}
+
+// Finished jobs (results and failure history included) stay listable until the registry holds
+// this many; then the oldest succeeded or skipped jobs are dropped first, then the oldest dead
+// ones. Queued, running and held jobs are never dropped. The jobs table is trimmed the same way.
+pub const MAX_JOBS: usize = 1_000;
+
+static JOBS: OnceLock<Mutex<HashMap<String, Job>>> = OnceLock::new();
+
+fn jobs() -> &'static Mutex<HashMap<String, Job>> {
+    JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn max_attempts() -> u32 {
+    env::var("JOBS_MAX_ATTEMPTS").ok().and_then(|v| v.trim().parse().ok()).filter(|n| *n > 0).unwrap_or(2)
+}
+
+pub fn retry_delay() -> std::time::Duration {
+    let secs = env::var("JOBS_RETRY_DELAY_SECS").ok().and_then(|v| v.trim().parse().ok()).unwrap_or(30);
+    std::time::Duration::from_secs(secs)
+}
+
+// Identifies this process as the runner of its jobs
+pub fn process_id() -> &'static str {
+    static ID: OnceLock<String> = OnceLock::new();
+    ID.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+// Runs here unless this is an API process, which leaves the job to a worker. Jobs with the
+// caller's own keys always run here: the keys are never written to the database.
+pub fn create(kind: &str, priority: Priority, request: serde_json::Value) -> Job {
+    let now = Utc::now();
+    let credentials = crate::credentials::current();
+    let local = !credentials.is_empty();
+    let job = Job {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind: kind.to_string(),
+        priority,
+        status: JobStatus::Queued,
+        created_at: now,
+        updated_at: now,
+        stage: None,
+        stage_details: None,
+        result: None,
+        error: None,
+        attempts: 0,
+        failures: Vec::new(),
+        sensitive: Vec::new(),
+        request: Some(request),
+        credentials,
+        runner: (crate::roles::runs_jobs() || local).then(|| process_id().to_string()),
+        local,
+    };
+    let mut jobs = jobs().lock().unwrap();
+    prune(&mut jobs);
+    jobs.insert(job.id.clone(), job.clone());
+    persist(&job);
+    job
+}
+
+// Makes room for one more job
+fn prune(jobs: &mut HashMap<String, Job>) {
+    let excess = (jobs.len() + 1).saturating_sub(MAX_JOBS);
+    if excess == 0 {
+        return;
+    }
+    let mut finished: Vec<(bool, DateTime<Utc>, String)> = jobs
+        .values()
+        .filter_map(|job| match job.status {
+            JobStatus::Succeeded | JobStatus::Skipped => Some((false, job.updated_at, job.id.clone())),
+            JobStatus::Dead | JobStatus::Interrupted => Some((true, job.updated_at, job.id.clone())),
+            JobStatus::Queued | JobStatus::Running | JobStatus::AwaitingApproval => None,
+        })
+        .collect();
+    finished.sort();
+    for (_, _, id) in finished.into_iter().take(excess) {
+        jobs.remove(&id);
+    }
+}
+
+// This process's copy; see load() for jobs other processes run
+pub fn get(id: &str) -> Option<Job> {
+    jobs().lock().unwrap().get(id).cloned()
+}
+
+// The freshest copy: this process's, or the database's when another process has moved it on
+pub async fn load(id: &str) -> Option<Job> {
+    let local = get(id);
+    if !crate::archive::enabled() {
+        return local;
+    }
+    let stored = crate::archive::job(id).await.unwrap_or_else(|e| {
+        error!("Failed to load job {}: {}", id, e);
+        None
+    });
+    newer(local, stored)
+}
+
+fn newer(local: Option<Job>, stored: Option<Job>) -> Option<Job> {
+    match (local, stored) {
+        (Some(local), Some(stored)) if stored.updated_at > local.updated_at => Some(stored),
+        (local, stored) => local.or(stored),
+    }
+}
+
+// This process's jobs and the database's in a given status, each in its freshest copy
+async fn with_status(status: JobStatus) -> Vec<Job> {
+    let mut found: HashMap<String, Job> = jobs().lock().unwrap().iter().map(|(id, job)| (id.clone(), job.clone())).collect();
+    if crate::archive::enabled() {
+        match crate::archive::jobs_with_status(status.name()).await {
+            Ok(stored) => {
+                for job in stored {
+                    let local = found.remove(&job.id);
+                    if let Some(job) = newer(local, Some(job)) {
+                        found.insert(job.id.clone(), job);
+                    }
+                }
+            }
+            Err(e) => error!("Failed to load {} jobs: {}", status.name(), e),
+        }
+    }
+    found.into_values().filter(|job| job.status == status).collect()
+}
+
+// Resolves once a job another process runs has finished, like the task handle of a local job
+pub async fn wait(id: String) -> Result<serde_json::Value, String> {
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let Some(job) = load(&id).await else { return Err(format!("Job {} no longer exists", id)) };
+        match job.status {
+            JobStatus::Succeeded => return Ok(job.result.unwrap_or_default()),
+            status if status.finished() => return Err(job.error.unwrap_or_else(|| format!("Job {} {}", id, status.name()))),
+            _ => {}
+        }
+    }
+}
+
+// Takes the most urgent job waiting in one of `lanes` for this process
+pub async fn claim(lanes: &[Priority]) -> Result<Option<Job>, String> {
+    let Some(mut job) = crate::archive::claim_job(process_id(), lanes).await? else { return Ok(None) };
+    job.runner = Some(process_id().to_string());
+    let mut jobs = jobs().lock().unwrap();
+    prune(&mut jobs);
+    jobs.insert(job.id.clone(), job.clone());
+    Ok(Some(job))
+}
+
+pub fn mark_running(id: &str) {
+    update(id, |job| {
+        job.status = JobStatus::Running;
+        job.attempts += 1;
+    });
+    publish("job_progress", id);
+}
+
+pub fn set_stage(id: &str, stage: &str) {
+    update(id, |job| {
+        job.stage = Some(stage.to_string());
+        job.stage_details = None;
+    });
+    publish("job_progress", id);
+}
+
+pub fn set_stage_details(id: &str, details: serde_json::Value) {
+    update(id, |job| job.stage_details = Some(details));
+    publish("job_progress", id);
+}
+
+pub fn mark_succeeded(id: &str, result: serde_json::Value) {
+    update(id, |job| {
+        job.status = JobStatus::Succeeded;
+        job.result = Some(result);
+        job.credentials = Default::default();
+    });
+    publish("job_finished", id);
+}
+
+// A generation result counts as a success only when it carries a script. The generator answers
+// 200 with "raw_response" or "success_but_unexpected_format" when it couldn't read the upstream
+// reply; those are failed attempts, not episodes to publish.
+pub fn check_result(result: &serde_json::Value) -> Result<(), String> {
+    let status = result.get("status").and_then(|s| s.as_str()).unwrap_or("missing");
+    if status != "success" {
+        return Err(format!("Generation returned no usable script (status: {})", status));
+    }
+    match result.get("podcast_script") {
+        None | Some(serde_json::Value::Null) => Err("Generation succeeded but returned no script".to_string()),
+        Some(serde_json::Value::String(s)) if s.trim().is_empty() => Err("Generation succeeded but returned an empty script".to_string()),
+        Some(_) => Ok(()),
+    }
+}
+
+// Returns true when the job has attempts left and should be run again
+pub fn record_failure(id: &str, error: String) -> bool {
+    let mut retry = false;
+    update(id, |job| {
+        job.failures.push(Failure { attempt: job.attempts, stage: job.stage.clone(), error: error.clone(), at: Utc::now() });
+        job.error = Some(error);
+        retry = job.attempts < max_attempts();
+        job.status = if retry { JobStatus::Queued } else { JobStatus::Dead };
+    });
+    publish(if retry { "job_progress" } else { "job_finished" }, id);
+    retry
+}
+
+// Dead-letters a job that can't be run at all
+pub fn mark_dead(id: &str, error: String) {
+    update(id, |job| {
+        job.status = JobStatus::Dead;
+        job.error = Some(error);
+    });
+    publish("job_finished", id);
+}
+
+// Most recently failed first
+pub async fn dead() -> Vec<Job> {
+    let mut dead = with_status(JobStatus::Dead).await;
+    dead.sort_by_key(|job| std::cmp::Reverse(job.updated_at));
+    dead
+}
+
+// Puts a dead job back in the queue with a fresh attempt budget; failure history is kept. It runs
+// here, or on a worker when this is an API process. Err when the job isn't dead-lettered (e.g. it
+// lost a race with another retry) or needs caller keys this process doesn't hold.
+pub async fn requeue(id: &str) -> Result<Job, String> {
+    let mut job = load(id).await.filter(|job| job.status == JobStatus::Dead).ok_or_else(|| format!("Job {} is not dead-lettered", id))?;
+    if job.local && job.credentials.is_empty() {
+        return Err(format!("Job {} was started with the caller's own keys and can only be retried by the process that accepted it", id));
+    }
+    job.status = JobStatus::Queued;
+    job.attempts = 0;
+    job.stage = None;
+    job.error = None;
+    job.updated_at = Utc::now();
+    job.runner = (crate::roles::runs_jobs() || job.local).then(|| process_id().to_string());
+    {
+        let mut jobs = jobs().lock().unwrap();
+        if jobs.get(id).is_some_and(|local| local.status != JobStatus::Dead) {
+            return Err(format!("Job {} is not dead-lettered", id));
+        }
+        jobs.insert(id.to_string(), job.clone());
+    }
+    // The database decides between retries that reached different processes
+    if crate::archive::enabled() && !crate::archive::requeue_job(&job).await.unwrap_or(false) {
+        return Err(format!("Job {} is not dead-lettered", id));
+    }
+    publish("job_progress", id);
+    Ok(job)
+}
+
+// --- Approvals ---
+
+static APPROVALS: OnceLock<Mutex<HashMap<String, oneshot::Sender<bool>>>> = OnceLock::new();
+
+fn approvals() -> &'static Mutex<HashMap<String, oneshot::Sender<bool>>> {
+    APPROVALS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Holds the job until decide() is called, here or (through the database) in another process;
+// true when approved
+pub async fn await_approval(id: &str, sensitive: Vec<String>) -> bool {
+    let (decision, mut wait) = oneshot::channel();
+    approvals().lock().unwrap().insert(id.to_string(), decision);
+    update(id, |job| {
+        job.status = JobStatus::AwaitingApproval;
+        job.sensitive = sensitive;
+    });
+    publish("job_progress", id);
+    loop {
+        tokio::select! {
+            decision = &mut wait => return decision.unwrap_or(false),
+            _ = tokio::time::sleep(POLL_INTERVAL), if crate::archive::enabled() => {
+                let approve = match crate::archive::take_job_decision(id).await {
+                    Ok(Some(approve)) => approve,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        error!("Failed to check job {} for a decision: {}", id, e);
+                        continue;
+                    }
+                };
+                approvals().lock().unwrap().remove(id);
+                if approve {
+                    update(id, |job| job.status = JobStatus::Queued);
+                }
+                return approve;
+            }
+        }
+    }
+}
+
+// None when the job isn't waiting for approval
+pub async fn decide(id: &str, approve: bool) -> Option<Job> {
+    let decision = approvals().lock().unwrap().remove(id);
+    if let Some(decision) = decision {
+        if approve {
+            update(id, |job| job.status = JobStatus::Queued);
+        }
+        let _ = decision.send(approve);
+        return get(id);
+    }
+    // Held by another process, which picks the decision up from the database
+    let mut job = load(id).await.filter(|job| job.status == JobStatus::AwaitingApproval)?;
+    if !crate::archive::decide_job(id, approve).await.unwrap_or(false) {
+        return None;
+    }
+    if approve {
+        job.status = JobStatus::Queued;
+    }
+    Some(job)
+}
+
+pub fn mark_skipped(id: &str, reason: String, sensitive: Vec<String>) {
+    update(id, |job| {
+        job.status = JobStatus::Skipped;
+        job.error = Some(reason);
+        job.credentials = Default::default();
+        job.sensitive = sensitive;
+    });
+    publish("job_finished", id);
+}
+
+// Oldest first, so they are reviewed in the order they arrived
+pub async fn awaiting_approval() -> Vec<Job> {
+    let mut pending = with_status(JobStatus::AwaitingApproval).await;
+    pending.sort_by_key(|job| job.created_at);
+    pending
+}
+
+fn update(id: &str, f: impl FnOnce(&mut Job)) {
+    if let Some(job) = jobs().lock().unwrap().get_mut(id) {
+        f(job);
+        job.updated_at = Utc::now();
+        persist(job);
+    }
+}
+
+// --- Persistence ---
+
+// How often a process looks in the database for changes another process made
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// The jobs table is trimmed after this many writes
+const PRUNE_EVERY: usize = 100;
+
+static WRITER: OnceLock<mpsc::UnboundedSender<Job>> = OnceLock::new();
+
+// One task writes every change to the jobs table, in the order they happened
+pub fn spawn_writer() {
+    if !crate::archive::enabled() {
+        return;
+    }
+    let (sender, mut changes) = mpsc::unbounded_channel::<Job>();
+    if WRITER.set(sender).is_err() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut writes = 0;
+        while let Some(job) = changes.recv().await {
+            if let Err(e) = crate::archive::save_job(&job).await {
+                error!("Failed to save job {}: {}", job.id, e);
+            }
+            writes += 1;
+            if writes % PRUNE_EVERY == 0 {
+                if let Err(e) = crate::archive::prune_jobs(MAX_JOBS).await {
+                    error!("Failed to trim the jobs table: {}", e);
+                }
+            }
+        }
+    });
+}
+
+fn persist(job: &Job) {
+    if let Some(writer) = WRITER.get() {
+        let _ = writer.send(job.clone());
+    }
+}
+
+// --- Recovery ---
+
+// Every process records that it's alive this often; a runner silent for RUNNER_TIMEOUT is gone
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+const RUNNER_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Registers this process as alive, recovers jobs left behind by processes that stopped (e.g. a
+// deploy mid-generation), then keeps doing both in the background
+pub async fn start_heartbeat() {
+    if !crate::archive::enabled() {
+        return;
+    }
+    beat().await;
+    recover().await;
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + HEARTBEAT_INTERVAL, HEARTBEAT_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            beat().await;
+            recover().await;
+        }
+    });
+}
+
+async fn beat() {
+    if let Err(e) = crate::archive::touch_process(process_id(), crate::roles::get().name()).await {
+        error!("Failed to record the process heartbeat: {}", e);
+    }
+}
+
+async fn recover() {
+    let alive_since = Utc::now() - chrono::Duration::from_std(RUNNER_TIMEOUT).unwrap_or_default();
+    match crate::archive::orphaned_jobs(alive_since).await {
+        Ok(orphaned) => {
+            for job in orphaned {
+                recover_job(job).await;
+            }
+        }
+        Err(e) => error!("Failed to look for interrupted jobs: {}", e),
+    }
+}
+
+// Marks the job interrupted, then re-queues it for any worker when its stored request can be
+// replayed: the attempt restarts from the beginning and doesn't count against the budget. Jobs
+// with the caller's own keys can't be resumed, since the keys were only held by the process.
+async fn recover_job(mut job: Job) {
+    let (runner, status) = (job.runner.take(), job.status);
+    let now = Utc::now();
+    let error = format!("Interrupted: the process running it stopped while it was {}", status.name());
+    job.failures.push(Failure { attempt: job.attempts, stage: job.stage.clone(), error: error.clone(), at: now });
+    job.error = Some(error);
+    job.status = JobStatus::Interrupted;
+    job.updated_at = now;
+    // Another process may have recovered it first
+    match crate::archive::replace_job(&job, runner.as_deref(), status).await {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            error!("Failed to mark job {} interrupted: {}", job.id, e);
+            return;
+        }
+    }
+    warn!("Job {} was interrupted: its process stopped while it was {}", job.id, status.name());
+
+    if job.local || job.request.is_none() {
+        publish_job("job_finished", &job);
+        let callback_url = job.request.as_ref().and_then(|request| request.get("callback_url")).and_then(|url| url.as_str());
+        if let Some(url) = callback_url {
+            crate::callbacks::spawn_delivery(url.to_string(), job.clone());
+        }
+        return;
+    }
+    job.status = JobStatus::Queued;
+    job.stage = None;
+    job.error = None;
+    job.updated_at = Utc::now();
+    match crate::archive::replace_job(&job, None, JobStatus::Interrupted).await {
+        Ok(true) => {
+            info!("Re-queued interrupted job {}", job.id);
+            publish_job("job_progress", &job);
+        }
+        Ok(false) => {}
+        Err(e) => error!("Failed to re-queue interrupted job {}: {}", job.id, e),
+    }
+}
+
+// Job state without the (potentially large) result; clients fetch that from /api/jobs/:id
+fn publish(kind: &'static str, id: &str) {
+    if let Some(job) = get(id) {
+        publish_job(kind, &job);
+    }
+}
+
+fn publish_job(kind: &'static str, job: &Job) {
+    crate::events::publish(kind, serde_json::json!({
+        "job_id": job.id,
+        "kind": job.kind,
+        "priority": job.priority,
+        "status": job.status,
+        "stage": job.stage,
+        "stage_details": job.stage_details,
+        "error": job.error,
+    }));
+}
+
+// --- Lanes ---
+
+// Jobs wait here for a slot. Freed slots go to the most urgent waiting job whose lane is under
+// its limit, so a queued interactive request overtakes earlier backfill jobs, while a lane at
+// its limit doesn't hold up the others. Limits are per process.
+
+pub fn max_concurrent() -> usize {
+    env::var("JOBS_MAX_CONCURRENT").ok().and_then(|v| v.trim().parse().ok()).filter(|n| *n > 0).unwrap_or(4)
+}
+
+struct Waiting {
+    priority: Priority,
+    seq: u64,
+    wake: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+struct Lanes {
+    running: HashMap<Priority, usize>,
+    waiting: Vec<Waiting>,
+    next_seq: u64,
+}
+
+static LANES: OnceLock<Mutex<Lanes>> = OnceLock::new();
+
+fn lanes() -> &'static Mutex<Lanes> {
+    LANES.get_or_init(|| Mutex::new(Lanes::default()))
+}
+
+impl Lanes {
+    fn has_room(&self, priority: Priority) -> bool {
+        self.running.values().sum::<usize>() < max_concurrent()
+            && self.running.get(&priority).copied().unwrap_or(0) < priority.max_running()
+    }
+
+    fn dispatch(&mut self) {
+        self.waiting.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.seq.cmp(&b.seq)));
+        let mut i = 0;
+        while i < self.waiting.len() {
+            let priority = self.waiting[i].priority;
+            if !self.has_room(priority) {
+                i += 1;
+                continue;
+            }
+            let waiting = self.waiting.remove(i);
+            // A dropped receiver means the job went away while queued
+            if waiting.wake.send(()).is_ok() {
+                *self.running.entry(priority).or_default() += 1;
+            }
+        }
+    }
+}
+
+// Held while a job runs; dropping it frees the slot
+pub struct Slot(Priority);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut lanes = lanes().lock().unwrap();
+        if let Some(running) = lanes.running.get_mut(&self.0) {
+            *running = running.saturating_sub(1);
+        }
+        lanes.dispatch();
+    }
+}
+
+pub async fn acquire(priority: Priority) -> Slot {
+    let wake = {
+        let mut lanes = lanes().lock().unwrap();
+        let (wake, woken) = oneshot::channel();
+        let seq = lanes.next_seq;
+        lanes.next_seq += 1;
+        lanes.waiting.push(Waiting { priority, seq, wake });
+        lanes.dispatch();
+        woken
+    };
+    // The sender is only dropped after a successful send, so this can't fail
+    let _ = wake.await;
+    Slot(priority)
+}
+
+// Lanes that could start another job right away, for claiming jobs from the database
+pub fn lanes_with_room() -> Vec<Priority> {
+    let lanes = lanes().lock().unwrap();
+    if lanes.running.values().sum::<usize>() + lanes.waiting.len() >= max_concurrent() {
+        return Vec::new();
+    }
+    Priority::ALL
+        .into_iter()
+        .filter(|p| {
+            let waiting = lanes.waiting.iter().filter(|w| w.priority == *p).count();
+            lanes.running.get(p).copied().unwrap_or(0) + waiting < p.max_running()
+        })
+        .collect()
+}
+
+// Running and waiting counts per lane, for /api/jobs/lanes
+pub fn lane_stats() -> serde_json::Value {
+    let lanes = lanes().lock().unwrap();
+    let per_lane: serde_json::Map<String, serde_json::Value> = Priority::ALL
+        .iter()
+        .map(|p| {
+            (p.name().to_string(), serde_json::json!({
+                "running": lanes.running.get(p).copied().unwrap_or(0),
+                "waiting": lanes.waiting.iter().filter(|w| w.priority == *p).count(),
+                "max_running": p.max_running(),
+            }))
+        })
+        .collect();
+    serde_json::json!({ "max_concurrent": max_concurrent(), "lanes": per_lane })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait(lanes: &mut Lanes, priority: Priority) -> oneshot::Receiver<()> {
+        let (wake, woken) = oneshot::channel();
+        let seq = lanes.next_seq;
+        lanes.next_seq += 1;
+        lanes.waiting.push(Waiting { priority, seq, wake });
+        woken
+    }
+
+    fn running(lanes: &Lanes, priority: Priority) -> usize {
+        lanes.running.get(&priority).copied().unwrap_or(0)
+    }
+
+    // Defaults: 4 slots in total, at most 2 scheduled and 1 backfill job at a time
+    #[test]
+    fn dispatch_fills_urgent_lanes_first_within_lane_limits() {
+        let mut lanes = Lanes::default();
+        let mut woken: Vec<_> = (0..3).map(|_| wait(&mut lanes, Priority::Backfill)).collect();
+        woken.extend((0..3).map(|_| wait(&mut lanes, Priority::Scheduled)));
+        woken.push(wait(&mut lanes, Priority::Interactive));
+        lanes.dispatch();
+
+        assert_eq!(running(&lanes, Priority::Interactive), 1);
+        assert_eq!(running(&lanes, Priority::Scheduled), 2);
+        assert_eq!(running(&lanes, Priority::Backfill), 1);
+        assert_eq!(lanes.waiting.len(), 3);
+        // The earliest backfill job got the backfill slot
+        assert!(woken[0].try_recv().is_ok());
+        assert!(woken[1].try_recv().is_err());
+    }
+
+    #[test]
+    fn dispatch_skips_jobs_that_went_away() {
+        let mut lanes = Lanes::default();
+        drop(wait(&mut lanes, Priority::Backfill));
+        let mut next = wait(&mut lanes, Priority::Backfill);
+        lanes.dispatch();
+
+        assert_eq!(running(&lanes, Priority::Backfill), 1);
+        assert!(next.try_recv().is_ok());
+        assert!(lanes.waiting.is_empty());
+    }
+
+    fn job(id: &str, status: JobStatus, updated_secs: i64) -> Job {
+        let updated_at = DateTime::from_timestamp(updated_secs, 0).unwrap();
+        Job {
+            id: id.to_string(),
+            kind: "story".to_string(),
+            priority: Priority::Scheduled,
+            status,
+            created_at: updated_at,
+            updated_at,
+            stage: None,
+            stage_details: None,
+            result: None,
+            error: None,
+            attempts: 0,
+            failures: Vec::new(),
+            sensitive: Vec::new(),
+            request: None,
+            credentials: Default::default(),
+            runner: None,
+            local: false,
+        }
+    }
+
+    // A full registry of running jobs plus the given ones
+    fn full_registry(extra: &[Job]) -> HashMap<String, Job> {
+        let mut jobs: HashMap<String, Job> = extra.iter().map(|job| (job.id.clone(), job.clone())).collect();
+        while jobs.len() < MAX_JOBS {
+            let id = format!("running-{}", jobs.len());
+            jobs.insert(id.clone(), job(&id, JobStatus::Running, 1_000));
+        }
+        jobs
+    }
+
+    #[test]
+    fn prune_drops_finished_jobs_before_dead_ones() {
+        let mut jobs = full_registry(&[job("dead", JobStatus::Dead, 1), job("done", JobStatus::Succeeded, 2)]);
+        prune(&mut jobs);
+        assert_eq!(jobs.len(), MAX_JOBS - 1);
+        assert!(!jobs.contains_key("done"));
+        assert!(jobs.contains_key("dead"));
+    }
+
+    #[test]
+    fn prune_drops_the_oldest_dead_job_when_nothing_else_finished() {
+        let mut jobs = full_registry(&[job("old", JobStatus::Dead, 1), job("new", JobStatus::Dead, 2)]);
+        prune(&mut jobs);
+        assert!(!jobs.contains_key("old"));
+        assert!(jobs.contains_key("new"));
+    }
+
+    #[test]
+    fn results_without_a_script_are_retried_then_dead_lettered() {
+        let unexpected = serde_json::json!({ "raw_platform_response": {}, "status": "success_but_unexpected_format" });
+        let unparsed = serde_json::json!({ "raw_response": "<html>", "status": 502, "parse_error": "expected value" });
+        assert!(check_result(&serde_json::json!({ "status": "success", "podcast_script": "" })).is_err());
+        assert!(check_result(&serde_json::json!({ "status": "success", "podcast_script": "Welcome back" })).is_ok());
+
+        let job = create("story_episode", Priority::Scheduled, serde_json::json!({}));
+        let mut retries = Vec::new();
+        for result in [&unexpected, &unparsed] {
+            mark_running(&job.id);
+            let error = check_result(result).unwrap_err();
+            retries.push(record_failure(&job.id, error));
+        }
+
+        assert_eq!(retries, [true, false]);
+        let job = get(&job.id).unwrap();
+        assert_eq!(job.status, JobStatus::Dead);
+        assert!(job.result.is_none());
+        assert_eq!(job.failures.len(), 2);
+    }
+
+    #[test]
+    fn prune_keeps_interrupted_jobs_like_dead_ones() {
+        let mut jobs = full_registry(&[job("interrupted", JobStatus::Interrupted, 1), job("done", JobStatus::Succeeded, 2)]);
+        prune(&mut jobs);
+        assert!(!jobs.contains_key("done"));
+        assert!(jobs.contains_key("interrupted"));
+    }
+
+    #[test]
+    fn prune_never_drops_active_jobs() {
+        let mut jobs = full_registry(&[job("held", JobStatus::AwaitingApproval, 1), job("queued", JobStatus::Queued, 1)]);
+        prune(&mut jobs);
+        assert_eq!(jobs.len(), MAX_JOBS);
+    }
+}
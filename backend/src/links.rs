@@ -0,0 +1,201 @@
+use crate::{fetch, polite};
+use serde::Serialize;
+use std::env;
+use std::sync::OnceLock;
+use tracing::info;
+
+// Post-generation check of URLs cited in a script. LLMs sometimes invent links, so each one is
+// probed through the SSRF-safe fetcher and dead ones are swapped for a known-good link from the
+// pipeline data (same host), removed, or just flagged depending on LINK_VERIFICATION.
+
+// Bound the fan-out for scripts that cite a lot of links
+const MAX_CHECKED_LINKS: usize = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Off,
+    Flag,
+    Remove,
+}
+
+// LINK_VERIFICATION=off|flag|remove (default: flag)
+pub fn mode() -> Mode {
+    match env::var("LINK_VERIFICATION").unwrap_or_default().as_str() {
+        "off" => Mode::Off,
+        "remove" => Mode::Remove,
+        _ => Mode::Flag,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkStatus {
+    Ok,
+    Dead,
+    // Reachable but refused us (auth walls, bot protection, rate limits)
+    Unverified,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkAction {
+    Kept,
+    Flagged,
+    Replaced,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkCheck {
+    pub url: String,
+    pub status: LinkStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    pub action: LinkAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkReport {
+    pub checked: usize,
+    pub dead: usize,
+    pub links: Vec<LinkCheck>,
+}
+
+fn url_pattern() -> &'static regex::Regex {
+    static PATTERN: OnceLock<regex::Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| regex::Regex::new(r#"https?://[^\s<>"'()\[\]]+"#).unwrap())
+}
+
+// Sentence punctuation after a URL isn't part of it
+fn trim_url(matched: &str) -> &str {
+    matched.trim_end_matches(['.', ',', ';', ':', '!', '?', '*'])
+}
+
+pub fn extract_urls(text: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for m in url_pattern().find_iter(text) {
+        let url = trim_url(m.as_str());
+        if !urls.iter().any(|u| u == url) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+async fn check(url: &str) -> (LinkStatus, Option<u16>) {
+    let parsed = match fetch::parse_url(url) {
+        Ok(parsed) => parsed,
+        Err(_) => return (LinkStatus::Dead, None),
+    };
+
+    let mut response = polite::send(reqwest::Method::HEAD, parsed.clone()).await;
+    // Some servers don't implement HEAD
+    if let Ok(r) = &response {
+        if r.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED || r.status() == reqwest::StatusCode::NOT_IMPLEMENTED {
+            response = polite::get(parsed).await;
+        }
+    }
+
+    match response {
+        Ok(r) => {
+            let code = r.status().as_u16();
+            let status = match code {
+                200..=399 => LinkStatus::Ok,
+                401 | 403 | 429 => LinkStatus::Unverified,
+                _ => LinkStatus::Dead,
+            };
+            (status, Some(code))
+        }
+        // We chose not to look, which says nothing about the link
+        Err(polite::PoliteError::Disallowed) => (LinkStatus::Unverified, None),
+        Err(_) => (LinkStatus::Dead, None),
+    }
+}
+
+// Rewrites whole occurrences of `url` only, so a longer URL that merely starts with it (".../x"
+// inside ".../xyz") is left alone
+fn replace_url(script: &str, url: &str, with: &str) -> String {
+    let mut replaced = String::with_capacity(script.len());
+    let mut last = 0;
+    for m in url_pattern().find_iter(script) {
+        if trim_url(m.as_str()) == url {
+            replaced.push_str(&script[last..m.start()]);
+            replaced.push_str(with);
+            last = m.start() + url.len();
+        }
+    }
+    replaced.push_str(&script[last..]);
+    replaced
+}
+
+// Replace "[text](url)" with "text", then drop any bare occurrence
+fn remove_link(script: &str, url: &str) -> String {
+    let markdown = regex::Regex::new(&format!(r"\[([^\]]*)\]\({}\)", regex::escape(url))).unwrap();
+    let script = markdown.replace_all(script, "$1");
+    replace_url(&script, url, "")
+}
+
+pub async fn verify_script(script: &str, reference_urls: &[String], mode: Mode) -> (String, LinkReport) {
+    let urls: Vec<String> = extract_urls(script).into_iter().take(MAX_CHECKED_LINKS).collect();
+    let results = futures::future::join_all(urls.iter().map(|url| check(url))).await;
+
+    let mut script = script.to_string();
+    let mut links = Vec::new();
+
+    for (url, (status, http_status)) in urls.into_iter().zip(results) {
+        let mut replacement = None;
+        let action = if status != LinkStatus::Dead {
+            LinkAction::Kept
+        } else if let Some(known) = crate::urls::canonical_host(&url).and_then(|host| {
+            reference_urls
+                .iter()
+                .find(|r| *r != &url && crate::urls::canonical_host(r).as_deref() == Some(host.as_str()))
+        }) {
+            script = replace_url(&script, &url, known);
+            replacement = Some(known.clone());
+            LinkAction::Replaced
+        } else if mode == Mode::Remove {
+            script = remove_link(&script, &url);
+            LinkAction::Removed
+        } else {
+            LinkAction::Flagged
+        };
+
+        links.push(LinkCheck { url, status, http_status, action, replacement });
+    }
+
+    let dead = links.iter().filter(|l| l.status == LinkStatus::Dead).count();
+    if dead > 0 {
+        info!("Link verification found {} dead link(s) out of {}", dead, links.len());
+    }
+
+    (script, LinkReport { checked: links.len(), dead, links })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_unique_urls_without_trailing_punctuation() {
+        let script = "See https://a.com/x. Also (https://b.com/y), and https://a.com/x again!";
+        assert_eq!(extract_urls(script), vec!["https://a.com/x", "https://b.com/y"]);
+    }
+
+    #[test]
+    fn replace_url_leaves_longer_urls_alone() {
+        let script = "Read https://a.com/x and https://a.com/xyz. Then https://a.com/x.";
+        assert_eq!(
+            replace_url(script, "https://a.com/x", "https://real.com/"),
+            "Read https://real.com/ and https://a.com/xyz. Then https://real.com/."
+        );
+    }
+
+    #[test]
+    fn remove_link_keeps_markdown_text() {
+        let script = "Check [the post](https://a.com/x), not https://a.com/xyz or https://a.com/x";
+        assert_eq!(remove_link(script, "https://a.com/x"), "Check the post, not https://a.com/xyz or ");
+    }
+}
@@ -1,18 +1,81 @@
+mod activitypub;
+mod alerts;
+mod analysis;
+mod announcements;
+mod archive;
+mod artifacts;
+mod audio;
+mod authors;
+mod backfill;
+#[cfg(feature = "bench")]
+mod bench;
+mod callbacks;
+mod chats;
+mod config;
+mod credentials;
+mod diff;
+mod embargo;
+mod episode_audio;
+mod embed;
+mod engagement;
+mod episodes;
+mod events;
+mod feed;
+mod feed_check;
+mod feedback;
+mod fetch;
+mod fields;
+mod frontpage;
+mod history;
+mod hn_cache;
+mod hn_sync;
+mod jobs;
+mod links;
+mod personas;
+mod player;
+mod polite;
+mod privacy;
+mod quality;
+mod radar;
+mod ranking;
+mod redact;
+mod roles;
+mod script;
+mod sandbox;
+mod schedules;
+mod secrets;
+mod segments;
+mod seo;
+mod server;
+mod site;
+mod syndication;
+mod telegram;
+mod threads;
+mod topics;
+mod transcribe;
+mod tts;
+mod upstream;
+mod urls;
+mod usage;
+mod voices;
+mod websub;
+
 use axum::{
     extract::{Json, Query, Path},
     http::StatusCode,
-    response::{Json as AxumJson, Sse, Response},
-    routing::{get, post},
+    response::{Json as AxumJson, Redirect, Sse, Response},
+    routing::{delete, get, post, put},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tower_http::cors::CorsLayer;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use std::env;
 use axum::response::sse::{Event, KeepAlive};
 use axum::body::Body;
-use axum::http::HeaderMap;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::body::Bytes;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct HNStory {
@@ -25,6 +88,10 @@ struct HNStory {
     time: Option<u64>,
     descendants: Option<u32>,
     kids: Option<Vec<u32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dead: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,6 +102,60 @@ struct HNComment {
     text: Option<String>,
     kids: Option<Vec<u32>>,
     parent: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dead: Option<bool>,
+    // Filled in with ?enrich_authors=true
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<authors::AuthorProfile>,
+}
+
+// A comment with its replies, as returned by /api/stories/:id/comments/tree
+#[derive(Debug, Serialize)]
+struct HNCommentTree {
+    comment: HNComment,
+    replies: Vec<HNCommentTree>,
+}
+
+impl HNCommentTree {
+    // Nests comments under their parents, keeping HN's order; `children` is keyed by parent id.
+    // Text-less placeholders (deleted or dead comments) are dropped unless replies ended up under them.
+    fn build(parent: u32, children: &mut HashMap<u32, Vec<HNComment>>) -> Vec<HNCommentTree> {
+        children
+            .remove(&parent)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|comment| {
+                let replies = Self::build(comment.id, children);
+                HNCommentTree { comment, replies }
+            })
+            .filter(|node| node.comment.text.is_some() || !node.replies.is_empty())
+            .collect()
+    }
+}
+
+// Recently changed items and profiles from /v0/updates
+#[derive(Debug, Default, Deserialize)]
+struct HNUpdates {
+    #[serde(default)]
+    items: Vec<u32>,
+    #[serde(default)]
+    profiles: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HNUser {
+    karma: Option<i64>,
+    created: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RelatedStory {
+    id: u32,
+    title: String,
+    url: Option<String>,
+    points: Option<u32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -46,6 +167,10 @@ struct ApiError {
 struct ContentGenerationRequest {
     story_id: u32,
     comments: Vec<String>,
+    // HN usernames of the story and comment authors, scrubbed from the context when anonymizing
+    #[serde(default)]
+    authors: Vec<String>,
+    anonymize_authors: Option<bool>,
 }
 
 // Update the ContentGenerationResponse struct to match the new implementation
@@ -54,12 +179,70 @@ struct ContentGenerationResponse {
     message: String,
     context_added: bool,
     story_id: u32,
+    // Junk comments dropped before the context add
+    #[serde(skip_deserializing)]
+    comment_filter: quality::FilterStats,
+}
+
+// Why an item was left out of a batch response
+#[derive(Debug, Serialize)]
+struct ItemError {
+    id: u32,
+    error: String,
+}
+
+struct ItemBatch<T> {
+    items: Vec<T>,
+    errors: Vec<ItemError>,
+}
+
+// Deleted/dead items come back as stubs with the flag set; missing ones as `null`
+fn removed_reason(deleted: Option<bool>, dead: Option<bool>) -> Option<&'static str> {
+    if deleted == Some(true) {
+        Some("deleted")
+    } else if dead == Some(true) {
+        Some("dead")
+    } else {
+        None
+    }
+}
+
+// HN story lists, each served by <name>stories.json
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Feed {
+    #[default]
+    Top,
+    New,
+    Best,
+    Ask,
+    Show,
+    Job,
+}
+
+impl Feed {
+    const ALL: [Feed; 6] = [Feed::Top, Feed::New, Feed::Best, Feed::Ask, Feed::Show, Feed::Job];
+
+    fn name(self) -> &'static str {
+        match self {
+            Feed::Top => "top",
+            Feed::New => "new",
+            Feed::Best => "best",
+            Feed::Ask => "ask",
+            Feed::Show => "show",
+            Feed::Job => "job",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Feed> {
+        Feed::ALL.into_iter().find(|feed| feed.name() == name.trim().to_lowercase())
+    }
 }
 
 // HackerNews API client
 struct HNClient {
     client: reqwest::Client,
     base_url: String,
+    cache: hn_cache::Cache,
 }
 
 impl HNClient {
@@ -67,96 +250,364 @@ impl HNClient {
         Self {
             client: reqwest::Client::new(),
             base_url: "https://hacker-news.firebaseio.com/v0".to_string(),
+            cache: hn_cache::Cache::default(),
         }
     }
 
-    async fn get_top_stories(&self) -> Result<Vec<u32>, anyhow::Error> {
-        let url = format!("{}/topstories.json", self.base_url);
+    // Served from the cache within HN_CACHE_TTL_SECS; missing items (null) aren't cached
+    async fn get_cached<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, anyhow::Error> {
+        if let Some(value) = self.cache.get(path) {
+            return Ok(serde_json::from_value(value)?);
+        }
+        let url = format!("{}/{}.json", self.base_url, path);
         let response = self.client.get(&url).send().await?;
-        let story_ids: Vec<u32> = response.json().await?;
-        Ok(story_ids)
+        let value: serde_json::Value = response.json().await?;
+        if !value.is_null() {
+            self.cache.put(path, value.clone());
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    async fn get_top_stories(&self) -> Result<Vec<u32>, anyhow::Error> {
+        self.get_feed(Feed::Top).await
+    }
+
+    // Story ids of any HN list, in its own order
+    async fn get_feed(&self, feed: Feed) -> Result<Vec<u32>, anyhow::Error> {
+        self.get_cached(&format!("{}stories", feed.name())).await
+    }
+
+    async fn get_item<T: serde::de::DeserializeOwned>(&self, id: u32) -> Result<Option<T>, anyhow::Error> {
+        self.get_cached(&format!("item/{}", id)).await
     }
 
     async fn get_story(&self, id: u32) -> Result<HNStory, anyhow::Error> {
-        let url = format!("{}/item/{}.json", self.base_url, id);
+        self.get_item(id).await?.ok_or_else(|| anyhow::anyhow!("Item {} does not exist", id))
+    }
+
+    // One bad item shouldn't sink the whole batch: failures, missing, deleted and dead items are
+    // reported per id instead
+    async fn get_batch<T: serde::de::DeserializeOwned>(
+        &self,
+        ids: &[u32],
+        removed: impl Fn(&T) -> Option<&'static str>,
+    ) -> ItemBatch<T> {
+        let results = futures::future::join_all(ids.iter().map(|&id| self.get_item::<T>(id))).await;
+        let mut batch = ItemBatch { items: Vec::new(), errors: Vec::new() };
+        for (&id, result) in ids.iter().zip(results) {
+            let error = match result {
+                Ok(Some(item)) => match removed(&item) {
+                    None => {
+                        batch.items.push(item);
+                        continue;
+                    }
+                    Some(reason) => reason.to_string(),
+                },
+                Ok(None) => "not_found".to_string(),
+                Err(e) => {
+                    error!("Failed to fetch item {}: {}", id, e);
+                    format!("fetch_failed: {}", e)
+                }
+            };
+            batch.errors.push(ItemError { id, error });
+        }
+        batch
+    }
+
+    async fn get_stories_batch(&self, ids: &[u32]) -> ItemBatch<HNStory> {
+        self.get_batch(ids, |story: &HNStory| removed_reason(story.deleted, story.dead)).await
+    }
+
+    async fn get_updates(&self) -> Result<HNUpdates, anyhow::Error> {
+        let url = format!("{}/updates.json", self.base_url);
         let response = self.client.get(&url).send().await?;
-        let story: HNStory = response.json().await?;
-        Ok(story)
+        let updates: HNUpdates = response.json().await?;
+        Ok(updates)
     }
 
-    async fn get_comment(&self, id: u32) -> Result<HNComment, anyhow::Error> {
-        let url = format!("{}/item/{}.json", self.base_url, id);
+    async fn get_user(&self, username: &str) -> Result<Option<HNUser>, anyhow::Error> {
+        let url = format!("{}/user/{}.json", self.base_url, username);
         let response = self.client.get(&url).send().await?;
-        let comment: HNComment = response.json().await?;
-        Ok(comment)
+        let user: Option<HNUser> = response.json().await?;
+        Ok(user)
+    }
+
+    // Attach karma/account age to each comment, fetching every distinct uncached author once
+    async fn enrich_authors(&self, comments: &mut [HNComment]) {
+        use futures::StreamExt;
+
+        let mut missing: Vec<String> = comments
+            .iter()
+            .filter_map(|c| c.by.clone())
+            .filter(|by| authors::get(by).is_none())
+            .collect();
+        missing.sort();
+        missing.dedup();
+
+        futures::stream::iter(missing)
+            .for_each_concurrent(10, |username| async move {
+                match self.get_user(&username).await {
+                    Ok(Some(user)) => authors::put(&username, authors::AuthorProfile::new(user.karma, user.created)),
+                    Ok(None) => {}
+                    Err(e) => error!("Failed to fetch HN user {}: {}", username, e),
+                }
+            })
+            .await;
+
+        for comment in comments.iter_mut() {
+            comment.author = comment.by.as_deref().and_then(authors::get);
+        }
+    }
+
+    // Related stories via the Algolia HN search API
+    async fn search_stories(&self, query: &str, limit: usize) -> Result<Vec<RelatedStory>, anyhow::Error> {
+        let response = self
+            .client
+            .get("https://hn.algolia.com/api/v1/search")
+            .query(&[("query", query), ("tags", "story"), ("hitsPerPage", &limit.to_string())])
+            .send()
+            .await?;
+        let value: serde_json::Value = response.json().await?;
+        Ok(algolia_hits(&value))
     }
 
-    async fn get_stories_batch(&self, ids: &[u32]) -> Result<Vec<HNStory>, anyhow::Error> {
-        let futures: Vec<_> = ids.iter().map(|&id| self.get_story(id)).collect();
-        let results = futures::future::try_join_all(futures).await?;
-        Ok(results)
+    // Stories that made the front page on `date` (UTC), most points first, via Algolia's
+    // front_page tag; the live front page is only snapshotted from startup on
+    async fn get_front_page(&self, date: chrono::NaiveDate, limit: usize) -> Result<Vec<RelatedStory>, anyhow::Error> {
+        let start = date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp();
+        let filters = format!("created_at_i>={},created_at_i<{}", start, start + 86_400);
+        let response = self
+            .client
+            .get("https://hn.algolia.com/api/v1/search")
+            .query(&[("tags", "front_page"), ("numericFilters", filters.as_str()), ("hitsPerPage", "100")])
+            .send()
+            .await?
+            .error_for_status()?;
+        let value: serde_json::Value = response.json().await?;
+        let mut stories = algolia_hits(&value);
+        stories.sort_by_key(|s| std::cmp::Reverse(s.points.unwrap_or(0)));
+        stories.truncate(limit);
+        Ok(stories)
     }
 
     async fn get_comments_for_story(&self, story: &HNStory) -> Result<Vec<HNComment>, anyhow::Error> {
         if let Some(kids) = &story.kids {
-            // Fetch ALL comments without limit
-            let comment_futures: Vec<_> = kids.iter().map(|&id| self.get_comment(id)).collect();
-            let comments = futures::future::try_join_all(comment_futures).await?;
-            Ok(comments.into_iter().filter(|c| c.text.is_some()).collect())
+            // Fetch ALL comments without limit; deleted/dead/unavailable ones are dropped
+            let batch = self.get_batch(kids, |comment: &HNComment| removed_reason(comment.deleted, comment.dead)).await;
+            if !batch.errors.is_empty() {
+                info!("Skipped {} comment(s) on story {}", batch.errors.len(), story.id);
+            }
+            Ok(batch.items.into_iter().filter(|c| c.text.is_some()).collect())
         } else {
             Ok(vec![])
         }
     }
+
+    // Comments newer than `since`, oldest first; the flag is true when the walk was cut short.
+    // Item ids grow with time, so top-level kids are fetched from the highest id down and the walk
+    // stops at the first batch that reaches an older comment: every lower id is older still. With
+    // `replies`, descendants are walked too, which means the whole thread (up to
+    // ACTIVITY_MAX_COMMENTS), since an old comment may have new replies.
+    async fn get_comments_since(&self, story: &HNStory, since: u64, replies: bool) -> (Vec<HNComment>, bool) {
+        let newer = |c: &HNComment| c.time.is_some_and(|t| t > since);
+        let (mut comments, truncated) = if replies {
+            let (thread, truncated) = self.get_thread(story, ACTIVITY_MAX_COMMENTS).await;
+            (thread.into_iter().filter(newer).collect::<Vec<_>>(), truncated)
+        } else {
+            let mut kids = story.kids.clone().unwrap_or_default();
+            kids.sort_unstable_by(|a, b| b.cmp(a));
+            let mut comments = Vec::new();
+            for ids in kids.chunks(SINCE_BATCH_SIZE) {
+                let batch = self.get_batch(ids, |comment: &HNComment| removed_reason(comment.deleted, comment.dead)).await;
+                let reached_older = batch.items.iter().any(|c| !newer(c));
+                comments.extend(batch.items.into_iter().filter(|c| c.text.is_some() && newer(c)));
+                if reached_older {
+                    break;
+                }
+            }
+            (comments, false)
+        };
+        comments.sort_by_key(|c| c.time);
+        (comments, truncated)
+    }
+
+    // Every comment in the thread, replies included, level by level up to `limit` items; the flag
+    // is true when the thread was cut short
+    async fn get_thread(&self, story: &HNStory, limit: usize) -> (Vec<HNComment>, bool) {
+        let mut comments = Vec::new();
+        let mut level: Vec<u32> = story.kids.clone().unwrap_or_default();
+        while !level.is_empty() && comments.len() < limit {
+            level.truncate(limit - comments.len());
+            let batch = self.get_batch(&level, |comment: &HNComment| removed_reason(comment.deleted, comment.dead)).await;
+            level = batch.items.iter().flat_map(|c| c.kids.iter().flatten().copied()).collect();
+            comments.extend(batch.items.into_iter().filter(|c| c.text.is_some()));
+        }
+        (comments, !level.is_empty())
+    }
+
+    // The thread as a tree, `depth` levels deep (1 = top-level comments only) and at most
+    // `max_comments` comments in total, fetched level by level so every branch gets its top
+    // replies before any branch goes deeper; the flag is true when comments were left out
+    async fn get_comment_tree(&self, story: &HNStory, depth: usize, max_comments: usize) -> (Vec<HNCommentTree>, usize, bool) {
+        let mut children: HashMap<u32, Vec<HNComment>> = HashMap::new();
+        let mut count = 0;
+        let mut level: Vec<u32> = story.kids.clone().unwrap_or_default();
+        for _ in 0..depth {
+            if level.is_empty() || count >= max_comments {
+                break;
+            }
+            level.truncate(max_comments - count);
+            // Removed comments are fetched too: their replies still belong in the tree
+            let batch = self.get_batch(&level, |_: &HNComment| None).await;
+            level = batch.items.iter().flat_map(|c| c.kids.iter().flatten().copied()).collect();
+            for mut comment in batch.items {
+                if removed_reason(comment.deleted, comment.dead).is_some() || comment.text.is_none() {
+                    // Kept as a text-less placeholder only when something hangs off it
+                    if comment.kids.as_ref().is_none_or(Vec::is_empty) {
+                        continue;
+                    }
+                    comment.text = None;
+                } else {
+                    count += 1;
+                }
+                children.entry(comment.parent.unwrap_or(story.id)).or_default().push(comment);
+            }
+        }
+        (HNCommentTree::build(story.id, &mut children), count, !level.is_empty())
+    }
+}
+
+// Stories from an Algolia HN search response
+fn algolia_hits(value: &serde_json::Value) -> Vec<RelatedStory> {
+    let hits = value.get("hits").and_then(|h| h.as_array()).cloned().unwrap_or_default();
+    hits.into_iter()
+        .filter_map(|hit| {
+            Some(RelatedStory {
+                id: hit.get("objectID")?.as_str()?.parse().ok()?,
+                title: hit.get("title")?.as_str()?.to_string(),
+                url: hit.get("url").and_then(|u| u.as_str()).map(String::from),
+                points: hit.get("points").and_then(|p| p.as_u64()).map(|p| p as u32),
+            })
+        })
+        .collect()
 }
 
 // Global client instances
 static HN_CLIENT: std::sync::OnceLock<HNClient> = std::sync::OnceLock::new();
 
 fn get_hn_client() -> &'static HNClient {
-    HN_CLIENT.get_or_init(|| HNClient::new())
+    HN_CLIENT.get_or_init(HNClient::new)
 }
 
 // API Handlers
-async fn get_top_stories() -> Result<AxumJson<Vec<HNStory>>, (StatusCode, AxumJson<ApiError>)> {
+const STORY_FIELDS: &[&str] = &["id", "title", "url", "text", "score", "by", "time", "descendants", "kids"];
+
+fn known_feed(name: &str) -> Result<Feed, (StatusCode, AxumJson<ApiError>)> {
+    Feed::parse(name).ok_or_else(|| {
+        let valid: Vec<&str> = Feed::ALL.iter().map(|feed| feed.name()).collect();
+        (
+            StatusCode::BAD_REQUEST,
+            AxumJson(ApiError { error: format!("Unknown feed '{}'. Valid feeds: {}", name, valid.join(", ")) }),
+        )
+    })
+}
+
+// `data` holds the stories of ?feed= (top by default, or new, best, ask, show, job);
+// `meta.skipped`/`meta.errors` report items left out (failed, missing, deleted, dead or untitled).
+// ?as_of= (unix seconds or RFC 3339) rebuilds the top feed from the last front-page snapshot taken
+// by then, in its order and with its scores; ?max_age_hours= drops stories posted longer ago than
+// that before now (or as_of).
+async fn get_top_stories(
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<AxumJson<fields::Envelope<Vec<serde_json::Value>>>, (StatusCode, AxumJson<ApiError>)> {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, AxumJson(ApiError { error }));
+    let selected = fields::parse(params.get("fields").map(String::as_str), STORY_FIELDS).map_err(bad_request)?;
+    let topic = params.get("topic").map(String::as_str).map(known_topic).transpose()?;
+    let feed = params.get("feed").map(|name| known_feed(name)).transpose()?.unwrap_or_default();
+    let as_of = params
+        .get("as_of")
+        .map(|value| parse_timestamp(value).ok_or_else(|| bad_request(format!("Invalid as_of '{}': expected unix seconds or RFC 3339", value))))
+        .transpose()?;
+    let max_age_hours = params
+        .get("max_age_hours")
+        .map(|value| value.parse::<u32>().ok().filter(|hours| *hours > 0).ok_or_else(|| bad_request("max_age_hours must be a positive integer".to_string())))
+        .transpose()?;
     let client = get_hn_client();
-    
-    match client.get_top_stories().await {
-        Ok(story_ids) => {
-            // Get first 50 stories for performance
-            let limited_ids = &story_ids[..std::cmp::min(50, story_ids.len())];
-            
-            match client.get_stories_batch(limited_ids).await {
-                Ok(stories) => {
-                    // Filter out stories without titles
-                    let valid_stories: Vec<HNStory> = stories
-                        .into_iter()
-                        .filter(|story| story.title.is_some() && !story.title.as_ref().unwrap().is_empty())
-                        .collect();
-                    
-                    info!("Successfully fetched {} top stories", valid_stories.len());
-                    Ok(AxumJson(valid_stories))
-                }
-                Err(e) => {
-                    error!("Failed to fetch story details: {}", e);
-                    Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        AxumJson(ApiError {
-                            error: "Failed to fetch story details".to_string(),
-                        }),
-                    ))
-                }
-            }
-        }
-        Err(e) => {
-            error!("Failed to fetch top stories: {}", e);
-            Err((
+
+    let snapshot = match as_of {
+        Some(_) if feed != Feed::Top => return Err(bad_request("as_of only applies to the top feed".to_string())),
+        Some(at) if at > chrono::Utc::now() => return Err(bad_request("as_of must not be in the future".to_string())),
+        Some(at) => Some(frontpage::as_of(at).await.map_err(archive_error)?.ok_or_else(|| {
+            (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("No front-page snapshot at or before {}", at.to_rfc3339()) }))
+        })?),
+        None => None,
+    };
+    let story_ids = match &snapshot {
+        Some(snapshot) => snapshot.stories.iter().map(|entry| entry.id).collect(),
+        None => client.get_feed(feed).await.map_err(|e| {
+            error!("Failed to fetch {} stories: {}", feed.name(), e);
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 AxumJson(ApiError {
-                    error: "Failed to fetch top stories".to_string(),
+                    error: format!("Failed to fetch {} stories", feed.name()),
                 }),
-            ))
+            )
+        })?,
+    };
+
+    // Get first 50 stories for performance
+    let limited_ids = &story_ids[..std::cmp::min(config::TOP_STORIES_LIMIT, story_ids.len())];
+
+    let batch = client.get_stories_batch(limited_ids).await;
+    if batch.items.is_empty() && !batch.errors.is_empty() {
+        error!("Failed to fetch any of {} {} stories", batch.errors.len(), feed.name());
+        return Err((
+            StatusCode::BAD_GATEWAY,
+            AxumJson(ApiError {
+                error: "Failed to fetch story details".to_string(),
+            }),
+        ));
+    }
+
+    // Filter out stories without titles
+    let mut errors = batch.errors;
+    let mut valid_stories = Vec::new();
+    for mut story in batch.items {
+        if story.title.as_deref().is_some_and(|t| !t.is_empty()) {
+            // The score the story had on the front page back then
+            if let Some(entry) = snapshot.as_ref().and_then(|snapshot| snapshot.stories.iter().find(|entry| entry.id == story.id)) {
+                story.score = entry.score.or(story.score);
+            }
+            valid_stories.push(story);
+        } else {
+            errors.push(ItemError { id: story.id, error: "untitled".to_string() });
         }
     }
+
+    // Titles only, so filtering a page of stories never waits on the model
+    if let Some(topic) = topic {
+        valid_stories.retain(|story| topics::classify_rules(story.title.as_deref().unwrap_or_default(), "").contains(&topic));
+    }
+    if let Some(hours) = max_age_hours {
+        let cutoff = as_of.unwrap_or_else(chrono::Utc::now).timestamp() - i64::from(hours) * 3600;
+        valid_stories.retain(|story| story.time.is_some_and(|time| time as i64 >= cutoff));
+    }
+
+    info!("Successfully fetched {} {} stories ({} skipped)", valid_stories.len(), feed.name(), errors.len());
+    Ok(AxumJson(fields::Envelope {
+        data: fields::select(&valid_stories, selected.as_deref()),
+        meta: serde_json::json!({
+            "count": valid_stories.len(),
+            "skipped": errors.len(),
+            "errors": errors,
+            "fields": selected,
+            "topic": topic,
+            "feed": feed.name(),
+            "as_of": as_of,
+            "snapshot_taken_at": snapshot.as_ref().map(|snapshot| snapshot.taken_at),
+            "max_age_hours": max_age_hours,
+        }),
+    }))
 }
 
 async fn get_story_by_id(Path(id): Path<u32>) -> Result<AxumJson<HNStory>, (StatusCode, AxumJson<ApiError>)> {
@@ -179,15 +630,56 @@ async fn get_story_by_id(Path(id): Path<u32>) -> Result<AxumJson<HNStory>, (Stat
     }
 }
 
-async fn get_story_comments(Path(id): Path<u32>) -> Result<AxumJson<Vec<HNComment>>, (StatusCode, AxumJson<ApiError>)> {
+// Top-level comments fetched per round trip by ?since=
+const SINCE_BATCH_SIZE: usize = 20;
+
+// Always an array of comments. ?since= keeps only the newer ones, oldest first, and reports the
+// walk in X-Comments-Scope and X-Comments-Truncated headers: scope=top (default) returns
+// top-level comments, scope=thread includes replies (see get_comments_since)
+async fn get_story_comments(
+    Path(id): Path<u32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<(HeaderMap, AxumJson<Vec<HNComment>>), (StatusCode, AxumJson<ApiError>)> {
+    let thread_scope = match params.get("scope").map(String::as_str) {
+        None | Some("top") => false,
+        Some("thread") => true,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                AxumJson(ApiError { error: format!("Invalid scope '{}': expected top or thread", other) }),
+            ))
+        }
+    };
+    let since = match params.get("since") {
+        Some(since) => Some(parse_timestamp(since).and_then(|t| u64::try_from(t.timestamp()).ok()).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                AxumJson(ApiError { error: format!("Invalid since '{}': expected unix seconds or RFC 3339", since) }),
+            )
+        })?),
+        None => None,
+    };
     let client = get_hn_client();
     
     match client.get_story(id).await {
         Ok(story) => {
-            match client.get_comments_for_story(&story).await {
-                Ok(comments) => {
+            let mut headers = HeaderMap::new();
+            let comments = match since {
+                Some(since) => {
+                    let (comments, truncated) = client.get_comments_since(&story, since, thread_scope).await;
+                    headers.insert("X-Comments-Scope", HeaderValue::from_static(if thread_scope { "thread" } else { "top" }));
+                    headers.insert("X-Comments-Truncated", HeaderValue::from_static(if truncated { "true" } else { "false" }));
+                    Ok(comments)
+                }
+                None => client.get_comments_for_story(&story).await,
+            };
+            match comments {
+                Ok(mut comments) => {
                     info!("Successfully fetched {} comments for story {}", comments.len(), id);
-                    Ok(AxumJson(comments))
+                    if params.get("enrich_authors").map(|v| v == "true").unwrap_or(false) {
+                        client.enrich_authors(&mut comments).await;
+                    }
+                    Ok((headers, AxumJson(comments)))
                 }
                 Err(e) => {
                     error!("Failed to fetch comments for story {}: {}", id, e);
@@ -212,15 +704,362 @@ async fn get_story_comments(Path(id): Path<u32>) -> Result<AxumJson<Vec<HNCommen
     }
 }
 
+// Comments ordered by the configured ranking (`?weights=recency:1,karma:0` overrides per request),
+// with each signal's contribution, for tuning selection behavior
+async fn get_ranked_comments(
+    Path(id): Path<u32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let weights = ranking::default_weights()
+        .with_overrides(params.get("weights").map(String::as_str).unwrap_or_default())
+        .map_err(|error| (StatusCode::BAD_REQUEST, AxumJson(ApiError { error })))?;
+
+    let client = get_hn_client();
+    let story = client.get_story(id).await.map_err(|e| {
+        error!("Failed to fetch story {}: {}", id, e);
+        (
+            StatusCode::NOT_FOUND,
+            AxumJson(ApiError { error: format!("Story {} not found", id) }),
+        )
+    })?;
+    let comments = client.get_comments_for_story(&story).await.map_err(|e| {
+        error!("Failed to fetch comments for story {}: {}", id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            AxumJson(ApiError { error: format!("Failed to fetch comments for story {}", id) }),
+        )
+    })?;
+
+    let total = comments.len();
+    let (keep, filter_stats) = quality::screen(&comments.iter().map(|c| c.text.as_deref().unwrap_or_default()).collect::<Vec<_>>());
+    let mut comments: Vec<HNComment> = comments.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(c, _)| c).collect();
+    // Karma needs the commenters' profiles
+    if weights.karma > 0.0 {
+        client.enrich_authors(&mut comments).await;
+    }
+
+    let signals: Vec<ranking::Signals> = comments
+        .iter()
+        .map(|c| ranking::Signals {
+            time: c.time,
+            words: c.text.as_deref().map(|t| analysis::strip_html(t).split_whitespace().count()).unwrap_or(0),
+            replies: c.kids.as_ref().map_or(0, Vec::len),
+            karma: c.author.as_ref().and_then(|a| a.karma),
+        })
+        .collect();
+    let mut ranked: Vec<(ranking::Score, HNComment)> = ranking::score(&signals, &weights).into_iter().zip(comments).collect();
+    ranked.sort_by(|a, b| b.0.score.total_cmp(&a.0.score));
+
+    Ok(AxumJson(serde_json::json!({
+        "story_id": id,
+        "weights": weights,
+        "comment_count": total,
+        "comment_filter": filter_stats,
+        "comments": ranked
+            .into_iter()
+            .map(|(score, comment)| serde_json::json!({ "ranking": score, "comment": comment }))
+            .collect::<Vec<_>>(),
+    })))
+}
+
+fn thread_posts(comments: &[HNComment]) -> Vec<threads::Post<'_>> {
+    comments
+        .iter()
+        .filter_map(|c| Some(threads::Post { id: c.id, parent: c.parent, by: c.by.as_deref(), html: c.text.as_deref()? }))
+        .collect()
+}
+
+// --- Comment tree ---
+const TREE_DEFAULT_DEPTH: usize = 3;
+const TREE_MAX_DEPTH: usize = 10;
+const TREE_DEFAULT_COMMENTS: usize = 200;
+
+// Nested replies; ?depth= (default 3, up to 10) and ?max_comments= (default 200, up to 1000)
+// bound the fan-out
+async fn get_comment_tree(
+    Path(id): Path<u32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let bounded = |key: &str, default: usize, max: usize| match params.get(key) {
+        Some(value) => value.parse::<usize>().ok().filter(|n| (1..=max).contains(n)).ok_or_else(|| {
+            (StatusCode::BAD_REQUEST, AxumJson(ApiError { error: format!("{} must be between 1 and {}", key, max) }))
+        }),
+        None => Ok(default),
+    };
+    let depth = bounded("depth", TREE_DEFAULT_DEPTH, TREE_MAX_DEPTH)?;
+    let max_comments = bounded("max_comments", TREE_DEFAULT_COMMENTS, ACTIVITY_MAX_COMMENTS)?;
+
+    let client = get_hn_client();
+    let story = client.get_story(id).await.map_err(|e| {
+        error!("Failed to fetch story {}: {}", id, e);
+        (
+            StatusCode::NOT_FOUND,
+            AxumJson(ApiError { error: format!("Story {} not found", id) }),
+        )
+    })?;
+    let (tree, count, truncated) = client.get_comment_tree(&story, depth, max_comments).await;
+    info!("Fetched a {}-level tree of {} comments for story {}", depth, count, id);
+
+    Ok(AxumJson(serde_json::json!({
+        "story_id": id,
+        "depth": depth,
+        "max_comments": max_comments,
+        "comment_count": count,
+        "truncated": truncated,
+        "comments": tree,
+    })))
+}
+
+// Show HN threads: the submitter's answers, which are mostly replies further down the thread
+async fn show_hn_maker(story: &HNStory) -> Option<threads::MakerResponses> {
+    let maker = story.by.as_deref()?;
+    if !threads::is_show_hn(story.title.as_deref().unwrap_or_default()) {
+        return None;
+    }
+    let (thread, _) = get_hn_client().get_thread(story, ACTIVITY_MAX_COMMENTS).await;
+    Some(threads::maker_responses(maker, &thread_posts(&thread)))
+}
+
+// --- Story detail aggregation ---
+const FULL_TOP_COMMENTS: usize = 10;
+
+async fn get_story_full(Path(id): Path<u32>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let client = get_hn_client();
+    let story = client.get_story(id).await.map_err(|e| {
+        error!("Failed to fetch story {}: {}", id, e);
+        (
+            StatusCode::NOT_FOUND,
+            AxumJson(ApiError { error: format!("Story {} not found", id) }),
+        )
+    })?;
+
+    let title = story.title.clone().unwrap_or_default();
+    let title_keywords = analysis::keywords(&title, &[], 4).join(" ");
+
+    // Everything past the story itself is independent, so fetch it all at once
+    let (comments, website, related, show_hn, topics) = tokio::join!(
+        client.get_comments_for_story(&story),
+        async {
+            match &story.url {
+                Some(url) => fetch_website(url).await.ok(),
+                None => None,
+            }
+        },
+        client.search_stories(&title_keywords, 6),
+        show_hn_maker(&story),
+        story_topics(&story),
+    );
+
+    let comments = comments.unwrap_or_else(|e| {
+        error!("Failed to fetch comments for story {}: {}", id, e);
+        vec![]
+    });
+    let comment_texts: Vec<String> = comments.iter().filter_map(|c| c.text.as_deref()).map(analysis::strip_html).collect();
+    let comment_refs: Vec<&str> = comment_texts.iter().map(String::as_str).collect();
+
+    let (metadata, html) = match website {
+        Some((metadata, html)) => (Some(metadata), html),
+        None => (None, None),
+    };
+    // Text posts (Ask HN etc.) have their body inline instead of an article
+    let article = html
+        .as_deref()
+        .and_then(analysis::article_extract)
+        .or_else(|| story.text.as_deref().map(analysis::strip_html));
+    let paywall = match (&story.url, &html) {
+        (Some(url), Some(html)) => analysis::detect_paywall(url, html, article.as_deref()),
+        _ => None,
+    };
+
+    let mut keyword_sources = comment_refs.clone();
+    keyword_sources.extend(article.as_deref());
+    let keywords = analysis::keywords(&title, &keyword_sources, 10);
+    let sentiment = analysis::sentiment(&comment_refs);
+
+    // Reposts of the same article under a different URL variant collapse to one entry
+    let mut seen_urls: Vec<String> = story.url.iter().map(|u| urls::canonicalize(u)).collect();
+    let related: Vec<RelatedStory> = related
+        .unwrap_or_else(|e| {
+            error!("Failed to search related stories for {}: {}", id, e);
+            vec![]
+        })
+        .into_iter()
+        .filter(|r| r.id != id)
+        .filter(|r| match &r.url {
+            Some(url) => {
+                let canonical = urls::canonicalize(url);
+                let duplicate = seen_urls.contains(&canonical);
+                seen_urls.push(canonical);
+                !duplicate
+            }
+            None => true,
+        })
+        .take(5)
+        .collect();
+
+    let ask_hn = threads::is_ask_hn(&title).then(|| threads::summarize_ask_hn(&thread_posts(&comments)));
+    let sensitive = story_sensitive_flags(&story, &comments);
+
+    let total_comments = comments.len();
+    let (keep, _) = quality::screen(&comments.iter().map(|c| c.text.as_deref().unwrap_or_default()).collect::<Vec<_>>());
+    let top_comments: Vec<HNComment> =
+        comments.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(c, _)| c).take(FULL_TOP_COMMENTS).collect();
+
+    Ok(AxumJson(serde_json::json!({
+        "story": story,
+        "article_extract": article,
+        "paywalled": paywall.is_some(),
+        "paywall_reason": paywall,
+        "metadata": metadata,
+        "topics": topics,
+        "sensitive": sensitive,
+        "ask_hn": ask_hn,
+        "show_hn": show_hn,
+        "top_comments": top_comments,
+        "comment_count": total_comments,
+        "sentiment": sentiment,
+        "keywords": keywords,
+        "related_stories": related,
+    })))
+}
+
+// --- Thread activity since a point in time ---
+const ACTIVITY_MAX_COMMENTS: usize = 1000;
+const ACTIVITY_TOP_COMMENTS: usize = 5;
+
+#[derive(Debug, Deserialize)]
+struct ActivityQuery {
+    // Unix seconds or RFC 3339
+    since: String,
+}
+
+// Unix seconds or RFC 3339 (seconds optional)
+fn parse_timestamp(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    value
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .or_else(|| chrono::DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&chrono::Utc)))
+        // RFC 3339 without seconds, e.g. 2024-06-01T12:00Z
+        .or_else(|| chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%MZ").ok().map(|t| t.and_utc()))
+}
+
+async fn get_story_activity(
+    Path(id): Path<u32>,
+    Query(query): Query<ActivityQuery>,
+) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let since = parse_timestamp(&query.since).and_then(|t| u64::try_from(t.timestamp()).ok()).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            AxumJson(ApiError { error: format!("Invalid since '{}': expected unix seconds or RFC 3339", query.since) }),
+        )
+    })?;
+
+    let client = get_hn_client();
+    let story = client.get_story(id).await.map_err(|e| {
+        error!("Failed to fetch story {}: {}", id, e);
+        (
+            StatusCode::NOT_FOUND,
+            AxumJson(ApiError { error: format!("Story {} not found", id) }),
+        )
+    })?;
+
+    let (thread, truncated) = client.get_thread(&story, ACTIVITY_MAX_COMMENTS).await;
+    let new_comments: Vec<HNComment> = thread.into_iter().filter(|c| c.time.is_some_and(|t| t > since)).collect();
+    let new_top_level = new_comments.iter().filter(|c| c.parent == Some(id)).count();
+    let newest_at = new_comments.iter().filter_map(|c| c.time).max();
+
+    let texts: Vec<String> = new_comments.iter().filter_map(|c| c.text.as_deref()).map(analysis::strip_html).collect();
+    let refs: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let keywords = analysis::keywords(story.title.as_deref().unwrap_or_default(), &refs, 8);
+
+    // Comments that drew the most replies lead, then the most substantial ones; junk isn't featured
+    let new_comment_count = new_comments.len();
+    let (keep, _) = quality::screen(&new_comments.iter().map(|c| c.text.as_deref().unwrap_or_default()).collect::<Vec<_>>());
+    let mut top_new: Vec<HNComment> = new_comments.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(c, _)| c).collect();
+    top_new.sort_by_key(|c| {
+        std::cmp::Reverse((c.kids.as_ref().map_or(0, Vec::len), c.text.as_ref().map_or(0, String::len)))
+    });
+    top_new.truncate(ACTIVITY_TOP_COMMENTS);
+
+    Ok(AxumJson(serde_json::json!({
+        "story_id": id,
+        "since": since,
+        "comment_count": story.descendants,
+        "new_comment_count": new_comment_count,
+        "new_top_level_comments": new_top_level,
+        "new_replies": new_comment_count - new_top_level,
+        "newest_comment_at": newest_at,
+        "top_new_comments": top_new,
+        "keywords": keywords,
+        "truncated": truncated,
+    })))
+}
+
+// --- Front-page movement ---
+#[derive(Debug, Deserialize)]
+struct FrontpageDiffQuery {
+    from: Option<String>,
+    to: Option<String>,
+}
+
+async fn get_frontpage_snapshots() -> AxumJson<serde_json::Value> {
+    AxumJson(serde_json::json!({ "snapshots": frontpage::taken_at() }))
+}
+
+// `to` defaults to the latest snapshot and `from` to the one before it; each picks the latest
+// snapshot taken at or before the given time
+async fn get_frontpage_diff(
+    Query(query): Query<FrontpageDiffQuery>,
+) -> Result<AxumJson<frontpage::Diff>, (StatusCode, AxumJson<ApiError>)> {
+    let parse = |value: &Option<String>| match value {
+        Some(value) => parse_timestamp(value).map(Some).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                AxumJson(ApiError { error: format!("Invalid timestamp '{}': expected unix seconds or RFC 3339", value) }),
+            )
+        }),
+        None => Ok(None),
+    };
+    let (from, to) = (parse(&query.from)?, parse(&query.to)?);
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err((StatusCode::BAD_REQUEST, AxumJson(ApiError { error: "'from' must not be after 'to'".to_string() })));
+        }
+    }
+
+    let not_found = |what: &str| {
+        (
+            StatusCode::NOT_FOUND,
+            AxumJson(ApiError { error: format!("No front-page snapshot {}", what) }),
+        )
+    };
+    let to_snapshot = frontpage::at_or_before(to).ok_or_else(|| not_found("at or before 'to'"))?;
+    let from_snapshot = match from {
+        Some(from) => frontpage::at_or_before(Some(from)).ok_or_else(|| not_found("at or before 'from'"))?,
+        None => frontpage::previous(&to_snapshot).ok_or_else(|| not_found("before 'to' to compare with"))?,
+    };
+
+    Ok(AxumJson(frontpage::diff(&from_snapshot, &to_snapshot)))
+}
+
 async fn generate_content(
     Json(payload): Json<ContentGenerationRequest>
 ) -> Result<AxumJson<ContentGenerationResponse>, (StatusCode, AxumJson<ApiError>)> {
     let story_id = payload.story_id;
+    let anonymize = privacy::enabled(payload.anonymize_authors);
+    if anonymize {
+        privacy::register(payload.authors.iter().map(String::as_str));
+    }
     let comments: Vec<String> = payload.comments
         .into_iter()
-        .filter_map(|comment| Some(comment))
         .filter(|c: &String| !c.is_empty())
         .collect();
+    let (comments, comment_filter) = quality::filter(comments).await;
+    let comments: Vec<String> = comments
+        .into_iter()
+        .map(|c| if anonymize { privacy::anonymize(&c) } else { c })
+        .collect();
 
     if comments.is_empty() {
         return Err((
@@ -233,11 +1072,13 @@ async fn generate_content(
     let combined_comments = comments.join("\n\n---\n\n");
     let source = format!("HackerNews Story #{} Comments", story_id);
 
+    let api_key = credentials::require("ALCHEMYST_API_KEY").map_err(|error| (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error })))?;
+
     // Send to Alchemyst AI context add endpoint
     let client = reqwest::Client::new();
     let response = client
         .post(format!("{}/api/v1/context/add", env::var("ALCHEMYST_API_URL").unwrap_or_else(|_| "https://platform-backend.getalchemystai.com".to_string())))
-        .header("Authorization", format!("Bearer {}", env::var("ALCHEMYST_API_KEY").unwrap_or_default()))
+        .header("Authorization", format!("Bearer {}", api_key))
         .header("Content-Type", "application/json")
         .json(&serde_json::json!({
             "documents": [{
@@ -283,7 +1124,8 @@ async fn generate_content(
         Ok(AxumJson(ContentGenerationResponse {
             message,
             context_added: true,
-            story_id
+            story_id,
+            comment_filter,
         }))
     } else {
         Err((
@@ -307,6 +1149,14 @@ async fn health_check() -> AxumJson<HashMap<String, String>> {
     AxumJson(response)
 }
 
+async fn get_config() -> AxumJson<config::Capabilities> {
+    AxumJson(config::capabilities())
+}
+
+async fn get_personas() -> AxumJson<personas::PersonaList> {
+    AxumJson(personas::list())
+}
+
 // Website metadata endpoint
 #[derive(Serialize)]
 struct WebsiteMetadata {
@@ -323,42 +1173,38 @@ async fn get_website_metadata(Query(params): Query<HashMap<String, String>>) ->
         None => return Err(StatusCode::BAD_REQUEST),
     };
 
-    // Validate URL
-    if !url.starts_with("http://") && !url.starts_with("https://") {
-        return Err(StatusCode::BAD_REQUEST);
-    }
+    let (metadata, _) = fetch_website(url).await?;
+    Ok(AxumJson(metadata))
+}
 
-    // Extract domain
-    let domain = url
-        .split('/')
-        .nth(2)
-        .unwrap_or("Unknown")
-        .to_string();
+// Metadata preview plus the page HTML (None when the page couldn't be fetched)
+async fn fetch_website(url: &str) -> Result<(WebsiteMetadata, Option<String>), StatusCode> {
+    // Validate URL (scheme and that it doesn't point at a private address)
+    let parsed = fetch::parse_url(url).map_err(|_| StatusCode::BAD_REQUEST)?;
 
-    // Create client with timeout and user agent
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .user_agent("Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
-        .build()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Canonical domain, so m./www./mirror variants of a site group together
+    let domain = urls::canonical_host(url).unwrap_or_else(|| "Unknown".to_string());
 
-    // Fetch the website HTML
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+    // Fetch the website HTML (rate-limited per host; robots.txt blocks fall through to basic metadata)
+    let response = match polite::get(parsed).await {
+        Ok(response) => Some(response),
+        Err(polite::PoliteError::Disallowed) => None,
+        Err(polite::PoliteError::Request(_)) => return Err(StatusCode::BAD_GATEWAY),
+    };
 
-    if !response.status().is_success() {
+    let Some(response) = response.filter(|r| r.status().is_success()) else {
         // Return basic metadata if we can't fetch the page
-        return Ok(AxumJson(WebsiteMetadata {
-            url: url.clone(),
-            title: None,
-            description: None,
-            domain,
-            favicon: None,
-        }));
-    }
+        return Ok((
+            WebsiteMetadata {
+                url: url.to_string(),
+                title: None,
+                description: None,
+                domain,
+                favicon: None,
+            },
+            None,
+        ));
+    };
 
     let html = response
         .text()
@@ -370,16 +1216,19 @@ async fn get_website_metadata(Query(params): Query<HashMap<String, String>>) ->
     let description = extract_meta_content(&html, "description")
         .or_else(|| extract_meta_property(&html, "og:description"));
     
-    // Try to get favicon
-    let favicon = extract_favicon(&html, &domain);
+    // Try to get favicon (relative paths resolve against the host actually fetched)
+    let favicon = extract_favicon(&html, url.split('/').nth(2).unwrap_or(&domain));
 
-    Ok(AxumJson(WebsiteMetadata {
-        url: url.clone(),
-        title,
-        description,
-        domain,
-        favicon,
-    }))
+    Ok((
+        WebsiteMetadata {
+            url: url.to_string(),
+            title,
+            description,
+            domain,
+            favicon,
+        },
+        Some(html),
+    ))
 }
 
 fn extract_html_tag(html: &str, tag: &str) -> Option<String> {
@@ -426,53 +1275,408 @@ fn extract_favicon(html: &str, domain: &str) -> Option<String> {
     Some(format!("https://{}/favicon.ico", domain))
 }
 
-// --- New: Podcast generation endpoint ---
-#[derive(Debug, Deserialize)]
-struct PodcastGenerationRequest {
+// --- SEO ---
+
+// Home page plus the current top stories' pages
+async fn get_sitemap() -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let client = get_hn_client();
+    let story_ids = client.get_top_stories().await.map_err(|e| {
+        error!("Failed to fetch top stories for the sitemap: {}", e);
+        (StatusCode::BAD_GATEWAY, AxumJson(ApiError { error: "Failed to fetch top stories".to_string() }))
+    })?;
+    let limited_ids = &story_ids[..std::cmp::min(config::TOP_STORIES_LIMIT, story_ids.len())];
+    let batch = client.get_stories_batch(limited_ids).await;
+
+    let mut entries = vec![seo::SitemapEntry { loc: format!("{}/", seo::site_url()), lastmod: None, changefreq: "hourly" }];
+    entries.extend(batch.items.iter().filter(|s| s.title.as_deref().is_some_and(|t| !t.is_empty())).map(|story| {
+        seo::SitemapEntry {
+            loc: seo::story_page(story.id),
+            lastmod: story.time.and_then(|secs| chrono::DateTime::from_timestamp(secs as i64, 0)),
+            changefreq: "daily",
+        }
+    }));
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/xml; charset=utf-8")
+        .body(Body::from(seo::sitemap(&entries)))
+        .map_err(|e| {
+            error!("Failed to build sitemap response: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error: "Failed to build response".to_string() }))
+        })
+}
+
+// One document of the paged feed (see feed.rs), optionally for one ?topic=; 404 for a page that
+// doesn't exist
+async fn podcast_feed(
+    format: feed::Format,
+    archive: Option<u32>,
+    params: &HashMap<String, String>,
+) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let topic = params.get("topic").map(String::as_str).map(known_topic).transpose()?;
+    let quality = params.get("quality").map(|name| audio_quality(name)).transpose()?;
+    let size = feed::page_size();
+    let qa_passed = audio::qa_blocks_publish();
+    let (_, total) = archive::with_audio(0, 0, qa_passed, topic).await.map_err(archive_error)?;
+    let mut page = feed::Page::new(format, archive, topic.map(String::from), total, size);
+    page.quality = quality.filter(|quality| *quality != episode_audio::Quality::High);
+    if !page.exists() {
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: "No such feed page".to_string() })));
+    }
+    let (offset, limit) = page.window(total, size);
+    let (episodes, _) = archive::with_audio(limit, offset, qa_passed, topic).await.map_err(archive_error)?;
+    typed_response(format.content_type(), feed::render(&episodes, &page))
+}
+
+fn audio_quality(name: &str) -> Result<episode_audio::Quality, (StatusCode, AxumJson<ApiError>)> {
+    episode_audio::Quality::parse(name).ok_or_else(|| {
+        (StatusCode::BAD_REQUEST, AxumJson(ApiError { error: "quality must be low, medium or high".to_string() }))
+    })
+}
+
+fn typed_response(content_type: &str, body: String) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", content_type)
+        .body(Body::from(body))
+        .map_err(|e| {
+            error!("Failed to build {} response: {}", content_type, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error: "Failed to build response".to_string() }))
+        })
+}
+
+// Podcast RSS feed of the newest episodes with rendered audio
+async fn get_podcast_feed(Query(params): Query<HashMap<String, String>>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    podcast_feed(feed::Format::Rss, None, &params).await
+}
+
+// The same episodes as Atom
+async fn get_atom_feed(Query(params): Query<HashMap<String, String>>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    podcast_feed(feed::Format::Atom, None, &params).await
+}
+
+// The same episodes as JSON Feed 1.1
+async fn get_json_feed(Query(params): Query<HashMap<String, String>>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    podcast_feed(feed::Format::Json, None, &params).await
+}
+
+// RFC 5005 archive page of older episodes, numbered from the oldest; ?format=atom or json for
+// the other formats
+async fn get_podcast_feed_archive(
+    Path(number): Path<u32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let format = match params.get("format") {
+        Some(name) => feed::Format::parse(name).ok_or_else(|| {
+            (StatusCode::BAD_REQUEST, AxumJson(ApiError { error: format!("Invalid format '{}': expected rss, atom or json", name) }))
+        })?,
+        None => feed::Format::Rss,
+    };
+    podcast_feed(format, Some(number), &params).await
+}
+
+// OPML of the main feed and the per-topic feeds of topics that have episodes
+async fn get_feeds_opml() -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let stats = archive::topic_stats().await.map_err(archive_error)?;
+    let topics: Vec<&str> = topics::names().into_iter().filter(|name| stats.iter().any(|s| s.topic == *name && s.episodes > 0)).collect();
+    typed_response("text/x-opml; charset=utf-8", feed::opml(&topics))
+}
+
+// Runs every page of the RSS feed through the checks podcast directories apply on submission
+async fn validate_feed(headers: HeaderMap) -> Result<AxumJson<feed_check::Report>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let size = feed::page_size();
+    let qa_passed = audio::qa_blocks_publish();
+    let (_, total) = archive::with_audio(0, 0, qa_passed, None).await.map_err(archive_error)?;
+    let archive_pages = feed::Page::new(feed::Format::Rss, None, None, total, size).archive_pages;
+    let mut documents = Vec::new();
+    for number in std::iter::once(None).chain((1..=archive_pages).map(Some)) {
+        let page = feed::Page::new(feed::Format::Rss, number, None, total, size);
+        let (offset, limit) = page.window(total, size);
+        let (episodes, _) = archive::with_audio(limit, offset, qa_passed, None).await.map_err(archive_error)?;
+        documents.push(feed::render(&episodes, &page));
+    }
+    Ok(AxumJson(feed_check::validate(&documents).await))
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct SiteExportRequest {
+    // Where the export will be served; default PUBLIC_BASE_URL (the mirror replaces the backend)
+    base_url: Option<String>,
+}
+
+// One export at a time: they share SITE_EXPORT_DIR
+static SITE_EXPORT: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+// Renders every published episode, its page, transcript and chapters, the feeds and the audio
+// into SITE_EXPORT_DIR for a static host (see site.rs)
+async fn export_site(
+    headers: HeaderMap,
+    payload: Option<Json<SiteExportRequest>>,
+) -> Result<AxumJson<site::Export>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let Json(payload) = payload.unwrap_or_default();
+    let base_url = match payload.base_url {
+        Some(base_url) => url::Url::parse(&base_url)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https") && url.query().is_none())
+            .map(|_| base_url.trim_end_matches('/').to_string())
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, AxumJson(ApiError { error: "base_url must be an http(s) URL without a query".to_string() })))?,
+        None => alerts::public_base_url(),
+    };
+    let _export = SITE_EXPORT
+        .try_lock()
+        .map_err(|_| (StatusCode::CONFLICT, AxumJson(ApiError { error: "A site export is already running".to_string() })))?;
+
+    let qa_passed = audio::qa_blocks_publish();
+    let mut episodes = Vec::new();
+    loop {
+        let (page, total) = archive::with_audio(archive::MAX_PAGE_SIZE, episodes.len() as u32, qa_passed, None).await.map_err(archive_error)?;
+        let done = page.is_empty();
+        episodes.extend(page);
+        if done || episodes.len() as u64 >= total {
+            break;
+        }
+    }
+    let export = site::export(episodes, &base_url).await.map_err(|error| {
+        error!("Site export failed: {}", error);
+        (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error }))
+    })?;
+    info!("Exported {} episode(s) to {} ({} files, {} bytes)", export.episodes, export.dir, export.files, export.bytes);
+    audit(&headers, "site.export", &export.dir, None, serde_json::to_value(&export).ok()).await;
+    Ok(AxumJson(export))
+}
+
+// Title, description and og:image for the story page; the description prefers the article's own
+// and falls back to the post text, then to the score and comment count
+async fn get_story_seo(Path(id): Path<u32>) -> Result<AxumJson<seo::PageSeo>, (StatusCode, AxumJson<ApiError>)> {
+    let story = get_hn_client().get_story(id).await.map_err(|e| {
+        error!("Failed to fetch story {}: {}", id, e);
+        (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Story {} not found", id) }))
+    })?;
+    let title = story.title.clone().filter(|t| !t.is_empty()).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Story {} has no title", id) }))
+    })?;
+
+    let (metadata, html) = match &story.url {
+        Some(url) => match fetch_website(url).await {
+            Ok((metadata, html)) => (Some(metadata), html),
+            Err(_) => (None, None),
+        },
+        None => (None, None),
+    };
+    let og_image = story
+        .url
+        .as_deref()
+        .zip(html.as_deref().and_then(|html| extract_meta_property(html, "og:image")))
+        .and_then(|(url, image)| seo::absolute_image(url, &image));
+    let description = metadata
+        .and_then(|m| m.description)
+        .or_else(|| story.text.as_deref().map(analysis::strip_html))
+        .filter(|d| !d.trim().is_empty())
+        .unwrap_or_else(|| {
+            format!("{} points and {} comments on Hacker News.", story.score.unwrap_or(0), story.descendants.unwrap_or(0))
+        });
+
+    Ok(AxumJson(seo::PageSeo {
+        title,
+        description: seo::description(&description),
+        canonical_url: seo::story_page(id),
+        og_type: "article",
+        og_image,
+        published_at: story.time.and_then(|secs| chrono::DateTime::from_timestamp(secs as i64, 0)),
+    }))
+}
+
+// --- New: Podcast generation endpoint ---
+#[derive(Debug, Deserialize, Default)]
+struct PodcastGenerationRequest {
     persona: Option<String>,
     scope: Option<String>,
     title: Option<String>,
+    season: Option<u32>,
+    // Script length caps, bounded by SCRIPT_MAX_TOKENS
+    max_tokens: Option<u32>,
+    target_minutes: Option<u32>,
+    // "full" (default) or "flash" for a 60-90 second single-story brief
+    mode: Option<script::Mode>,
+    // Check cited URLs after generation (default: on unless LINK_VERIFICATION=off)
+    verify_links: Option<bool>,
+    // Known-good links (story/HN URLs) used to replace dead ones
+    #[serde(default)]
+    reference_urls: Vec<String>,
+    // Replace HN usernames with pseudonyms in the script (default from ANONYMIZE_AUTHORS)
+    anonymize_authors: Option<bool>,
+    // Caveats about the sources the script should acknowledge (e.g. a paywalled article)
+    #[serde(default)]
+    source_notes: Vec<String>,
+    // Per-request tool toggles, e.g. {"deep_research": true} (defaults from GENERATION_TOOLS)
+    #[serde(default)]
+    tools: HashMap<String, bool>,
+    // HN stories the episode is about, stored with it in the archive
+    #[serde(default)]
+    story_ids: Vec<u32>,
+    // Topics of those stories, when the caller already classified them; otherwise the episode is
+    // classified from its title and script
+    #[serde(skip)]
+    topics: Vec<String>,
+    // Stories of a digest to draft as parallel segments before a stitching pass (see segments.rs)
+    #[serde(skip)]
+    segments: Vec<RelatedStory>,
+    // Set for background jobs so the raw upstream exchange is kept (GET /api/jobs/:id/raw)
+    #[serde(skip)]
+    job_id: Option<String>,
+    // Embargo: the archived episode stays out of the feeds until then (see embargo.rs)
+    publish_at: Option<chrono::DateTime<chrono::Utc>>,
+    // Topic feed to file the episode under on top of its own topics (scheduled digests)
+    #[serde(skip)]
+    feed_topic: Option<String>,
+    // The episode this one follows up on (see follow_up_episode)
+    #[serde(skip)]
+    follow_up_of: Option<String>,
+    // Opens the show notes
+    #[serde(skip)]
+    lead_notes: Option<String>,
+}
+
+// 422 naming the personas that would have worked
+fn unknown_persona(requested: Option<&str>, valid: &[String]) -> (StatusCode, AxumJson<ApiError>) {
+    let requested = requested.map(String::from).unwrap_or_else(config::default_persona);
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        AxumJson(ApiError { error: format!("Unknown persona '{}'. Valid personas: {}", requested, valid.join(", ")) })
+    )
+}
+
+fn invalid_tools(error: String) -> (StatusCode, AxumJson<ApiError>) {
+    (StatusCode::UNPROCESSABLE_ENTITY, AxumJson(ApiError { error }))
 }
 
 async fn generate_podcast(
     Json(payload): Json<PodcastGenerationRequest>
 ) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
     let api_url = env::var("ALCHEMYST_API_URL").unwrap_or_else(|_| "https://platform-backend.getalchemystai.com".to_string());
-    let api_key = env::var("ALCHEMYST_API_KEY").unwrap_or_default();
-    if api_key.is_empty() {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            AxumJson(ApiError { error: "ALCHEMYST_API_KEY is not configured".to_string() })
-        ));
-    }
-
-    let persona = payload.persona.unwrap_or_else(|| "maya".to_string());
+    let persona = personas::resolve(payload.persona.clone()).map_err(|valid| unknown_persona(payload.persona.as_deref(), &valid))?;
+    let tools = config::upstream_tools(&payload.tools).map_err(invalid_tools)?;
     let scope = payload.scope.unwrap_or_else(|| "internal".to_string());
     let title = payload.title.unwrap_or_else(|| "HackerNews Podcast".to_string());
+    let mode = payload.mode.unwrap_or_default();
+    // Same shape as a real episode, minus what only the upstream or the archive would add
+    if usage::sandbox() {
+        return Ok((StatusCode::OK, AxumJson(serde_json::json!({
+            "episode_id": null,
+            "mode": mode,
+            "podcast_script": sandbox::SCRIPT,
+            "title": sandbox::TITLE,
+            "slug": episodes::slugify(sandbox::TITLE),
+            "season": payload.season,
+            "sandbox": true,
+            "status": "success"
+        }))));
+    }
+    let api_key = credentials::require("ALCHEMYST_API_KEY").map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            AxumJson(ApiError { error })
+        )
+    })?;
+    charge(usage::Metric::Generations, 1).await?;
+    let max_tokens = mode.max_tokens(payload.max_tokens, payload.target_minutes);
+    let anonymize = privacy::enabled(payload.anonymize_authors);
 
     // Read podcast prompt as system message
-    let system_prompt: &str = include_str!("prompt.md");
+    let system_prompt = mode.system_prompt();
+
+    let mut chat_history = vec![chats::ChatMessage::new("system", system_prompt)];
+    if anonymize {
+        chat_history.push(chats::ChatMessage::new(
+            "system",
+            "Do not mention HackerNews usernames. Refer to people generically, e.g. \"one commenter\" or \"another developer\".",
+        ));
+    }
+    if !payload.source_notes.is_empty() {
+        chat_history.push(chats::ChatMessage::new(
+            "system",
+            format!("Notes about the sources (mention these where relevant):\n- {}", payload.source_notes.join("\n- ")),
+        ));
+    }
+    if let Some(note) = feedback::prompt_note().await {
+        chat_history.push(chats::ChatMessage::new("system", note));
+    }
+    // Segments only make sense for a multi-story episode
+    let segmented = mode == script::Mode::Full && payload.segments.len() > 1;
+    let drafts = if segmented {
+        if let Some(job_id) = &payload.job_id {
+            jobs::set_stage(job_id, "draft_segments");
+        }
+        let drafts = segments::draft(&payload.segments, &segments::Context {
+            api_url: &api_url,
+            api_key: &api_key,
+            persona: &persona,
+            scope: &scope,
+            tools: &tools,
+            max_tokens,
+            notes: &chat_history[1..],
+        })
+        .await
+        .map_err(|error| {
+            error!("Drafting segments for \"{}\" failed: {}", title, error);
+            (StatusCode::BAD_GATEWAY, AxumJson(ApiError { error }))
+        })?;
+        if let Some(job_id) = &payload.job_id {
+            jobs::set_stage(job_id, "stitch_segments");
+        }
+        for omission in &drafts.omitted {
+            error!("Leaving \"{}\" out of \"{}\": {}", omission.title, title, omission.error);
+        }
+        drafts
+    } else {
+        segments::Drafts::default()
+    };
+    let episode_status = if drafts.omitted.is_empty() { archive::EpisodeStatus::Complete } else { archive::EpisodeStatus::Degraded };
+    let show_notes = match (payload.lead_notes.clone(), segmented.then(|| segments::show_notes(&drafts))) {
+        (Some(lead), Some(notes)) => Some(format!("{}\n\n{}", lead, notes)),
+        (lead, notes) => lead.or(notes),
+    };
 
-    let chat_history = vec![
-        serde_json::json!({ "role": "system", "content": system_prompt }),
-        serde_json::json!({ "role": "user", "content": format!(
+    chat_history.push(chats::ChatMessage::new("user", match mode {
+        script::Mode::Full if segmented => segments::stitch_request(&title, &drafts.segments),
+        script::Mode::Full => format!(
             "Generate a podcast episode titled \"{}\" using the most relevant available context from my workspace (HackerNews stories, comments, and any added documents). Automatically retrieve context as needed and produce the full script per the instructions.",
             title
-        ) }),
-    ];
+        ),
+        script::Mode::Flash => format!(
+            "Write a news flash about the HackerNews story \"{}\" using the available context from my workspace (the story, its article and comments). Produce only the spoken brief per the instructions.",
+            title
+        ),
+    }));
 
     let body = serde_json::json!({
         "chat_history": chat_history,
         "persona": persona,
         "scope": scope,
         "stream": false,
-        "tools": {
-            "researchIcps": false
-        }
+        "max_tokens": max_tokens,
+        "tools": tools,
     });
 
     let client = reqwest::Client::new();
     let url = format!("{}/api/v1/chat/generate", api_url);
+    let record_exchange = |status: Option<u16>, chunks: Vec<String>, error: Option<String>| {
+        if let Some(job_id) = &payload.job_id {
+            history::record(history::Exchange {
+                job_id: job_id.clone(),
+                provider: "alchemyst".to_string(),
+                url: url.clone(),
+                request: body.clone(),
+                status,
+                chunks,
+                output: None,
+                error,
+                recorded_at: chrono::Utc::now(),
+            });
+        }
+    };
     let resp = client
         .post(&url)
         .header("Authorization", format!("Bearer {}", api_key))
@@ -482,6 +1686,7 @@ async fn generate_podcast(
         .await
         .map_err(|e| {
             error!("Podcast generation request failed: {}", e);
+            record_exchange(None, Vec::new(), Some(e.to_string()));
             (
                 StatusCode::BAD_GATEWAY,
                 AxumJson(ApiError { error: "Upstream request failed".to_string() })
@@ -491,20 +1696,22 @@ async fn generate_podcast(
     let status = resp.status();
     let response_text = resp.text().await.map_err(|e| {
         error!("Failed to read upstream response text: {}", e);
+        record_exchange(Some(status.as_u16()), Vec::new(), Some(e.to_string()));
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             AxumJson(ApiError { error: "Failed to read upstream response".to_string() })
         )
     })?;
+    record_exchange(Some(status.as_u16()), vec![response_text.clone()], None);
 
     info!("Alchemyst response status: {}", status);
-    info!("Alchemyst response body: {}", response_text);
+    info!("Alchemyst response body: {}", redact::body(&response_text));
 
     // Try to parse as JSON
     let value: serde_json::Value = match serde_json::from_str(&response_text) {
         Ok(v) => v,
         Err(e) => {
-            error!("Failed to parse upstream response as JSON: {}. Raw response: {}", e, response_text);
+            error!("Failed to parse upstream response as JSON: {}. Raw response: {}", e, redact::body(&response_text));
             // Return the raw response as a string in a JSON wrapper
             return Ok((StatusCode::OK, AxumJson(serde_json::json!({
                 "raw_response": response_text,
@@ -516,28 +1723,106 @@ async fn generate_podcast(
 
     if status.is_success() || status.as_u16() == 201 {
         // Platform API returns: { result: { response: { content: "..." } }, chatId, title, researchMode, stream }
-        if let Some(result) = value.get("result") {
-            if let Some(response_content) = result.get("response") {
-                if let Some(content) = response_content.get("content") {
-                    return Ok((StatusCode::OK, AxumJson(serde_json::json!({
-                        "podcast_script": content,
-                        "title": value.get("title").unwrap_or(&serde_json::Value::String(title.clone())),
-                        "chat_id": value.get("chatId"),
-                        "research_mode": value.get("researchMode"),
-                        "status": "success"
-                    }))));
+        if let Some(content) = chats::extract_content(&value) {
+            // The upstream may ignore max_tokens, so enforce the cap on our side too
+            let (content, length) = match content.as_str() {
+                Some(text) => {
+                    let text = if segmented { segments::assemble(&drafts.segments, text) } else { text.to_string() };
+                    // The model may still quote usernames it saw in older context
+                    let text = if anonymize { privacy::anonymize(&text) } else { text };
+                    let text = if mode == script::Mode::Flash { script::strip_cues(&text) } else { text };
+                    let (text, length) = script::enforce_length(&text, max_tokens);
+                    (serde_json::Value::String(text), Some(length))
+                }
+                None => (content.clone(), None),
+            };
+            if length.as_ref().is_some_and(|l| l.truncated) {
+                info!("Truncated podcast script \"{}\" to {} tokens", title, max_tokens);
+            }
+
+            let link_mode = links::mode();
+            let (content, link_report) = match content.as_str() {
+                Some(text) if payload.verify_links.unwrap_or(link_mode != links::Mode::Off) => {
+                    let mode = if link_mode == links::Mode::Off { links::Mode::Flag } else { link_mode };
+                    let (text, report) = links::verify_script(text, &payload.reference_urls, mode).await;
+                    (serde_json::Value::String(text), Some(report))
                 }
+                _ => (content, None),
+            };
+
+            if let Some(job_id) = &payload.job_id {
+                history::set_output(job_id, &chats::content_text(&content));
             }
-            // Handle case where result.response is the content directly
-            else if let Some(content) = result.get("content") {
-                return Ok((StatusCode::OK, AxumJson(serde_json::json!({
-                    "podcast_script": content,
-                    "title": value.get("title").unwrap_or(&serde_json::Value::String(title.clone())),
-                    "chat_id": value.get("chatId"),
-                    "research_mode": value.get("researchMode"),
-                    "status": "success"
-                }))));
+            usage::record(usage::Metric::Tokens, generation_tokens(&chat_history, &chats::content_text(&content))).await;
+
+            if let Some(chat_id) = value.get("chatId").and_then(|c| c.as_str()) {
+                let mut messages = chat_history.clone();
+                messages.push(chats::ChatMessage::new("assistant", chats::content_text(&content)));
+                chats::record(chat_id, &persona, &scope, Some(title.clone()), messages);
             }
+
+            // The slug follows the title the episode is archived under, which the upstream may
+            // have changed
+            let episode_title = value.get("title").and_then(|t| t.as_str()).unwrap_or(&title);
+            let slug = episodes::slugify(episode_title);
+            // A failed save is logged but doesn't cost the caller the script
+            let archived = if archive::enabled() {
+                let script_text = chats::content_text(&content);
+                let mut topics: Vec<String> = if payload.topics.is_empty() {
+                    // The upstream may rename the episode; both titles say what it's about
+                    let titles = if episode_title == title { title.clone() } else { format!("{} {}", title, episode_title) };
+                    topics::classify_rules(&titles, &script_text).into_iter().map(String::from).collect()
+                } else {
+                    payload.topics.clone()
+                };
+                if let Some(topic) = payload.feed_topic.as_ref().filter(|topic| !topics.contains(topic)) {
+                    topics.push(topic.clone());
+                }
+                let saved = archive::save(archive::NewEpisode {
+                    title: episode_title,
+                    slug: &slug,
+                    season: payload.season,
+                    persona: &persona,
+                    script: &script_text,
+                    story_ids: &payload.story_ids,
+                    topics: &topics,
+                    chat_id: value.get("chatId").and_then(|c| c.as_str()),
+                    job_id: payload.job_id.as_deref(),
+                    status: episode_status,
+                    show_notes: show_notes.as_deref(),
+                    publish_at: payload.publish_at,
+                    follow_up_of: payload.follow_up_of.as_deref(),
+                })
+                .await;
+                match saved {
+                    Ok(episode) => Some(episode),
+                    Err(e) => {
+                        error!("Failed to archive episode \"{}\": {}", episode_title, e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            return Ok((StatusCode::OK, AxumJson(serde_json::json!({
+                "episode_id": archived.as_ref().map(|episode| &episode.id),
+                "episode_number": archived.as_ref().map(|episode| episode.episode_number),
+                "publish_at": archived.as_ref().and_then(|episode| episode.publish_at),
+                "episode_status": episode_status,
+                "show_notes": show_notes,
+                "omitted": drafts.omitted,
+                "mode": mode,
+                "podcast_script": content,
+                "length": length,
+                "links": link_report,
+                "title": value.get("title").unwrap_or(&serde_json::Value::String(title.clone())),
+                "chat_id": value.get("chatId"),
+                "research_mode": value.get("researchMode"),
+                "slug": slug,
+                "season": payload.season,
+                "status": "success"
+            }))));
         }
         // Fallback: return the full response for debugging
         Ok((StatusCode::OK, AxumJson(serde_json::json!({
@@ -545,7 +1830,7 @@ async fn generate_podcast(
             "status": "success_but_unexpected_format"
         }))))
     } else {
-        error!("Upstream returned error status: {} body: {}", status, value);
+        error!("Upstream returned error status: {} body: {}", status, redact::json(&value));
         Err((
             StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
             AxumJson(ApiError { error: format!("Podcast generation failed: {}", response_text) })
@@ -553,198 +1838,1165 @@ async fn generate_podcast(
     }
 }
 
-// New structs for the generate endpoint
-#[derive(Debug, Deserialize)]
-struct LangChainMessage {
-    content: String,
-    role: String,
-}
-
+// --- Episode archive ---
 
-#[derive(Debug, Deserialize)]
-struct GenerateRequest {
-    chat_history: Vec<LangChainMessage>,
-    persona: Option<String>,
-    scope: Option<String>,
+fn archive_error(error: String) -> (StatusCode, AxumJson<ApiError>) {
+    let status = if archive::enabled() { StatusCode::INTERNAL_SERVER_ERROR } else { StatusCode::SERVICE_UNAVAILABLE };
+    if status == StatusCode::INTERNAL_SERVER_ERROR {
+        error!("Episode archive error: {}", error);
+    }
+    (status, AxumJson(ApiError { error }))
 }
 
-#[derive(Debug, Deserialize)]
-struct TTSRequest {
-    text: String,
+// Newest first, without scripts; ?limit= (default 20, max 100) and ?offset= page through them,
+// ?topic= keeps one topic
+async fn list_episodes(
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<AxumJson<fields::Envelope<Vec<archive::Episode>>>, (StatusCode, AxumJson<ApiError>)> {
+    let page = |key: &str, default: u32| match params.get(key) {
+        Some(value) => value.parse::<u32>().map_err(|_| {
+            (StatusCode::BAD_REQUEST, AxumJson(ApiError { error: format!("{} must be a non-negative integer", key) }))
+        }),
+        None => Ok(default),
+    };
+    let limit = page("limit", 20)?.min(archive::MAX_PAGE_SIZE);
+    let offset = page("offset", 0)?;
+    let topic = params.get("topic").map(String::as_str).map(known_topic).transpose()?;
+    let (episodes, total) = archive::list(limit, offset, topic).await.map_err(archive_error)?;
+    Ok(AxumJson(fields::Envelope {
+        meta: serde_json::json!({ "count": episodes.len(), "total": total, "limit": limit, "offset": offset, "topic": topic }),
+        data: episodes,
+    }))
 }
 
-#[derive(Debug, Serialize)]
-struct StreamingResponse {
-    r#type: String,
-    content: serde_json::Value,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    icon: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<serde_json::Value>,
-}
+// --- Topics ---
 
-// Generate streaming endpoint
-async fn generate_stream(
-    Json(payload): Json<GenerateRequest>
-) -> Result<Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>>, (StatusCode, AxumJson<ApiError>)> {
-    let api_url = env::var("ALCHEMYST_API_URL").unwrap_or_else(|_| "https://platform-backend.getalchemystai.com".to_string());
-    let api_key = env::var("ALCHEMYST_API_KEY").unwrap_or_default();
-    
-    if api_key.is_empty() {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            AxumJson(ApiError { error: "ALCHEMYST_API_KEY is not configured".to_string() })
-        ));
-    }
+// Title, post text and the first top-level comments
+const SENSITIVE_SCREENED_COMMENTS: usize = 30;
 
-    let persona = payload.persona.unwrap_or_else(|| "maya".to_string());
-    let scope = payload.scope.unwrap_or_else(|| "internal".to_string());
+fn story_sensitive_flags(story: &HNStory, comments: &[HNComment]) -> Vec<String> {
+    let mut text = story.text.as_deref().map(analysis::strip_html).unwrap_or_default();
+    for comment in comments.iter().take(SENSITIVE_SCREENED_COMMENTS) {
+        text.push('\n');
+        text.push_str(&analysis::strip_html(comment.text.as_deref().unwrap_or_default()));
+    }
+    topics::sensitive_flags(story.title.as_deref().unwrap_or_default(), &text).into_iter().map(String::from).collect()
+}
 
-    // Read podcast prompt as system message
-    let system_prompt: &str = include_str!("prompt.md");
+fn known_topic(topic: &str) -> Result<&str, (StatusCode, AxumJson<ApiError>)> {
+    if topics::is_known(topic) {
+        Ok(topic)
+    } else {
+        Err((
+            StatusCode::BAD_REQUEST,
+            AxumJson(ApiError { error: format!("Unknown topic '{}'. Valid topics: {}", topic, topics::names().join(", ")) }),
+        ))
+    }
+}
 
-    // Convert user messages and add system prompt for podcast generation
-    let mut chat_history: Vec<serde_json::Value> = vec![
-        serde_json::json!({ "role": "system", "content": system_prompt })
-    ];
-    
-    // Add user messages but modify them to focus on podcast generation
-    for msg in payload.chat_history {
-        if msg.role == "user" {
-            let podcast_content = format!(
-                "Generate a podcast episode using the available context from my workspace. User request: {}. Automatically retrieve relevant context and produce the full podcast script per the instructions.",
-                msg.content
-            );
-            chat_history.push(serde_json::json!({
-                "content": podcast_content,
-                "role": "user"
-            }));
-        } else {
-            chat_history.push(serde_json::json!({
-                "content": msg.content,
-                "role": msg.role
-            }));
+// Archived topics when the story was classified before; otherwise classify it now (rules, then
+// the model when TOPIC_CLASSIFIER_LLM is on) and archive the result
+async fn story_topics(story: &HNStory) -> Vec<String> {
+    if archive::enabled() {
+        match archive::story_topics(story.id).await {
+            Ok(Some(topics)) => return topics,
+            Ok(None) => {}
+            Err(e) => error!("Failed to read topics for story {}: {}", story.id, e),
         }
     }
-
-    let body = serde_json::json!({
-        "chat_history": chat_history,
-        "persona": persona,
-        "scope": scope,
-        "stream": true,
-        "tools": {
-            "researchIcps": false,
-            "deepResearch": false,
-            "webSearch": false,
-            "organizationSearch": true
+    let text = story.text.as_deref().map(analysis::strip_html).unwrap_or_default();
+    let (topics, source) = topics::classify(story.title.as_deref().unwrap_or_default(), &text).await;
+    if archive::enabled() {
+        if let Err(e) = archive::save_story_topics(story.id, &topics, source).await {
+            error!("Failed to archive topics for story {}: {}", story.id, e);
         }
-    });
+    }
+    topics
+}
 
-    info!("Sending request to Alchemyst API: {}", serde_json::to_string_pretty(&body).unwrap_or_default());
+// The vocabulary, with episode and story counts per topic when the archive is on
+async fn get_topics() -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let stats = if archive::enabled() { archive::topic_stats().await.map_err(archive_error)? } else { Vec::new() };
+    let count = |name: &str, field: fn(&archive::TopicStats) -> u64| {
+        archive::enabled().then(|| stats.iter().find(|s| s.topic == name).map_or(0, field))
+    };
+    let topics: Vec<serde_json::Value> = topics::names()
+        .into_iter()
+        .map(|name| {
+            serde_json::json!({
+                "topic": name,
+                "episodes": count(name, |s| s.episodes),
+                "stories": count(name, |s| s.stories),
+            })
+        })
+        .collect();
+    Ok(AxumJson(serde_json::json!({ "topics": topics })))
+}
 
-    let client = reqwest::Client::new();
-    let url = format!("{}/api/v1/chat/generate/stream", api_url);
-    
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&body)
-        .send()
+async fn get_episode(Path(id): Path<String>) -> Result<AxumJson<archive::Episode>, (StatusCode, AxumJson<ApiError>)> {
+    let mut episode = archive::get(&id)
         .await
-        .map_err(|e| {
-            error!("Generate stream request failed: {}", e);
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    episode.follow_ups = archive::follow_ups(&id).await.map_err(archive_error)?;
+    Ok(AxumJson(episode))
+}
+
+// The episode's script as plain text, linked from the feed as its podcast:transcript
+async fn get_episode_transcript(Path(id): Path<String>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let episode = archive::get(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    let script = episode.script.filter(|script| !script.trim().is_empty()).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} has no script", id) }))
+    })?;
+    typed_response("text/plain; charset=utf-8", script)
+}
+
+// Word and sentence timestamps for the transcript, from Whisper run over the rendered audio
+async fn align_transcript(id: String, rendered_at: chrono::DateTime<chrono::Utc>, source: episode_audio::Rendition) {
+    let path = episode_audio::dir().join(&source.file);
+    let audio = match tokio::fs::read(&path).await {
+        Ok(audio) => audio,
+        Err(e) => return error!("Failed to read {} to align episode {}: {}", path.display(), id, e),
+    };
+    match transcribe::transcribe(audio, &source.mime_type).await {
+        Ok(timings) => match archive::set_transcript_timings(&id, rendered_at, &timings).await {
+            Ok(true) => info!("Aligned {} words of episode {}'s transcript", timings.words.len(), id),
+            Ok(false) => info!("Episode {} changed while aligning its transcript; timings dropped", id),
+            Err(e) => error!("Failed to store transcript timings of episode {}: {}", id, e),
+        },
+        Err(e) => error!("Failed to align episode {}'s transcript: {}", id, e),
+    }
+}
+
+// Word-level and sentence-level timestamps of the rendered audio, for synchronized transcripts
+async fn get_episode_transcript_timings(Path(id): Path<String>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let timings = archive::transcript_timings(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?
+        .ok_or_else(|| {
             (
-                StatusCode::BAD_GATEWAY,
-                AxumJson(ApiError { error: "Upstream request failed".to_string() })
+                StatusCode::NOT_FOUND,
+                AxumJson(ApiError { error: format!("Episode {}'s audio hasn't been aligned; render it with WHISPER_PROVIDER configured", id) }),
             )
         })?;
+    Ok(AxumJson(serde_json::json!({
+        "episode_id": id,
+        "words": timings.words,
+        "sentences": timings.segments,
+    })))
+}
 
-    let status = response.status();
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        error!("Upstream API returned error: {} - {}", status, error_text);
-        return Err((
-            StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
-            AxumJson(ApiError { error: format!("Upstream API error: {}", error_text) })
-        ));
+// Everything the web player needs for the episode, including who is speaking when
+async fn get_episode_player(Path(id): Path<String>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let episode = archive::get(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    player::manifest(&episode).map(AxumJson).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} has no rendered audio", id) }))
+    })
+}
+
+// Embeddable episodes: embargoed episodes and ones without audio aren't embeddable
+async fn embeddable_episode(id: &str) -> Result<archive::Episode, (StatusCode, AxumJson<ApiError>)> {
+    archive::get(id)
+        .await
+        .map_err(archive_error)?
+        .filter(|episode| !episode.embargoed() && episode.audio.is_some())
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found or has no rendered audio", id) })))
+}
+
+// Self-contained HTML player for iframes on other sites (see embed.rs)
+async fn get_embed_page(Path(id): Path<String>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let episode = embeddable_episode(&id).await?;
+    let mut response = typed_response("text/html; charset=utf-8", embed::page(&episode).unwrap_or_default())?;
+    // Only its own inline script and style, and the audio, wherever it's served from; any site may frame it
+    response.headers_mut().insert(
+        axum::http::header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static("default-src 'none'; media-src * data: blob:; style-src 'unsafe-inline'; script-src 'unsafe-inline'; frame-ancestors *"),
+    );
+    Ok(response)
+}
+
+#[derive(Debug, Deserialize)]
+struct OembedQuery {
+    url: String,
+    format: Option<String>,
+    maxwidth: Option<u32>,
+    maxheight: Option<u32>,
+}
+
+// oEmbed provider for embed page URLs; only JSON is offered (501 for other formats, per the spec)
+async fn get_oembed(Query(query): Query<OembedQuery>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    if query.format.as_deref().is_some_and(|format| format != "json") {
+        return Err((StatusCode::NOT_IMPLEMENTED, AxumJson(ApiError { error: "Only format=json is supported".to_string() })));
     }
+    let id = embed::episode_id(&query.url).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("{} is not an embed URL of this podcast", query.url) }))
+    })?;
+    let episode = embeddable_episode(id).await?;
+    Ok(AxumJson(embed::oembed(&episode, query.maxwidth, query.maxheight)))
+}
 
-    // Get the response text and process it
-    let response_text = response.text().await.map_err(|e| {
-        error!("Failed to read response text: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            AxumJson(ApiError { error: "Failed to read response".to_string() })
-        )
+// Chapter markers of the rendered audio in the Podcasting 2.0 JSON chapters format
+async fn get_episode_chapters(Path(id): Path<String>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let episode = archive::get(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    let chapters = episode.audio.map(|audio| audio.chapters).filter(|chapters| !chapters.is_empty()).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} has no chapters; render its audio first", id) }))
     })?;
+    typed_response("application/json+chapters", feed::chapters_json(&chapters).to_string())
+}
+
+// Admin only: removes the episode from the feed for good
+async fn delete_episode(headers: HeaderMap, Path(id): Path<String>) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let episode = archive::get(&id).await.map_err(archive_error)?;
+    match archive::delete(&id).await.map_err(archive_error)? {
+        true => {
+            audit(&headers, "episode.delete", &id, episode.as_ref().and_then(|e| serde_json::to_value(e).ok()), None).await;
+            if let Some(audio) = episode.and_then(|episode| episode.audio) {
+                for file in audio.files() {
+                    episode_audio::remove(file).await;
+                }
+                if let Some(hls) = &audio.hls {
+                    episode_audio::remove_hls(&hls.dir).await;
+                }
+            }
+            Ok(StatusCode::NO_CONTENT)
+        }
+        false => Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) }))),
+    }
+}
+
+// Audio for an episode a schedule generated, so it reaches the feeds without an admin rendering it
+async fn render_scheduled_episode(id: &str, voice: Option<String>) -> Result<(), String> {
+    let request = EpisodeAudioRequest { voice, ..Default::default() };
+    render_episode_audio(Path(id.to_string()), Some(Json(request))).await.map(|_| ()).map_err(|(_, AxumJson(e))| e.error)
+}
 
-    info!("Received response from Alchemyst API: {}", response_text);
+#[derive(Debug, Deserialize, Default)]
+struct EpisodeAudioRequest {
+    // Defaults as for /api/tts/generate, with the episode's persona
+    voice: Option<String>,
+    include_announcements: Option<bool>,
+    trim_silence: Option<bool>,
+}
 
-    // Create a stream from the response text
-    let stream = async_stream::stream! {
-        let lines: Vec<&str> = response_text.lines().collect();
-        
-        for line in lines {
-            let line = line.trim();
-            
-            if line.is_empty() || line == "data: [DONE]" {
+// Renders the stored script to MP3 (see episode_audio.rs), replacing any earlier rendering. The QA
+// report is kept with the audio; audio that fails it stays out of the feed (see AUDIO_QA_BLOCK_PUBLISH).
+async fn render_episode_audio(
+    Path(id): Path<String>,
+    payload: Option<Json<EpisodeAudioRequest>>,
+) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
+    sandbox_forbidden()?;
+    let Json(payload) = payload.unwrap_or_default();
+    let episode = archive::get(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    let script = episode.script.clone().unwrap_or_default();
+    if script.trim().is_empty() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, AxumJson(ApiError { error: format!("Episode {} has no script", id) })));
+    }
+    let voice = tts_voice(payload.voice, Some(&episode.persona))?;
+    charge(usage::Metric::TtsCharacters, script.chars().count() as u64).await?;
+
+    info!("Rendering audio for episode {} (\"{}\") with voice {}", id, episode.title, voice);
+    let (pcm, chapters, omitted) = render_script_pcm(&script, &voice, payload.include_announcements, payload.trim_silence).await?;
+    let qa = audio::analyze(&pcm, TTS_SAMPLE_RATE, &audio::QaThresholds::from_env());
+    let published = qa.passed || !audio::qa_blocks_publish();
+    if !qa.passed {
+        info!("Audio QA flagged {} issue(s): {}", qa.violations.len(), qa.violations.join("; "));
+    }
+    let mp3 = episode_audio::encode(&pcm, TTS_SAMPLE_RATE, episode_audio::Quality::High).await.map_err(|error| {
+        (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error }))
+    })?;
+    let tag = episode_audio::id3_tag(&episode.title, &chapters, pcm_duration_secs(pcm.len()));
+    let mp3 = [tag.clone(), mp3].concat();
+
+    let file = episode_audio::file_name(&episode.slug, episode.season, episode.episode_number);
+    episode_audio::save(&file, &mp3).await.map_err(|error| {
+        error!("Failed to save audio for episode {}: {}", id, error);
+        (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error: "Failed to save the episode audio".to_string() }))
+    })?;
+    // Best effort: an ffmpeg without libopus shouldn't cost the episode its audio
+    let mut renditions = Vec::new();
+    for quality in episode_audio::Quality::RENDITIONS {
+        let encoded = match episode_audio::encode(&pcm, TTS_SAMPLE_RATE, quality).await {
+            Ok(bytes) if quality.mime_type() == "audio/mpeg" => [tag.clone(), bytes].concat(),
+            Ok(bytes) => bytes,
+            Err(error) => {
+                error!("Skipping the {} rendition of episode {}: {}", quality.name(), id, error);
                 continue;
             }
-            
-            if line.starts_with("data: ") {
-                let data = &line[6..];
-                if data.trim() == "[DONE]" {
-                    break;
-                }
-                
-                // Try to parse and process the data
-                match serde_json::from_str::<serde_json::Value>(data) {
-                    Ok(json_data) => {
-                        // Extract and display only the content from specific message types
-                        if let Some(message_type) = json_data.get("type").and_then(|t| t.as_str()) {
-                            match message_type {
-                                "thinking_update" => {
-                                    if let Some(content) = json_data.get("content") {
-                                        let content_str = if content.is_string() {
-                                            content.as_str().unwrap_or("").to_string()
+        };
+        let file = episode_audio::rendition_file_name(&episode.slug, episode.season, episode.episode_number, quality);
+        if let Err(error) = episode_audio::save(&file, &encoded).await {
+            error!("Failed to save the {} rendition of episode {}: {}", quality.name(), id, error);
+            continue;
+        }
+        renditions.push(episode_audio::Rendition {
+            quality,
+            url: episode_audio::url(&file),
+            file,
+            bytes: encoded.len() as u64,
+            mime_type: quality.mime_type().to_string(),
+            bits_per_second: quality.bits_per_second(),
+        });
+    }
+    let stem = episodes::episode_file_stem(&episode.slug, episode.season, Some(episode.episode_number));
+    let hls = match episode_audio::encode_hls(&pcm, TTS_SAMPLE_RATE, &stem).await {
+        Ok(hls) => Some(hls),
+        Err(error) => {
+            error!("Skipping HLS for episode {}: {}", id, error);
+            None
+        }
+    };
+    let rendered = archive::EpisodeAudio {
+        url: episode_audio::url(&file),
+        file,
+        bytes: mp3.len() as u64,
+        duration_secs: pcm_duration_secs(pcm.len()),
+        rendered_at: chrono::Utc::now(),
+        qa: Some(qa.clone()),
+        chapters: chapters.clone(),
+        renditions,
+        hls,
+    };
+    // Deleted while rendering
+    let key = usage::current();
+    if !archive::set_audio(&id, &rendered, key.as_ref().map(|key| key.id.as_str())).await.map_err(archive_error)? {
+        for file in rendered.files() {
+            episode_audio::remove(file).await;
+        }
+        if let Some(hls) = &rendered.hls {
+            episode_audio::remove_hls(&hls.dir).await;
+        }
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })));
+    }
+    // Rendered before episodes were numbered, under the old naming scheme, or a rendition this
+    // render couldn't produce
+    if let Some(previous) = &episode.audio {
+        for file in previous.files().filter(|file| !rendered.files().any(|current| current == *file)) {
+            episode_audio::remove(file).await;
+        }
+        if let Some(hls) = previous.hls.as_ref().filter(|hls| rendered.hls.as_ref().is_none_or(|current| current.dir != hls.dir)) {
+            episode_audio::remove_hls(&hls.dir).await;
+        }
+    }
+    info!("Saved {:.0}s of audio for episode {} as {}", rendered.duration_secs, id, rendered.file);
+    usage::record(usage::Metric::StorageBytes, rendered.bytes + rendered.renditions.iter().map(|rendition| rendition.bytes).sum::<u64>()).await;
+    if transcribe::is_configured() {
+        // The smallest encoding stays under the hosted Whisper upload limit for long episodes
+        let source = [episode_audio::Quality::Low, episode_audio::Quality::Medium, episode_audio::Quality::High]
+            .into_iter()
+            .find_map(|quality| rendered.rendition(quality));
+        if let Some(source) = source {
+            tokio::spawn(credentials::scope(credentials::current(), align_transcript(id.clone(), rendered.rendered_at, source)));
+        }
+    }
+    if episode.embargoed() {
+        info!("Episode {} stays out of the feed until {}", id, episode.published_at().to_rfc3339());
+    } else if published {
+        websub::spawn_ping(feed::subscription_urls(&episode.topics));
+        activitypub::spawn_publish(id.clone());
+    } else {
+        info!("Episode {} is held out of the feed until its audio passes QA", id);
+    }
+    if !omitted.is_empty() {
+        let note = segments::omission_note("Left out of the audio because they couldn't be synthesized:", &omitted);
+        archive::mark_degraded(&id, &note).await.map_err(archive_error)?;
+        info!("Episode {} is degraded: {} section(s) left out of the audio", id, omitted.len());
+    }
+
+    Ok((StatusCode::CREATED, AxumJson(serde_json::json!({
+        "episode_id": id,
+        "format": "mp3",
+        "voice": voice,
+        "audio": rendered,
+        "episode_status": if omitted.is_empty() { episode.status } else { archive::EpisodeStatus::Degraded },
+        "omitted": omitted,
+        "chapters": chapters,
+        "qa": qa,
+        "published": published,
+    }))))
+}
+
+// Redirects to the episode's audio file; ?quality=low|medium picks a lighter rendition, falling
+// back to the main file when it wasn't rendered
+async fn get_episode_audio(
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Redirect, (StatusCode, AxumJson<ApiError>)> {
+    let quality = params.get("quality").map(|name| audio_quality(name)).transpose()?.unwrap_or(episode_audio::Quality::High);
+    let episode = archive::get(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    let Some(audio) = episode.audio else {
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} has no rendered audio", id) })));
+    };
+    let url = audio.rendition(quality).map_or_else(|| audio.url.clone(), |rendition| rendition.url);
+    Ok(Redirect::temporary(&url))
+}
+
+// HLS master playlist of the episode's audio for the web player
+async fn get_episode_hls(Path(id): Path<String>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let episode = archive::get(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    let hls = episode.audio.and_then(|audio| audio.hls).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} has no HLS stream; render its audio first", id) }))
+    })?;
+    typed_response("application/vnd.apple.mpegurl", hls.master_playlist())
+}
+
+// QA report of the episode's rendered audio and whether it's in the feed
+async fn get_episode_audio_qa(Path(id): Path<String>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let episode = archive::get(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    let Some(audio) = episode.audio else {
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} has no rendered audio", id) })));
+    };
+    let Some(qa) = audio.qa else {
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {}'s audio has no QA report; render it again", id) })));
+    };
+    Ok(AxumJson(serde_json::json!({
+        "episode_id": id,
+        "rendered_at": audio.rendered_at,
+        "published": qa.passed || !audio::qa_blocks_publish(),
+        "qa": qa,
+    })))
+}
+
+// --- User libraries ---
+
+// Listener ids are opaque and chosen by the client (e.g. a random id kept in local storage)
+fn user_id(id: &str) -> Result<(), (StatusCode, AxumJson<ApiError>)> {
+    if (1..=64).contains(&id.len()) && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Ok(());
+    }
+    Err((StatusCode::BAD_REQUEST, AxumJson(ApiError { error: "user id must be 1 to 64 letters, digits, '-' or '_'".to_string() })))
+}
+
+async fn get_user_library(Path(user): Path<String>) -> Result<AxumJson<archive::Library>, (StatusCode, AxumJson<ApiError>)> {
+    user_id(&user)?;
+    archive::library(&user).await.map(AxumJson).map_err(archive_error)
+}
+
+async fn add_user_bookmark(Path((user, episode_id)): Path<(String, String)>) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    user_id(&user)?;
+    if archive::get(&episode_id).await.map_err(archive_error)?.is_none() {
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", episode_id) })));
+    }
+    match archive::add_bookmark(&user, &episode_id).await.map_err(archive_error)? {
+        true => Ok(StatusCode::CREATED),
+        false => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaylistRequest {
+    episode_ids: Vec<String>,
+}
+
+const MAX_PLAYLIST_EPISODES: usize = 500;
+
+async fn save_user_playlist(
+    Path((user, name)): Path<(String, String)>,
+    Json(payload): Json<PlaylistRequest>,
+) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    user_id(&user)?;
+    if name.trim().is_empty() || name.chars().count() > 100 || payload.episode_ids.len() > MAX_PLAYLIST_EPISODES {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            AxumJson(ApiError { error: format!("Playlists need a name of 1 to 100 characters and at most {} episodes", MAX_PLAYLIST_EPISODES) })
+        ));
+    }
+    archive::save_playlist(&user, name.trim(), &payload.episode_ids).await.map_err(archive_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn add_user_subscription(Path((user, topic)): Path<(String, String)>) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    user_id(&user)?;
+    let topic = known_topic(&topic)?;
+    match archive::add_subscription(&user, topic).await.map_err(archive_error)? {
+        true => Ok(StatusCode::CREATED),
+        false => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+async fn remove_library_item(user: &str, item: archive::LibraryItem, key: &str) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    user_id(user)?;
+    match archive::remove_library_item(user, item, key).await.map_err(archive_error)? {
+        true => Ok(StatusCode::NO_CONTENT),
+        false => Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("No such item in {}'s library", user) }))),
+    }
+}
+
+async fn remove_user_bookmark(Path((user, episode_id)): Path<(String, String)>) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    remove_library_item(&user, archive::LibraryItem::Bookmark, &episode_id).await
+}
+
+async fn delete_user_playlist(Path((user, name)): Path<(String, String)>) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    remove_library_item(&user, archive::LibraryItem::Playlist, &name).await
+}
+
+async fn remove_user_subscription(Path((user, topic)): Path<(String, String)>) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    remove_library_item(&user, archive::LibraryItem::Subscription, &topic).await
+}
+
+// Admin only (GDPR access requests): everything stored about the user
+async fn export_user_data(headers: HeaderMap, Path(user): Path<String>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    user_id(&user)?;
+    let export = serde_json::json!({
+        "user_id": user,
+        "exported_at": chrono::Utc::now(),
+        "library": archive::library(&user).await.map_err(archive_error)?,
+        "feedback": archive::feedback(u32::MAX, None, None, Some(&user), None).await.map_err(archive_error)?,
+        "playback": archive::playback_events(&user).await.map_err(archive_error)?,
+    });
+    audit(&headers, "user.export", &user, None, None).await;
+    let body = serde_json::to_string_pretty(&export).unwrap_or_default();
+    let mut response = typed_response("application/json", body)?;
+    if let Ok(value) = HeaderValue::from_str(&format!("attachment; filename=\"user-{}.json\"", user)) {
+        response.headers_mut().insert(axum::http::header::CONTENT_DISPOSITION, value);
+    }
+    Ok(response)
+}
+
+// Admin only (GDPR erasure requests): removes everything tied to the user
+async fn delete_user_data(headers: HeaderMap, Path(user): Path<String>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    user_id(&user)?;
+    let removed = archive::delete_user_data(&user).await.map_err(archive_error)?;
+    info!("Erased the data of user {}", user);
+    let removed: serde_json::Map<String, serde_json::Value> = removed.into_iter().map(|(table, rows)| (table.to_string(), rows.into())).collect();
+    // Only the counts: a snapshot of the erased data would defeat the erasure
+    audit(&headers, "user.delete", &user, None, Some(serde_json::json!({ "removed": removed }))).await;
+    Ok(AxumJson(serde_json::json!({ "user_id": user, "removed": removed })))
+}
+
+// --- Listener feedback ---
+
+#[derive(Debug, Deserialize)]
+struct FeedbackRequest {
+    // 1 to 5
+    rating: u8,
+    comment: Option<String>,
+    // Library user id; a user's later rating of the episode replaces the earlier one
+    user_id: Option<String>,
+}
+
+async fn add_episode_feedback(
+    Path(id): Path<String>,
+    Json(payload): Json<FeedbackRequest>,
+) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
+    let comment = feedback::validate(payload.rating, payload.comment.as_deref())
+        .map_err(|error| (StatusCode::UNPROCESSABLE_ENTITY, AxumJson(ApiError { error })))?;
+    if let Some(user) = &payload.user_id {
+        user_id(user)?;
+    }
+    if archive::get(&id).await.map_err(archive_error)?.is_none() {
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })));
+    }
+    let feedback_id = archive::add_feedback(&id, payload.user_id.as_deref(), payload.rating, comment.as_deref()).await.map_err(archive_error)?;
+    Ok((StatusCode::CREATED, AxumJson(serde_json::json!({ "id": feedback_id, "episode_id": id }))))
+}
+
+// Public aggregate; the comments themselves are admin only (GET /admin/feedback)
+async fn get_episode_feedback(Path(id): Path<String>) -> Result<AxumJson<archive::FeedbackSummary>, (StatusCode, AxumJson<ApiError>)> {
+    if archive::get(&id).await.map_err(archive_error)?.is_none() {
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })));
+    }
+    let (_, mut episodes) = archive::feedback_summaries(None, Some(&id)).await.map_err(archive_error)?;
+    Ok(AxumJson(episodes.pop().unwrap_or(archive::FeedbackSummary { episode_id: Some(id), ..Default::default() })))
+}
+
+#[derive(Debug, Deserialize)]
+struct FeedbackQuery {
+    limit: Option<u32>,
+    // Entry id to page back from
+    before: Option<i64>,
+    episode_id: Option<String>,
+    user_id: Option<String>,
+}
+
+// Admin only: individual ratings with their comments, newest first
+async fn list_feedback(headers: HeaderMap, Query(query): Query<FeedbackQuery>) -> Result<AxumJson<Vec<archive::Feedback>>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    archive::feedback(limit, query.before, query.episode_id.as_deref(), query.user_id.as_deref(), None)
+        .await
+        .map(AxumJson)
+        .map_err(archive_error)
+}
+
+#[derive(Debug, Deserialize)]
+struct FeedbackSummaryQuery {
+    // Only ratings from the last this many days; all of them by default
+    days: Option<i64>,
+}
+
+// Admin only: ratings per episode, most rated first, and over all episodes
+async fn get_feedback_summary(
+    headers: HeaderMap,
+    Query(query): Query<FeedbackSummaryQuery>,
+) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let since = query.days.filter(|days| *days > 0).map(|days| chrono::Utc::now() - chrono::Duration::days(days));
+    let (total, episodes) = archive::feedback_summaries(since, None).await.map_err(archive_error)?;
+    Ok(AxumJson(serde_json::json!({ "since": since, "total": total, "episodes": episodes })))
+}
+
+// --- Topic engagement ---
+
+#[derive(Debug, Deserialize)]
+struct PlaybackRequest {
+    event: engagement::Event,
+    // Where in the audio the listener skipped or finished; picks the segment
+    position_secs: f64,
+    // Library user id; a user's later event for a segment replaces the earlier one
+    user_id: Option<String>,
+}
+
+async fn add_playback_event(
+    Path(id): Path<String>,
+    Json(payload): Json<PlaybackRequest>,
+) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
+    if let Some(user) = &payload.user_id {
+        user_id(user)?;
+    }
+    let Some(episode) = archive::get(&id).await.map_err(archive_error)? else {
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })));
+    };
+    let Some(audio) = &episode.audio else {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, AxumJson(ApiError { error: format!("Episode {} has no rendered audio", id) })));
+    };
+    let Some((chapter, segment)) = engagement::chapter_at(&audio.chapters, payload.position_secs) else {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            AxumJson(ApiError { error: format!("Position {} is not in a story segment", payload.position_secs) }),
+        ));
+    };
+    let topics = engagement::segment_topics(&segment.title, &episode.topics);
+    let event_id = archive::add_playback_event(&id, chapter as u32, &segment.title, payload.event.as_str(), &topics, payload.user_id.as_deref())
+        .await
+        .map_err(archive_error)?;
+    Ok((
+        StatusCode::CREATED,
+        AxumJson(serde_json::json!({ "id": event_id, "episode_id": id, "chapter": chapter, "title": segment.title, "topics": topics })),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct EngagementQuery {
+    // Only events and ratings from the last this many days; ENGAGEMENT_DAYS by default
+    days: Option<i64>,
+}
+
+// Admin only: per-topic engagement scores, best engaged first
+async fn get_engagement(headers: HeaderMap, Query(query): Query<EngagementQuery>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let days = query.days.filter(|days| *days > 0).unwrap_or_else(engagement::days);
+    let topics = engagement::scores(Some(days)).await.map_err(archive_error)?;
+    Ok(AxumJson(serde_json::json!({ "days": days, "bias": engagement::bias_enabled(), "topics": topics })))
+}
+
+// --- ActivityPub ---
+
+fn activitypub_actor() -> Result<activitypub::Actor, (StatusCode, AxumJson<ApiError>)> {
+    match activitypub::settings() {
+        Ok(Some(actor)) if archive::enabled() => Ok(actor),
+        _ => Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: "ActivityPub is not enabled".to_string() }))),
+    }
+}
+
+fn activity_response(document: serde_json::Value) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    typed_response(activitypub::CONTENT_TYPE, document.to_string())
+}
+
+async fn get_webfinger(Query(params): Query<HashMap<String, String>>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let actor = activitypub_actor()?;
+    let resource = params.get("resource").map(String::as_str).unwrap_or_default();
+    let document = activitypub::webfinger(&actor, resource)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Unknown resource '{}'", resource) })))?;
+    typed_response("application/jrd+json", document.to_string())
+}
+
+async fn get_activitypub_actor() -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let actor = activitypub_actor()?;
+    let document = activitypub::actor_document(&actor).map_err(|error| {
+        error!("Failed to build the ActivityPub actor: {}", error);
+        (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error }))
+    })?;
+    activity_response(document)
+}
+
+// Follows, unfollows and account deletions; 401 unless the request is signed by the activity's actor
+async fn post_activitypub_inbox(headers: HeaderMap, body: Bytes) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    let actor = activitypub_actor()?;
+    let activity: serde_json::Value = serde_json::from_slice(&body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, AxumJson(ApiError { error: format!("Invalid activity: {}", e) })))?;
+    let signer = activitypub::verify(&actor, "post /ap/inbox", &headers, &body).await.map_err(|error| {
+        warn!("Rejected an ActivityPub {} from {}: {}", activity["type"], activity["actor"], error);
+        (StatusCode::UNAUTHORIZED, AxumJson(ApiError { error }))
+    })?;
+    activitypub::receive(&actor, activity, signer).await.map_err(|error| {
+        error!("Failed to handle an ActivityPub activity: {}", error);
+        (StatusCode::UNPROCESSABLE_ENTITY, AxumJson(ApiError { error }))
+    })?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+// The episodes in the feeds, newest first, as Create activities
+async fn get_activitypub_outbox() -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    activitypub_actor()?;
+    let (episodes, total) = archive::with_audio(activitypub::OUTBOX_ITEMS, 0, audio::qa_blocks_publish(), None).await.map_err(archive_error)?;
+    activity_response(activitypub::outbox(&episodes, total))
+}
+
+async fn get_activitypub_followers() -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    activitypub_actor()?;
+    activity_response(activitypub::followers(archive::follower_count().await.map_err(archive_error)?))
+}
+
+// An episode's note, for servers resolving its id; only episodes in the feeds have one
+async fn get_activitypub_note(Path(id): Path<String>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    activitypub_actor()?;
+    let note = archive::get(&id)
+        .await
+        .map_err(archive_error)?
+        .filter(|episode| !episode.embargoed())
+        .and_then(|episode| activitypub::note(&episode))
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} has no note", id) })))?;
+    let mut document = note;
+    document["@context"] = serde_json::json!("https://www.w3.org/ns/activitystreams");
+    activity_response(document)
+}
+
+// --- Radar ---
+
+fn radar_keyword(keyword: &str) -> Result<String, (StatusCode, AxumJson<ApiError>)> {
+    radar::normalize_keyword(keyword).ok_or_else(|| {
+        (StatusCode::BAD_REQUEST, AxumJson(ApiError { error: "keyword must contain 1 to 64 characters of words".to_string() }))
+    })
+}
+
+// Watched keywords and the front-page stories that matched them, newest first; ?keyword= keeps
+// one keyword, ?limit= (default 50, max 100)
+async fn get_radar(Query(params): Query<HashMap<String, String>>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let limit = match params.get("limit") {
+        Some(value) => value.parse::<u32>().map_err(|_| {
+            (StatusCode::BAD_REQUEST, AxumJson(ApiError { error: "limit must be a non-negative integer".to_string() }))
+        })?,
+        None => 50,
+    };
+    let keyword = params.get("keyword").map(|k| radar_keyword(k)).transpose()?;
+    let keywords = archive::radar_keywords().await.map_err(archive_error)?;
+    let hits = archive::radar_hits(limit, keyword.as_deref()).await.map_err(archive_error)?;
+    Ok(AxumJson(serde_json::json!({ "keywords": keywords, "hits": hits })))
+}
+
+#[derive(Debug, Deserialize)]
+struct RadarKeywordRequest {
+    keyword: String,
+}
+
+// 201 for a new keyword, 200 when it was already watched
+async fn add_radar_keyword(
+    headers: HeaderMap,
+    Json(payload): Json<RadarKeywordRequest>,
+) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let keyword = radar_keyword(&payload.keyword)?;
+    let added = archive::add_radar_keyword(&keyword).await.map_err(archive_error)?;
+    if added {
+        info!("Radar now watching '{}'", keyword);
+        audit(&headers, "radar.keyword.add", &keyword, None, None).await;
+    }
+    let status = if added { StatusCode::CREATED } else { StatusCode::OK };
+    Ok((status, AxumJson(serde_json::json!({ "keyword": keyword }))))
+}
+
+async fn remove_radar_keyword(headers: HeaderMap, Path(keyword): Path<String>) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let keyword = radar_keyword(&keyword)?;
+    match archive::remove_radar_keyword(&keyword).await.map_err(archive_error)? {
+        true => {
+            audit(&headers, "radar.keyword.remove", &keyword, None, None).await;
+            Ok(StatusCode::NO_CONTENT)
+        }
+        false => Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("'{}' is not on the radar", keyword) }))),
+    }
+}
+
+async fn list_schedules() -> AxumJson<Vec<serde_json::Value>> {
+    AxumJson(schedules::all().await.iter().map(schedules::describe).collect())
+}
+
+async fn get_schedule(Path(name): Path<String>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    match schedules::get(&name).await {
+        Some(schedule) => Ok(AxumJson(schedules::describe(&schedule))),
+        None => Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("No schedule named '{}'", name) }))),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ScheduleRequest {
+    name: String,
+    cron: String,
+    // IANA name; defaults to UTC
+    timezone: Option<String>,
+    enabled: Option<bool>,
+    #[serde(flatten)]
+    settings: schedules::Settings,
+}
+
+// Settings that would only fail once the schedule runs are rejected up front
+fn validate_schedule(payload: &ScheduleRequest) -> Result<chrono_tz::Tz, (StatusCode, AxumJson<ApiError>)> {
+    let invalid = |error: String| (StatusCode::BAD_REQUEST, AxumJson(ApiError { error }));
+    if !schedules::valid_name(&payload.name) {
+        return Err(invalid("Schedule names are lowercase letters, digits and dashes, up to 64 characters".to_string()));
+    }
+    schedules::parse_cron(&payload.cron).map_err(invalid)?;
+    let tz = schedules::parse_timezone(payload.timezone.as_deref().unwrap_or("UTC")).map_err(invalid)?;
+    let settings = &payload.settings;
+    if settings.persona.is_some() {
+        personas::resolve(settings.persona.clone()).map_err(|valid| unknown_persona(settings.persona.as_deref(), &valid))?;
+    }
+    if settings.voice.is_some() {
+        tts_voice(settings.voice.clone(), None)?;
+    }
+    if let Some(topic) = settings.feed_topic.as_deref().filter(|topic| !topics::is_known(topic)) {
+        return Err(invalid(format!("Unknown topic '{}'; see GET /api/topics", topic)));
+    }
+    if let Some(source) = &settings.source {
+        if let Some(feed) = source.feed.as_deref().filter(|feed| Feed::parse(feed).is_none()) {
+            return Err(invalid(format!("Unknown story list '{}'; use top, new, best, ask, show or job", feed)));
+        }
+        if let Some(topic) = source.topic.as_deref().filter(|topic| !topics::is_known(topic)) {
+            return Err(invalid(format!("Unknown topic '{}'; see GET /api/topics", topic)));
+        }
+        if source.limit.is_some_and(|limit| limit == 0 || limit > schedules::MAX_STORIES) {
+            return Err(invalid(format!("limit must be between 1 and {}", schedules::MAX_STORIES)));
+        }
+    }
+    Ok(tz)
+}
+
+// Admin only: creates the schedule (201) or replaces the one with that name (200). It's picked up
+// at the next check.
+async fn save_schedule(
+    headers: HeaderMap,
+    Json(payload): Json<ScheduleRequest>,
+) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let tz = validate_schedule(&payload)?;
+    let schedule = archive::Schedule {
+        name: payload.name,
+        cron: payload.cron.trim().to_string(),
+        timezone: tz.name().to_string(),
+        enabled: payload.enabled.unwrap_or(true),
+        settings: payload.settings,
+        updated_at: Some(chrono::Utc::now()),
+    };
+    let before = schedules::get(&schedule.name).await;
+    archive::save_schedule(&schedule).await.map_err(archive_error)?;
+    info!("Schedule {} is now '{}' in {}", schedule.name, schedule.cron, schedule.timezone);
+    let (action, status) = if before.is_some() { ("schedule.update", StatusCode::OK) } else { ("schedule.create", StatusCode::CREATED) };
+    audit(&headers, action, &schedule.name, before.map(|before| serde_json::json!(before)), Some(serde_json::json!(schedule))).await;
+    Ok((status, AxumJson(schedules::describe(&schedule))))
+}
+
+// Admin only. A SCHEDULE_CRON schedule can only be turned off in the environment.
+async fn delete_schedule(headers: HeaderMap, Path(name): Path<String>) -> Result<StatusCode, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let before = schedules::get(&name).await;
+    match archive::delete_schedule(&name).await.map_err(archive_error)? {
+        true => {
+            info!("Schedule {} deleted", name);
+            audit(&headers, "schedule.delete", &name, before.map(|before| serde_json::json!(before)), None).await;
+            Ok(StatusCode::NO_CONTENT)
+        }
+        false => Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("No saved schedule named '{}'", name) }))),
+    }
+}
+
+// New structs for the generate endpoint
+#[derive(Debug, Deserialize)]
+struct LangChainMessage {
+    content: String,
+    role: String,
+}
+
+
+#[derive(Debug, Deserialize)]
+struct GenerateRequest {
+    chat_history: Vec<LangChainMessage>,
+    persona: Option<String>,
+    scope: Option<String>,
+    max_tokens: Option<u32>,
+    target_minutes: Option<u32>,
+    #[serde(default)]
+    tools: HashMap<String, bool>,
+    // Finish the generation (and record the chat) even if the client disconnects
+    persist: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TTSRequest {
+    text: String,
+    // Gemini prebuilt voice name or a registered custom voice (default: the persona's custom
+    // voice, then TTS_VOICE or "Kore")
+    voice: Option<String>,
+    persona: Option<String>,
+    // Splice configured sponsor/community announcements into the audio (default: true)
+    include_announcements: Option<bool>,
+    // Trim leading/trailing silence and cap pauses at AUDIO_MAX_GAP_SECS (default: AUDIO_TRIM_SILENCE)
+    trim_silence: Option<bool>,
+    // Used to name the downloaded file, e.g. "s01e05-some-title.wav"
+    title: Option<String>,
+    season: Option<u32>,
+    episode_number: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamingResponse {
+    r#type: String,
+    content: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<serde_json::Value>,
+}
+
+// SSE_HEARTBEAT_SECS (default 15): besides showing clients the server is alive, the periodic
+// write is what surfaces dead connections so their streams get dropped
+fn sse_heartbeat_interval() -> std::time::Duration {
+    let secs = env::var("SSE_HEARTBEAT_SECS").ok().and_then(|v| v.parse().ok()).filter(|s| *s > 0).unwrap_or(15);
+    std::time::Duration::from_secs(secs)
+}
+
+fn heartbeat_event() -> Event {
+    Event::default()
+        .event("heartbeat")
+        .data(serde_json::json!({ "type": "heartbeat", "server_time": chrono::Utc::now() }).to_string())
+}
+
+// Generate streaming endpoint
+async fn generate_stream(
+    Json(payload): Json<GenerateRequest>
+) -> Result<Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>>, (StatusCode, AxumJson<ApiError>)> {
+    let persona = personas::resolve(payload.persona.clone()).map_err(|valid| unknown_persona(payload.persona.as_deref(), &valid))?;
+    let tools = config::upstream_tools(&payload.tools).map_err(invalid_tools)?;
+    let scope = payload.scope.unwrap_or_else(|| "internal".to_string());
+    let max_tokens = script::max_tokens(payload.max_tokens, payload.target_minutes);
+    charge(usage::Metric::Generations, 1).await?;
+    if usage::sandbox() {
+        let final_response = serde_json::json!({ "type": "final_response", "content": sandbox::SCRIPT, "sandbox": true });
+        let events = [Event::default().data(final_response.to_string()), Event::default().data("[DONE]")];
+        return Ok(Sse::new(futures::StreamExt::boxed(futures::stream::iter(events.map(Ok)))).keep_alive(KeepAlive::default()));
+    }
+
+    // Read podcast prompt as system message
+    let system_prompt: &str = include_str!("prompt.md");
+
+    // Convert user messages and add system prompt for podcast generation
+    let mut chat_history = vec![chats::ChatMessage::new("system", system_prompt)];
+    
+    // Add user messages but modify them to focus on podcast generation
+    for msg in payload.chat_history {
+        if msg.role == "user" {
+            let podcast_content = format!(
+                "Generate a podcast episode using the available context from my workspace. User request: {}. Automatically retrieve relevant context and produce the full podcast script per the instructions.",
+                msg.content
+            );
+            chat_history.push(chats::ChatMessage::new("user", podcast_content));
+        } else {
+            chat_history.push(chats::ChatMessage::new(&msg.role, msg.content));
+        }
+    }
+
+    let adapter = upstream::adapter();
+    let client = reqwest::Client::new();
+    let request = adapter
+        .request(&client, &upstream::StreamRequest { chat_history: &chat_history, persona: &persona, scope: &scope, max_tokens, tools: &tools })
+        .map_err(|error| (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error })))?;
+
+    info!("Sending streaming request to {} with {} message(s)", adapter.name(), chat_history.len());
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Generate stream request failed: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                AxumJson(ApiError { error: "Upstream request failed".to_string() })
+            )
+        })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        error!("Upstream API returned error: {} - {}", status, redact::body(&error_text));
+        return Err((
+            StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
+            AxumJson(ApiError { error: format!("Upstream API error: {}", error_text) })
+        ));
+    }
+
+    // The upstream is read in a task so heartbeats keep flowing while it generates. When the client
+    // goes away the task stops and drops the upstream request, unless the caller asked for the
+    // result to be kept (`persist`), in which case it finishes so the chat is still recorded.
+    let persist = payload.persist.unwrap_or(false);
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Event>(64);
+    tokio::spawn(credentials::scope(credentials::current(), usage::scope(usage::current(), async move {
+        // A closed channel only matters when nobody asked for the result to be kept
+        macro_rules! send {
+            ($event:expr) => {
+                if tx.send($event).await.is_err() && !persist {
+                    info!("Stream client disconnected, dropping the rest of the generation");
+                    return;
+                }
+            };
+        }
+
+        // Remember the conversation so it can be continued via /api/chat/:chat_id/continue
+        let mut final_text = String::new();
+        let mut streamed_text = String::new();
+        let mut chat_id: Option<String> = None;
+
+        // Lines are handled as they arrive so tokens reach the client while the model is still
+        // generating
+        use futures::StreamExt;
+        let mut body = response.bytes_stream();
+        let mut buffer = upstream::LineBuffer::default();
+        let mut received = 0usize;
+        'read: loop {
+            let chunk = tokio::select! {
+                chunk = body.next() => chunk,
+                _ = tx.closed(), if !persist => {
+                    info!("Stream client disconnected, cancelling upstream generation");
+                    return;
+                }
+            };
+            let (lines, finished) = match chunk {
+                Some(Ok(bytes)) => {
+                    received += bytes.len();
+                    (buffer.push(&bytes), false)
+                }
+                Some(Err(e)) => {
+                    error!("Failed to read response stream: {}", e);
+                    send!(Event::default().data(serde_json::to_string(&StreamingResponse {
+                        r#type: "error".to_string(),
+                        content: serde_json::json!("Failed to read response"),
+                        icon: None,
+                        error: None,
+                    }).unwrap_or_default()));
+                    return;
+                }
+                None => {
+                    info!("Finished reading {} bytes from {}", received, adapter.name());
+                    (buffer.finish().into_iter().collect(), true)
+                }
+            };
+
+            for line in lines {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                match adapter.parse_line(line) {
+                    Ok(None) => continue,
+                    Ok(Some(upstream::StreamEvent::Done)) => break 'read,
+                    Ok(Some(upstream::StreamEvent::Message(json_data))) => {
+                        // Tool calls and retrieved sources go out as typed tool_call/source_retrieved events
+                        if let Some(tool_events) = upstream::tool_events(&json_data) {
+                            for event in tool_events {
+                                info!("🔧 Tool usage: {}", redact::body(&serde_json::to_string(&event).unwrap_or_default()));
+                                send!(Event::default().data(serde_json::to_string(&event).unwrap_or_default()));
+                            }
+                            continue;
+                        }
+
+                        // Extract and display only the content from specific message types
+                        if let Some(message_type) = json_data.get("type").and_then(|t| t.as_str()) {
+                            match message_type {
+                                "thinking_update" => {
+                                    if let Some(content) = json_data.get("content") {
+                                        let content_str = if content.is_string() {
+                                            content.as_str().unwrap_or("").to_string()
                                         } else {
                                             serde_json::to_string(content).unwrap_or_default()
                                         };
-                                        info!("🤔 Thinking: {}", content_str);
-                                        yield Ok(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
+                                        info!("🤔 Thinking: {}", redact::body(&content_str));
+                                        send!(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
+                                    }
+                                }
+                                // Token-streaming providers; the full text goes out as a final_response at the end
+                                "delta" => {
+                                    if let Some(content) = json_data.get("content").and_then(|c| c.as_str()) {
+                                        streamed_text.push_str(content);
                                     }
+                                    send!(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
                                 }
                                 "final_response" => {
+                                    let mut json_data = json_data;
+                                    if let Some(text) = json_data.get("content").and_then(|c| c.as_str()) {
+                                        let (text, length) = script::enforce_length(text, max_tokens);
+                                        if length.truncated {
+                                            info!("Truncated streamed script to {} tokens", max_tokens);
+                                            json_data["content"] = serde_json::Value::String(text);
+                                            json_data["truncated"] = serde_json::Value::Bool(true);
+                                        }
+                                    }
                                     if let Some(content) = json_data.get("content") {
                                         let content_str = if content.is_string() {
                                             content.as_str().unwrap_or("").to_string()
                                         } else {
                                             serde_json::to_string(content).unwrap_or_default()
                                         };
-                                        info!("💬 Response: {}", content_str);
-                                        yield Ok(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
+                                        info!("💬 Response: {}", redact::body(&content_str));
+                                        final_text.push_str(&content_str);
+                                        send!(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
                                     }
                                 }
                                 "metadata" => {
                                     if let Some(content) = json_data.get("content") {
-                                        info!("📊 Metadata: {}", serde_json::to_string(content).unwrap_or_default());
-                                        yield Ok(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
+                                        info!("📊 Metadata: {}", redact::json(content));
+                                        if let Some(id) = content.get("chatId").and_then(|c| c.as_str()) {
+                                            chat_id = Some(id.to_string());
+                                        }
+                                        send!(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
                                     }
                                 }
                                 _ => {
                                     // Forward other types as-is but log them
-                                    info!("📤 Other message type '{}': {}", message_type, serde_json::to_string(&json_data).unwrap_or_default());
-                                    yield Ok(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
+                                    info!("📤 Other message type '{}': {}", message_type, redact::json(&json_data));
+                                    send!(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
                                 }
                             }
                         } else {
                             // Forward messages without type as-is
-                            yield Ok(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
+                            send!(Event::default().data(serde_json::to_string(&json_data).unwrap_or_default()));
                         }
                     }
                     Err(e) => {
-                        error!("Failed to parse JSON from stream: {} - Data: {}", e, data);
+                        error!("Failed to parse {} stream data: {} - Data: {}", adapter.name(), e, redact::body(line));
                         // Send error as thinking update
                         let error_response = StreamingResponse {
                             r#type: "thinking_update".to_string(),
@@ -752,167 +3004,1756 @@ async fn generate_stream(
                             icon: None,
                             error: None,
                         };
-                        yield Ok(Event::default().data(serde_json::to_string(&error_response).unwrap_or_default()));
+                        send!(Event::default().data(serde_json::to_string(&error_response).unwrap_or_default()));
                     }
                 }
             }
+            if finished {
+                break;
+            }
+        }
+
+        if final_text.is_empty() && !streamed_text.is_empty() {
+            let (text, length) = script::enforce_length(&streamed_text, max_tokens);
+            if length.truncated {
+                info!("Truncated streamed script to {} tokens", max_tokens);
+            }
+            final_text = text;
+            info!("💬 Response: {}", redact::body(&final_text));
+            let final_response = serde_json::json!({
+                "type": "final_response",
+                "content": final_text,
+                "truncated": length.truncated,
+            });
+            send!(Event::default().data(final_response.to_string()));
+        }
+
+        usage::record(usage::Metric::Tokens, generation_tokens(&chat_history, &final_text)).await;
+        if let Some(chat_id) = chat_id {
+            let mut messages = chat_history;
+            messages.push(chats::ChatMessage::new("assistant", final_text));
+            chats::record(&chat_id, &persona, &scope, None, messages);
+        }
+
+        // Send completion signal
+        let _ = tx.send(Event::default().data("[DONE]")).await;
+    })));
+
+    let stream = async_stream::stream! {
+        let mut heartbeat = tokio::time::interval(sse_heartbeat_interval());
+        heartbeat.tick().await;
+        loop {
+            tokio::select! {
+                event = rx.recv() => match event {
+                    Some(event) => yield Ok::<_, std::convert::Infallible>(event),
+                    None => break,
+                },
+                _ = heartbeat.tick() => yield Ok(heartbeat_event()),
+            }
+        }
+    };
+
+    Ok(Sse::new(futures::StreamExt::boxed(stream)).keep_alive(KeepAlive::default()))
+}
+
+// --- Speaking-time estimate ---
+#[derive(Debug, Deserialize)]
+struct EstimateRequest {
+    // Either a script or an archived episode whose script to estimate
+    script: Option<String>,
+    episode_id: Option<String>,
+    // Defaults to the voice the episode's audio would be rendered with
+    voice: Option<String>,
+    // Overrides the voice's configured words per minute
+    wpm: Option<f64>,
+}
+
+async fn estimate_script(
+    Json(payload): Json<EstimateRequest>
+) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let bad_request = |error: &str| (StatusCode::BAD_REQUEST, AxumJson(ApiError { error: error.to_string() }));
+    let (text, persona) = match (payload.script, &payload.episode_id) {
+        (Some(text), None) => (text, None),
+        (None, Some(id)) => {
+            let episode = archive::get(id)
+                .await
+                .map_err(archive_error)?
+                .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+            (episode.script.unwrap_or_default(), Some(episode.persona))
+        }
+        _ => return Err(bad_request("Provide either script or episode_id")),
+    };
+    if text.trim().is_empty() {
+        return Err(bad_request(if payload.episode_id.is_some() { "The episode has no script" } else { "script must not be empty" }));
+    }
+
+    let voice = payload
+        .voice
+        .or_else(|| persona.as_deref().and_then(voices::for_persona).map(|v| v.name))
+        .unwrap_or_else(config::default_tts_voice);
+    let wpm = match payload.wpm {
+        Some(wpm) if wpm > 0.0 => wpm,
+        Some(_) => return Err(bad_request("wpm must be positive")),
+        None => script::voice_wpm(&voice),
+    };
+
+    let estimate = script::estimate(&text, wpm);
+    Ok(AxumJson(serde_json::json!({
+        "episode_id": payload.episode_id,
+        "voice": voice,
+        "wpm": estimate.wpm,
+        "total_words": estimate.total_words,
+        "estimated_secs": estimate.estimated_secs,
+        "segments": estimate.segments,
+    })))
+}
+
+// --- Chat continuation ---
+#[derive(Debug, Deserialize)]
+struct ChatContinueRequest {
+    message: String,
+}
+
+async fn get_chat(Path(chat_id): Path<String>) -> Result<AxumJson<chats::ChatSession>, (StatusCode, AxumJson<ApiError>)> {
+    chats::get(&chat_id).map(AxumJson).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            AxumJson(ApiError { error: format!("Chat {} not found", chat_id) })
+        )
+    })
+}
+
+async fn continue_chat(
+    Path(chat_id): Path<String>,
+    Json(payload): Json<ChatContinueRequest>
+) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    if payload.message.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            AxumJson(ApiError { error: "message must not be empty".to_string() })
+        ));
+    }
+
+    let session = chats::get(&chat_id).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            AxumJson(ApiError { error: format!("Chat {} not found", chat_id) })
+        )
+    })?;
+
+    if usage::sandbox() {
+        return Ok(AxumJson(serde_json::json!({
+            "chat_id": chat_id,
+            "response": sandbox::SCRIPT,
+            "turns": session.messages.len(),
+            "sandbox": true,
+            "status": "success"
+        })));
+    }
+    let api_key = credentials::require("ALCHEMYST_API_KEY").map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            AxumJson(ApiError { error })
+        )
+    })?;
+    charge(usage::Metric::Generations, 1).await?;
+
+    let user_message = chats::ChatMessage::new("user", payload.message);
+    let mut chat_history = session.messages.clone();
+    chat_history.push(user_message.clone());
+
+    let body = serde_json::json!({
+        "chatId": chat_id,
+        "chat_history": chat_history,
+        "persona": session.persona,
+        "scope": session.scope,
+        "stream": false,
+        "tools": config::upstream_tools(&HashMap::new()).unwrap_or_default(),
+    });
+
+    let url = format!("{}/api/v1/chat/generate", config::alchemyst_api_url());
+    let resp = reqwest::Client::new()
+        .post(&url)
+        .query(&[("chatId", &chat_id)])
+        .header("Authorization", format!("Bearer {}", api_key))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Chat continuation request failed: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                AxumJson(ApiError { error: "Upstream request failed".to_string() })
+            )
+        })?;
+
+    let status = resp.status();
+    let response_text = resp.text().await.map_err(|e| {
+        error!("Failed to read upstream response text: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            AxumJson(ApiError { error: "Failed to read upstream response".to_string() })
+        )
+    })?;
+
+    if !status.is_success() {
+        error!("Upstream returned error status: {} body: {}", status, redact::body(&response_text));
+        return Err((
+            StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
+            AxumJson(ApiError { error: format!("Chat continuation failed: {}", response_text) })
+        ));
+    }
+
+    let value: serde_json::Value = serde_json::from_str(&response_text).map_err(|e| {
+        error!("Failed to parse upstream response as JSON: {}. Raw response: {}", e, redact::body(&response_text));
+        (
+            StatusCode::BAD_GATEWAY,
+            AxumJson(ApiError { error: "Upstream returned an unexpected response".to_string() })
+        )
+    })?;
+
+    let content = chats::extract_content(&value).ok_or_else(|| {
+        error!("No content in chat continuation response: {}", redact::json(&value));
+        (
+            StatusCode::BAD_GATEWAY,
+            AxumJson(ApiError { error: "Upstream returned no content".to_string() })
+        )
+    })?;
+
+    usage::record(usage::Metric::Tokens, generation_tokens(&chat_history, &chats::content_text(content))).await;
+    chats::append(&chat_id, vec![user_message, chats::ChatMessage::new("assistant", chats::content_text(content))]);
+
+    Ok(AxumJson(serde_json::json!({
+        "chat_id": chat_id,
+        "response": content,
+        "turns": chats::get(&chat_id).map(|s| s.messages.len()).unwrap_or_default(),
+        "status": "success"
+    })))
+}
+
+// --- Script refinement ---
+#[derive(Debug, Deserialize)]
+struct RefineRequest {
+    // e.g. "make it 30% shorter" or "less jargon"
+    instruction: String,
+}
+
+const MAX_INSTRUCTION_CHARS: usize = 500;
+
+// Reruns generation with the episode's current script and the instruction as context and saves the
+// result as the episode's next script version. Rendered audio is left alone until it's re-rendered
+async fn refine_episode(
+    Path(id): Path<String>,
+    Json(payload): Json<RefineRequest>
+) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
+    let api_key = credentials::require("ALCHEMYST_API_KEY").map_err(|error| {
+        (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error }))
+    })?;
+    sandbox_forbidden()?;
+    let instruction = payload.instruction.trim();
+    if instruction.is_empty() || instruction.chars().count() > MAX_INSTRUCTION_CHARS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            AxumJson(ApiError { error: format!("instruction must be 1 to {} characters", MAX_INSTRUCTION_CHARS) })
+        ));
+    }
+    let episode = archive::get(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    let current = episode.script.clone().unwrap_or_default();
+    if current.trim().is_empty() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, AxumJson(ApiError { error: format!("Episode {} has no script", id) })));
+    }
+    charge(usage::Metric::Generations, 1).await?;
+
+    let chat_history = vec![chats::ChatMessage::new("user", format!(
+        "Here is the current script of the podcast episode \"{}\":\n\n{}\n\nRevise it following this instruction: {}\n\nReply with the complete revised script only, in the same format.",
+        episode.title, current, instruction
+    ))];
+    let body = serde_json::json!({
+        "chat_history": chat_history,
+        "persona": episode.persona,
+        "scope": "internal",
+        "stream": false,
+        "max_tokens": script::max_tokens(None, None).max(script::estimate_tokens(&current) as u32 * 2),
+    });
+
+    let url = format!("{}/api/v1/chat/generate", config::alchemyst_api_url());
+    let resp = reqwest::Client::new()
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Refinement request failed: {}", e);
+            (StatusCode::BAD_GATEWAY, AxumJson(ApiError { error: "Upstream request failed".to_string() }))
+        })?;
+    let status = resp.status();
+    let response_text = resp.text().await.map_err(|e| {
+        error!("Failed to read upstream response text: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error: "Failed to read upstream response".to_string() }))
+    })?;
+    if !status.is_success() {
+        error!("Upstream returned error status: {} body: {}", status, redact::body(&response_text));
+        return Err((
+            StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
+            AxumJson(ApiError { error: format!("Refinement failed: {}", response_text) })
+        ));
+    }
+    let value: serde_json::Value = serde_json::from_str(&response_text).map_err(|e| {
+        error!("Failed to parse upstream response as JSON: {}. Raw response: {}", e, redact::body(&response_text));
+        (StatusCode::BAD_GATEWAY, AxumJson(ApiError { error: "Upstream returned an unexpected response".to_string() }))
+    })?;
+    let refined = chats::extract_content(&value).map(chats::content_text).unwrap_or_default().trim().to_string();
+    if refined.is_empty() {
+        error!("No content in refinement response: {}", redact::json(&value));
+        return Err((StatusCode::BAD_GATEWAY, AxumJson(ApiError { error: "Upstream returned no content".to_string() })));
+    }
+    usage::record(usage::Metric::Tokens, generation_tokens(&chat_history, &refined)).await;
+
+    let version = archive::add_version(&id, &refined, instruction)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    info!("Refined episode {} into version {} (\"{}\")", id, version.version, instruction);
+    Ok((StatusCode::CREATED, AxumJson(serde_json::json!({
+        "episode_id": id,
+        "version": version,
+        "diff": diff::lines(&current, &refined),
+        // The audio still speaks the previous version until it's rendered again
+        "audio_outdated": episode.audio.is_some(),
+    }))))
+}
+
+// --- Follow-up episodes ---
+#[derive(Debug, Deserialize, Default)]
+struct FollowUpRequest {
+    // Defaults to the original episode's
+    persona: Option<String>,
+    // Default FOLLOW_UP_MINUTES
+    target_minutes: Option<u32>,
+    publish_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+const FOLLOW_UP_MINUTES: u32 = 3;
+// Newest comments per story handed to the script, each cut to FOLLOW_UP_COMMENT_CHARS
+const FOLLOW_UP_MAX_COMMENTS: usize = 40;
+const FOLLOW_UP_COMMENT_CHARS: usize = 600;
+
+// Generates a short update on an archived episode from only the discussion its stories got since
+// it was generated, archived as a new episode linked to the original (follow_up_of/follow_ups,
+// and a lead line in the show notes the feeds use)
+async fn follow_up_episode(
+    Path(id): Path<String>,
+    payload: Option<Json<FollowUpRequest>>,
+) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
+    sandbox_forbidden()?;
+    let Json(payload) = payload.unwrap_or_default();
+    let episode = archive::get(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    if episode.story_ids.is_empty() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, AxumJson(ApiError { error: format!("Episode {} has no stories to follow up on", id) })));
+    }
+
+    let since = episode.created_at.timestamp().max(0) as u64;
+    let anonymize = privacy::enabled(None);
+    let client = get_hn_client();
+    let mut discussions = Vec::new();
+    let mut reference_urls = Vec::new();
+    let mut new_comments = 0;
+    for story_id in &episode.story_ids {
+        let story = client.get_story(*story_id).await.map_err(|e| {
+            (StatusCode::BAD_GATEWAY, AxumJson(ApiError { error: format!("Failed to fetch story {}: {}", story_id, e) }))
+        })?;
+        let (mut comments, _) = client.get_comments_since(&story, since, true).await;
+        if comments.is_empty() {
+            continue;
+        }
+        if anonymize {
+            privacy::register(story.by.iter().chain(comments.iter().filter_map(|c| c.by.as_ref())).map(String::as_str));
+        }
+        comments.reverse();
+        let texts = comments.into_iter().filter_map(|c| c.text).map(|text| analysis::strip_html(&text)).collect();
+        let (texts, _) = quality::filter(texts).await;
+        if texts.is_empty() {
+            continue;
+        }
+        new_comments += texts.len();
+        let lines: Vec<String> = texts
+            .iter()
+            .take(FOLLOW_UP_MAX_COMMENTS)
+            .map(|text| {
+                let text = if anonymize { privacy::anonymize(text) } else { text.clone() };
+                format!("- {}", text.chars().take(FOLLOW_UP_COMMENT_CHARS).collect::<String>())
+            })
+            .collect();
+        let title = story.title.as_deref().unwrap_or("this story");
+        discussions.push(format!(
+            "New comments on \"{}\" ({} points, {} comments in total now):\n{}",
+            title,
+            story.score.unwrap_or(0),
+            story.descendants.unwrap_or(0),
+            lines.join("\n")
+        ));
+        reference_urls.push(format!("https://news.ycombinator.com/item?id={}", story_id));
+    }
+    if discussions.is_empty() {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            AxumJson(ApiError { error: format!("No new discussion on episode {}'s stories since {}", id, episode.created_at.to_rfc3339()) }),
+        ));
+    }
+
+    let date = episode.published_at().format("%B %-d, %Y");
+    let mut source_notes = vec![format!(
+        "This is a short follow-up to the episode \"{}\" from {}. Cover only the discussion below, which is new since then: what changed, \
+         new arguments and corrections. Don't repeat what the original episode covered beyond a one-sentence reminder.",
+        episode.title, date
+    )];
+    source_notes.extend(discussions);
+    info!("Generating a follow-up to episode {} from {} new comment(s)", id, new_comments);
+    let (_, AxumJson(mut result)) = generate_podcast(Json(PodcastGenerationRequest {
+        persona: payload.persona.or(Some(episode.persona.clone())),
+        title: Some(format!("Follow-up: {}", episode.title)),
+        season: episode.season,
+        target_minutes: Some(payload.target_minutes.unwrap_or(FOLLOW_UP_MINUTES)),
+        reference_urls,
+        source_notes,
+        story_ids: episode.story_ids.clone(),
+        topics: episode.topics.clone(),
+        publish_at: payload.publish_at,
+        follow_up_of: Some(episode.id.clone()),
+        lead_notes: Some(format!("Follow-up to episode {}, \"{}\" ({}): what's new in the discussion since.", episode.episode_number, episode.title, date)),
+        ..Default::default()
+    }))
+    .await?;
+    result["follow_up_of"] = serde_json::json!(episode.id);
+    result["new_comments"] = serde_json::json!(new_comments);
+    Ok((StatusCode::CREATED, AxumJson(result)))
+}
+
+fn episode_versions_error(id: &str) -> (StatusCode, AxumJson<ApiError>) {
+    (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) }))
+}
+
+// Script versions of the episode, oldest first, without their scripts
+async fn list_episode_versions(Path(id): Path<String>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let versions = archive::versions(&id).await.map_err(archive_error)?.ok_or_else(|| episode_versions_error(&id))?;
+    let versions: Vec<serde_json::Value> = versions
+        .iter()
+        .map(|v| serde_json::json!({ "version": v.version, "instruction": v.instruction, "created_at": v.created_at, "words": script::spoken_words(&v.script) }))
+        .collect();
+    Ok(AxumJson(serde_json::json!({ "episode_id": id, "versions": versions })))
+}
+
+// One version's script and its line diff against the version before it
+async fn get_episode_version(Path((id, number)): Path<(String, u32)>) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let versions = archive::versions(&id).await.map_err(archive_error)?.ok_or_else(|| episode_versions_error(&id))?;
+    let index = versions.iter().position(|v| v.version == number).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} has no version {}", id, number) }))
+    })?;
+    let previous = index.checked_sub(1).map(|i| versions[i].script.as_str()).unwrap_or_default();
+    let version = &versions[index];
+    Ok(AxumJson(serde_json::json!({
+        "episode_id": id,
+        "version": version,
+        "diff": diff::lines(previous, &version.script),
+    })))
+}
+
+// Structured comparison of two versions, either way round
+async fn diff_episode_versions(
+    Path((id, a, b)): Path<(String, u32, u32)>,
+) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    let versions = archive::versions(&id).await.map_err(archive_error)?.ok_or_else(|| episode_versions_error(&id))?;
+    let script = |number: u32| {
+        versions.iter().find(|v| v.version == number).map(|v| v.script.as_str()).ok_or_else(|| {
+            (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} has no version {}", id, number) }))
+        })
+    };
+    let comparison = diff::compare(script(a)?, script(b)?);
+    Ok(AxumJson(serde_json::json!({
+        "episode_id": id,
+        "from": a,
+        "to": b,
+        "diff": comparison,
+    })))
+}
+
+// --- Whisper re-transcription ---
+async fn transcribe_audio(
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, (StatusCode, AxumJson<ApiError>)> {
+    if body.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            AxumJson(ApiError { error: "Request body must contain the episode audio".to_string() })
+        ));
+    }
+    if !transcribe::is_configured() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            AxumJson(ApiError { error: "Transcription is not configured (OPENAI_API_KEY or WHISPER_PROVIDER=local)".to_string() })
+        ));
+    }
+
+    let content_type = headers
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("audio/wav")
+        .to_string();
+    let transcript = transcribe::transcribe(body.to_vec(), &content_type).await.map_err(|e| {
+        error!("Transcription failed: {}", e);
+        (StatusCode::BAD_GATEWAY, AxumJson(ApiError { error: e }))
+    })?;
+
+    let response = if params.get("format").map(String::as_str) == Some("vtt") {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "text/vtt; charset=utf-8")
+            .body(Body::from(transcribe::to_vtt(&transcript)))
+    } else {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&transcript).unwrap_or_default()))
+    };
+    response.map_err(|e| {
+        error!("Failed to build transcript response: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error: "Failed to build response".to_string() }))
+    })
+}
+
+fn create_wav_from_pcm(
+    pcm_data: &[u8], 
+    sample_rate: u32, 
+    channels: u16, 
+    bits_per_sample: u16
+) -> Result<Vec<u8>, (StatusCode, AxumJson<ApiError>)> {
+    let data_size = pcm_data.len() as u32;
+    let file_size = 36 + data_size;
+    let byte_rate = sample_rate * channels as u32 * (bits_per_sample as u32 / 8);
+    let block_align = channels * (bits_per_sample / 8);
+
+    let mut wav_data = Vec::new();
+    
+    // RIFF header
+    wav_data.extend_from_slice(b"RIFF");
+    wav_data.extend_from_slice(&file_size.to_le_bytes());
+    wav_data.extend_from_slice(b"WAVE");
+    
+    // fmt chunk
+    wav_data.extend_from_slice(b"fmt ");
+    wav_data.extend_from_slice(&16u32.to_le_bytes()); // chunk size
+    wav_data.extend_from_slice(&1u16.to_le_bytes()); // audio format (PCM)
+    wav_data.extend_from_slice(&channels.to_le_bytes());
+    wav_data.extend_from_slice(&sample_rate.to_le_bytes());
+    wav_data.extend_from_slice(&byte_rate.to_le_bytes());
+    wav_data.extend_from_slice(&block_align.to_le_bytes());
+    wav_data.extend_from_slice(&bits_per_sample.to_le_bytes());
+    
+    // data chunk
+    wav_data.extend_from_slice(b"data");
+    wav_data.extend_from_slice(&data_size.to_le_bytes());
+    wav_data.extend_from_slice(pcm_data);
+    
+    Ok(wav_data)
+}
+
+// Pipeline audio format (raw PCM); every tts::AudioSynthesizer returns this
+const TTS_SAMPLE_RATE: u32 = tts::SAMPLE_RATE;
+const TTS_CHANNELS: u16 = 1;
+const TTS_BITS_PER_SAMPLE: u16 = 16;
+
+async fn synthesize_pcm(text: &str, voice: &str) -> Result<Vec<u8>, (StatusCode, AxumJson<ApiError>)> {
+    tts::synthesizer_for(voice).synthesize(text, voice).await.map_err(|e| {
+        (StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error: e }))
+    })
+}
+
+fn tts_max_attempts() -> u32 {
+    env::var("TTS_MAX_ATTEMPTS").ok().and_then(|v| v.parse().ok()).filter(|&n| n > 0).unwrap_or(2)
+}
+
+async fn synthesize_with_retries(text: &str, voice: &str) -> Result<Vec<u8>, (StatusCode, AxumJson<ApiError>)> {
+    let attempts = tts_max_attempts();
+    let mut attempt = 1;
+    loop {
+        match synthesize_pcm(text, voice).await {
+            Err((_, AxumJson(e))) if attempt < attempts => {
+                error!("TTS failed (attempt {}/{}): {}", attempt, attempts, e.error);
+                attempt += 1;
+                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+// Scripts are synthesized one story section ("## " heading, as in segmented digests) at a time,
+// each retried up to TTS_MAX_ATTEMPTS times (default 2). A story section that still fails is left
+// out and reported instead of failing the whole rendering; the opening before the first heading
+// can't be skipped.
+async fn synthesize_script_pcm(text: &str, voice: &str) -> Result<(Vec<u8>, Vec<segments::Omission>), (StatusCode, AxumJson<ApiError>)> {
+    let mut sections: Vec<(Option<String>, String)> = vec![(None, String::new())];
+    for line in text.lines() {
+        match line.trim_start().strip_prefix("## ") {
+            Some(heading) => sections.push((Some(heading.trim().to_string()), format!("{}\n", line))),
+            None => {
+                let body = &mut sections.last_mut().unwrap().1;
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+    }
+
+    let mut pcm = Vec::new();
+    let mut omitted = Vec::new();
+    for (heading, section) in sections {
+        if section.trim().is_empty() {
+            continue;
+        }
+        match (synthesize_with_retries(section.trim(), voice).await, heading) {
+            (Ok(section_pcm), _) => pcm.extend_from_slice(&section_pcm),
+            (Err((_, AxumJson(e))), Some(title)) => {
+                error!("Leaving \"{}\" out of the audio: {}", title, e.error);
+                omitted.push(segments::Omission { story_id: None, title, error: e.error });
+            }
+            (Err(e), None) => return Err(e),
+        }
+    }
+    if pcm.is_empty() && !omitted.is_empty() {
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, AxumJson(ApiError { error: "No part of the script could be synthesized".to_string() })));
+    }
+    Ok((pcm, omitted))
+}
+
+fn pcm_duration_secs(pcm_len: usize) -> f64 {
+    let bytes_per_sec = TTS_SAMPLE_RATE as f64 * TTS_CHANNELS as f64 * (TTS_BITS_PER_SAMPLE as f64 / 8.0);
+    pcm_len as f64 / bytes_per_sec
+}
+
+async fn announcement_pcm(announcement: &announcements::Announcement, voice: &str) -> Result<Vec<u8>, (StatusCode, AxumJson<ApiError>)> {
+    if let Some(path) = &announcement.audio_path {
+        return announcements::read_wav_pcm(path, TTS_SAMPLE_RATE, TTS_CHANNELS, TTS_BITS_PER_SAMPLE).map_err(|e| {
+            error!("Failed to load announcement audio '{}': {}", announcement.title, e);
+            (StatusCode::INTERNAL_SERVER_ERROR,
+             AxumJson(ApiError { error: format!("Failed to load announcement '{}'", announcement.title) }))
+        });
+    }
+    synthesize_pcm(announcement.text.as_deref().unwrap_or_default(), voice).await
+}
+
+// Synthesize the script in parts and splice announcements in between, recording chapter markers
+async fn assemble_episode_pcm(
+    text: &str,
+    announcements: &[announcements::Announcement],
+    voice: &str,
+    max_gap_secs: Option<f64>,
+) -> Result<RenderedScript, (StatusCode, AxumJson<ApiError>)> {
+    use announcements::{Chapter, ChapterKind, Placement};
+
+    enum Segment<'a> {
+        Script(&'static str, String),
+        Announcement(&'a announcements::Announcement),
+    }
+
+    let parts = announcements::split_script(text);
+    let after_intro: Vec<_> = announcements.iter().filter(|a| a.placement == Placement::AfterIntro).collect();
+    let mid_roll: Vec<_> = announcements.iter().filter(|a| a.placement == Placement::MidRoll).collect();
+
+    // Only split the script where something is actually inserted
+    let mut segments = Vec::new();
+    let mut pending = String::new();
+    if !after_intro.is_empty() && !parts.intro.is_empty() {
+        segments.push(Segment::Script("Intro", parts.intro));
+        segments.extend(after_intro.into_iter().map(Segment::Announcement));
+    } else {
+        pending = parts.intro;
+    }
+    pending = format!("{}\n\n{}", pending, parts.first_half).trim().to_string();
+    if !mid_roll.is_empty() && !parts.second_half.is_empty() {
+        segments.push(Segment::Script("Episode", pending));
+        segments.extend(mid_roll.into_iter().map(Segment::Announcement));
+        segments.push(Segment::Script("Episode (continued)", parts.second_half));
+    } else {
+        segments.push(Segment::Script("Episode", format!("{}\n\n{}", pending, parts.second_half).trim().to_string()));
+    }
+
+    let mut pcm = Vec::new();
+    let mut chapters = Vec::new();
+    let mut omitted = Vec::new();
+
+    for segment in segments {
+        let (chapter, segment_pcm) = match segment {
+            Segment::Script(title, section_text) => {
+                if section_text.is_empty() {
+                    continue;
+                }
+                let (section_pcm, section_omitted) = synthesize_script_pcm(&section_text, voice).await?;
+                omitted.extend(section_omitted);
+                ((title.to_string(), ChapterKind::Content, false), section_pcm)
+            }
+            Segment::Announcement(announcement) => (
+                (announcement.title.clone(), ChapterKind::Announcement, announcement.audio_path.is_some()),
+                announcement_pcm(announcement, voice).await?,
+            ),
+        };
+
+        // When trimming, segments are joined with a uniform pause instead of whatever silence TTS left
+        let segment_pcm = match max_gap_secs {
+            Some(max_gap) => {
+                if !pcm.is_empty() {
+                    pcm.extend_from_slice(&audio::silence(TTS_SAMPLE_RATE, max_gap));
+                }
+                audio::trim_silence(&segment_pcm, TTS_SAMPLE_RATE, max_gap)
+            }
+            None => segment_pcm,
+        };
+
+        chapters.push(Chapter { start_secs: pcm_duration_secs(pcm.len()), title: chapter.0, kind: chapter.1, prerecorded: chapter.2 });
+        pcm.extend_from_slice(&segment_pcm);
+    }
+
+    Ok((pcm, chapters, omitted))
+}
+
+// The requested voice, else the persona's custom voice, else TTS_VOICE
+fn tts_voice(requested: Option<String>, persona: Option<&str>) -> Result<String, (StatusCode, AxumJson<ApiError>)> {
+    let voice = requested
+        .or_else(|| persona.and_then(voices::for_persona).map(|v| v.name))
+        .unwrap_or_else(config::default_tts_voice);
+    if !config::GEMINI_VOICES.contains(&voice.as_str()) && voices::get(&voice).is_none() && !tts::is_provider_voice(&voice) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            AxumJson(ApiError { error: format!("Unknown voice '{}'; see GET /api/config for available voices", voice) })
+        ));
+    }
+    Ok(voice)
+}
+
+// PCM, chapter markers, and the story sections left out because they couldn't be synthesized
+type RenderedScript = (Vec<u8>, Vec<announcements::Chapter>, Vec<segments::Omission>);
+
+// A whole script as PCM, with announcements spliced in (default: on) and silence trimmed
+// (default: AUDIO_TRIM_SILENCE)
+async fn render_script_pcm(
+    text: &str,
+    voice: &str,
+    include_announcements: Option<bool>,
+    trim_silence: Option<bool>,
+) -> Result<RenderedScript, (StatusCode, AxumJson<ApiError>)> {
+    let announcements = if include_announcements.unwrap_or(true) {
+        announcements::load_announcements()
+    } else {
+        vec![]
+    };
+
+    let max_gap_secs = trim_silence
+        .unwrap_or_else(audio::trim_enabled_by_default)
+        .then(audio::max_gap_secs);
+
+    if usage::sandbox() {
+        return Ok((sandbox::silent_pcm(text, voice, TTS_SAMPLE_RATE), vec![], vec![]));
+    }
+
+    if announcements.is_empty() {
+        let (pcm, omitted) = synthesize_script_pcm(text, voice).await?;
+        Ok(match max_gap_secs {
+            Some(max_gap) => (audio::trim_silence(&pcm, TTS_SAMPLE_RATE, max_gap), vec![], omitted),
+            None => (pcm, vec![], omitted),
+        })
+    } else {
+        info!("Assembling episode audio with {} announcement(s)", announcements.len());
+        assemble_episode_pcm(text, &announcements, voice, max_gap_secs).await
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TTSQuery {
+    // Pre-rendered playback speed (1.0, 1.25 or 1.5), pitch preserved
+    speed: Option<f64>,
+}
+
+// JSON for a response header. serde_json leaves non-ASCII characters (e.g. in chapter or story
+// titles) as raw UTF-8, which header values don't reliably carry, so they're written as \u escapes;
+// the value still parses as the same JSON
+fn json_header<T: Serialize>(value: &T) -> Result<HeaderValue, axum::http::header::InvalidHeaderValue> {
+    let json = serde_json::to_string(value).unwrap_or_default();
+    let mut ascii = String::with_capacity(json.len());
+    for c in json.chars() {
+        if c.is_ascii() {
+            ascii.push(c);
+        } else {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                ascii.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    HeaderValue::from_str(&ascii)
+}
+
+async fn generate_tts(
+    Query(query): Query<TTSQuery>,
+    Json(payload): Json<TTSRequest>
+) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    let speed = query.speed.unwrap_or(1.0);
+    if !audio::SUPPORTED_SPEEDS.contains(&speed) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            AxumJson(ApiError { error: format!("Unsupported speed {}; expected one of {:?}", speed, audio::SUPPORTED_SPEEDS) })
+        ));
+    }
+
+    let voice = tts_voice(payload.voice.clone(), payload.persona.as_deref())?;
+    charge(usage::Metric::TtsCharacters, payload.text.chars().count() as u64).await?;
+    let (pcm_bytes, chapters, omitted) = render_script_pcm(&payload.text, &voice, payload.include_announcements, payload.trim_silence).await?;
+
+    let (pcm_bytes, chapters) = if speed != 1.0 {
+        info!("Rendering {}x speed variant", speed);
+        let stretched = tokio::task::spawn_blocking(move || audio::time_stretch(&pcm_bytes, TTS_SAMPLE_RATE, speed))
+            .await
+            .map_err(|e| {
+                error!("Time-stretch task failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR,
+                 AxumJson(ApiError { error: "Failed to render speed variant".to_string() }))
+            })?;
+        let chapters = chapters
+            .into_iter()
+            .map(|chapter| announcements::Chapter { start_secs: chapter.start_secs / speed, ..chapter })
+            .collect();
+        (stretched, chapters)
+    } else {
+        (pcm_bytes, chapters)
+    };
+
+    // Convert PCM to WAV format by adding WAV header
+    let wav_bytes = create_wav_from_pcm(&pcm_bytes, TTS_SAMPLE_RATE, TTS_CHANNELS, TTS_BITS_PER_SAMPLE)?;
+    
+    info!("Generated WAV data length: {}", wav_bytes.len());
+
+    let mut file_stem = match &payload.title {
+        Some(title) => episodes::episode_file_stem(&episodes::slugify(title), payload.season, payload.episode_number),
+        None => "podcast-audio".to_string(),
+    };
+    if speed != 1.0 {
+        file_stem = format!("{}-{}x", file_stem, speed);
+    }
+
+    let mut builder = Response::builder()
+        .status(200)
+        .header("Content-Type", "audio/wav")
+        .header("Content-Disposition", format!("attachment; filename=\"{}.wav\"", file_stem));
+
+    let qa = audio::analyze(&pcm_bytes, TTS_SAMPLE_RATE, &audio::QaThresholds::from_env());
+    if !qa.passed {
+        info!("Audio QA flagged {} issue(s): {}", qa.violations.len(), qa.violations.join("; "));
+    }
+    match json_header(&qa) {
+        Ok(value) => builder = builder.header("X-Audio-QA", value),
+        Err(e) => error!("Failed to encode audio QA header: {}", e),
+    }
+
+    if !omitted.is_empty() {
+        match json_header(&omitted) {
+            Ok(value) => builder = builder.header("X-Omitted-Segments", value),
+            Err(e) => error!("Failed to encode omitted segments header: {}", e),
+        }
+    }
+
+    if !chapters.is_empty() {
+        // Chapter markers travel alongside the audio
+        match json_header(&chapters) {
+            Ok(value) => builder = builder.header("X-Podcast-Chapters", value),
+            Err(e) => error!("Failed to encode chapter header: {}", e),
+        }
+    }
+
+    Ok(builder.body(Body::from(wav_bytes)).unwrap())
+}
+
+// --- Custom voices ---
+async fn list_voices() -> AxumJson<Vec<voices::CustomVoice>> {
+    AxumJson(voices::list())
+}
+
+#[derive(Debug, Deserialize)]
+struct PublishAtRequest {
+    // null lifts the embargo
+    publish_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// Admin only: sets or lifts the episode's embargo
+async fn set_episode_publish_at(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(payload): Json<PublishAtRequest>,
+) -> Result<AxumJson<archive::Episode>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let before = archive::get(&id).await.map_err(archive_error)?;
+    let episode = archive::set_publish_at(&id, payload.publish_at)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Episode {} not found", id) })))?;
+    audit(
+        &headers,
+        "episode.schedule",
+        &id,
+        Some(serde_json::json!({ "publish_at": before.and_then(|episode| episode.publish_at) })),
+        Some(serde_json::json!({ "publish_at": episode.publish_at })),
+    )
+    .await;
+    Ok(AxumJson(episode))
+}
+
+// Admin only. Multipart form: `name`, optional `persona`, and one or more `sample` audio files;
+// 409 when the name is taken
+async fn register_voice(
+    headers: HeaderMap,
+    mut multipart: axum::extract::Multipart,
+) -> Result<(StatusCode, AxumJson<voices::CustomVoice>), (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    if !voices::cloning_available() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            AxumJson(ApiError { error: "Voice cloning requires ELEVENLABS_API_KEY".to_string() })
+        ));
+    }
+
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, AxumJson(ApiError { error }));
+    let mut name = None;
+    let mut persona = None;
+    let mut samples = Vec::new();
+
+    while let Some(field) = multipart.next_field().await.map_err(|e| bad_request(format!("Invalid form data: {}", e)))? {
+        match field.name().unwrap_or_default() {
+            "name" => name = Some(field.text().await.map_err(|e| bad_request(e.to_string()))?.trim().to_string()),
+            "persona" => persona = Some(field.text().await.map_err(|e| bad_request(e.to_string()))?.trim().to_string()),
+            "sample" => {
+                let file_name = field.file_name().unwrap_or("sample.wav").to_string();
+                let content_type = field.content_type().unwrap_or("audio/wav").to_string();
+                let data = field.bytes().await.map_err(|e| bad_request(e.to_string()))?.to_vec();
+                samples.push(voices::Sample { file_name, content_type, data });
+            }
+            _ => {}
+        }
+    }
+
+    let name = name.filter(|n| !n.is_empty()).ok_or_else(|| bad_request("name is required".to_string()))?;
+    if config::GEMINI_VOICES.contains(&name.as_str()) {
+        return Err(bad_request(format!("'{}' is a built-in voice name", name)));
+    }
+    if samples.is_empty() {
+        return Err(bad_request("At least one sample file is required".to_string()));
+    }
+
+    let voice = voices::register(&name, persona.filter(|p| !p.is_empty()), samples).await.map_err(|e| match e {
+        voices::RegisterError::Exists => {
+            (StatusCode::CONFLICT, AxumJson(ApiError { error: format!("Voice '{}' already exists", name) }))
+        }
+        voices::RegisterError::Provider(e) => {
+            error!("Failed to register voice '{}': {}", name, e);
+            (StatusCode::BAD_GATEWAY, AxumJson(ApiError { error: e }))
+        }
+    })?;
+    audit(&headers, "voice.register", &name, None, serde_json::to_value(&voice).ok()).await;
+    Ok((StatusCode::CREATED, AxumJson(voice)))
+}
+
+// --- Inbound webhook trigger ---
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct GenerationJobRequest {
+    // Generate a story-specific episode; omit for a digest of the workspace context
+    story_id: Option<u32>,
+    persona: Option<String>,
+    scope: Option<String>,
+    title: Option<String>,
+    season: Option<u32>,
+    anonymize_authors: Option<bool>,
+    #[serde(default)]
+    tools: HashMap<String, bool>,
+    // "flash" for a 60-90 second brief; needs story_id
+    mode: Option<script::Mode>,
+    // Digest of the front page on this past date (YYYY-MM-DD) instead of the workspace context
+    date: Option<chrono::NaiveDate>,
+    // Queue lane; webhook-triggered jobs default to scheduled
+    priority: Option<jobs::Priority>,
+    // Receives the finished job as a signed POST (see callbacks.rs)
+    callback_url: Option<String>,
+    // Hold the episode back until then; announced by the embargo worker instead of on completion
+    publish_at: Option<chrono::DateTime<chrono::Utc>>,
+    // Digest of the HN list stories that pass these filters (scheduled digests)
+    source: Option<schedules::Source>,
+    // Extra instructions for the script
+    prompt: Option<String>,
+    // Topic feed the episode is filed under on top of its own topics
+    feed_topic: Option<String>,
+}
+
+// Verify an `X-Hub-Signature-256: sha256=<hex hmac of body>` header against HOOKS_SECRET
+fn verify_hook_signature(secret: &str, headers: &HeaderMap, body: &[u8]) -> bool {
+    use hmac::{Hmac, Mac};
+
+    let signature = match headers
+        .get("X-Hub-Signature-256")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("sha256="))
+        .and_then(|v| hex::decode(v).ok())
+    {
+        Some(signature) => signature,
+        None => return false,
+    };
+
+    let mut mac = match Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+async fn run_generation_job(job_id: &str, payload: GenerationJobRequest) -> Result<serde_json::Value, String> {
+    let persona = personas::resolve_with_fallback(payload.persona.clone())
+        .map_err(|valid| unknown_persona(payload.persona.as_deref(), &valid))
+        .map_err(|(_, AxumJson(e))| e.error)?;
+    let mut title = payload.title;
+    let mut reference_urls = Vec::new();
+    let mut source_notes = Vec::new();
+    let mut story_topic_tags = Vec::new();
+    let mut story_ids: Vec<u32> = payload.story_id.into_iter().collect();
+    let mut segment_stories = Vec::new();
+
+    if let Some(story_id) = payload.story_id {
+        jobs::set_stage(job_id, "fetch_story");
+        let client = get_hn_client();
+        let story = client.get_story(story_id).await.map_err(|e| format!("Failed to fetch story {}: {}", story_id, e))?;
+        let comments = client
+            .get_comments_for_story(&story)
+            .await
+            .map_err(|e| format!("Failed to fetch comments for story {}: {}", story_id, e))?;
+
+        let story_title = story.title.as_deref().unwrap_or_default();
+        if threads::is_ask_hn(story_title) {
+            let ask_hn = threads::summarize_ask_hn(&thread_posts(&comments));
+            if let Some(summary) = &ask_hn.summary {
+                let counts: Vec<String> = ask_hn.recommendations.iter().map(|r| format!("{} ({})", r.label, r.commenters)).collect();
+                source_notes.push(format!(
+                    "\"{}\" is an Ask HN thread with {} answers. Structure the segment around what commenters recommended and how many agreed: {} Mentions by distinct commenters: {}.",
+                    story_title, ask_hn.answers, summary, counts.join(", ")
+                ));
+            }
+        }
+        if let Some(maker) = show_hn_maker(&story).await {
+            source_notes.extend(threads::maker_context(story_title, &maker));
+        }
+
+        jobs::set_stage(job_id, "add_context");
+        let authors = story.by.iter().chain(comments.iter().filter_map(|c| c.by.as_ref())).cloned().collect();
+        let AxumJson(context) = generate_content(Json(ContentGenerationRequest {
+            story_id,
+            comments: comments.into_iter().filter_map(|c| c.text).collect(),
+            authors,
+            anonymize_authors: payload.anonymize_authors,
+        }))
+        .await
+        .map_err(|(_, AxumJson(e))| e.error)?;
+        info!("{}", redact::body(&context.message));
+        jobs::set_stage_details(job_id, serde_json::json!({ "comment_filter": context.comment_filter }));
+
+        if let Some(url) = &story.url {
+            jobs::set_stage(job_id, "fetch_article");
+            if let Ok((_, Some(html))) = fetch_website(url).await {
+                let extract = analysis::article_extract(&html);
+                if let Some(reason) = analysis::detect_paywall(url, &html, extract.as_deref()) {
+                    info!("Article for story {} looks paywalled ({})", story_id, reason);
+                    source_notes.push(format!(
+                        "The linked article for \"{}\" is paywalled, so say so and let this segment lean on the HackerNews comments rather than the article itself.",
+                        story.title.as_deref().unwrap_or("this story")
+                    ));
+                }
+            }
+        }
+
+        story_topic_tags = story_topics(&story).await;
+        reference_urls.push(format!("https://news.ycombinator.com/item?id={}", story_id));
+        reference_urls.extend(story.url);
+        title = title.or(story.title);
+    } else if payload.date.is_some() || payload.source.is_some() {
+        let (stories, intro) = if let Some(date) = payload.date {
+            jobs::set_stage(job_id, "fetch_front_page");
+            let stories = get_hn_client()
+                .get_front_page(date, backfill::stories_per_day())
+                .await
+                .map_err(|e| format!("Failed to look up the front page for {}: {}", date, e))?;
+            if stories.is_empty() {
+                return Err(format!("No front-page stories found for {}", date));
+            }
+            let intro = format!(
+                "This is a look back at the HackerNews front page on {}. Cover these stories from that day, most upvoted first, as news of that date rather than today's:",
+                date.format("%B %-d, %Y")
+            );
+            (stories, intro)
+        } else {
+            jobs::set_stage(job_id, "select_stories");
+            let stories = select_stories(payload.source.as_ref().unwrap_or(&schedules::Source::default())).await?;
+            if stories.is_empty() {
+                return Err("No stories match the source filters".to_string());
+            }
+            (stories, "Cover these HackerNews stories, in this order:".to_string())
+        };
+        let lines: Vec<String> = stories
+            .iter()
+            .map(|s| format!("- \"{}\" ({} points){}", s.title, s.points.unwrap_or(0), s.url.as_deref().map(|u| format!(", {}", u)).unwrap_or_default()))
+            .collect();
+        source_notes.push(format!("{}\n{}", intro, lines.join("\n")));
+        for story in &stories {
+            reference_urls.push(format!("https://news.ycombinator.com/item?id={}", story.id));
+            reference_urls.extend(story.url.clone());
+        }
+        story_ids = stories.iter().map(|s| s.id).collect();
+        segment_stories = stories;
+        title = title.or_else(|| payload.date.map(backfill::digest_title));
+    }
+    source_notes.extend(payload.prompt.filter(|prompt| !prompt.trim().is_empty()));
+
+    jobs::set_stage(job_id, "generate_script");
+    let (_, AxumJson(result)) = generate_podcast(Json(PodcastGenerationRequest {
+        persona: Some(persona),
+        scope: payload.scope,
+        title,
+        season: payload.season,
+        reference_urls,
+        anonymize_authors: payload.anonymize_authors,
+        source_notes,
+        tools: payload.tools,
+        story_ids,
+        segments: segment_stories,
+        topics: story_topic_tags,
+        mode: payload.mode,
+        job_id: Some(job_id.to_string()),
+        publish_at: payload.publish_at,
+        feed_topic: payload.feed_topic,
+        ..Default::default()
+    }))
+    .await
+    .map_err(|(_, AxumJson(e))| e.error)?;
+    jobs::check_result(&result)?;
+
+    Ok(result)
+}
+
+// How many of a list's stories are looked at when filtering for a digest
+const SOURCE_CANDIDATES: usize = 100;
+
+// The list's stories that pass the source filters, in list order
+async fn select_stories(source: &schedules::Source) -> Result<Vec<RelatedStory>, String> {
+    let feed = match source.feed.as_deref() {
+        Some(name) => Feed::parse(name).ok_or_else(|| format!("Unknown story list '{}'", name))?,
+        None => Feed::Top,
+    };
+    let client = get_hn_client();
+    let ids = client.get_feed(feed).await.map_err(|e| format!("Failed to fetch the {} stories: {}", feed.name(), e))?;
+    let batch = client.get_stories_batch(&ids[..ids.len().min(SOURCE_CANDIDATES)]).await;
+    let cutoff = source.max_age_hours.map(|hours| chrono::Utc::now().timestamp() - i64::from(hours) * 3600);
+    let mut stories: Vec<RelatedStory> = batch
+        .items
+        .into_iter()
+        .filter(|story| source.min_score.is_none_or(|min| story.score.unwrap_or(0) >= min))
+        .filter(|story| cutoff.is_none_or(|cutoff| story.time.is_some_and(|time| time as i64 >= cutoff)))
+        .filter_map(|story| {
+            let title = story.title.filter(|title| !title.is_empty())?;
+            // Titles only, as for /api/stories?topic=
+            let on_topic = source.topic.as_deref().is_none_or(|topic| topics::classify_rules(&title, "").contains(&topic));
+            on_topic.then_some(RelatedStory { id: story.id, title, url: story.url, points: story.score })
+        })
+        .collect();
+    if engagement::bias_enabled() && archive::enabled() {
+        // Without scores the list order stands
+        match engagement::scores(None).await {
+            Ok(scores) => {
+                let scores = scores.into_iter().map(|topic| (topic.topic, topic.score)).collect();
+                stories = engagement::rerank(stories, &scores, |story| topics::classify_rules(&story.title, ""));
+            }
+            Err(e) => warn!("Failed to load topic engagement for digest selection: {}", e),
         }
-        
-        // Send completion signal
-        yield Ok(Event::default().data("[DONE]"));
+    }
+    stories.truncate(source.limit());
+    Ok(stories)
+}
+
+// Sensitive-topic gate for story jobs nobody is actively waiting on (SENSITIVE_TOPICS); Err with
+// the reason and flags when the job must not run. Runs before taking a slot, so a job waiting for
+// approval doesn't hold one.
+async fn gate_sensitive(job_id: &str, payload: &GenerationJobRequest) -> Result<(), (String, Vec<String>)> {
+    let policy = topics::sensitive_policy();
+    let interactive = payload.priority == Some(jobs::Priority::Interactive);
+    let Some(story_id) = payload.story_id.filter(|_| policy != topics::SensitivePolicy::Allow && !interactive) else {
+        return Ok(());
     };
 
-    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+    jobs::set_stage(job_id, "screen_topics");
+    // Fetch failures are left to the job itself, which retries them
+    let client = get_hn_client();
+    let Ok(story) = client.get_story(story_id).await else { return Ok(()) };
+    let flags = story_sensitive_flags(&story, &client.get_comments_for_story(&story).await.unwrap_or_default());
+    if flags.is_empty() {
+        return Ok(());
+    }
+
+    let title = story.title.as_deref().unwrap_or_default();
+    match policy {
+        topics::SensitivePolicy::Approve => {
+            info!("Job {} for \"{}\" is waiting for approval (sensitive: {})", job_id, title, flags.join(", "));
+            if jobs::await_approval(job_id, flags.clone()).await {
+                Ok(())
+            } else {
+                Err((format!("Rejected: \"{}\" touches sensitive topics ({})", title, flags.join(", ")), flags))
+            }
+        }
+        _ => Err((format!("Skipped: \"{}\" touches sensitive topics ({})", title, flags.join(", ")), flags)),
+    }
 }
 
-fn create_wav_from_pcm(
-    pcm_data: &[u8], 
-    sample_rate: u32, 
-    channels: u16, 
-    bits_per_sample: u16
-) -> Result<Vec<u8>, (StatusCode, AxumJson<ApiError>)> {
-    let data_size = pcm_data.len() as u32;
-    let file_size = 36 + data_size;
-    let byte_rate = sample_rate * channels as u32 * (bits_per_sample as u32 / 8);
-    let block_align = channels * (bits_per_sample / 8);
+// Register a job and run the generation in the background
+fn spawn_generation_job(payload: GenerationJobRequest) -> (jobs::Job, tokio::task::JoinHandle<Result<serde_json::Value, String>>) {
+    let kind = if payload.story_id.is_some() { "story_episode" } else { "digest" };
+    let priority = payload.priority.unwrap_or(jobs::Priority::Scheduled);
+    let job = jobs::create(kind, priority, serde_json::to_value(&payload).unwrap_or_default());
+    info!("Queued {} generation job {} ({})", kind, job.id, priority.name());
+    // API processes leave the job to whichever worker claims it
+    let handle = if job.runner.as_deref() == Some(jobs::process_id()) {
+        start_generation_job(job.id.clone(), payload)
+    } else {
+        tokio::spawn(jobs::wait(job.id.clone()))
+    };
+    (job, handle)
+}
 
-    let mut wav_data = Vec::new();
-    
-    // RIFF header
-    wav_data.extend_from_slice(b"RIFF");
-    wav_data.extend_from_slice(&file_size.to_le_bytes());
-    wav_data.extend_from_slice(b"WAVE");
-    
-    // fmt chunk
-    wav_data.extend_from_slice(b"fmt ");
-    wav_data.extend_from_slice(&16u32.to_le_bytes()); // chunk size
-    wav_data.extend_from_slice(&1u16.to_le_bytes()); // audio format (PCM)
-    wav_data.extend_from_slice(&channels.to_le_bytes());
-    wav_data.extend_from_slice(&sample_rate.to_le_bytes());
-    wav_data.extend_from_slice(&byte_rate.to_le_bytes());
-    wav_data.extend_from_slice(&block_align.to_le_bytes());
-    wav_data.extend_from_slice(&bits_per_sample.to_le_bytes());
-    
-    // data chunk
-    wav_data.extend_from_slice(b"data");
-    wav_data.extend_from_slice(&data_size.to_le_bytes());
-    wav_data.extend_from_slice(pcm_data);
-    
-    Ok(wav_data)
+// Runs a job a worker claimed from the queue
+fn run_claimed_job(job: jobs::Job) {
+    match job.request.clone().and_then(|request| serde_json::from_value::<GenerationJobRequest>(request).ok()) {
+        Some(payload) => {
+            start_generation_job(job.id, payload);
+        }
+        None => jobs::mark_dead(&job.id, format!("Job {} has no replayable request", job.id)),
+    }
 }
 
-async fn generate_tts(
-    Json(payload): Json<TTSRequest>
-) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
-    let gemini_api_key = env::var("GEMINI_API_KEY").map_err(|_| {
-        (StatusCode::INTERNAL_SERVER_ERROR, 
-         AxumJson(ApiError { error: "GEMINI_API_KEY is not configured".to_string() }))
+// Runs a queued job, retrying failed attempts until the budget is spent. Every attempt, including
+// admin retries, runs with the keys of the caller who created the job.
+fn start_generation_job(job_id: String, payload: GenerationJobRequest) -> tokio::task::JoinHandle<Result<serde_json::Value, String>> {
+    let kind = if payload.story_id.is_some() { "story_episode" } else { "digest" };
+    let priority = payload.priority.unwrap_or(jobs::Priority::Scheduled);
+    let credentials = jobs::get(&job_id).map(|job| job.credentials).unwrap_or_default();
+
+    tokio::spawn(credentials::scope(credentials, async move {
+        if let Err((reason, sensitive)) = gate_sensitive(&job_id, &payload).await {
+            info!("Job {} not generated: {}", job_id, reason);
+            jobs::mark_skipped(&job_id, reason.clone(), sensitive);
+            if let (Some(url), Some(job)) = (&payload.callback_url, jobs::get(&job_id)) {
+                callbacks::spawn_delivery(url.clone(), job);
+            }
+            return Err(reason);
+        }
+
+        let outcome = loop {
+            let outcome = {
+                let _slot = jobs::acquire(priority).await;
+                info!("Starting {} generation job {}", kind, job_id);
+                jobs::mark_running(&job_id);
+                run_generation_job(&job_id, payload.clone()).await
+            };
+            match outcome {
+                Ok(result) => break Ok(result),
+                Err(e) if jobs::record_failure(&job_id, e.clone()) => {
+                    let delay = jobs::retry_delay();
+                    warn!("Job {} attempt failed, retrying in {}s: {}", job_id, delay.as_secs(), e);
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => break Err(e),
+            }
+        };
+
+        match &outcome {
+            Ok(result) => {
+                info!("Job {} finished", job_id);
+                jobs::mark_succeeded(&job_id, result.clone());
+                // Only an archived episode can be held back
+                let embargoed = payload.publish_at.is_some_and(|at| at > chrono::Utc::now())
+                    && result.get("episode_id").is_some_and(|id| !id.is_null());
+                if embargoed {
+                    info!("Job {} is embargoed until {}", job_id, payload.publish_at.unwrap_or_default().to_rfc3339());
+                } else {
+                    events::publish("episode_published", serde_json::json!({
+                        "job_id": job_id,
+                        "title": result.get("title"),
+                        "slug": result.get("slug"),
+                        "season": result.get("season"),
+                        "chat_id": result.get("chat_id"),
+                    }));
+                    syndication::publish_episode(&job_id, result).await;
+                }
+            }
+            Err(e) => error!("Job {} is dead after {} attempts: {}", job_id, jobs::max_attempts(), e),
+        }
+        if let Some(job) = jobs::get(&job_id) {
+            if let Some(url) = &payload.callback_url {
+                callbacks::spawn_delivery(url.clone(), job.clone());
+            }
+            alerts::record_job_outcome(&job).await;
+        }
+        outcome
+    }))
+}
+
+async fn hook_generate(
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
+    let secret = env::var("HOOKS_SECRET").unwrap_or_default();
+    if secret.is_empty() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            AxumJson(ApiError { error: "HOOKS_SECRET is not configured".to_string() })
+        ));
+    }
+
+    if !verify_hook_signature(&secret, &headers, &body) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            AxumJson(ApiError { error: "Invalid or missing X-Hub-Signature-256 header".to_string() })
+        ));
+    }
+
+    let payload: GenerationJobRequest = serde_json::from_slice(&body).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            AxumJson(ApiError { error: format!("Invalid request body: {}", e) })
+        )
     })?;
 
-    let client = reqwest::Client::new();
-    let url = "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash-preview-tts:generateContent";
-    
-    info!("Generating TTS for text length: {}", payload.text.len());
-    
-    let request_body = serde_json::json!({
-        "contents": [{
-            "parts": [{
-                "text": payload.text
-            }]
-        }],
-        "generationConfig": {
-            "responseModalities": ["AUDIO"],
-            "speechConfig": {
-                "voiceConfig": {
-                    "prebuiltVoiceConfig": {
-                        "voiceName": "Kore"
+    // Reject up front rather than accepting a job that can only fail
+    if let Err(valid) = personas::resolve_with_fallback(payload.persona.clone()) {
+        return Err(unknown_persona(payload.persona.as_deref(), &valid));
+    }
+    config::upstream_tools(&payload.tools).map_err(invalid_tools)?;
+    if let Some(url) = &payload.callback_url {
+        callbacks::validate_url(url).map_err(|error| (StatusCode::UNPROCESSABLE_ENTITY, AxumJson(ApiError { error })))?;
+    }
+    if payload.mode == Some(script::Mode::Flash) && payload.story_id.is_none() {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            AxumJson(ApiError { error: "Flash episodes cover a single story: story_id is required".to_string() })
+        ));
+    }
+
+    let (job, _) = spawn_generation_job(payload);
+
+    Ok((StatusCode::ACCEPTED, AxumJson(serde_json::json!({
+        "job_id": job.id,
+        "status": job.status,
+    }))))
+}
+
+fn sse_event(event: &events::ServerEvent) -> Event {
+    Event::default()
+        .id(event.id.to_string())
+        .event(event.kind)
+        .data(serde_json::to_string(event).unwrap_or_default())
+}
+
+// Long-lived SSE feed of server events; each event's `event:` field is its kind. Reconnecting
+// clients send Last-Event-ID and get the buffered events they missed first.
+async fn stream_events(headers: HeaderMap) -> Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>> {
+    // Subscribe before reading the buffer so nothing published in between is lost
+    let mut receiver = events::subscribe();
+    let missed = headers
+        .get("Last-Event-ID")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(events::since)
+        .unwrap_or_default();
+
+    let stream = async_stream::stream! {
+        let mut last_sent = 0;
+        for event in missed {
+            last_sent = event.id;
+            yield Ok(sse_event(&event));
+        }
+        // Heartbeats carry no ID, so they don't move the client's Last-Event-ID
+        let mut heartbeat = tokio::time::interval(sse_heartbeat_interval());
+        heartbeat.tick().await;
+        loop {
+            tokio::select! {
+                received = receiver.recv() => match received {
+                    // Already replayed from the buffer
+                    Ok(event) if event.id <= last_sent => {}
+                    Ok(event) => {
+                        yield Ok(sse_event(&event));
                     }
-                }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                        info!("Events subscriber lagged, skipped {} event(s)", missed);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                },
+                _ = heartbeat.tick() => yield Ok(heartbeat_event()),
             }
-        },
-        "model": "gemini-2.5-flash-preview-tts"
-    });
+        }
+    };
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
 
-    info!("Request body: {}", serde_json::to_string_pretty(&request_body).unwrap());
+// Synthetic stories/comments through the CPU-bound pipeline stages, with per-stage timings
+#[cfg(feature = "bench")]
+async fn bench_pipeline(Json(request): Json<bench::BenchRequest>) -> Result<AxumJson<bench::BenchReport>, (StatusCode, AxumJson<ApiError>)> {
+    tokio::task::spawn_blocking(move || bench::run_pipeline(&request)).await.map(AxumJson).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            AxumJson(ApiError { error: format!("Benchmark failed: {}", e) })
+        )
+    })
+}
 
-    let response = client
-        .post(url)
-        .header("x-goog-api-key", &gemini_api_key)
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
+async fn get_job_raw(Path(id): Path<String>) -> Result<AxumJson<history::Exchange>, (StatusCode, AxumJson<ApiError>)> {
+    history::get(&id).map(AxumJson).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            AxumJson(ApiError { error: format!("No upstream exchange recorded for job {}", id) })
+        )
+    })
+}
+
+// --- Admin ---
+
+// Bearer ADMIN_TOKEN; admin routes are unavailable until one is configured
+fn require_admin(headers: &HeaderMap) -> Result<(), (StatusCode, AxumJson<ApiError>)> {
+    let token = env::var("ADMIN_TOKEN").unwrap_or_default();
+    if token.is_empty() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            AxumJson(ApiError { error: "ADMIN_TOKEN is not configured".to_string() })
+        ));
+    }
+
+    let provided = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    // Constant-time comparison
+    let matches = provided.len() == token.len()
+        && provided.bytes().zip(token.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0;
+    if !matches {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            AxumJson(ApiError { error: "Invalid or missing admin token".to_string() })
+        ));
+    }
+    Ok(())
+}
+
+// The admin token is shared, so admins name themselves with X-Admin-Actor for the audit log
+fn admin_actor(headers: &HeaderMap) -> String {
+    headers
+        .get("x-admin-actor")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().chars().take(100).collect::<String>())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "admin".to_string())
+}
+
+// A failed write is logged rather than failing an action that already happened
+async fn audit(headers: &HeaderMap, action: &str, target: &str, before: Option<serde_json::Value>, after: Option<serde_json::Value>) {
+    if let Err(e) = archive::record_audit(&admin_actor(headers), action, target, before.as_ref(), after.as_ref()).await {
+        error!("Failed to record {} of {} in the audit log: {}", action, target, e);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AuditQuery {
+    limit: Option<u32>,
+    // Entry id to page back from
+    before: Option<i64>,
+    action: Option<String>,
+}
+
+// Admin only: the audit log, newest first
+async fn get_audit_log(headers: HeaderMap, Query(query): Query<AuditQuery>) -> Result<AxumJson<Vec<archive::AuditEntry>>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    archive::audit_log(limit, query.before, query.action.as_deref()).await.map(AxumJson).map_err(archive_error)
+}
+
+async fn charge(metric: usage::Metric, amount: u64) -> Result<(), (StatusCode, AxumJson<ApiError>)> {
+    usage::charge(metric, amount).await.map_err(|(status, error)| (status, AxumJson(ApiError { error })))
+}
+
+// Prompt plus reply, estimated the same way as /api/estimate
+fn generation_tokens(history: &[chats::ChatMessage], output: &str) -> u64 {
+    (history.iter().map(|message| script::estimate_tokens(&message.content)).sum::<usize>() + script::estimate_tokens(output)) as u64
+}
+
+// Archived episodes are real content, so sandbox keys can't change them
+fn sandbox_forbidden() -> Result<(), (StatusCode, AxumJson<ApiError>)> {
+    if usage::sandbox() {
+        return Err((StatusCode::FORBIDDEN, AxumJson(ApiError { error: "Sandbox API keys can't change archived episodes".to_string() })));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiKeyRequest {
+    name: String,
+    generation_quota: Option<u64>,
+    tts_quota: Option<u64>,
+    #[serde(default)]
+    sandbox: bool,
+}
+
+// Admin only: issues a key; the secret is in this response and nowhere else
+async fn create_api_key(
+    headers: HeaderMap,
+    Json(payload): Json<ApiKeyRequest>,
+) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let name = payload.name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err((StatusCode::BAD_REQUEST, AxumJson(ApiError { error: "name must be 1 to 100 characters".to_string() })));
+    }
+    let secret = usage::new_secret();
+    let key = archive::create_api_key(name, &usage::hash(&secret), payload.generation_quota, payload.tts_quota, payload.sandbox)
         .await
-        .map_err(|e| {
-            error!("TTS request failed: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, 
-             AxumJson(ApiError { error: "Failed to generate TTS".to_string() }))
-        })?;
+        .map_err(archive_error)?;
+    audit(&headers, "key.create", &key.id, None, serde_json::to_value(&key).ok()).await;
+    Ok((StatusCode::CREATED, AxumJson(serde_json::json!({ "key": key, "secret": secret }))))
+}
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        error!("TTS API error {}: {}", status, error_text);
-        return Err((StatusCode::INTERNAL_SERVER_ERROR, 
-                   AxumJson(ApiError { error: format!("TTS API error: {}", status) })));
+async fn list_api_keys(headers: HeaderMap) -> Result<AxumJson<Vec<archive::ApiKey>>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    archive::api_keys().await.map(AxumJson).map_err(archive_error)
+}
+
+async fn revoke_api_key(headers: HeaderMap, Path(id): Path<String>) -> Result<AxumJson<archive::ApiKey>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let before = archive::api_key(&id).await.map_err(archive_error)?;
+    let key = archive::revoke_api_key(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("API key {} not found", id) })))?;
+    audit(&headers, "key.revoke", &id, before.and_then(|key| serde_json::to_value(key).ok()), serde_json::to_value(&key).ok()).await;
+    Ok(AxumJson(key))
+}
+
+// The key itself (X-API-Key) or an admin; ?month=YYYY-MM, default this month
+async fn get_api_key_usage(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    if usage::current().is_none_or(|key| key.id != id) {
+        require_admin(&headers)?;
+    }
+    let now = chrono::Utc::now();
+    let month = params.get("month").cloned().unwrap_or_else(|| usage::month(now));
+    if !usage::valid_month(&month) {
+        return Err((StatusCode::BAD_REQUEST, AxumJson(ApiError { error: "month must be YYYY-MM".to_string() })));
+    }
+    let key = archive::api_key(&id)
+        .await
+        .map_err(archive_error)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("API key {} not found", id) })))?;
+    let mut report = usage::report(&key, &month).await.map_err(archive_error)?;
+    if month == usage::month(now) {
+        report["resets_at"] = serde_json::json!(usage::month_end(now));
     }
+    Ok(AxumJson(report))
+}
 
-    let response_text = response.text().await
-        .map_err(|e| {
-            error!("Failed to get response text: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR,
-             AxumJson(ApiError { error: "Failed to read response".to_string() }))
-        })?;
+#[derive(Debug, Deserialize)]
+struct UsageReportQuery {
+    month: Option<String>,
+    format: Option<String>,
+}
+
+// Admin only: every key's usage for a month (default: this one) as JSON or CSV
+async fn get_usage_report(headers: HeaderMap, Query(query): Query<UsageReportQuery>) -> Result<Response<Body>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let month = query.month.unwrap_or_else(|| usage::month(chrono::Utc::now()));
+    if !usage::valid_month(&month) {
+        return Err((StatusCode::BAD_REQUEST, AxumJson(ApiError { error: "month must be YYYY-MM".to_string() })));
+    }
+    let rows = usage::month_report(&month).await.map_err(archive_error)?;
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => typed_response("application/json", serde_json::json!({ "month": month, "keys": rows }).to_string()),
+        "csv" => typed_response("text/csv; charset=utf-8", usage::csv(&rows)),
+        other => Err((StatusCode::BAD_REQUEST, AxumJson(ApiError { error: format!("Unknown format {}; expected csv or json", other) }))),
+    }
+}
 
-    info!("Got response with length: {}", response_text.len());
+async fn list_dead_jobs(headers: HeaderMap) -> Result<AxumJson<Vec<jobs::Job>>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    Ok(AxumJson(jobs::dead().await))
+}
 
-    let response_json: serde_json::Value = serde_json::from_str(&response_text)
-        .map_err(|e| {
-            error!("Failed to parse TTS response: {} - Response: {}", e, response_text);
-            (StatusCode::INTERNAL_SERVER_ERROR, 
-             AxumJson(ApiError { error: "Invalid TTS response".to_string() }))
-        })?;
+async fn retry_job(
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<(StatusCode, AxumJson<serde_json::Value>), (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
 
-    info!("Parsed JSON response");
-
-    // Extract the base64 audio data
-    let audio_data = response_json
-        .get("candidates")
-        .and_then(|c| c.get(0))
-        .and_then(|c| c.get("content"))
-        .and_then(|c| c.get("parts"))
-        .and_then(|p| p.get(0))
-        .and_then(|p| p.get("inlineData"))
-        .and_then(|d| d.get("data"))
-        .and_then(|d| d.as_str())
+    let job = jobs::load(&id).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            AxumJson(ApiError { error: format!("Job {} not found", id) })
+        )
+    })?;
+    if job.status != jobs::JobStatus::Dead {
+        return Err((
+            StatusCode::CONFLICT,
+            AxumJson(ApiError { error: format!("Job {} is not dead-lettered", id) })
+        ));
+    }
+    let payload: GenerationJobRequest = job
+        .request
+        .and_then(|request| serde_json::from_value(request).ok())
         .ok_or_else(|| {
-            error!("Failed to extract audio data from response: {}", serde_json::to_string_pretty(&response_json).unwrap());
-            (StatusCode::INTERNAL_SERVER_ERROR, 
-             AxumJson(ApiError { error: "No audio data in response".to_string() }))
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                AxumJson(ApiError { error: format!("Job {} has no replayable request", id) })
+            )
         })?;
 
-    info!("Extracted base64 audio data, length: {}", audio_data.len());
+    // Lost a race with another retry, or needs caller keys held by another process
+    let job = jobs::requeue(&id).await.map_err(|error| (StatusCode::CONFLICT, AxumJson(ApiError { error })))?;
+    info!("Re-driving dead job {}", id);
+    audit(&headers, "job.retry", &id, Some(serde_json::json!({ "status": jobs::JobStatus::Dead })), serde_json::to_value(&job).ok()).await;
+    if job.runner.as_deref() == Some(jobs::process_id()) {
+        start_generation_job(job.id.clone(), payload);
+    }
+
+    Ok((StatusCode::ACCEPTED, AxumJson(serde_json::json!({
+        "job_id": job.id,
+        "status": job.status,
+    }))))
+}
 
-    // Decode base64 to bytes (this is raw PCM data)
-    let pcm_bytes = base64::decode(audio_data)
-        .map_err(|e| {
-            error!("Failed to decode base64 audio: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, 
-             AxumJson(ApiError { error: "Invalid audio data".to_string() }))
-        })?;
+async fn list_pending_jobs(headers: HeaderMap) -> Result<AxumJson<Vec<jobs::Job>>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    Ok(AxumJson(jobs::awaiting_approval().await))
+}
 
-    info!("Decoded PCM data length: {}", pcm_bytes.len());
+// For jobs held by SENSITIVE_TOPICS=approve
+async fn approve_job(headers: HeaderMap, Path(id): Path<String>) -> Result<AxumJson<jobs::Job>, (StatusCode, AxumJson<ApiError>)> {
+    decide_job(&headers, &id, true).await
+}
 
-    // Convert PCM to WAV format by adding WAV header
-    let wav_bytes = create_wav_from_pcm(&pcm_bytes, 24000, 1, 16)?;
-    
-    info!("Generated WAV data length: {}", wav_bytes.len());
+async fn reject_job(headers: HeaderMap, Path(id): Path<String>) -> Result<AxumJson<jobs::Job>, (StatusCode, AxumJson<ApiError>)> {
+    decide_job(&headers, &id, false).await
+}
 
-    Ok(Response::builder()
-        .status(200)
-        .header("Content-Type", "audio/wav")
-        .header("Content-Disposition", "attachment; filename=\"podcast-audio.wav\"")
-        .body(Body::from(wav_bytes))
-        .unwrap())
+async fn decide_job(headers: &HeaderMap, id: &str, approve: bool) -> Result<AxumJson<jobs::Job>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(headers)?;
+    let Some(before) = jobs::load(id).await else {
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Job {} not found", id) })));
+    };
+    let job = jobs::decide(id, approve).await.ok_or_else(|| {
+        (StatusCode::CONFLICT, AxumJson(ApiError { error: format!("Job {} is not awaiting approval", id) }))
+    })?;
+    info!("Job {} {} by an admin", id, if approve { "approved" } else { "rejected" });
+    let action = if approve { "job.approve" } else { "job.reject" };
+    audit(headers, action, id, serde_json::to_value(&before).ok(), serde_json::to_value(&job).ok()).await;
+    Ok(AxumJson(job))
+}
+
+#[derive(Debug, Deserialize)]
+struct BackfillRequest {
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+// One run at a time; 202 with the run, whose progress is at GET /admin/backfill/:id
+async fn start_backfill(
+    headers: HeaderMap,
+    Json(payload): Json<BackfillRequest>,
+) -> Result<(StatusCode, AxumJson<backfill::Run>), (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, AxumJson(ApiError { error }));
+    if payload.from > payload.to {
+        return Err(bad_request("from must not be after to".to_string()));
+    }
+    if payload.to >= chrono::Utc::now().date_naive() {
+        return Err(bad_request("to must be a past date".to_string()));
+    }
+    if (payload.to - payload.from).num_days() >= backfill::MAX_DAYS {
+        return Err(bad_request(format!("A backfill covers at most {} days", backfill::MAX_DAYS)));
+    }
+    if let Some(run) = backfill::running() {
+        return Err((StatusCode::CONFLICT, AxumJson(ApiError { error: format!("Backfill {} is still running", run.id) })));
+    }
+    let run = backfill::start(payload.from, payload.to, payload.dry_run);
+    audit(&headers, "backfill.start", &run.id, None, serde_json::to_value(&run).ok()).await;
+    Ok((StatusCode::ACCEPTED, AxumJson(run)))
+}
+
+async fn list_backfills(headers: HeaderMap) -> Result<AxumJson<Vec<backfill::Run>>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    Ok(AxumJson(backfill::list()))
+}
+
+async fn get_backfill(headers: HeaderMap, Path(id): Path<String>) -> Result<AxumJson<backfill::Run>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    backfill::get(&id).map(AxumJson).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Backfill {} not found", id) }))
+    })
+}
+
+// Stops before the next day; the day in progress still finishes
+async fn cancel_backfill(headers: HeaderMap, Path(id): Path<String>) -> Result<AxumJson<backfill::Run>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    if backfill::get(&id).is_none() {
+        return Err((StatusCode::NOT_FOUND, AxumJson(ApiError { error: format!("Backfill {} not found", id) })));
+    }
+    if !backfill::cancel(&id) {
+        return Err((StatusCode::CONFLICT, AxumJson(ApiError { error: format!("Backfill {} is not running", id) })));
+    }
+    info!("Backfill {} cancelled by an admin", id);
+    let run = backfill::get(&id).expect("backfill run exists");
+    audit(&headers, "backfill.cancel", &id, None, serde_json::to_value(&run).ok()).await;
+    Ok(AxumJson(run))
+}
+
+// How much the content-addressed store saves (see artifacts.rs)
+async fn get_artifact_stats(headers: HeaderMap) -> Result<AxumJson<serde_json::Value>, (StatusCode, AxumJson<ApiError>)> {
+    require_admin(&headers)?;
+    let stats = archive::artifact_stats().await.map_err(archive_error)?;
+    Ok(AxumJson(serde_json::json!({
+        "files": stats.files,
+        "objects": stats.objects,
+        "referenced_bytes": stats.referenced_bytes,
+        "stored_bytes": stats.stored_bytes,
+        "saved_bytes": stats.referenced_bytes - stats.stored_bytes,
+    })))
+}
+
+async fn get_job_lanes() -> AxumJson<serde_json::Value> {
+    AxumJson(jobs::lane_stats())
+}
+
+async fn get_job(Path(id): Path<String>) -> Result<AxumJson<jobs::Job>, (StatusCode, AxumJson<ApiError>)> {
+    jobs::load(&id).await.map(AxumJson).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            AxumJson(ApiError { error: format!("Job {} not found", id) })
+        )
+    })
 }
 
 #[tokio::main]
@@ -923,31 +4764,183 @@ async fn main() -> anyhow::Result<()> {
     // Initialize tracing
     tracing_subscriber::fmt::init();
 
+    // --role api|worker|all (default all); see roles.rs
+    let role = roles::parse_args(std::env::args().skip(1)).map_err(anyhow::Error::msg)?;
+    roles::init(role);
+
+    // Secrets from <KEY>_FILE or Vault have to be in place before anything reads them
+    let secret_errors = secrets::load().await;
+
+    // Report every configuration problem at once instead of failing at request time
+    let mut config_report = config::validate();
+    config_report.errors.extend(secret_errors);
+    config::check_reachability(&mut config_report).await;
+    for warning in &config_report.warnings {
+        warn!("{}", warning);
+    }
+    if !config_report.errors.is_empty() {
+        for problem in &config_report.errors {
+            error!("Invalid configuration: {}", problem);
+        }
+        anyhow::bail!("{} configuration problem(s) found, see above", config_report.errors.len());
+    }
+
+    // Episode archive (DATABASE_URL)
+    archive::init().await.map_err(anyhow::Error::msg)?;
+    if role != roles::Role::All && !archive::enabled() {
+        anyhow::bail!("--role {} needs the archive, which holds the shared job queue (DATABASE_URL)", role.name());
+    }
+    info!("Running as {}", role.name());
+
+    // Job queue shared with other processes, and recovery of jobs whose process stopped
+    jobs::spawn_writer();
+    jobs::start_heartbeat().await;
+    roles::spawn_claimer();
+
+    if roles::serves_api() {
+        // Incremental cache invalidation from HN's updates feed (HN_UPDATES_INTERVAL_SECS)
+        hn_sync::spawn_updates_worker();
+
+        // Front-page history for /api/frontpage/diff and the radar (FRONTPAGE_SNAPSHOT_INTERVAL_SECS)
+        frontpage::spawn_snapshot_worker();
+    }
+
+    if roles::runs_jobs() {
+        // Releases embargoed episodes at their publish_at (EMBARGO_CHECK_INTERVAL_SECS)
+        embargo::spawn_release_worker();
+
+        // Queues digests whenever a schedule is due (SCHEDULE_CRON or /api/schedules)
+        schedules::spawn_scheduler();
+
+        // Optional Telegram bot (TELEGRAM_BOT_TOKEN)
+        telegram::spawn_bot();
+    }
+
     // Build our application with routes
+    #[allow(unused_mut)]
     let app = Router::new()
         .route("/health", get(health_check))
+        .route("/sitemap.xml", get(get_sitemap))
+        .route("/feed.xml", get(get_podcast_feed))
+        .route("/feed.atom", get(get_atom_feed))
+        .route("/feed.json", get(get_json_feed))
+        .route("/feed/archive/:number", get(get_podcast_feed_archive))
+        .route("/api/feeds/opml", get(get_feeds_opml))
+        .route("/api/config", get(get_config))
+        .route("/api/personas", get(get_personas))
         .route("/api/stories", get(get_top_stories))
         .route("/api/stories/:id", get(get_story_by_id))
         .route("/api/stories/:id/comments", get(get_story_comments))
+        .route("/api/stories/:id/full", get(get_story_full))
+        .route("/api/stories/:id/comments/ranked", get(get_ranked_comments))
+        .route("/api/stories/:id/comments/tree", get(get_comment_tree))
+        .route("/api/stories/:id/activity", get(get_story_activity))
+        .route("/api/stories/:id/seo", get(get_story_seo))
+        .route("/api/frontpage/snapshots", get(get_frontpage_snapshots))
+        .route("/api/frontpage/diff", get(get_frontpage_diff))
         .route("/api/generate-content", post(generate_content))
         .route("/api/metadata", get(get_website_metadata))
         .route("/api/podcast/generate", post(generate_podcast))
+        .route("/api/podcast/:id/audio", post(render_episode_audio))
+        .route("/api/episodes/:id/audio", get(get_episode_audio))
+        .route("/api/episodes/:id/audio/qa", get(get_episode_audio_qa))
+        .route("/api/episodes/:id/hls/playlist.m3u8", get(get_episode_hls))
+        .route("/api/episodes/:id/transcript", get(get_episode_transcript))
+        .route("/api/episodes/:id/transcript/timings", get(get_episode_transcript_timings))
+        .route("/api/episodes/:id/chapters", get(get_episode_chapters))
+        .route("/api/episodes/:id/player", get(get_episode_player))
+        .route("/embed/:id", get(get_embed_page))
+        .route("/oembed", get(get_oembed))
+        .route("/.well-known/webfinger", get(get_webfinger))
+        .route("/ap/actor", get(get_activitypub_actor))
+        .route("/ap/inbox", post(post_activitypub_inbox))
+        .route("/ap/outbox", get(get_activitypub_outbox))
+        .route("/ap/followers", get(get_activitypub_followers))
+        .route("/ap/notes/:id", get(get_activitypub_note))
+        .route("/api/episodes/:id/refine", post(refine_episode))
+        .route("/api/episodes/:id/follow-up", post(follow_up_episode))
+        .route("/api/episodes/:id/feedback", get(get_episode_feedback).post(add_episode_feedback))
+        .route("/api/episodes/:id/playback", post(add_playback_event))
+        .route("/api/episodes/:id/versions", get(list_episode_versions))
+        .route("/api/episodes/:id/versions/:version", get(get_episode_version))
+        .route("/api/episodes/:id/versions/:a/diff/:b", get(diff_episode_versions))
+        .route("/api/users/:id/library", get(get_user_library))
+        .route("/api/users/:id/bookmarks/:episode_id", put(add_user_bookmark).delete(remove_user_bookmark))
+        .route("/api/users/:id/playlists/:name", put(save_user_playlist).delete(delete_user_playlist))
+        .route("/api/users/:id/subscriptions/:topic", put(add_user_subscription).delete(remove_user_subscription))
+        .route("/api/users/:id/export", get(export_user_data))
+        .route("/api/users/:id/data", delete(delete_user_data))
+        .nest_service(
+            "/audio",
+            tower::ServiceBuilder::new()
+                .layer(axum::middleware::from_fn(usage::meter_downloads))
+                .service(tower_http::services::ServeDir::new(episode_audio::dir())),
+        )
+        .route("/api/topics", get(get_topics))
+        .route("/api/episodes", get(list_episodes))
+        .route("/api/episodes/:id", get(get_episode).delete(delete_episode))
+        .route("/api/episodes/:id/publish_at", put(set_episode_publish_at))
+        .route("/api/schedules", get(list_schedules).post(save_schedule))
+        .route("/api/schedules/:name", get(get_schedule).delete(delete_schedule))
+        .route("/api/radar", get(get_radar))
         .route("/api/v1/chat/generate/stream", post(generate_stream))
         .route("/api/tts/generate", post(generate_tts))
+        .route("/api/estimate", post(estimate_script))
+        // Episode audio can be far larger than the default 2 MB body limit
+        .route("/api/transcribe", post(transcribe_audio).layer(axum::extract::DefaultBodyLimit::max(200 * 1024 * 1024)))
+        .route("/api/voices", get(list_voices).post(register_voice).layer(axum::extract::DefaultBodyLimit::max(50 * 1024 * 1024)))
+        .route("/api/jobs/lanes", get(get_job_lanes))
+        .route("/api/jobs/:id", get(get_job))
+        .route("/api/jobs/:id/raw", get(get_job_raw))
+        .route("/api/events", get(stream_events))
+        .route("/api/chat/:chat_id", get(get_chat))
+        .route("/api/chat/:chat_id/continue", post(continue_chat))
+        .route("/hooks/generate", post(hook_generate))
+        .route("/admin/jobs/dead", get(list_dead_jobs))
+        .route("/admin/jobs/:id/retry", post(retry_job))
+        .route("/admin/jobs/pending", get(list_pending_jobs))
+        .route("/admin/jobs/:id/approve", post(approve_job))
+        .route("/admin/jobs/:id/reject", post(reject_job))
+        .route("/admin/radar/keywords", post(add_radar_keyword))
+        .route("/admin/radar/keywords/:keyword", delete(remove_radar_keyword))
+        .route("/admin/backfill", get(list_backfills).post(start_backfill))
+        .route("/admin/backfill/:id", get(get_backfill))
+        .route("/admin/backfill/:id/cancel", post(cancel_backfill))
+        .route("/admin/feed/validate", get(validate_feed))
+        .route("/admin/audit", get(get_audit_log))
+        .route("/admin/export/site", post(export_site))
+        .route("/admin/feedback", get(list_feedback))
+        .route("/admin/feedback/summary", get(get_feedback_summary))
+        .route("/admin/engagement", get(get_engagement))
+        .route("/admin/artifacts", get(get_artifact_stats))
+        .route("/admin/keys", get(list_api_keys).post(create_api_key))
+        .route("/admin/keys/:id", delete(revoke_api_key))
+        .route("/api/keys/:id/usage", get(get_api_key_usage))
+        .route("/admin/usage/report", get(get_usage_report));
+
+    // Registered before the layers so the benchmark goes through the same middleware as the API
+    #[cfg(feature = "bench")]
+    let app = {
+        info!("bench feature enabled: POST /bench/pipeline is available");
+        app.route("/bench/pipeline", post(bench_pipeline))
+    };
+
+    // Workers only answer health checks
+    let app = if roles::serves_api() { app } else { Router::new().route("/health", get(health_check)) };
+
+    let app = app
+        .layer(axum::middleware::from_fn(credentials::layer))
+        .layer(axum::middleware::from_fn(usage::layer))
+        .layer(axum::middleware::from_fn(hn_cache::layer))
         .layer(
             CorsLayer::new()
                 .allow_origin(tower_http::cors::Any)
                 .allow_methods(tower_http::cors::Any)
-                .allow_headers(tower_http::cors::Any),
+                .allow_headers(tower_http::cors::Any)
+                .expose_headers(tower_http::cors::Any),
         );
 
-    let port = std::env::var("PORT").unwrap_or_else(|_| "3001".to_string());
-    let addr = format!("0.0.0.0:{}", port);
-    
-    info!("Starting HackerNews backend server on {}", addr);
-    
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
-
-    Ok(())
+    // TCP addresses from BIND_ADDRS (default 0.0.0.0:$PORT), HTTPS when TLS_CERT_PATH/TLS_KEY_PATH
+    // are set, plus an optional UNIX_SOCKET_PATH
+    server::run(app).await
 }
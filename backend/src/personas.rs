@@ -0,0 +1,76 @@
+use crate::config;
+use serde::Serialize;
+use tracing::info;
+
+// Personas the Alchemyst platform ships with, listed alongside locally configured ones
+const PLATFORM_PERSONAS: [&str; 2] = ["maya", "ron"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PersonaInfo {
+    pub name: String,
+    // Listed in the local PERSONAS config
+    pub configured: bool,
+    // Whether the configured API key can use it; always None because the platform has no
+    // persona listing API to check against
+    pub available: Option<bool>,
+    pub default: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PersonaList {
+    pub personas: Vec<PersonaInfo>,
+    // "unknown" until Alchemyst exposes the personas on a key's plan
+    pub platform_availability: &'static str,
+    pub note: &'static str,
+}
+
+pub fn list() -> PersonaList {
+    let configured = config::personas();
+    let default = config::default_persona();
+
+    let personas = names()
+        .into_iter()
+        .map(|name| PersonaInfo {
+            configured: configured.contains(&name),
+            default: name == default,
+            available: None,
+            name,
+        })
+        .collect();
+
+    PersonaList {
+        personas,
+        platform_availability: "unknown",
+        note: "Alchemyst has no persona listing API, so whether a persona is on your plan can't be checked here",
+    }
+}
+
+// Personas a request may use: configured ones followed by the platform's built-in ones
+pub fn names() -> Vec<String> {
+    let mut names = config::personas();
+    for persona in PLATFORM_PERSONAS {
+        if !names.iter().any(|n| n == persona) {
+            names.push(persona.to_string());
+        }
+    }
+    names
+}
+
+// Requested persona (or the default) normalized to its registered name; Err lists the valid ones
+pub fn resolve(requested: Option<String>) -> Result<String, Vec<String>> {
+    let valid = names();
+    let requested = requested.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).unwrap_or_else(config::default_persona);
+    valid.iter().find(|name| name.eq_ignore_ascii_case(&requested)).cloned().ok_or(valid)
+}
+
+// For automated jobs: unknown personas fall back to FALLBACK_PERSONA when one is configured
+pub fn resolve_with_fallback(requested: Option<String>) -> Result<String, Vec<String>> {
+    let requested_name = requested.clone();
+    resolve(requested).or_else(|valid| match config::fallback_persona() {
+        Some(fallback) => {
+            info!("Unknown persona {:?}, falling back to '{}'", requested_name, fallback);
+            resolve(Some(fallback))
+        }
+        None => Err(valid),
+    })
+}
@@ -0,0 +1,110 @@
+use crate::announcements::Chapter;
+use crate::archive::Episode;
+use serde::Serialize;
+
+// Player manifest (GET /api/episodes/:id/player): what the web player needs for one episode in a
+// single request, including a speaker timeline so the UI can show which avatar is talking. Turns
+// come from the rendered audio's chapters, whose start times are the TTS durations of the
+// preceding segments: script segments and synthesized announcements are the host (the episode's
+// persona), pre-recorded announcements the announcer.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Host,
+    Announcer,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeakerTurn {
+    pub speaker: String,
+    pub role: Role,
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
+// Consecutive segments of the same speaker are one turn; without chapters the host speaks throughout
+pub fn speaker_timeline(chapters: &[Chapter], duration_secs: f64, host: &str) -> Vec<SpeakerTurn> {
+    let mut turns: Vec<SpeakerTurn> = Vec::new();
+    for (index, chapter) in chapters.iter().enumerate() {
+        let end_secs = chapters.get(index + 1).map_or(duration_secs, |next| next.start_secs);
+        let (speaker, role) = if chapter.prerecorded { ("announcer", Role::Announcer) } else { (host, Role::Host) };
+        match turns.last_mut() {
+            Some(turn) if turn.role == role => turn.end_secs = end_secs,
+            _ => turns.push(SpeakerTurn { speaker: speaker.to_string(), role, start_secs: chapter.start_secs, end_secs }),
+        }
+    }
+    if let Some(first) = turns.first_mut() {
+        first.start_secs = 0.0;
+    }
+    if turns.is_empty() && duration_secs > 0.0 {
+        turns.push(SpeakerTurn { speaker: host.to_string(), role: Role::Host, start_secs: 0.0, end_secs: duration_secs });
+    }
+    turns
+}
+
+// None until the episode's audio is rendered
+pub fn manifest(episode: &Episode) -> Option<serde_json::Value> {
+    let audio = episode.audio.as_ref()?;
+    let api = format!("{}/api/episodes/{}", crate::alerts::public_base_url(), episode.id);
+    Some(serde_json::json!({
+        "episode_id": episode.id,
+        "title": episode.title,
+        "episode_number": episode.episode_number,
+        "season": episode.season,
+        "host": episode.persona,
+        "duration_secs": audio.duration_secs,
+        "audio": {
+            "url": audio.url,
+            "bytes": audio.bytes,
+            "renditions": audio.renditions,
+        },
+        "hls": audio.hls.as_ref().map(|_| format!("{}/hls/playlist.m3u8", api)),
+        "transcript": format!("{}/transcript", api),
+        "transcript_timings": format!("{}/transcript/timings", api),
+        "chapters": audio.chapters,
+        "speakers": speaker_timeline(&audio.chapters, audio.duration_secs, &episode.persona),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::announcements::ChapterKind;
+
+    fn chapter(start_secs: f64, kind: ChapterKind, prerecorded: bool) -> Chapter {
+        Chapter { start_secs, title: "t".to_string(), kind, prerecorded }
+    }
+
+    #[test]
+    fn announcers_interrupt_the_host() {
+        let chapters = [
+            chapter(0.0, ChapterKind::Content, false),
+            chapter(30.0, ChapterKind::Announcement, true),
+            chapter(45.0, ChapterKind::Content, false),
+        ];
+        let turns = speaker_timeline(&chapters, 300.0, "maya");
+        let spans: Vec<(&str, f64, f64)> = turns.iter().map(|t| (t.speaker.as_str(), t.start_secs, t.end_secs)).collect();
+        assert_eq!(spans, [("maya", 0.0, 30.0), ("announcer", 30.0, 45.0), ("maya", 45.0, 300.0)]);
+    }
+
+    #[test]
+    fn synthesized_announcements_stay_with_the_host() {
+        let chapters = [
+            chapter(0.0, ChapterKind::Content, false),
+            chapter(30.0, ChapterKind::Announcement, false),
+            chapter(45.0, ChapterKind::Content, false),
+        ];
+        let turns = speaker_timeline(&chapters, 300.0, "maya");
+        assert_eq!(turns.len(), 1);
+        assert_eq!((turns[0].start_secs, turns[0].end_secs), (0.0, 300.0));
+    }
+
+    #[test]
+    fn host_speaks_throughout_without_chapters() {
+        let turns = speaker_timeline(&[], 120.0, "maya");
+        assert_eq!(turns.len(), 1);
+        assert_eq!(turns[0].role, Role::Host);
+        assert_eq!(turns[0].end_secs, 120.0);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::fetch;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::info;
+
+// Polite fetching for third-party sites (metadata, articles, link checks): a per-host concurrency
+// limit, a minimum delay between requests to the same host and, with FETCH_RESPECT_ROBOTS=true,
+// robots.txt rules. Keeps a busy front page from hammering one blog until it blocks the server.
+
+// Token matched against robots.txt User-agent groups
+const ROBOTS_AGENT: &str = "hackernews-podcast";
+const ROBOTS_TTL: Duration = Duration::from_secs(24 * 3600);
+// Don't let a robots.txt Crawl-delay stall a request for minutes
+const MAX_CRAWL_DELAY: Duration = Duration::from_secs(30);
+
+fn max_per_domain() -> usize {
+    env::var("FETCH_MAX_PER_DOMAIN").ok().and_then(|v| v.parse().ok()).filter(|n| *n > 0).unwrap_or(2)
+}
+
+fn crawl_delay() -> Duration {
+    Duration::from_millis(env::var("FETCH_CRAWL_DELAY_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(500))
+}
+
+fn respect_robots() -> bool {
+    matches!(env::var("FETCH_RESPECT_ROBOTS").unwrap_or_default().to_lowercase().as_str(), "1" | "true" | "yes")
+}
+
+// --- robots.txt ---
+
+#[derive(Debug, Default)]
+struct Robots {
+    // (allow, path pattern)
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<Duration>,
+}
+
+impl Robots {
+    // Uses our own group when present, otherwise the "*" group
+    fn parse(body: &str) -> Self {
+        let mut groups: Vec<(Vec<String>, Robots)> = Vec::new();
+        let mut in_agents = false;
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else { continue };
+            let (key, value) = (key.trim().to_lowercase(), value.trim());
+            if key == "user-agent" {
+                if !in_agents {
+                    groups.push((Vec::new(), Robots::default()));
+                }
+                in_agents = true;
+                if let Some((agents, _)) = groups.last_mut() {
+                    agents.push(value.to_lowercase());
+                }
+                continue;
+            }
+            in_agents = false;
+            let Some((_, robots)) = groups.last_mut() else { continue };
+            match key.as_str() {
+                // An empty Disallow allows everything
+                "disallow" if !value.is_empty() => robots.rules.push((false, value.to_string())),
+                "allow" if !value.is_empty() => robots.rules.push((true, value.to_string())),
+                // Negative, NaN or infinite values from a hostile robots.txt are ignored rather than panicking
+                // and huge ones are clamped
+                "crawl-delay" => {
+                    robots.crawl_delay = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|secs| secs.is_finite())
+                        .and_then(|secs| Duration::try_from_secs_f64(secs.min(MAX_CRAWL_DELAY.as_secs_f64())).ok())
+                }
+                _ => {}
+            }
+        }
+
+        // Agents are lowercased above, so this matches our token case-insensitively; a group for
+        // "news" or "podcast" isn't ours
+        let ours = groups.iter().position(|(agents, _)| agents.iter().any(|a| a == ROBOTS_AGENT));
+        let any = groups.iter().position(|(agents, _)| agents.iter().any(|a| a == "*"));
+        match ours.or(any) {
+            Some(index) => groups.swap_remove(index).1,
+            None => Robots::default(),
+        }
+    }
+
+    // Longest matching rule wins; "*" wildcards and a trailing "$" are supported
+    fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| rule_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .map(|(allow, _)| *allow)
+            .unwrap_or(true)
+    }
+}
+
+fn rule_matches(pattern: &str, path: &str) -> bool {
+    let regex = regex::escape(pattern).replace(r"\*", ".*");
+    let regex = match regex.strip_suffix(r"\$") {
+        Some(anchored) => format!("^{}$", anchored),
+        None => format!("^{}", regex),
+    };
+    regex::Regex::new(&regex).map(|re| re.is_match(path)).unwrap_or(false)
+}
+
+// Missing or unreadable robots.txt means no restrictions
+async fn fetch_robots(url: &reqwest::Url) -> Robots {
+    let mut robots_url = url.clone();
+    robots_url.set_path("/robots.txt");
+    robots_url.set_query(None);
+    robots_url.set_fragment(None);
+
+    match fetch::safe_client().get(robots_url).send().await {
+        Ok(response) if response.status().is_success() => Robots::parse(&response.text().await.unwrap_or_default()),
+        _ => Robots::default(),
+    }
+}
+
+// --- Per-host state ---
+
+struct Host {
+    permits: Arc<Semaphore>,
+    next_request: tokio::sync::Mutex<Instant>,
+    robots: tokio::sync::Mutex<Option<(Arc<Robots>, Instant)>>,
+}
+
+static HOSTS: OnceLock<Mutex<HashMap<String, Arc<Host>>>> = OnceLock::new();
+
+fn host_state(host: &str) -> Arc<Host> {
+    let mut hosts = HOSTS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+    hosts
+        .entry(host.to_string())
+        .or_insert_with(|| {
+            Arc::new(Host {
+                permits: Arc::new(Semaphore::new(max_per_domain())),
+                next_request: tokio::sync::Mutex::new(Instant::now()),
+                robots: tokio::sync::Mutex::new(None),
+            })
+        })
+        .clone()
+}
+
+async fn robots_for(host: &Host, url: &reqwest::Url) -> Arc<Robots> {
+    let mut cached = host.robots.lock().await;
+    if let Some((robots, fetched_at)) = cached.as_ref() {
+        if fetched_at.elapsed() < ROBOTS_TTL {
+            return robots.clone();
+        }
+    }
+    let robots = Arc::new(fetch_robots(url).await);
+    *cached = Some((robots.clone(), Instant::now()));
+    robots
+}
+
+#[derive(Debug)]
+pub enum PoliteError {
+    // robots.txt disallows the path
+    Disallowed,
+    Request(reqwest::Error),
+}
+
+impl std::fmt::Display for PoliteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PoliteError::Disallowed => write!(f, "disallowed by robots.txt"),
+            PoliteError::Request(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// Waits for a slot and the host's crawl delay; the permit is held until response headers arrive
+async fn acquire(url: &reqwest::Url) -> Result<OwnedSemaphorePermit, PoliteError> {
+    let host_name = url.host_str().unwrap_or_default().to_lowercase();
+    let host = host_state(&host_name);
+
+    let mut delay = crawl_delay();
+    if respect_robots() {
+        let robots = robots_for(&host, url).await;
+        if !robots.allows(url.path()) {
+            info!("robots.txt disallows {}", url);
+            return Err(PoliteError::Disallowed);
+        }
+        if let Some(robots_delay) = robots.crawl_delay {
+            delay = delay.max(robots_delay);
+        }
+    }
+
+    let permit = host.permits.clone().acquire_owned().await.expect("host semaphore is never closed");
+    let mut next_request = host.next_request.lock().await;
+    let now = Instant::now();
+    if *next_request > now {
+        tokio::time::sleep(*next_request - now).await;
+    }
+    *next_request = Instant::now() + delay;
+    Ok(permit)
+}
+
+// Sends a request built on the SSRF-safe client once the host is ready for it
+pub async fn send(method: reqwest::Method, url: reqwest::Url) -> Result<reqwest::Response, PoliteError> {
+    let _permit = acquire(&url).await?;
+    fetch::safe_client().request(method, url).send().await.map_err(PoliteError::Request)
+}
+
+pub async fn get(url: reqwest::Url) -> Result<reqwest::Response, PoliteError> {
+    send(reqwest::Method::GET, url).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_our_group_over_the_wildcard() {
+        let robots = Robots::parse("User-agent: *\nDisallow: /\n\nUser-agent: HackerNews-Podcast\nDisallow: /private\n");
+        assert!(robots.allows("/article"));
+        assert!(!robots.allows("/private/page"));
+    }
+
+    #[test]
+    fn falls_back_to_the_wildcard_group() {
+        let robots = Robots::parse("User-agent: otherbot\nDisallow: /\n\nUser-agent: *\nDisallow: /admin\n");
+        assert!(robots.allows("/"));
+        assert!(!robots.allows("/admin/login"));
+    }
+
+    #[test]
+    fn empty_user_agent_is_not_ours() {
+        let robots = Robots::parse("User-agent:\nDisallow: /\n\nUser-agent: *\nDisallow: /tmp\n");
+        assert!(robots.allows("/article"));
+        assert!(!robots.allows("/tmp/x"));
+    }
+
+    #[test]
+    fn partial_user_agent_is_not_ours() {
+        let robots = Robots::parse("User-agent: news\nDisallow: /\n\nUser-agent: *\nDisallow: /tmp\n");
+        assert!(robots.allows("/article"));
+        assert!(!robots.allows("/tmp/x"));
+    }
+
+    #[test]
+    fn longest_rule_wins_with_wildcards_and_anchors() {
+        let robots = Robots::parse("User-agent: *\nDisallow: /docs\nAllow: /docs/public\nDisallow: /*.pdf$\n");
+        assert!(!robots.allows("/docs/internal"));
+        assert!(robots.allows("/docs/public/intro"));
+        assert!(!robots.allows("/files/report.pdf"));
+        assert!(robots.allows("/files/report.pdf.html"));
+    }
+
+    #[test]
+    fn crawl_delay_is_clamped() {
+        assert_eq!(Robots::parse("User-agent: *\nCrawl-delay: 2.5\n").crawl_delay, Some(Duration::from_millis(2500)));
+        assert_eq!(Robots::parse("User-agent: *\nCrawl-delay: 86400\n").crawl_delay, Some(MAX_CRAWL_DELAY));
+        assert_eq!(Robots::parse("User-agent: *\nCrawl-delay: 1e300\n").crawl_delay, Some(MAX_CRAWL_DELAY));
+    }
+
+    #[test]
+    fn invalid_crawl_delay_is_ignored() {
+        for value in ["-1", "NaN", "inf", "-inf", "soon"] {
+            let robots = Robots::parse(&format!("User-agent: *\nCrawl-delay: {}\n", value));
+            assert_eq!(robots.crawl_delay, None, "Crawl-delay: {}", value);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+
+// Replaces HN usernames with stable pseudonyms for deployments that don't want to broadcast them.
+// Pseudonyms are assigned on first sight and kept for the life of the process, so the same person
+// is "another developer" in the context, the script and anything derived from them.
+
+const LABELS: [&str; 8] = [
+    "one commenter",
+    "another commenter",
+    "a developer",
+    "another developer",
+    "one reader",
+    "another reader",
+    "one engineer",
+    "another engineer",
+];
+
+// ANONYMIZE_AUTHORS=true turns it on for every request that doesn't say otherwise
+pub fn enabled(requested: Option<bool>) -> bool {
+    requested.unwrap_or_else(|| env::var("ANONYMIZE_AUTHORS").map(|v| v == "true" || v == "1").unwrap_or(false))
+}
+
+static PSEUDONYMS: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+fn pseudonyms() -> &'static Mutex<HashMap<String, String>> {
+    PSEUDONYMS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn register<'a>(authors: impl IntoIterator<Item = &'a str>) {
+    let mut pseudonyms = pseudonyms().lock().unwrap();
+    for author in authors {
+        let author = author.trim();
+        if author.is_empty() || pseudonyms.contains_key(author) {
+            continue;
+        }
+        let index = pseudonyms.len();
+        let label = match LABELS.get(index) {
+            Some(label) => label.to_string(),
+            None => format!("commenter {}", index + 1),
+        };
+        pseudonyms.insert(author.to_string(), label);
+    }
+}
+
+// Replace every registered username (optionally @-prefixed) with its pseudonym
+pub fn anonymize(text: &str) -> String {
+    let pseudonyms = pseudonyms().lock().unwrap();
+    if pseudonyms.is_empty() {
+        return text.to_string();
+    }
+
+    // Longest names first so "pg" doesn't win over "pg_fan"
+    let mut names: Vec<&String> = pseudonyms.keys().collect();
+    names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+    let pattern = names.iter().map(|name| regex::escape(name)).collect::<Vec<_>>().join("|");
+    let re = regex::Regex::new(&format!(r"@?\b({})\b", pattern)).unwrap();
+
+    re.replace_all(text, |caps: &regex::Captures| pseudonyms[&caps[1]].clone()).into_owned()
+}
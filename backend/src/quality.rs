@@ -0,0 +1,175 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use tracing::{error, info};
+
+// Drops junk comments before they are ranked or added as context. Heuristics always run unless
+// COMMENT_FILTER=false; COMMENT_QUALITY_LLM=true additionally asks the Alchemyst model to score
+// the survivors 0-10 and drops those under COMMENT_QUALITY_MIN_SCORE (default 4). Scoring
+// failures keep every comment rather than lose the discussion.
+//   COMMENT_MIN_WORDS        - shorter comments are dropped (default 4)
+//   COMMENT_FLAGGED_PHRASES  - comma-separated, case-insensitive (default: common spam phrases)
+
+const DEFAULT_FLAGGED_PHRASES: &str = "[flagged],[dead],buy now,check out my,crypto giveaway,dm me,limited offer,promo code";
+const ALL_CAPS_MIN_LETTERS: usize = 20;
+// Scored comments are cut to this many characters to bound the scoring prompt
+const SCORED_CHARS: usize = 500;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FilterStats {
+    pub received: usize,
+    pub kept: usize,
+    // Reason -> count: too_short, all_caps, link_only, duplicate, flagged_phrase, low_score
+    pub dropped: BTreeMap<&'static str, usize>,
+}
+
+fn enabled() -> bool {
+    env::var("COMMENT_FILTER").map(|v| v != "false" && v != "0").unwrap_or(true)
+}
+
+fn llm_enabled() -> bool {
+    env::var("COMMENT_QUALITY_LLM").is_ok_and(|v| v == "true" || v == "1")
+}
+
+fn min_words() -> usize {
+    env::var("COMMENT_MIN_WORDS").ok().and_then(|v| v.trim().parse().ok()).unwrap_or(4)
+}
+
+fn min_score() -> f64 {
+    env::var("COMMENT_QUALITY_MIN_SCORE").ok().and_then(|v| v.trim().parse().ok()).unwrap_or(4.0)
+}
+
+fn flagged_phrases() -> Vec<String> {
+    env::var("COMMENT_FLAGGED_PHRASES")
+        .unwrap_or_else(|_| DEFAULT_FLAGGED_PHRASES.to_string())
+        .split(',')
+        .map(|p| p.trim().to_lowercase())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+fn is_url(word: &str) -> bool {
+    word.starts_with("http://") || word.starts_with("https://") || word.starts_with("www.")
+}
+
+// `text` is plain text (HTML already stripped)
+fn heuristic_reason(text: &str, seen: &mut HashSet<String>, phrases: &[String]) -> Option<&'static str> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let prose_words = words.iter().filter(|w| !is_url(w)).count();
+    if prose_words < words.len() && prose_words < 3 {
+        return Some("link_only");
+    }
+    if words.len() < min_words() {
+        return Some("too_short");
+    }
+
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() >= ALL_CAPS_MIN_LETTERS && letters.iter().filter(|c| c.is_uppercase()).count() * 10 > letters.len() * 8 {
+        return Some("all_caps");
+    }
+
+    let lower = text.to_lowercase();
+    if phrases.iter().any(|p| lower.contains(p.as_str())) {
+        return Some("flagged_phrase");
+    }
+
+    let normalized = lower.split_whitespace().collect::<Vec<_>>().join(" ");
+    if !seen.insert(normalized) {
+        return Some("duplicate");
+    }
+    None
+}
+
+// Heuristics only, for ranking paths that shouldn't wait on the model; true = keep
+pub fn screen(texts: &[&str]) -> (Vec<bool>, FilterStats) {
+    let mut stats = FilterStats { received: texts.len(), ..Default::default() };
+    if !enabled() {
+        stats.kept = texts.len();
+        return (vec![true; texts.len()], stats);
+    }
+
+    let phrases = flagged_phrases();
+    let mut seen = HashSet::new();
+    let keep: Vec<bool> = texts
+        .iter()
+        .map(|text| match heuristic_reason(&crate::analysis::strip_html(text), &mut seen, &phrases) {
+            Some(reason) => {
+                *stats.dropped.entry(reason).or_default() += 1;
+                false
+            }
+            None => true,
+        })
+        .collect();
+    stats.kept = keep.iter().filter(|k| **k).count();
+    (keep, stats)
+}
+
+// Heuristics plus the optional model score; comments may be HTML
+pub async fn filter(comments: Vec<String>) -> (Vec<String>, FilterStats) {
+    let refs: Vec<&str> = comments.iter().map(String::as_str).collect();
+    let (keep, mut stats) = screen(&refs);
+    let mut kept: Vec<String> = comments.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(c, _)| c).collect();
+
+    if enabled() && llm_enabled() && !kept.is_empty() {
+        match score(&kept).await {
+            Ok(scores) => {
+                let threshold = min_score();
+                let before = kept.len();
+                kept = kept.into_iter().zip(scores).filter(|(_, score)| *score >= threshold).map(|(c, _)| c).collect();
+                if before > kept.len() {
+                    stats.dropped.insert("low_score", before - kept.len());
+                }
+            }
+            Err(e) => error!("Comment quality scoring failed, keeping all comments: {}", e),
+        }
+    }
+
+    stats.kept = kept.len();
+    if stats.kept < stats.received {
+        info!("Comment filter kept {} of {} comments ({:?})", stats.kept, stats.received, stats.dropped);
+    }
+    (kept, stats)
+}
+
+async fn score(comments: &[String]) -> Result<Vec<f64>, String> {
+    let api_key = crate::credentials::require("ALCHEMYST_API_KEY")?;
+    let numbered: String = comments
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let text: String = crate::analysis::strip_html(c).chars().take(SCORED_CHARS).collect();
+            format!("{}. {}\n", i + 1, text)
+        })
+        .collect();
+    let no_tools: HashMap<String, bool> = crate::config::GENERATION_TOOLS.iter().map(|(name, _)| (name.to_string(), false)).collect();
+
+    let body = serde_json::json!({
+        "chat_history": [
+            crate::chats::ChatMessage::new("system", "You rate Hacker News comments for a podcast. Score each numbered comment from 0 (spam, noise, insults, no content) to 10 (insightful, informative). Reply with only a JSON array of numbers, one per comment, in order."),
+            crate::chats::ChatMessage::new("user", numbered),
+        ],
+        "persona": crate::config::default_persona(),
+        "scope": "internal",
+        "stream": false,
+        "max_tokens": 16 + comments.len() * 4,
+        "tools": crate::config::upstream_tools(&no_tools)?,
+    });
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/chat/generate", crate::config::alchemyst_api_url()))
+        .bearer_auth(api_key)
+        .json(&body)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let value: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    let text = crate::chats::extract_content(&value).map(crate::chats::content_text).ok_or("response has no content")?;
+
+    let array = text.find('[').zip(text.rfind(']')).map(|(start, end)| &text[start..=end]).ok_or("response has no JSON array")?;
+    let scores: Vec<f64> = serde_json::from_str(array).map_err(|e| e.to_string())?;
+    if scores.len() != comments.len() {
+        return Err(format!("expected {} scores, got {}", comments.len(), scores.len()));
+    }
+    Ok(scores)
+}
@@ -0,0 +1,116 @@
+use crate::archive::{self, RadarHit};
+use crate::frontpage::Snapshot;
+use std::env;
+use tracing::{error, info};
+
+// Front-page radar: operators watch keywords (POST /admin/radar/keywords) and every front-page
+// snapshot (see frontpage.rs) is checked for stories whose title mentions one. Each matching
+// story is recorded once in the archive and listed at GET /api/radar; new hits can also go out as
+// breaking-news alerts:
+//   RADAR_WEBHOOK_URL       - receives a JSON POST per hit
+//   RADAR_TELEGRAM_CHAT_ID  - gets a Telegram message per hit (needs TELEGRAM_BOT_TOKEN)
+//   RADAR_GENERATE=true     - queues a flash episode (60-90 second brief) for each hit
+
+const MAX_KEYWORD_CHARS: usize = 64;
+
+// Lowercased words, the way titles are matched; None when nothing usable is left
+pub fn normalize_keyword(keyword: &str) -> Option<String> {
+    let normalized = crate::topics::normalize(keyword).trim().to_string();
+    (!normalized.is_empty() && normalized.chars().count() <= MAX_KEYWORD_CHARS).then_some(normalized)
+}
+
+// Watched keywords the title mentions as whole words
+pub fn matches(title: &str, keywords: &[String]) -> Vec<String> {
+    let title = crate::topics::normalize(title);
+    keywords.iter().filter(|keyword| title.contains(&format!(" {} ", keyword))).cloned().collect()
+}
+
+fn generate_enabled() -> bool {
+    env::var("RADAR_GENERATE").is_ok_and(|v| v == "true" || v == "1")
+}
+
+// Records and announces stories that newly match a watched keyword
+pub async fn check(snapshot: &Snapshot) {
+    if !archive::enabled() {
+        return;
+    }
+    let keywords: Vec<String> = match archive::radar_keywords().await {
+        Ok(keywords) => keywords.into_iter().map(|k| k.keyword).collect(),
+        Err(e) => {
+            error!("Failed to load radar keywords: {}", e);
+            return;
+        }
+    };
+    if keywords.is_empty() {
+        return;
+    }
+
+    for entry in &snapshot.stories {
+        let Some(title) = entry.title.as_deref() else { continue };
+        let matched = matches(title, &keywords);
+        if matched.is_empty() {
+            continue;
+        }
+        let mut hit = RadarHit {
+            story_id: entry.id,
+            title: title.to_string(),
+            keywords: matched,
+            rank: entry.rank as u32,
+            score: entry.score,
+            job_id: None,
+            matched_at: snapshot.taken_at,
+        };
+        match archive::record_radar_hit(&hit).await {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                error!("Failed to record radar hit for story {}: {}", entry.id, e);
+                continue;
+            }
+        }
+        info!("Radar: story {} (#{}) matched {}", entry.id, entry.rank, hit.keywords.join(", "));
+
+        if generate_enabled() {
+            let (job, _) = crate::spawn_generation_job(crate::GenerationJobRequest {
+                story_id: Some(entry.id),
+                mode: Some(crate::script::Mode::Flash),
+                ..Default::default()
+            });
+            if let Err(e) = archive::set_radar_hit_job(entry.id, &job.id).await {
+                error!("Failed to link job {} to radar hit {}: {}", job.id, entry.id, e);
+            }
+            hit.job_id = Some(job.id);
+        }
+        alert(&hit).await;
+    }
+}
+
+async fn alert(hit: &RadarHit) {
+    let story_url = format!("https://news.ycombinator.com/item?id={}", hit.story_id);
+    let text = format!("📡 On the front page (#{}), matching {}: {}\n{}", hit.rank, hit.keywords.join(", "), hit.title, story_url);
+
+    if let Ok(webhook_url) = env::var("RADAR_WEBHOOK_URL") {
+        let result = reqwest::Client::new()
+            .post(&webhook_url)
+            .json(&serde_json::json!({
+                "text": text,
+                "story_id": hit.story_id,
+                "title": hit.title,
+                "keywords": hit.keywords,
+                "rank": hit.rank,
+                "score": hit.score,
+                "story_url": story_url,
+                "job_id": hit.job_id,
+                "matched_at": hit.matched_at,
+            }))
+            .send()
+            .await;
+        if let Err(e) = result.and_then(|r| r.error_for_status()) {
+            error!("Failed to deliver radar webhook: {}", e);
+        }
+    }
+
+    if let Some(chat_id) = env::var("RADAR_TELEGRAM_CHAT_ID").ok().filter(|c| !c.is_empty()) {
+        crate::telegram::send_message(&chat_id, &text).await;
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+use std::env;
+
+// Comment ranking for GET /api/stories/:id/comments/ranked. Each signal is normalized to 0-1
+// within the thread (log-scaled for length, replies and karma so one outlier doesn't flatten the
+// rest) and combined with weights from COMMENT_RANK_WEIGHTS, e.g. "recency:1,length:0.5,replies:2,karma:0",
+// which a request's `?weights=` overrides key by key.
+
+pub const SIGNALS: [&str; 4] = ["recency", "length", "replies", "karma"];
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Weights {
+    pub recency: f64,
+    pub length: f64,
+    pub replies: f64,
+    pub karma: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self { recency: 1.0, length: 1.0, replies: 1.0, karma: 0.5 }
+    }
+}
+
+impl Weights {
+    // "key:value" pairs applied on top of `self`
+    pub fn with_overrides(mut self, spec: &str) -> Result<Self, String> {
+        for pair in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once(':').ok_or_else(|| format!("Invalid weight '{}': expected signal:weight", pair))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|v: &f64| v.is_finite() && *v >= 0.0)
+                .ok_or_else(|| format!("Invalid weight for '{}': expected a non-negative number", key.trim()))?;
+            match key.trim() {
+                "recency" => self.recency = value,
+                "length" => self.length = value,
+                "replies" => self.replies = value,
+                "karma" => self.karma = value,
+                other => return Err(format!("Unknown ranking signal '{}'. Valid signals: {}", other, SIGNALS.join(", "))),
+            }
+        }
+        Ok(self)
+    }
+}
+
+pub fn configured_weights() -> Result<Weights, String> {
+    Weights::default().with_overrides(&env::var("COMMENT_RANK_WEIGHTS").unwrap_or_default())
+}
+
+// Falls back to the defaults when the configured weights are invalid (reported at startup)
+pub fn default_weights() -> Weights {
+    configured_weights().unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Signals {
+    pub time: Option<u64>,
+    pub words: usize,
+    pub replies: usize,
+    pub karma: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Score {
+    pub score: f64,
+    // Each signal's normalized value, before weighting
+    pub recency: f64,
+    pub length: f64,
+    pub replies: f64,
+    pub karma: f64,
+}
+
+fn log_scale(value: f64, max: f64) -> f64 {
+    if max <= 0.0 {
+        0.0
+    } else {
+        (1.0 + value).ln() / (1.0 + max).ln()
+    }
+}
+
+// Scores in input order
+pub fn score(signals: &[Signals], weights: &Weights) -> Vec<Score> {
+    let times: Vec<u64> = signals.iter().filter_map(|s| s.time).collect();
+    let (oldest, newest) = (times.iter().min().copied().unwrap_or(0), times.iter().max().copied().unwrap_or(0));
+    let max_words = signals.iter().map(|s| s.words).max().unwrap_or(0) as f64;
+    let max_replies = signals.iter().map(|s| s.replies).max().unwrap_or(0) as f64;
+    let max_karma = signals.iter().filter_map(|s| s.karma).max().unwrap_or(0).max(0) as f64;
+
+    signals
+        .iter()
+        .map(|s| {
+            let recency = match s.time {
+                Some(t) if newest > oldest => (t - oldest) as f64 / (newest - oldest) as f64,
+                Some(_) => 1.0,
+                None => 0.0,
+            };
+            let length = log_scale(s.words as f64, max_words);
+            let replies = log_scale(s.replies as f64, max_replies);
+            let karma = log_scale(s.karma.unwrap_or(0).max(0) as f64, max_karma);
+            let score = weights.recency * recency + weights.length * length + weights.replies * replies + weights.karma * karma;
+            Score { score, recency, length, replies, karma }
+        })
+        .collect()
+}
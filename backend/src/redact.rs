@@ -0,0 +1,67 @@
+use std::env;
+use std::sync::OnceLock;
+
+// Redaction for logged request/response payloads: bearer tokens, API keys and configured secret
+// values are masked, and bodies are cut to MAX_LOGGED_CHARS unless LOG_FULL_PAYLOADS=true.
+// Upstream bodies carry the whole context (comments, scripts), which doesn't belong in logs.
+
+const MAX_LOGGED_CHARS: usize = 500;
+// Secret values shorter than this are too likely to match ordinary text
+const MIN_SECRET_LEN: usize = 8;
+
+fn full_payloads() -> bool {
+    env::var("LOG_FULL_PAYLOADS").is_ok_and(|v| v == "true" || v == "1")
+}
+
+fn patterns() -> &'static [(regex::Regex, &'static str)] {
+    static PATTERNS: OnceLock<Vec<(regex::Regex, &'static str)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (r"(?i)(bearer\s+)[A-Za-z0-9._~+/=-]+", "${1}***"),
+            (r#"(?i)("(?:api_?key|token|access_token|password|secret|authorization|x-goog-api-key|xi-api-key)"\s*:\s*")[^"]*""#, "${1}***\""),
+            // Google (AIza...), OpenAI (sk-...) and Telegram bot tokens
+            (r"AIza[0-9A-Za-z_-]{35}", "***"),
+            (r"sk-[A-Za-z0-9_-]{20,}", "***"),
+            (r"\b\d{6,}:[A-Za-z0-9_-]{30,}\b", "***"),
+        ]
+        .into_iter()
+        .map(|(pattern, replacement)| (regex::Regex::new(pattern).unwrap(), replacement))
+        .collect()
+    })
+}
+
+pub fn mask(text: &str) -> String {
+    let mut text = text.to_string();
+    for key in crate::secrets::SECRET_KEYS {
+        if let Ok(value) = env::var(key) {
+            if value.len() >= MIN_SECRET_LEN {
+                text = text.replace(&value, "***");
+            }
+        }
+    }
+    for value in crate::credentials::supplied_values() {
+        text = text.replace(&value, "***");
+    }
+    for (pattern, replacement) in patterns() {
+        text = pattern.replace_all(&text, *replacement).into_owned();
+    }
+    text
+}
+
+// Masked body, truncated unless full payload logging is on
+pub fn body(text: &str) -> String {
+    let text = mask(text);
+    if full_payloads() {
+        return text;
+    }
+    let total = text.chars().count();
+    if total <= MAX_LOGGED_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(MAX_LOGGED_CHARS).collect();
+    format!("{}… [{} more chars; LOG_FULL_PAYLOADS=true to log everything]", cut, total - MAX_LOGGED_CHARS)
+}
+
+pub fn json(value: &serde_json::Value) -> String {
+    body(&value.to_string())
+}
@@ -0,0 +1,115 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::{error, info};
+
+// Process roles, chosen with --role: `api` serves HTTP and queues generation jobs in the archive
+// database, `worker` claims and runs them (and the schedulers and Telegram bot) so heavy LLM/TTS
+// work can be scaled apart from the latency-sensitive routes, and `all` (the default) does both
+// in one process. Split roles share the job queue through DATABASE_URL.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    All,
+    Api,
+    Worker,
+}
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::All => "all",
+            Role::Api => "api",
+            Role::Worker => "worker",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Role> {
+        [Role::All, Role::Api, Role::Worker].into_iter().find(|role| role.name() == name.trim().to_lowercase())
+    }
+}
+
+static ROLE: OnceLock<Role> = OnceLock::new();
+
+// Reads --role <name> (or --role=<name>) from the command line
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Role, String> {
+    let mut args = args.into_iter();
+    let mut role = Role::All;
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--role") {
+            Some("") => args.next().ok_or_else(|| "--role needs a value: api, worker or all".to_string())?,
+            Some(rest) if rest.starts_with('=') => rest[1..].to_string(),
+            _ => return Err(format!("Unknown argument '{}'; usage: backend [--role api|worker|all]", arg)),
+        };
+        role = Role::parse(&value).ok_or_else(|| format!("Unknown role '{}'; use api, worker or all", value))?;
+    }
+    Ok(role)
+}
+
+pub fn init(role: Role) {
+    let _ = ROLE.set(role);
+}
+
+pub fn get() -> Role {
+    ROLE.get().copied().unwrap_or(Role::All)
+}
+
+// Serves the HTTP API
+pub fn serves_api() -> bool {
+    get() != Role::Worker
+}
+
+// Runs generation jobs and the background schedulers
+pub fn runs_jobs() -> bool {
+    get() != Role::Api
+}
+
+const CLAIM_INTERVAL: Duration = Duration::from_secs(2);
+
+// Picks up jobs queued by API processes whenever a lane has room
+pub fn spawn_claimer() {
+    if !runs_jobs() || !crate::archive::enabled() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CLAIM_INTERVAL);
+        loop {
+            ticker.tick().await;
+            loop {
+                let lanes = crate::jobs::lanes_with_room();
+                if lanes.is_empty() {
+                    break;
+                }
+                match crate::jobs::claim(&lanes).await {
+                    Ok(Some(job)) => {
+                        info!("Claimed {} job {} ({})", job.kind, job.id, job.priority.name());
+                        crate::run_claimed_job(job);
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("Failed to claim a job: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_the_role_flag() {
+        assert_eq!(parse_args(args(&[])), Ok(Role::All));
+        assert_eq!(parse_args(args(&["--role", "worker"])), Ok(Role::Worker));
+        assert_eq!(parse_args(args(&["--role=API"])), Ok(Role::Api));
+        assert!(parse_args(args(&["--role"])).is_err());
+        assert!(parse_args(args(&["--role", "cron"])).is_err());
+        assert!(parse_args(args(&["--verbose"])).is_err());
+    }
+}
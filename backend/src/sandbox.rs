@@ -0,0 +1,50 @@
+use crate::script;
+
+// Mock pipeline for sandbox API keys (see usage.rs), so integrators can build against the API
+// without spending LLM or TTS credits: generation answers with the canned script below and TTS
+// with silence as long as the text would take to speak. Nothing is archived, metered or billed.
+
+pub const TITLE: &str = "Sandbox Episode";
+
+pub const SCRIPT: &str = "Welcome to the sandbox edition of the HackerNews Podcast. This script is canned: \
+sandbox keys never reach the language model, so every request gets the same episode.
+
+[TRANSITION]
+
+## First story
+
+Here a real episode would cover the first story, with what the commenters made of it. \
+The structure matches a generated script, so headings, cues and links parse the same way. \
+More at https://news.ycombinator.com/.
+
+## Second story
+
+A second segment, so chapter markers and segment estimates have something to split on.
+
+[TRANSITION]
+
+That's all for the sandbox. Switch to a regular API key to generate real episodes.
+";
+
+// Keeps a long text from allocating an hour of silence
+const MAX_SILENCE_SECS: f64 = 600.0;
+
+// 16-bit mono silence, as long as `text` would take to speak in `voice`
+pub fn silent_pcm(text: &str, voice: &str, sample_rate: u32) -> Vec<u8> {
+    let secs = script::estimate(text, script::voice_wpm(voice)).estimated_secs.clamp(1.0, MAX_SILENCE_SECS);
+    vec![0; (secs * sample_rate as f64) as usize * 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silence_follows_the_text_length() {
+        let short = silent_pcm("Hello there.", "Kore", 24_000);
+        assert_eq!(short.len(), 24_000 * 2);
+        let script = silent_pcm(SCRIPT, "Kore", 24_000);
+        assert!(script.len() > short.len() && script.iter().all(|&b| b == 0));
+        assert_eq!(silent_pcm(&"word ".repeat(100_000), "Kore", 24_000).len(), 600 * 24_000 * 2);
+    }
+}
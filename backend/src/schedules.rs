@@ -0,0 +1,265 @@
+use crate::archive::{self, Schedule};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::time::Duration;
+use tracing::{error, info};
+
+// Scheduled digests: each named schedule (a daily top-10 digest, a weekly Ask HN special, a
+// monthly recap...) queues a digest job whenever its cron expression matches and renders the
+// episode's audio once the script is done, so it lands in the feeds. Every schedule has its own
+// story source, persona, prompt, voice and topic feed; they're managed through /api/schedules and
+// kept in the archive.
+//
+// With several workers (see roles.rs) each checks every schedule, and the first to claim a run in
+// the archive queues it, so a digest is generated once per period however many replicas run.
+//
+// Cron expressions are evaluated in the schedule's IANA time zone, so "0 6 * * *" in
+// Europe/Berlin is 6am there in summer and winter alike. A time that doesn't exist on the day
+// clocks spring forward runs at the first instant after the gap; a time that happens twice when
+// they fall back runs once. Runs missed while the server was down are not made up.
+//
+// SCHEDULE_CRON and SCHEDULE_TIMEZONE (default UTC) configure a schedule named "digest" with the
+// default settings until one by that name is saved through the API.
+
+pub const ENV_NAME: &str = "digest";
+const MAX_NAME_CHARS: usize = 64;
+const CHECK_INTERVAL: Duration = Duration::from_secs(20);
+const PREVIEW_RUNS: usize = 3;
+// Claimed runs are remembered this long
+const CLAIM_DAYS: i64 = 7;
+pub const DEFAULT_STORIES: usize = 10;
+pub const MAX_STORIES: usize = 30;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Settings {
+    // Stories the digest covers; without one it covers the workspace context, like /hooks/generate
+    pub source: Option<Source>,
+    pub persona: Option<String>,
+    // Extra instructions for the script, e.g. "lead with the most argued-about answer"
+    pub prompt: Option<String>,
+    // TTS voice for the audio; defaults to the persona's
+    pub voice: Option<String>,
+    // Topic feed the episode is filed under (/feed.xml?topic=), on top of its own topics
+    pub feed_topic: Option<String>,
+    // Shown before the date in the episode title; default "HackerNews on <date>"
+    pub title: Option<String>,
+}
+
+// Filters applied to an HN list at run time, in list order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Source {
+    // top (default), new, best, ask, show or job
+    pub feed: Option<String>,
+    // Stories per episode, default 10
+    pub limit: Option<usize>,
+    // Only stories whose title matches this topic
+    pub topic: Option<String>,
+    pub min_score: Option<u32>,
+    pub max_age_hours: Option<u32>,
+}
+
+impl Source {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_STORIES).clamp(1, MAX_STORIES)
+    }
+}
+
+// Lowercase letters, digits and dashes, e.g. "weekly-ask-hn"
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_NAME_CHARS
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+pub fn parse_cron(expression: &str) -> Result<Cron, String> {
+    Cron::new(expression.trim()).parse().map_err(|e| format!("Invalid cron expression '{}': {}", expression, e))
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.trim().parse::<Tz>().map_err(|_| format!("Unknown time zone '{}' (use an IANA name such as Europe/Berlin)", name))
+}
+
+// The next `count` run times after `after`, in the schedule's time zone
+pub fn next_runs(cron: &Cron, tz: Tz, after: DateTime<Utc>, count: usize) -> Vec<DateTime<Tz>> {
+    let mut runs = Vec::new();
+    let mut at = after.with_timezone(&tz);
+    while runs.len() < count {
+        let Ok(next) = cron.find_next_occurrence(&at, false) else { break };
+        at = next;
+        runs.push(next);
+    }
+    runs
+}
+
+fn from_env() -> Option<Schedule> {
+    let cron = env::var("SCHEDULE_CRON").ok().filter(|v| !v.trim().is_empty())?;
+    Some(Schedule {
+        name: ENV_NAME.to_string(),
+        cron,
+        timezone: env::var("SCHEDULE_TIMEZONE").ok().filter(|v| !v.trim().is_empty()).unwrap_or_else(|| "UTC".to_string()),
+        enabled: true,
+        settings: Settings::default(),
+        updated_at: None,
+    })
+}
+
+// Saved schedules, plus the environment's unless one by its name was saved
+pub async fn all() -> Vec<Schedule> {
+    let mut schedules = if archive::enabled() {
+        archive::schedules().await.unwrap_or_else(|e| {
+            error!("Failed to load schedules: {}", e);
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+    if let Some(schedule) = from_env().filter(|env| schedules.iter().all(|saved| saved.name != env.name)) {
+        schedules.push(schedule);
+        schedules.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    schedules
+}
+
+pub async fn get(name: &str) -> Option<Schedule> {
+    all().await.into_iter().find(|schedule| schedule.name == name)
+}
+
+// The schedule with its next run times, for /api/schedules
+pub fn describe(schedule: &Schedule) -> serde_json::Value {
+    let next_runs: Vec<String> = match (parse_cron(&schedule.cron), parse_timezone(&schedule.timezone)) {
+        (Ok(cron), Ok(tz)) if schedule.enabled => next_runs(&cron, tz, Utc::now(), PREVIEW_RUNS).iter().map(|at| at.to_rfc3339()).collect(),
+        _ => Vec::new(),
+    };
+    let mut value = serde_json::json!(schedule);
+    value["next_runs"] = serde_json::json!(next_runs);
+    value
+}
+
+pub fn spawn_scheduler() {
+    tokio::spawn(async move {
+        let mut checked = Utc::now();
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let now = Utc::now();
+            // Re-read every time so API changes take effect without a restart
+            for schedule in all().await.into_iter().filter(|schedule| schedule.enabled) {
+                if let Some(at) = due(&schedule, checked, now) {
+                    if claim(&schedule, at).await {
+                        run(schedule, at);
+                    }
+                }
+            }
+            checked = now;
+        }
+    });
+}
+
+// The run time that passed between two checks, if any
+fn due(schedule: &Schedule, since: DateTime<Utc>, now: DateTime<Utc>) -> Option<DateTime<Tz>> {
+    let (cron, tz) = match (parse_cron(&schedule.cron), parse_timezone(&schedule.timezone)) {
+        (Ok(cron), Ok(tz)) => (cron, tz),
+        (Err(e), _) | (_, Err(e)) => {
+            error!("Schedule {} is invalid: {}", schedule.name, e);
+            return None;
+        }
+    };
+    // The second pass through a repeated hour finds the first one again, which has already run
+    next_runs(&cron, tz, since, 1).into_iter().next().filter(|at| *at > since && *at <= now)
+}
+
+// Without the archive there is a single process, which always runs it
+async fn claim(schedule: &Schedule, at: DateTime<Tz>) -> bool {
+    if !archive::enabled() {
+        return true;
+    }
+    match archive::claim_schedule_run(&schedule.name, at.with_timezone(&Utc), CLAIM_DAYS).await {
+        Ok(true) => true,
+        Ok(false) => {
+            info!("Schedule {} run at {} was queued by another replica", schedule.name, at.to_rfc3339());
+            false
+        }
+        Err(e) => {
+            error!("Failed to claim schedule {} run at {}: {}", schedule.name, at.to_rfc3339(), e);
+            false
+        }
+    }
+}
+
+fn run(schedule: Schedule, at: DateTime<Tz>) {
+    info!("Schedule {} is due ({}), queueing a digest", schedule.name, at.to_rfc3339());
+    let date = at.date_naive();
+    let settings = schedule.settings;
+    let title = match &settings.title {
+        Some(title) => format!("{}: {}", title, date.format("%B %-d, %Y")),
+        None => crate::backfill::digest_title(date),
+    };
+    let (job, handle) = crate::spawn_generation_job(crate::GenerationJobRequest {
+        persona: settings.persona,
+        title: Some(title),
+        source: settings.source,
+        prompt: settings.prompt,
+        feed_topic: settings.feed_topic,
+        ..Default::default()
+    });
+    let voice = settings.voice;
+    tokio::spawn(async move {
+        let Ok(Ok(result)) = handle.await else { return };
+        let Some(episode_id) = result.get("episode_id").and_then(|id| id.as_str()) else { return };
+        if let Err(e) = crate::render_scheduled_episode(episode_id, voice).await {
+            error!("Schedule {} generated episode {} (job {}) but rendering failed: {}", schedule.name, episode_id, job.id, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn runs_at_local_time_across_dst() {
+        let cron = parse_cron("0 6 * * *").unwrap();
+        let tz = parse_timezone("Europe/Berlin").unwrap();
+        // Clocks go forward on March 30, 2025: 6am is 05:00 UTC before and 04:00 UTC after
+        let runs: Vec<DateTime<Utc>> = next_runs(&cron, tz, utc(2025, 3, 29, 0, 0), 2).iter().map(|at| at.with_timezone(&Utc)).collect();
+        assert_eq!(runs, vec![utc(2025, 3, 29, 5, 0), utc(2025, 3, 30, 4, 0)]);
+    }
+
+    #[test]
+    fn skipped_and_repeated_times_run_once() {
+        let cron = parse_cron("30 2 * * *").unwrap();
+        let tz = parse_timezone("America/New_York").unwrap();
+        // 2:30 doesn't exist on March 9, 2025 and runs right after the gap, at 3:00 EDT
+        let spring = next_runs(&cron, tz, utc(2025, 3, 9, 0, 0), 1);
+        assert_eq!(spring[0].with_timezone(&Utc), utc(2025, 3, 9, 7, 0));
+        // 1:30 happens twice on November 2, 2025; only the first one runs
+        let cron = parse_cron("30 1 * * *").unwrap();
+        let fall: Vec<DateTime<Utc>> = next_runs(&cron, tz, utc(2025, 11, 2, 0, 0), 2).iter().map(|at| at.with_timezone(&Utc)).collect();
+        assert_eq!(fall, vec![utc(2025, 11, 2, 5, 30), utc(2025, 11, 3, 6, 30)]);
+    }
+
+    #[test]
+    fn rejects_bad_settings() {
+        assert!(parse_cron("every morning").is_err());
+        assert!(parse_timezone("Mars/Olympus").is_err());
+        assert!(parse_timezone("UTC").is_ok());
+        assert!(valid_name("weekly-ask-hn"));
+        assert!(!valid_name("Weekly Ask HN"));
+        assert!(!valid_name(""));
+    }
+
+    #[test]
+    fn source_limits_are_bounded() {
+        assert_eq!(Source::default().limit(), DEFAULT_STORIES);
+        assert_eq!(Source { limit: Some(500), ..Default::default() }.limit(), MAX_STORIES);
+    }
+}
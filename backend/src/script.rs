@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+
+// Post-processing for generated podcast scripts
+
+// --- Length caps ---
+
+// Rough spoken-English ratio used when the upstream doesn't report token usage
+const CHARS_PER_TOKEN: usize = 4;
+// ~150 spoken words per minute at ~1.3 tokens per word
+const TOKENS_PER_MINUTE: u32 = 200;
+
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+// Hard cap from SCRIPT_MAX_TOKENS (default 6000), optionally tightened per request
+pub fn max_tokens(requested: Option<u32>, target_minutes: Option<u32>) -> u32 {
+    let hard_cap = env::var("SCRIPT_MAX_TOKENS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&v: &u32| v > 0)
+        .unwrap_or(6000);
+
+    // Allow 20% slack over the requested duration before cutting
+    let from_duration = target_minutes.map(|m| m.saturating_mul(TOKENS_PER_MINUTE) * 6 / 5);
+
+    [Some(hard_cap), requested, from_duration]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(hard_cap)
+}
+
+// --- Episode modes ---
+
+// `flash` is a 60-90 second single-story news brief for breaking stories (e.g. radar hits): a
+// tight prompt, one host and no music or segment cues
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    #[default]
+    Full,
+    Flash,
+}
+
+const FLASH_MAX_SECS: u32 = 90;
+
+impl Mode {
+    pub fn system_prompt(self) -> &'static str {
+        match self {
+            Mode::Full => include_str!("prompt.md"),
+            Mode::Flash => include_str!("flash_prompt.md"),
+        }
+    }
+
+    // Flashes stay within FLASH_MAX_SECS of speech whatever the request asks for
+    pub fn max_tokens(self, requested: Option<u32>, target_minutes: Option<u32>) -> u32 {
+        let max = max_tokens(requested, target_minutes);
+        match self {
+            Mode::Full => max,
+            Mode::Flash => max.min(FLASH_MAX_SECS * TOKENS_PER_MINUTE / 60 * 6 / 5),
+        }
+    }
+}
+
+// Drops [MUSIC]/[TRANSITION]-style cues and heading lines the model added anyway
+pub fn strip_cues(script: &str) -> String {
+    let cue = regex::Regex::new(r"\[[A-Z][A-Z0-9 _:/&'-]*\]").unwrap();
+    let lines: Vec<String> = script
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .map(|line| cue.replace_all(line, "").split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    lines.join("\n").split("\n\n").map(str::trim).filter(|p| !p.is_empty()).collect::<Vec<_>>().join("\n\n")
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LengthReport {
+    pub max_tokens: u32,
+    pub estimated_tokens: usize,
+    pub truncated: bool,
+}
+
+// Cut the script at the last sentence boundary that fits in `max_tokens`
+pub fn enforce_length(script: &str, max_tokens: u32) -> (String, LengthReport) {
+    let estimated_tokens = estimate_tokens(script);
+    if estimated_tokens <= max_tokens as usize {
+        return (
+            script.to_string(),
+            LengthReport { max_tokens, estimated_tokens, truncated: false },
+        );
+    }
+
+    let max_chars = max_tokens as usize * CHARS_PER_TOKEN;
+    let limit = script.char_indices().nth(max_chars).map(|(i, _)| i).unwrap_or(script.len());
+    let head = &script[..limit];
+
+    // Prefer a sentence end, then a paragraph break, then a word boundary
+    let cut = head
+        .rmatch_indices(['.', '!', '?'])
+        .map(|(i, _)| i + 1)
+        .find(|&i| head[i..].starts_with(char::is_whitespace) || i == head.len())
+        .or_else(|| head.rfind("\n\n"))
+        .or_else(|| head.rfind(char::is_whitespace))
+        .unwrap_or(limit);
+
+    let truncated = head[..cut].trim_end().to_string();
+    let report = LengthReport {
+        max_tokens,
+        estimated_tokens: estimate_tokens(&truncated),
+        truncated: true,
+    };
+    (truncated, report)
+}
+
+// --- Speaking-time estimation ---
+
+const DEFAULT_WPM: f64 = 150.0;
+
+// Words per minute for a voice: VOICE_WPM="Kore=155,Puck=170" overrides the 150 wpm default
+pub fn voice_wpm(voice: &str) -> f64 {
+    env::var("VOICE_WPM")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case(voice))
+        .and_then(|(_, wpm)| wpm.trim().parse().ok())
+        .filter(|&wpm: &f64| wpm > 0.0)
+        .unwrap_or(DEFAULT_WPM)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SegmentEstimate {
+    pub index: usize,
+    pub title: String,
+    pub words: usize,
+    pub start_secs: f64,
+    pub estimated_secs: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptEstimate {
+    pub wpm: f64,
+    pub total_words: usize,
+    pub estimated_secs: f64,
+    pub segments: Vec<SegmentEstimate>,
+}
+
+// A line that starts a new segment: a markdown heading, a bold-only line, or a [CUE]
+fn segment_heading(line: &str) -> Option<String> {
+    let line = line.trim();
+    if let Some(heading) = line.strip_prefix('#') {
+        return Some(heading.trim_start_matches('#').trim().to_string());
+    }
+    if line.len() > 4 && line.starts_with("**") && line.ends_with("**") {
+        return Some(line.trim_matches('*').trim().to_string());
+    }
+    if line.starts_with('[') && line.ends_with(']') {
+        return Some(line.trim_matches(|c| c == '[' || c == ']').trim().to_string());
+    }
+    None
+}
+
+// Words that will actually be spoken: drops [CUES], (timing notes) and markdown emphasis
+pub fn spoken_words(text: &str) -> usize {
+    let cues = regex::Regex::new(r"\[[^\]]*\]|\([^)]*\b(?:sec|second|seconds|min|minute|minutes)\b[^)]*\)").unwrap();
+    cues.replace_all(text, " ")
+        .split_whitespace()
+        .filter(|word| word.chars().any(|c| c.is_alphanumeric()))
+        .count()
+}
+
+pub fn estimate(script: &str, wpm: f64) -> ScriptEstimate {
+    let mut sections: Vec<(String, String)> = vec![("Opening".to_string(), String::new())];
+    for line in script.lines() {
+        match segment_heading(line) {
+            Some(heading) => sections.push((heading, String::new())),
+            None => {
+                let body = &mut sections.last_mut().unwrap().1;
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+    }
+
+    let mut start_secs = 0.0;
+    let segments: Vec<SegmentEstimate> = sections
+        .into_iter()
+        .map(|(title, body)| (title, spoken_words(&body)))
+        .filter(|(_, words)| *words > 0)
+        .enumerate()
+        .map(|(index, (title, words))| {
+            let estimated_secs = words as f64 / wpm * 60.0;
+            let segment = SegmentEstimate { index, title, words, start_secs, estimated_secs };
+            start_secs += estimated_secs;
+            segment
+        })
+        .collect();
+
+    ScriptEstimate {
+        wpm,
+        total_words: segments.iter().map(|s| s.words).sum(),
+        estimated_secs: start_secs,
+        segments,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_within_budget_are_untouched() {
+        let (script, report) = enforce_length("Short and sweet.", 100);
+        assert_eq!(script, "Short and sweet.");
+        assert!(!report.truncated);
+    }
+
+    #[test]
+    fn cuts_at_the_last_sentence_that_fits() {
+        let (script, report) = enforce_length("One two three. Four five six. Seven eight nine.", 8);
+        assert_eq!(script, "One two three. Four five six.");
+        assert!(report.truncated);
+        assert!(report.estimated_tokens <= 8);
+    }
+
+    #[test]
+    fn falls_back_to_a_word_boundary() {
+        let (script, _) = enforce_length("alpha beta gamma delta epsilon", 3);
+        assert_eq!(script, "alpha beta");
+    }
+
+    #[test]
+    fn cuts_multibyte_text_on_a_char_boundary() {
+        let (script, report) = enforce_length("ééééééééééééé", 1);
+        assert_eq!(script, "éééé");
+        assert!(report.truncated);
+    }
+
+    #[test]
+    fn strip_cues_drops_cues_and_headings() {
+        assert_eq!(strip_cues("[MUSIC] Hello there\n# Heading\nWorld [TRANSITION]\n\n\n[OUTRO]"), "Hello there\nWorld");
+    }
+}
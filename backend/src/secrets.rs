@@ -0,0 +1,99 @@
+use std::env;
+use tracing::info;
+
+// Secrets can come from files (`<KEY>_FILE`, e.g. Docker/Kubernetes secret mounts) or a HashiCorp
+// Vault KV secret, and are exported into the environment at startup so the rest of the code keeps
+// reading plain env vars. Values already set in the environment win.
+
+pub const SECRET_KEYS: [&str; 10] = [
+    "ALCHEMYST_API_KEY",
+    "GEMINI_API_KEY",
+    "OPENAI_API_KEY",
+    "ELEVENLABS_API_KEY",
+    "HOOKS_SECRET",
+    "ADMIN_TOKEN",
+    "TELEGRAM_BOT_TOKEN",
+    "NOSTR_PRIVATE_KEY",
+    "SMTP_USERNAME",
+    "SMTP_PASSWORD",
+];
+
+fn is_unset(key: &str) -> bool {
+    env::var(key).map(|v| v.is_empty()).unwrap_or(true)
+}
+
+fn read_secret_file(key: &str) -> Result<Option<String>, String> {
+    let path = match env::var(format!("{}_FILE", key)) {
+        Ok(path) if !path.is_empty() => path,
+        _ => return Ok(None),
+    };
+    std::fs::read_to_string(&path)
+        .map(|value| Some(value.trim().to_string()))
+        .map_err(|e| format!("{}_FILE {} can't be read: {}", key, path, e))
+}
+
+// VAULT_ADDR + VAULT_TOKEN (or VAULT_TOKEN_FILE) + VAULT_SECRET_PATH, e.g. "secret/data/hn-podcast"
+async fn load_vault(errors: &mut Vec<String>) {
+    let (addr, path) = match (env::var("VAULT_ADDR"), env::var("VAULT_SECRET_PATH")) {
+        (Ok(addr), Ok(path)) if !addr.is_empty() && !path.is_empty() => (addr, path),
+        _ => return,
+    };
+    let token = match read_secret_file("VAULT_TOKEN") {
+        Ok(Some(token)) => token,
+        Ok(None) => env::var("VAULT_TOKEN").unwrap_or_default(),
+        Err(e) => return errors.push(e),
+    };
+    if token.is_empty() {
+        return errors.push("VAULT_TOKEN (or VAULT_TOKEN_FILE) is required when VAULT_ADDR is set".to_string());
+    }
+
+    let url = format!("{}/v1/{}", addr.trim_end_matches('/'), path.trim_start_matches('/'));
+    let response = reqwest::Client::new()
+        .get(&url)
+        .header("X-Vault-Token", token)
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await;
+
+    let value: serde_json::Value = match response {
+        Ok(r) if r.status().is_success() => match r.json().await {
+            Ok(value) => value,
+            Err(e) => return errors.push(format!("Vault secret {} is not valid JSON: {}", path, e)),
+        },
+        Ok(r) => return errors.push(format!("Vault returned {} for secret {}", r.status(), path)),
+        Err(e) => return errors.push(format!("Vault at {} is not reachable: {}", addr, e)),
+    };
+
+    // KV v2 nests the values under data.data, KV v1 under data
+    let data = value.get("data").map(|d| d.get("data").filter(|d| d.is_object()).unwrap_or(d));
+    let Some(data) = data.and_then(|d| d.as_object()) else {
+        return errors.push(format!("Vault secret {} has no data", path));
+    };
+
+    let mut loaded = 0;
+    for key in SECRET_KEYS {
+        if let Some(value) = data.get(key).and_then(|v| v.as_str()) {
+            if is_unset(key) {
+                env::set_var(key, value);
+                loaded += 1;
+            }
+        }
+    }
+    info!("Loaded {} secret(s) from Vault path {}", loaded, path);
+}
+
+// Returns problems to report alongside the rest of the startup validation
+pub async fn load() -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for key in SECRET_KEYS {
+        match read_secret_file(key) {
+            Ok(Some(value)) if is_unset(key) => env::set_var(key, value),
+            Ok(_) => {}
+            Err(e) => errors.push(e),
+        }
+    }
+
+    load_vault(&mut errors).await;
+    errors
+}
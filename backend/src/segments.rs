@@ -0,0 +1,282 @@
+use crate::chats::{self, ChatMessage};
+use crate::RelatedStory;
+use futures::StreamExt;
+use std::env;
+use std::time::Duration;
+use tracing::{error, info};
+
+// Digests of a known story list are drafted one segment per story, SEGMENT_CONCURRENCY at a time
+// (default 4), instead of in one long call. Each segment is retried on its own (SEGMENT_MAX_ATTEMPTS
+// in total, default 2), so a failure costs one story's call rather than the whole script; a story
+// whose segment still fails is left out and noted in the show notes, and the episode is marked
+// degraded. A final stitching pass then writes only the opening, the transitions and the closing,
+// and the episode is assembled from the pieces in story order.
+
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+// Share of the script budget left to the segments; the rest is for the stitching pass
+const SEGMENT_BUDGET_PERCENT: u32 = 85;
+const MIN_SEGMENT_TOKENS: u32 = 150;
+
+fn concurrency() -> usize {
+    env::var("SEGMENT_CONCURRENCY").ok().and_then(|v| v.parse().ok()).filter(|&n| n > 0).unwrap_or(4)
+}
+
+fn max_attempts() -> u32 {
+    env::var("SEGMENT_MAX_ATTEMPTS").ok().and_then(|v| v.parse().ok()).filter(|&n| n > 0).unwrap_or(2)
+}
+
+// Shared by every call of one episode
+pub struct Context<'a> {
+    pub api_url: &'a str,
+    pub api_key: &'a str,
+    pub persona: &'a str,
+    pub scope: &'a str,
+    pub tools: &'a serde_json::Value,
+    // The whole script's budget, split between the segments
+    pub max_tokens: u32,
+    // Extra system messages (anonymization, source caveats) every segment must follow
+    pub notes: &'a [ChatMessage],
+}
+
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub story: RelatedStory,
+    pub script: String,
+}
+
+// A story left out of the episode and why
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Omission {
+    // None for parts of a script that aren't tied to one story
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub story_id: Option<u32>,
+    pub title: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Drafts {
+    pub segments: Vec<Segment>,
+    pub omitted: Vec<Omission>,
+}
+
+fn segment_tokens(max_tokens: u32, stories: usize) -> u32 {
+    (max_tokens * SEGMENT_BUDGET_PERCENT / 100 / stories.max(1) as u32).max(MIN_SEGMENT_TOKENS)
+}
+
+// One segment per story, in story order, leaving out stories whose segment still failed after its
+// retries; Err only when no segment could be written
+pub async fn draft(stories: &[RelatedStory], context: &Context<'_>) -> Result<Drafts, String> {
+    let started = std::time::Instant::now();
+    let client = reqwest::Client::new();
+    let max_tokens = segment_tokens(context.max_tokens, stories.len());
+    let results: Vec<Result<Segment, String>> = futures::stream::iter(stories.iter().cloned())
+        .map(|story| draft_with_retries(&client, story, context, max_tokens))
+        .buffered(concurrency())
+        .collect()
+        .await;
+    let mut drafts = Drafts::default();
+    for (story, result) in stories.iter().zip(results) {
+        match result {
+            Ok(segment) => drafts.segments.push(segment),
+            Err(error) => drafts.omitted.push(Omission { story_id: Some(story.id), title: story.title.clone(), error }),
+        }
+    }
+    info!(
+        "Drafted {} segments in {:.1}s ({} left out)",
+        drafts.segments.len(),
+        started.elapsed().as_secs_f64(),
+        drafts.omitted.len()
+    );
+    if drafts.segments.is_empty() {
+        let first = drafts.omitted.first().map(|o| o.error.clone()).unwrap_or_default();
+        return Err(format!("No segment could be written: {}", first));
+    }
+    Ok(drafts)
+}
+
+async fn draft_with_retries(client: &reqwest::Client, story: RelatedStory, context: &Context<'_>, max_tokens: u32) -> Result<Segment, String> {
+    let attempts = max_attempts();
+    let mut attempt = 1;
+    loop {
+        match draft_one(client, &story, context, max_tokens).await {
+            Ok(script) => return Ok(Segment { story, script }),
+            Err(e) if attempt < attempts => {
+                error!("Segment for story {} failed (attempt {}/{}): {}", story.id, attempt, attempts, e);
+                attempt += 1;
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+            Err(e) => return Err(format!("Segment for \"{}\" failed after {} attempts: {}", story.title, attempts, e)),
+        }
+    }
+}
+
+async fn draft_one(client: &reqwest::Client, story: &RelatedStory, context: &Context<'_>, max_tokens: u32) -> Result<String, String> {
+    let mut chat_history = vec![ChatMessage::new("system", include_str!("segment_prompt.md"))];
+    chat_history.extend(context.notes.iter().cloned());
+    let mut details = vec![format!("https://news.ycombinator.com/item?id={}", story.id)];
+    details.extend(story.url.clone());
+    if let Some(points) = story.points {
+        details.insert(0, format!("{} points", points));
+    }
+    chat_history.push(ChatMessage::new("user", format!(
+        "Write the segment about the HackerNews story \"{}\" ({}) using the available context from my workspace. Keep it to about {} words.",
+        story.title,
+        details.join(", "),
+        max_tokens * 3 / 4,
+    )));
+
+    let body = serde_json::json!({
+        "chat_history": chat_history,
+        "persona": context.persona,
+        "scope": context.scope,
+        "stream": false,
+        "max_tokens": max_tokens,
+        "tools": context.tools,
+    });
+    let response = client
+        .post(format!("{}/api/v1/chat/generate", context.api_url))
+        .header("Authorization", format!("Bearer {}", context.api_key))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("upstream returned {}", status));
+    }
+    let value: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    let script = chats::extract_content(&value).map(chats::content_text).unwrap_or_default();
+    let script = crate::script::strip_cues(&script);
+    if script.trim().is_empty() {
+        return Err("upstream returned an empty segment".to_string());
+    }
+    Ok(script)
+}
+
+// Instructions for the stitching pass, which sees the drafts but only writes the glue
+pub fn stitch_request(title: &str, segments: &[Segment]) -> String {
+    let drafts: Vec<String> = segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| format!("SEGMENT {} - \"{}\"\n{}", i + 1, segment.story.title, segment.script))
+        .collect();
+    let transitions: Vec<String> = (1..segments.len()).map(|i| format!("=== TRANSITION {} ===", i)).collect();
+    format!(
+        "The segments of the podcast episode \"{}\" are already written, in this order:\n\n{}\n\n\
+         Write only the connecting parts: an opening that hooks the listener and previews the episode, \
+         a short transition between each pair of consecutive segments, and a closing reflection with a sign-off. \
+         Don't repeat or rewrite the segments. Reply in exactly this layout, with each marker on its own line:\n\
+         === INTRO ===\n{}\n=== OUTRO ===",
+        title,
+        drafts.join("\n\n"),
+        transitions.join("\n"),
+    )
+}
+
+// The episode in story order, with the stitching pass's opening, transitions and closing around
+// the segments; an unparseable reply is kept as the opening
+pub fn assemble(segments: &[Segment], stitched: &str) -> String {
+    let marker = regex::Regex::new(r"(?m)^\s*=+\s*(INTRO|OUTRO|TRANSITION\s+(\d+))\s*=+\s*$").unwrap();
+    let mut intro = String::new();
+    let mut outro = String::new();
+    let mut transitions = vec![String::new(); segments.len()];
+
+    let markers: Vec<_> = marker.captures_iter(stitched).collect();
+    if markers.is_empty() {
+        error!("Stitching pass ignored the section markers; keeping its reply as the opening");
+        intro = stitched.trim().to_string();
+    }
+    for (i, captures) in markers.iter().enumerate() {
+        let start = captures.get(0).map(|m| m.end()).unwrap_or_default();
+        let end = markers.get(i + 1).and_then(|next| next.get(0)).map(|m| m.start()).unwrap_or(stitched.len());
+        let text = stitched[start..end].trim().to_string();
+        match (&captures[1], captures.get(2).and_then(|n| n.as_str().parse::<usize>().ok())) {
+            (_, Some(n)) => {
+                if let Some(slot) = transitions.get_mut(n) {
+                    *slot = text;
+                }
+            }
+            ("INTRO", None) => intro = text,
+            _ => outro = text,
+        }
+    }
+
+    let mut parts = vec![intro];
+    for (i, segment) in segments.iter().enumerate() {
+        parts.push(std::mem::take(&mut transitions[i]));
+        parts.push(format!("## {}", segment.story.title));
+        parts.push(segment.script.trim().to_string());
+    }
+    parts.push(outro);
+    parts.retain(|part| !part.is_empty());
+    parts.join("\n\n")
+}
+
+// The stories covered, with their HN threads, and any left out
+pub fn show_notes(drafts: &Drafts) -> String {
+    let mut notes = String::from("Stories in this episode:");
+    for segment in &drafts.segments {
+        notes.push_str(&format!("\n- {} (https://news.ycombinator.com/item?id={})", segment.story.title, segment.story.id));
+    }
+    if !drafts.omitted.is_empty() {
+        notes.push_str("\n\n");
+        notes.push_str(&omission_note("Left out because their segments couldn't be generated:", &drafts.omitted));
+    }
+    notes
+}
+
+pub fn omission_note(heading: &str, omitted: &[Omission]) -> String {
+    let mut note = heading.to_string();
+    for omission in omitted {
+        match omission.story_id {
+            Some(id) => note.push_str(&format!("\n- {} (https://news.ycombinator.com/item?id={})", omission.title, id)),
+            None => note.push_str(&format!("\n- {}", omission.title)),
+        }
+    }
+    note
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(id: u32, title: &str, script: &str) -> Segment {
+        Segment { story: RelatedStory { id, title: title.to_string(), url: None, points: None }, script: script.to_string() }
+    }
+
+    #[test]
+    fn assemble_places_glue_between_segments() {
+        let segments = [segment(1, "First", "About the first."), segment(2, "Second", "About the second.")];
+        let stitched = "=== INTRO ===\nWelcome.\n=== TRANSITION 1 ===\nNext up.\n  == OUTRO ==  \nBye.";
+        assert_eq!(
+            assemble(&segments, stitched),
+            "Welcome.\n\n## First\n\nAbout the first.\n\nNext up.\n\n## Second\n\nAbout the second.\n\nBye."
+        );
+    }
+
+    #[test]
+    fn assemble_ignores_transitions_past_the_last_segment() {
+        let segments = [segment(1, "Only", "The one story.")];
+        let stitched = "=== INTRO ===\nHi.\n=== TRANSITION 5 ===\nStray.\n=== OUTRO ===\nBye.";
+        assert_eq!(assemble(&segments, stitched), "Hi.\n\n## Only\n\nThe one story.\n\nBye.");
+    }
+
+    #[test]
+    fn assemble_keeps_an_unmarked_reply_as_the_opening() {
+        let segments = [segment(1, "Only", "The one story.")];
+        assert_eq!(assemble(&segments, "  Just an intro.  "), "Just an intro.\n\n## Only\n\nThe one story.");
+    }
+
+    #[test]
+    fn omission_note_links_stories() {
+        let omitted = [
+            Omission { story_id: Some(7), title: "Story".to_string(), error: "timeout".to_string() },
+            Omission { story_id: None, title: "Closing".to_string(), error: "timeout".to_string() },
+        ];
+        assert_eq!(
+            omission_note("Left out:", &omitted),
+            "Left out:\n- Story (https://news.ycombinator.com/item?id=7)\n- Closing"
+        );
+    }
+}
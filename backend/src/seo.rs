@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::env;
+
+// Sitemap and page metadata for the frontend's story pages (/story/:id), so an SSR layer or
+// prerenderer can emit indexable pages. Page URLs are built from SITE_URL, the public address of
+// the frontend (PUBLIC_BASE_URL is the backend's).
+
+const DESCRIPTION_CHARS: usize = 200;
+
+pub fn site_url() -> String {
+    env::var("SITE_URL")
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| "http://localhost:5173".to_string())
+}
+
+pub fn story_page(id: u32) -> String {
+    format!("{}/story/{}", site_url(), id)
+}
+
+pub struct SitemapEntry {
+    pub loc: String,
+    pub lastmod: Option<DateTime<Utc>>,
+    pub changefreq: &'static str,
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+pub fn sitemap(entries: &[SitemapEntry]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for entry in entries {
+        xml.push_str(&format!("  <url>\n    <loc>{}</loc>\n", escape_xml(&entry.loc)));
+        if let Some(lastmod) = entry.lastmod {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", lastmod.format("%Y-%m-%d")));
+        }
+        xml.push_str(&format!("    <changefreq>{}</changefreq>\n  </url>\n", entry.changefreq));
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+#[derive(Debug, Serialize)]
+pub struct PageSeo {
+    pub title: String,
+    pub description: String,
+    pub canonical_url: String,
+    pub og_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub og_image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+// Whitespace collapsed and cut at a word boundary
+pub fn description(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= DESCRIPTION_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(DESCRIPTION_CHARS).collect();
+    format!("{}...", cut[..cut.rfind(' ').unwrap_or(cut.len())].trim_end_matches([',', ';', ':', '.']))
+}
+
+// og:image values are often relative to the article
+pub fn absolute_image(article_url: &str, image: &str) -> Option<String> {
+    let resolved = url::Url::parse(article_url).ok()?.join(image).ok()?;
+    matches!(resolved.scheme(), "http" | "https").then(|| resolved.to_string())
+}
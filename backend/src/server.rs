@@ -0,0 +1,71 @@
+use axum::Router;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::net::SocketAddr;
+use tracing::{error, info};
+
+// Listener setup: any number of TCP addresses (plain or TLS) plus an optional Unix domain socket
+
+async fn serve_tcp(addr: SocketAddr, app: Router) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Starting HackerNews backend server on {}", addr);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn serve_tls(addr: SocketAddr, app: Router, tls_config: axum_server::tls_rustls::RustlsConfig) -> anyhow::Result<()> {
+    info!("Starting HackerNews backend server on https://{}", addr);
+    axum_server::bind_rustls(addr, tls_config)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
+
+// axum::serve only takes a TcpListener, so Unix connections are driven through hyper-util
+async fn serve_unix(path: String, app: Router) -> anyhow::Result<()> {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::server::conn::auto::Builder;
+    use hyper_util::service::TowerToHyperService;
+
+    // A socket file left over from a previous run would make bind fail
+    if std::fs::metadata(&path).is_ok() {
+        std::fs::remove_file(&path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    info!("Starting HackerNews backend server on unix:{}", path);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            if let Err(e) = Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                error!("Unix socket connection error: {}", e);
+            }
+        });
+    }
+}
+
+// Runs every configured listener until one of them fails
+pub async fn run(app: Router) -> anyhow::Result<()> {
+    let tls_config = match crate::config::tls_paths() {
+        Some((cert_path, key_path)) => Some(axum_server::tls_rustls::RustlsConfig::from_pem_file(cert_path, key_path).await?),
+        None => None,
+    };
+
+    let mut servers: Vec<BoxFuture<'static, anyhow::Result<()>>> = Vec::new();
+    for addr in crate::config::bind_addrs() {
+        match &tls_config {
+            Some(tls_config) => servers.push(serve_tls(addr, app.clone(), tls_config.clone()).boxed()),
+            None => servers.push(serve_tcp(addr, app.clone()).boxed()),
+        }
+    }
+    if let Some(path) = crate::config::unix_socket_path() {
+        servers.push(serve_unix(path, app).boxed());
+    }
+
+    futures::future::try_join_all(servers).await?;
+    Ok(())
+}
@@ -0,0 +1,230 @@
+use crate::archive::Episode;
+use crate::embed;
+use crate::feed::{self, Format, Page};
+use crate::seo::escape_xml;
+use serde::Serialize;
+use std::env;
+use std::path::{Path, PathBuf};
+
+// Static site export (POST /admin/export/site): every published episode written to
+// SITE_EXPORT_DIR (default site-export) as a site any static host can serve, for an archive or
+// a mirror that needs no backend. It holds index.html, a page per episode (player, show notes,
+// chapters, transcript), the feeds and the audio. Paths mirror the backend's (/feed.xml,
+// /audio/<file>, /api/episodes/<id>/transcript and /chapters), so everything is rendered as the
+// backend serves it with PUBLIC_BASE_URL swapped for the mirror's address. Feeds aren't paged:
+// a static archive keeps every episode in one document per format.
+//
+// The export is built next to the directory and swapped in when complete, so a host serving the
+// directory never sees half an export. Audio files are hard links where the filesystem allows.
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Export {
+    pub dir: String,
+    pub base_url: String,
+    pub episodes: usize,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+pub fn dir() -> PathBuf {
+    PathBuf::from(env::var("SITE_EXPORT_DIR").ok().filter(|d| !d.is_empty()).unwrap_or_else(|| "site-export".to_string()))
+}
+
+// Backend URLs in `text` pointed at the mirror instead
+fn rebase(text: &str, base_url: &str) -> String {
+    text.replace(&crate::alerts::public_base_url(), base_url)
+}
+
+// Show notes are plain text: "- " lines become a list, other lines paragraphs
+fn notes_html(notes: &str) -> String {
+    let mut html = String::new();
+    let mut in_list = false;
+    for line in notes.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        match line.strip_prefix("- ") {
+            Some(item) => {
+                if !in_list {
+                    html.push_str("<ul>");
+                    in_list = true;
+                }
+                html.push_str(&format!("<li>{}</li>", escape_xml(item)));
+            }
+            None => {
+                if in_list {
+                    html.push_str("</ul>");
+                    in_list = false;
+                }
+                html.push_str(&format!("<p>{}</p>", escape_xml(line)));
+            }
+        }
+    }
+    if in_list {
+        html.push_str("</ul>");
+    }
+    html
+}
+
+fn head(title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n\
+         <link rel=\"alternate\" type=\"application/rss+xml\" href=\"{base}/feed.xml\">\n<style>{}</style>\n</head>\n<body>\n<main>\n",
+        escape_xml(title),
+        embed::STYLE,
+        base = crate::alerts::public_base_url()
+    )
+}
+
+fn episode_page(episode: &Episode) -> Option<String> {
+    let audio = episode.audio.as_ref()?;
+    let mut html = head(&episode.title);
+    html.push_str(&format!("<p><a href=\"../../\">{}</a></p>\n", escape_xml(&feed::show_title())));
+    html.push_str(&format!(
+        "<h1>{}</h1>\n<p><time datetime=\"{}\">{}</time> · Episode {}</p>\n",
+        escape_xml(&episode.title),
+        episode.published_at().to_rfc3339(),
+        episode.published_at().format("%B %-d, %Y"),
+        episode.episode_number
+    ));
+    html.push_str(&format!("<audio controls preload=\"metadata\" src=\"{}\"></audio>\n", escape_xml(&audio.url)));
+    html.push_str(&embed::chapters(&audio.chapters));
+    if let Some(notes) = episode.show_notes.as_deref().filter(|notes| !notes.trim().is_empty()) {
+        html.push_str(&format!("<section class=\"notes\">{}</section>\n", notes_html(notes)));
+    }
+    if let Some(script) = episode.script.as_deref().filter(|script| !script.trim().is_empty()) {
+        html.push_str(&format!("<details class=\"transcript\">\n<summary>Transcript</summary>\n{}\n</details>\n", embed::transcript(script)));
+    }
+    html.push_str(&format!("</main>\n<script>{}</script>\n</body>\n</html>\n", embed::SCRIPT));
+    Some(html)
+}
+
+// Newest first, like the feeds
+fn index_page(episodes: &[Episode]) -> String {
+    let title = feed::show_title();
+    let mut html = head(&title);
+    html.push_str(&format!("<h1>{}</h1>\n<p><a href=\"feed.xml\">RSS</a> · <a href=\"feed.atom\">Atom</a> · <a href=\"feed.json\">JSON Feed</a></p>\n<ol class=\"episodes\">\n", escape_xml(&title)));
+    for episode in episodes {
+        html.push_str(&format!(
+            "<li><a href=\"episodes/{}/\">{}</a> <time datetime=\"{}\">{}</time></li>\n",
+            escape_xml(&episode.id),
+            escape_xml(&episode.title),
+            episode.published_at().to_rfc3339(),
+            episode.published_at().format("%Y-%m-%d")
+        ));
+    }
+    html.push_str("</ol>\n</main>\n</body>\n</html>\n");
+    html
+}
+
+// The audio URLs as the backend serves them now, whatever address they were rendered under
+fn current_urls(mut episode: Episode) -> Episode {
+    if let Some(audio) = episode.audio.as_mut() {
+        audio.url = crate::episode_audio::url(&audio.file);
+        for rendition in &mut audio.renditions {
+            rendition.url = crate::episode_audio::url(&rendition.file);
+        }
+    }
+    episode
+}
+
+struct Writer {
+    root: PathBuf,
+    export: Export,
+}
+
+impl Writer {
+    async fn write(&mut self, name: &str, contents: String) -> Result<(), String> {
+        let path = self.root.join(name);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let contents = rebase(&contents, &self.export.base_url);
+        self.export.files += 1;
+        self.export.bytes += contents.len() as u64;
+        tokio::fs::write(&path, contents).await.map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    async fn link_audio(&mut self, file: &str) -> Result<(), String> {
+        let source = crate::episode_audio::dir().join(file);
+        let path = self.root.join("audio").join(file);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        crate::artifacts::link(&source, &path).await?;
+        self.export.files += 1;
+        self.export.bytes += tokio::fs::metadata(&path).await.map(|m| m.len()).unwrap_or(0);
+        Ok(())
+    }
+}
+
+// `episodes` newest first, all with rendered audio
+pub async fn export(episodes: Vec<Episode>, base_url: &str) -> Result<Export, String> {
+    let target = dir();
+    let staging = sibling(&target, "partial");
+    let _ = tokio::fs::remove_dir_all(&staging).await;
+    tokio::fs::create_dir_all(&staging).await.map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+    let episodes: Vec<Episode> = episodes.into_iter().map(current_urls).collect();
+    let mut writer = Writer {
+        root: staging.clone(),
+        export: Export { dir: target.display().to_string(), base_url: base_url.trim_end_matches('/').to_string(), ..Default::default() },
+    };
+
+    for episode in &episodes {
+        let Some(audio) = &episode.audio else { continue };
+        writer.link_audio(&audio.file).await?;
+        for rendition in &audio.renditions {
+            writer.link_audio(&rendition.file).await?;
+        }
+        let api = format!("api/episodes/{}", episode.id);
+        if let Some(script) = episode.script.clone().filter(|script| !script.trim().is_empty()) {
+            writer.write(&format!("{}/transcript", api), script).await?;
+        }
+        if !audio.chapters.is_empty() {
+            writer.write(&format!("{}/chapters", api), feed::chapters_json(&audio.chapters).to_string()).await?;
+        }
+        if let Some(page) = episode_page(episode) {
+            writer.write(&format!("episodes/{}/index.html", episode.id), page).await?;
+        }
+        writer.export.episodes += 1;
+    }
+    for (format, name) in [(Format::Rss, "feed.xml"), (Format::Atom, "feed.atom"), (Format::Json, "feed.json")] {
+        let page = Page { format, archive: None, archive_pages: 0, topic: None, quality: None };
+        writer.write(name, feed::render(&episodes, &page)).await?;
+    }
+    writer.write("index.html", index_page(&episodes)).await?;
+
+    // Swapped in whole
+    let previous = sibling(&target, "old");
+    let _ = tokio::fs::remove_dir_all(&previous).await;
+    if tokio::fs::try_exists(&target).await.unwrap_or(false) {
+        tokio::fs::rename(&target, &previous).await.map_err(|e| format!("Failed to move the previous export aside: {}", e))?;
+    }
+    tokio::fs::rename(&staging, &target).await.map_err(|e| format!("Failed to move the export into {}: {}", target.display(), e))?;
+    let _ = tokio::fs::remove_dir_all(&previous).await;
+    Ok(writer.export)
+}
+
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().map(|name| name.to_os_string()).unwrap_or_else(|| "site-export".into());
+    name.push(format!(".{}", suffix));
+    dir.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn show_notes_keep_their_lists() {
+        assert_eq!(
+            notes_html("Covered:\n- Rust <3\n- Zig\n\nLeft out: Go"),
+            "<p>Covered:</p><ul><li>Rust &lt;3</li><li>Zig</li></ul><p>Left out: Go</p>"
+        );
+    }
+
+    #[test]
+    fn backend_urls_point_at_the_mirror() {
+        let backend = crate::alerts::public_base_url();
+        let text = format!("<enclosure url=\"{}/audio/e1.mp3\"/><link>https://news.ycombinator.com</link>", backend);
+        assert_eq!(rebase(&text, "https://mirror.example"), "<enclosure url=\"https://mirror.example/audio/e1.mp3\"/><link>https://news.ycombinator.com</link>");
+        assert_eq!(sibling(Path::new("out/site"), "partial"), Path::new("out/site.partial"));
+    }
+}
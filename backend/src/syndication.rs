@@ -0,0 +1,256 @@
+use futures::future::BoxFuture;
+use futures::{SinkExt, StreamExt};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::time::Duration;
+use tracing::{error, info};
+
+// Publish targets for finished episodes. Each target implements `Syndicator` and is enabled by
+// its own settings; every finished job is announced to all of them concurrently.
+//   Telegram - TELEGRAM_BOT_TOKEN + TELEGRAM_CHANNEL_ID (see telegram.rs)
+//   Nostr    - NOSTR_PRIVATE_KEY (hex) + NOSTR_RELAYS (comma-separated wss:// URLs). Posts a short
+//              note (kind 1) by default, or the full script as a long-form article (kind 30023)
+//              with NOSTR_LONG_FORM=true
+// SYNDICATION_LINK_TEMPLATE (e.g. "https://example.com/episodes/{slug}", also {job_id}) adds a
+// link to each announcement.
+
+const SUMMARY_CHARS: usize = 280;
+const RELAY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Announcement {
+    pub job_id: String,
+    pub title: String,
+    pub slug: Option<String>,
+    // Opening of the script
+    pub summary: String,
+    pub script: String,
+    pub link: Option<String>,
+    // The job result as stored, for targets with their own formatting
+    pub result: serde_json::Value,
+}
+
+impl Announcement {
+    pub fn from_result(job_id: &str, result: &serde_json::Value) -> Self {
+        let field = |key: &str| result.get(key).and_then(|v| v.as_str()).map(String::from);
+        let script = field("podcast_script").unwrap_or_default();
+        let slug = field("slug");
+        let link = env::var("SYNDICATION_LINK_TEMPLATE").ok().filter(|t| !t.is_empty()).map(|template| {
+            template.replace("{slug}", slug.as_deref().unwrap_or(job_id)).replace("{job_id}", job_id)
+        });
+        Self {
+            job_id: job_id.to_string(),
+            title: field("title").unwrap_or_else(|| "New episode".to_string()),
+            slug,
+            summary: summary(&script),
+            script,
+            link,
+            result: result.clone(),
+        }
+    }
+}
+
+// First sentences up to SUMMARY_CHARS, without section headings or stage directions
+pub fn summary(script: &str) -> String {
+    let text = script
+        .lines()
+        .map(str::trim)
+        .filter(|line| !(line.starts_with('#') || line.starts_with('[') && line.ends_with(']')))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= SUMMARY_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(SUMMARY_CHARS).collect();
+    match cut.rfind(". ") {
+        Some(end) if end > SUMMARY_CHARS / 2 => cut[..=end].to_string(),
+        _ => format!("{}…", cut[..cut.rfind(' ').unwrap_or(cut.len())].trim_end_matches([',', ';', ':'])),
+    }
+}
+
+pub trait Syndicator: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn publish<'a>(&'a self, announcement: &'a Announcement) -> BoxFuture<'a, Result<(), String>>;
+}
+
+// Targets enabled by the current environment
+pub fn targets() -> Vec<Box<dyn Syndicator>> {
+    let mut targets: Vec<Box<dyn Syndicator>> = Vec::new();
+    if crate::telegram::channel_id().is_some() {
+        targets.push(Box::new(Telegram));
+    }
+    match Nostr::from_env() {
+        Ok(Some(nostr)) => targets.push(Box::new(nostr)),
+        Ok(None) => {}
+        Err(e) => error!("Nostr syndication disabled: {}", e),
+    }
+    targets
+}
+
+pub async fn publish_episode(job_id: &str, result: &serde_json::Value) {
+    let targets = targets();
+    if targets.is_empty() {
+        return;
+    }
+    let announcement = Announcement::from_result(job_id, result);
+    let results = futures::future::join_all(targets.iter().map(|target| target.publish(&announcement))).await;
+    for (target, result) in targets.iter().zip(results) {
+        match result {
+            Ok(()) => info!("Announced job {} via {}", job_id, target.name()),
+            Err(e) => error!("Failed to announce job {} via {}: {}", job_id, target.name(), e),
+        }
+    }
+}
+
+// --- Telegram ---
+
+struct Telegram;
+
+impl Syndicator for Telegram {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    // send_message logs its own failures
+    fn publish<'a>(&'a self, announcement: &'a Announcement) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            crate::telegram::announce_episode(&announcement.result).await;
+            Ok(())
+        })
+    }
+}
+
+// --- Nostr ---
+
+struct Nostr {
+    key: k256::schnorr::SigningKey,
+    relays: Vec<String>,
+    long_form: bool,
+}
+
+pub fn nostr_relays() -> Vec<String> {
+    env::var("NOSTR_RELAYS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+pub fn nostr_key() -> Result<Option<k256::schnorr::SigningKey>, String> {
+    let Some(hex_key) = env::var("NOSTR_PRIVATE_KEY").ok().filter(|k| !k.is_empty()) else { return Ok(None) };
+    let bytes = hex::decode(hex_key.trim()).map_err(|_| "NOSTR_PRIVATE_KEY must be a hex-encoded secret key".to_string())?;
+    k256::schnorr::SigningKey::from_bytes(&bytes)
+        .map(Some)
+        .map_err(|_| "NOSTR_PRIVATE_KEY is not a valid secp256k1 secret key".to_string())
+}
+
+impl Nostr {
+    fn from_env() -> Result<Option<Self>, String> {
+        let Some(key) = nostr_key()? else { return Ok(None) };
+        let relays = nostr_relays();
+        if relays.is_empty() {
+            return Ok(None);
+        }
+        let long_form = env::var("NOSTR_LONG_FORM").map(|v| v == "true" || v == "1").unwrap_or(false);
+        Ok(Some(Self { key, relays, long_form }))
+    }
+
+    // NIP-01 event: the ID is the SHA-256 of the canonical serialization, signed with BIP-340
+    fn event(&self, announcement: &Announcement, created_at: i64) -> Result<serde_json::Value, String> {
+        let pubkey = hex::encode(self.key.verifying_key().to_bytes());
+        let link_tags = announcement.link.iter().map(|link| serde_json::json!(["r", link]));
+        let (kind, tags, content) = if self.long_form {
+            let identifier = announcement.slug.clone().unwrap_or_else(|| announcement.job_id.clone());
+            let mut tags = vec![
+                serde_json::json!(["d", identifier]),
+                serde_json::json!(["title", announcement.title]),
+                serde_json::json!(["summary", announcement.summary]),
+                serde_json::json!(["published_at", created_at.to_string()]),
+            ];
+            tags.extend(link_tags);
+            (30023, tags, announcement.script.clone())
+        } else {
+            let mut content = format!("🎙 {}\n\n{}", announcement.title, announcement.summary);
+            if let Some(link) = &announcement.link {
+                content.push_str(&format!("\n\n{}", link));
+            }
+            (1, link_tags.collect(), content)
+        };
+
+        let serialized = serde_json::json!([0, pubkey, created_at, kind, tags, content]).to_string();
+        let id: [u8; 32] = Sha256::digest(serialized.as_bytes()).into();
+        let mut aux_rand = [0u8; 32];
+        aux_rand[..16].copy_from_slice(uuid::Uuid::new_v4().as_bytes());
+        aux_rand[16..].copy_from_slice(uuid::Uuid::new_v4().as_bytes());
+        let sig = self.key.sign_prehash_with_aux_rand(&id, &aux_rand).map_err(|e| e.to_string())?;
+
+        Ok(serde_json::json!({
+            "id": hex::encode(id),
+            "pubkey": pubkey,
+            "created_at": created_at,
+            "kind": kind,
+            "tags": tags,
+            "content": content,
+            "sig": hex::encode(sig.to_bytes()),
+        }))
+    }
+}
+
+// Sends the event and waits for the relay's ["OK", <id>, <accepted>, <message>]
+async fn send_to_relay(relay: &str, event: &serde_json::Value) -> Result<(), String> {
+    use tokio_tungstenite::tungstenite::Message;
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(relay).await.map_err(|e| e.to_string())?;
+    socket.send(Message::Text(serde_json::json!(["EVENT", event]).to_string())).await.map_err(|e| e.to_string())?;
+    while let Some(message) = socket.next().await {
+        let Message::Text(text) = message.map_err(|e| e.to_string())? else { continue };
+        let Ok(serde_json::Value::Array(reply)) = serde_json::from_str(&text) else { continue };
+        if reply.first().and_then(|v| v.as_str()) != Some("OK") || reply.get(1) != event.get("id") {
+            continue;
+        }
+        let _ = socket.close(None).await;
+        return match reply.get(2).and_then(|v| v.as_bool()) {
+            Some(true) => Ok(()),
+            _ => Err(format!("rejected: {}", reply.get(3).and_then(|v| v.as_str()).unwrap_or("no reason given"))),
+        };
+    }
+    Err("connection closed before the relay answered".to_string())
+}
+
+impl Syndicator for Nostr {
+    fn name(&self) -> &'static str {
+        "nostr"
+    }
+
+    // Succeeds when at least one relay accepted the event
+    fn publish<'a>(&'a self, announcement: &'a Announcement) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let event = self.event(announcement, chrono::Utc::now().timestamp())?;
+            let event = &event;
+            let results = futures::future::join_all(self.relays.iter().map(|relay| async move {
+                let result = tokio::time::timeout(RELAY_TIMEOUT, send_to_relay(relay, event))
+                    .await
+                    .unwrap_or_else(|_| Err("timed out".to_string()));
+                (relay, result)
+            }))
+            .await;
+
+            let mut failures = Vec::new();
+            for (relay, result) in results {
+                match result {
+                    Ok(()) => info!("Nostr relay {} accepted event {}", relay, event["id"].as_str().unwrap_or_default()),
+                    Err(e) => failures.push(format!("{}: {}", relay, e)),
+                }
+            }
+            if failures.len() == self.relays.len() {
+                return Err(failures.join("; "));
+            }
+            for failure in failures {
+                error!("Nostr relay {}", failure);
+            }
+            Ok(())
+        })
+    }
+}
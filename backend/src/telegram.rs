@@ -0,0 +1,222 @@
+use serde::Deserialize;
+use std::env;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+// Optional Telegram bot: accepts `/podcast <hn-url>` commands and posts finished episodes.
+//   TELEGRAM_BOT_TOKEN         - bot token from @BotFather (enables the integration)
+//   TELEGRAM_CHANNEL_ID        - channel/chat that receives every finished episode
+//   TELEGRAM_ALLOWED_CHAT_IDS  - comma-separated chats allowed to trigger generation
+//                                (default: only TELEGRAM_CHANNEL_ID)
+
+const TELEGRAM_API: &str = "https://api.telegram.org";
+// Telegram rejects messages longer than 4096 characters
+const MAX_MESSAGE_CHARS: usize = 4000;
+
+#[derive(Debug, Deserialize)]
+struct UpdatesResponse {
+    ok: bool,
+    #[serde(default)]
+    result: Vec<Update>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    chat: Chat,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+fn bot_token() -> Option<String> {
+    env::var("TELEGRAM_BOT_TOKEN").ok().filter(|t| !t.is_empty())
+}
+
+pub fn spawn_bot() {
+    let token = match bot_token() {
+        Some(token) => token,
+        None => return,
+    };
+
+    info!("Telegram bot integration enabled");
+    if allowed_chat_ids().is_empty() {
+        match channel_id() {
+            Some(channel) => warn!("TELEGRAM_ALLOWED_CHAT_IDS is not set; only chat {} can trigger generation", channel),
+            None => warn!("TELEGRAM_ALLOWED_CHAT_IDS is not set; no chat can trigger generation"),
+        }
+    }
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut offset = 0i64;
+
+        loop {
+            match poll_updates(&client, &token, offset).await {
+                Ok(updates) => {
+                    for update in updates {
+                        offset = update.update_id + 1;
+                        if let Some(message) = update.message {
+                            handle_message(message).await;
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Telegram getUpdates failed: {}", e);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+}
+
+async fn poll_updates(client: &reqwest::Client, token: &str, offset: i64) -> Result<Vec<Update>, anyhow::Error> {
+    let url = format!("{}/bot{}/getUpdates", TELEGRAM_API, token);
+    let response: UpdatesResponse = client
+        .get(&url)
+        .query(&[("offset", offset.to_string()), ("timeout", "30".to_string())])
+        .timeout(Duration::from_secs(40))
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    if !response.ok {
+        anyhow::bail!("Telegram API returned ok=false");
+    }
+    Ok(response.result)
+}
+
+fn allowed_chat_ids() -> Vec<i64> {
+    env::var("TELEGRAM_ALLOWED_CHAT_IDS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|id| id.trim().parse().ok())
+        .collect()
+}
+
+// Generation costs LLM and TTS credits, so with no allow-list only the configured channel may trigger it
+fn chat_allowed(chat_id: i64) -> bool {
+    let allowed = allowed_chat_ids();
+    if allowed.is_empty() {
+        return channel_id().is_some_and(|channel| channel.trim() == chat_id.to_string());
+    }
+    allowed.contains(&chat_id)
+}
+
+// Accepts "https://news.ycombinator.com/item?id=123" or a bare story id
+fn parse_story_id(arg: &str) -> Option<u32> {
+    let re = regex::Regex::new(r"news\.ycombinator\.com/item\?id=(\d+)").ok()?;
+    match re.captures(arg) {
+        Some(captures) => captures.get(1)?.as_str().parse().ok(),
+        None => arg.trim().parse().ok(),
+    }
+}
+
+async fn handle_message(message: Message) {
+    let chat_id = message.chat.id;
+    let text = match message.text {
+        Some(text) => text,
+        None => return,
+    };
+
+    // Commands may be addressed as "/podcast@SomeBot <url>" in group chats
+    let mut words = text.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    if command.split('@').next() != Some("/podcast") {
+        return;
+    }
+
+    if !chat_allowed(chat_id) {
+        send_message(&chat_id.to_string(), "This chat is not allowed to trigger podcast generation.").await;
+        return;
+    }
+
+    let story_id = match words.next().and_then(parse_story_id) {
+        Some(id) => id,
+        None => {
+            send_message(&chat_id.to_string(), "Usage: /podcast <https://news.ycombinator.com/item?id=...>").await;
+            return;
+        }
+    };
+
+    let (job, handle) = crate::spawn_generation_job(crate::GenerationJobRequest {
+        story_id: Some(story_id),
+        priority: Some(crate::jobs::Priority::Interactive),
+        ..Default::default()
+    });
+    send_message(
+        &chat_id.to_string(),
+        &format!("Generating an episode for story {} (job {}).", story_id, job.id),
+    )
+    .await;
+
+    tokio::spawn(async move {
+        match handle.await {
+            Ok(Ok(result)) => {
+                // The channel already receives every finished episode via announce_episode
+                if channel_id().as_deref() != Some(chat_id.to_string().as_str()) {
+                    send_message(&chat_id.to_string(), &episode_message(&result)).await;
+                }
+            }
+            Ok(Err(e)) => send_message(&chat_id.to_string(), &format!("Generation failed: {}", e)).await,
+            Err(e) => error!("Telegram generation task panicked: {}", e),
+        }
+    });
+}
+
+pub fn channel_id() -> Option<String> {
+    env::var("TELEGRAM_CHANNEL_ID").ok().filter(|c| !c.is_empty())
+}
+
+// Post a finished episode to the configured channel
+pub async fn announce_episode(result: &serde_json::Value) {
+    if let Some(channel) = channel_id() {
+        send_message(&channel, &episode_message(result)).await;
+    }
+}
+
+// Title plus the opening of the script, trimmed to the Telegram message limit
+fn episode_message(result: &serde_json::Value) -> String {
+    let title = result.get("title").and_then(|t| t.as_str()).unwrap_or("New episode");
+    let script = result.get("podcast_script").and_then(|s| s.as_str()).unwrap_or_default();
+
+    let mut text = format!("🎙 {}\n\n{}", title, script);
+    if text.chars().count() > MAX_MESSAGE_CHARS {
+        text = text.chars().take(MAX_MESSAGE_CHARS).collect::<String>() + "…";
+    }
+    text
+}
+
+pub async fn send_message(chat_id: &str, text: &str) {
+    let token = match bot_token() {
+        Some(token) => token,
+        None => return,
+    };
+
+    let url = format!("{}/bot{}/sendMessage", TELEGRAM_API, token);
+    let result = reqwest::Client::new()
+        .post(&url)
+        .json(&serde_json::json!({
+            "chat_id": chat_id,
+            "text": text,
+            "disable_web_page_preview": true
+        }))
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if !response.status().is_success() => {
+            error!("Telegram sendMessage returned {}", response.status());
+        }
+        Err(e) => error!("Telegram sendMessage failed: {}", e),
+        _ => {}
+    }
+}
@@ -0,0 +1,276 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+// Processing for thread types that need more than generic comment context. Ask HN threads are
+// mostly answers to one question, so answers are grouped by what they recommend (tools, products,
+// sites, projects) and counted per commenter. In Show HN threads the interesting part is the
+// submitter answering questions, so their replies are picked out and sorted by topic.
+
+const MAX_RECOMMENDATIONS: usize = 8;
+const MAX_PHRASE_WORDS: usize = 3;
+// Capitalized words that open sentences in answers without naming anything
+const FILLER: &[&str] = &[
+    "yes", "yeah", "personally", "honestly", "edit", "thanks", "agreed", "basically", "currently", "actually",
+    "definitely", "otherwise", "also", "however", "unfortunately", "sure", "ok", "great", "good", "both", "anything",
+];
+
+pub struct Post<'a> {
+    pub id: u32,
+    pub parent: Option<u32>,
+    pub by: Option<&'a str>,
+    pub html: &'a str,
+}
+
+pub fn is_ask_hn(title: &str) -> bool {
+    title.trim_start().to_lowercase().starts_with("ask hn")
+}
+
+pub fn is_show_hn(title: &str) -> bool {
+    title.trim_start().to_lowercase().starts_with("show hn")
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Recommendation {
+    pub label: String,
+    // Distinct commenters who mentioned it
+    pub commenters: usize,
+    pub comment_ids: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AskHnSummary {
+    pub answers: usize,
+    pub recommendations: Vec<Recommendation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+fn href_pattern() -> &'static regex::Regex {
+    static PATTERN: OnceLock<regex::Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| regex::Regex::new(r#"href="([^"]+)""#).unwrap())
+}
+
+fn name_pattern() -> &'static regex::Regex {
+    static PATTERN: OnceLock<regex::Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        regex::Regex::new(r"\b[A-Z][A-Za-z0-9+#]*(?:[.\-][A-Za-z0-9+#]+)*(?:[ \t]+[A-Z][A-Za-z0-9+#]*(?:[.\-][A-Za-z0-9+#]+)*)*").unwrap()
+    })
+}
+
+// Linked sites: the host, or the repository name for code hosts (so a link to logseq/logseq counts
+// together with mentions of "Logseq")
+fn link_label(href: &str) -> Option<String> {
+    let href = href.replace("&#x2F;", "/").replace("&amp;", "&");
+    let parsed = url::Url::parse(&crate::urls::canonicalize(&href)).ok()?;
+    let host = parsed.host_str()?.trim_start_matches("www.").to_string();
+    if matches!(host.as_str(), "github.com" | "gitlab.com" | "codeberg.org") {
+        let path: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).take(2).collect();
+        if path.len() == 2 {
+            return Some(path[1].to_string());
+        }
+    }
+    Some(host)
+}
+
+// Capitalized names, without leading stopwords ("The") or sentence-opening filler
+fn name_labels(text: &str) -> Vec<String> {
+    name_pattern()
+        .find_iter(text)
+        .filter_map(|m| {
+            let words: Vec<&str> = m
+                .as_str()
+                .split_whitespace()
+                .skip_while(|w| crate::analysis::is_stopword(w) || FILLER.contains(&w.to_lowercase().as_str()))
+                .take(MAX_PHRASE_WORDS)
+                .collect();
+            let phrase = words.join(" ");
+            let single_common = words.len() == 1 && (crate::analysis::is_stopword(&phrase) || phrase.len() < 2);
+            (!words.is_empty() && !single_common).then_some(phrase)
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Mentions {
+    // Spellings seen, e.g. "Postgres" and "postgres"
+    labels: HashMap<String, usize>,
+    commenters: HashSet<String>,
+    comment_ids: Vec<u32>,
+}
+
+pub fn summarize_ask_hn(answers: &[Post]) -> AskHnSummary {
+    let mut mentions: HashMap<String, Mentions> = HashMap::new();
+    for answer in answers {
+        let text = crate::analysis::strip_html(answer.html);
+        let mut labels: Vec<String> = href_pattern().captures_iter(answer.html).filter_map(|c| link_label(&c[1])).collect();
+        labels.extend(name_labels(&text));
+
+        let commenter = answer.by.map(String::from).unwrap_or_else(|| format!("#{}", answer.id));
+        let mut counted = HashSet::new();
+        for label in labels {
+            let key = label.to_lowercase();
+            if !counted.insert(key.clone()) {
+                continue;
+            }
+            let entry = mentions.entry(key).or_default();
+            *entry.labels.entry(label).or_default() += 1;
+            entry.commenters.insert(commenter.clone());
+            entry.comment_ids.push(answer.id);
+        }
+    }
+
+    let mut recommendations: Vec<Recommendation> = mentions
+        .into_values()
+        .filter(|m| m.commenters.len() >= 2)
+        .map(|Mentions { labels, commenters, comment_ids }| {
+            // Most common spelling wins
+            let label = labels.into_iter().max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0))).map(|(l, _)| l).unwrap_or_default();
+            Recommendation { label, commenters: commenters.len(), comment_ids }
+        })
+        .collect();
+    recommendations.sort_by(|a, b| b.commenters.cmp(&a.commenters).then_with(|| a.label.cmp(&b.label)));
+    recommendations.truncate(MAX_RECOMMENDATIONS);
+
+    let summary = recommendations.first().map(|top| {
+        let mut summary = format!("The top recommendation was {}, mentioned by {} commenters", top.label, top.commenters);
+        let others: Vec<String> = recommendations.iter().skip(1).take(3).map(|r| format!("{} ({})", r.label, r.commenters)).collect();
+        if !others.is_empty() {
+            summary.push_str(&format!(", followed by {}", others.join(", ")));
+        }
+        summary.push('.');
+        summary
+    });
+
+    AskHnSummary { answers: answers.len(), recommendations, summary }
+}
+
+// --- Show HN ---
+
+const MAX_MAKER_ANSWERS_PER_TOPIC: usize = 3;
+const EXCERPT_CHARS: usize = 280;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MakerTopic {
+    Pricing,
+    Stack,
+    Roadmap,
+}
+
+impl MakerTopic {
+    const ALL: [MakerTopic; 3] = [MakerTopic::Pricing, MakerTopic::Stack, MakerTopic::Roadmap];
+
+    fn name(self) -> &'static str {
+        match self {
+            MakerTopic::Pricing => "pricing",
+            MakerTopic::Stack => "the tech stack",
+            MakerTopic::Roadmap => "the roadmap",
+        }
+    }
+
+    // Matched against lowercased text, so multi-word cues need the surrounding spaces
+    fn cues(self) -> &'static [&'static str] {
+        match self {
+            MakerTopic::Pricing => &[
+                "price", "pricing", "cost", "free tier", "paid", "subscription", "per month", "/mo", "$", "licens",
+                "monetiz", "business model", "charge", "self-host",
+            ],
+            MakerTopic::Stack => &[
+                "stack", "built with", "built on", "written in", "framework", "database", "backend", "frontend",
+                "hosted on", "rust", "python", "golang", " go ", "typescript", "react", "postgres", "sqlite", "llm",
+                "architecture",
+            ],
+            MakerTopic::Roadmap => &[
+                "roadmap", "plan to", "planning", "plans for", "next step", "in the future", "coming soon", "soon",
+                "eventually", "on the list", "feature request", "will add", "we'll add", "i'll add", "working on",
+            ],
+        }
+    }
+
+    // Most cues wins; the question is what decides the topic, the answer is only a fallback
+    fn classify(question: Option<&str>, answer: &str) -> Option<MakerTopic> {
+        let best = |text: &str| {
+            let text = format!(" {} ", text.to_lowercase());
+            MakerTopic::ALL
+                .into_iter()
+                .map(|topic| (topic.cues().iter().filter(|cue| text.contains(*cue)).count(), topic))
+                .filter(|(hits, _)| *hits > 0)
+                .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)))
+                .map(|(_, topic)| topic)
+        };
+        question.and_then(best).or_else(|| best(answer))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MakerAnswer {
+    pub topic: MakerTopic,
+    pub comment_id: u32,
+    // The comment being answered; None when the maker posted at the top level
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub question: Option<String>,
+    pub answer: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MakerResponses {
+    pub maker: String,
+    pub replies: usize,
+    pub answers: Vec<MakerAnswer>,
+}
+
+// First sentences up to EXCERPT_CHARS, cut at a word boundary
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= EXCERPT_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(EXCERPT_CHARS).collect();
+    match cut.rfind(". ") {
+        Some(end) if end > EXCERPT_CHARS / 2 => cut[..=end].to_string(),
+        _ => format!("{}...", cut[..cut.rfind(' ').unwrap_or(cut.len())].trim_end_matches([',', ';', ':'])),
+    }
+}
+
+// The submitter's replies across the whole thread, keeping the ones that answer pricing, stack or
+// roadmap questions (a few per topic, in thread order)
+pub fn maker_responses(maker: &str, posts: &[Post]) -> MakerResponses {
+    let by_id: HashMap<u32, &Post> = posts.iter().map(|p| (p.id, p)).collect();
+    let replies: Vec<&Post> = posts.iter().filter(|p| p.by == Some(maker)).collect();
+
+    let mut answers: Vec<MakerAnswer> = Vec::new();
+    for reply in &replies {
+        let question = reply
+            .parent
+            .and_then(|parent| by_id.get(&parent))
+            .filter(|parent| parent.by != Some(maker))
+            .map(|parent| crate::analysis::strip_html(parent.html));
+        let answer = crate::analysis::strip_html(reply.html);
+        let Some(topic) = MakerTopic::classify(question.as_deref(), &answer) else { continue };
+        if answers.iter().filter(|a| a.topic == topic).count() < MAX_MAKER_ANSWERS_PER_TOPIC {
+            answers.push(MakerAnswer { topic, comment_id: reply.id, question: question.as_deref().map(excerpt), answer: excerpt(&answer) });
+        }
+    }
+    answers.sort_by_key(|a| a.topic);
+
+    MakerResponses { maker: maker.to_string(), replies: replies.len(), answers }
+}
+
+// Context block for the script prompt; None when the maker answered nothing on these topics
+pub fn maker_context(title: &str, responses: &MakerResponses) -> Option<String> {
+    if responses.answers.is_empty() {
+        return None;
+    }
+    let mut block = format!(
+        "\"{}\" is a Show HN post. Its maker ({}) replied {} times in the thread. Where it fits, feature their answers as \"the maker responded that...\":",
+        title, responses.maker, responses.replies
+    );
+    for answer in &responses.answers {
+        match &answer.question {
+            Some(question) => block.push_str(&format!("\n- On {}, asked \"{}\", the maker responded: \"{}\"", answer.topic.name(), question, answer.answer)),
+            None => block.push_str(&format!("\n- On {}, the maker wrote: \"{}\"", answer.topic.name(), answer.answer)),
+        }
+    }
+    Some(block)
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::env;
+use tracing::error;
+
+// Tags stories and episodes with topics from a fixed vocabulary, used for per-topic episode
+// lists, the ?topic= filter on stories and topic stats. Keyword rules run first; when they find
+// nothing and TOPIC_CLASSIFIER_LLM=true the Alchemyst model picks from the same vocabulary.
+// Story topics are kept in the episode archive so each story is classified once.
+//
+// The same rules flag sensitive stories (violence, tragedy, adult content), and
+// SENSITIVE_TOPICS decides what happens to background jobs about them: allow (default), skip, or
+// approve (hold until an admin approves or rejects the job).
+
+const MAX_TOPICS: usize = 3;
+// A keyword in the title counts this many times a keyword in the body
+const TITLE_WEIGHT: usize = 3;
+const MIN_SCORE: usize = 2;
+const CLASSIFIED_CHARS: usize = 1500;
+
+// Keywords are matched as whole (lowercased) words or phrases
+pub const VOCABULARY: &[(&str, &[&str])] = &[
+    ("ai", &["ai", "llm", "llms", "gpt", "chatgpt", "claude", "gemini", "openai", "anthropic", "machine learning", "neural", "transformer", "deep learning", "inference", "fine-tuning", "agents", "diffusion"]),
+    ("security", &["security", "vulnerability", "exploit", "cve", "malware", "ransomware", "breach", "hacked", "phishing", "backdoor", "zero-day", "encryption", "attack"]),
+    ("privacy", &["privacy", "surveillance", "tracking", "gdpr", "data broker", "facial recognition", "telemetry"]),
+    ("startups", &["startup", "startups", "founder", "founders", "yc", "y combinator", "seed round", "series a", "vc", "venture", "bootstrapped", "launch hn"]),
+    ("business", &["layoffs", "acquisition", "acquires", "ipo", "revenue", "antitrust", "earnings", "ceo", "lawsuit", "pricing"]),
+    ("hardware", &["hardware", "cpu", "gpu", "chip", "chips", "semiconductor", "risc-v", "fpga", "pcb", "nvidia", "intel", "amd", "tsmc", "keyboard", "raspberry pi"]),
+    ("programming-languages", &["rust", "python", "golang", "javascript", "typescript", "c++", "haskell", "zig", "lisp", "compiler", "compilers", "programming language", "type system", "ocaml", "elixir", "java", "kotlin", "swift"]),
+    ("web", &["browser", "browsers", "css", "html", "firefox", "chrome", "safari", "web", "frontend", "react", "wasm", "webassembly"]),
+    ("infrastructure", &["kubernetes", "docker", "database", "postgres", "sqlite", "cloud", "aws", "linux", "kernel", "outage", "devops", "server", "servers", "distributed"]),
+    ("open-source", &["open source", "open-source", "oss", "gpl", "license", "maintainer", "maintainers", "github", "foss"]),
+    ("science", &["science", "physics", "biology", "research", "study", "climate", "space", "nasa", "quantum", "math", "mathematics", "telescope"]),
+];
+
+const SENSITIVE: &[(&str, &[&str])] = &[
+    ("violence", &["shooting", "shooter", "murder", "murdered", "massacre", "stabbing", "terrorist", "terrorism", "bombing", "genocide", "war crimes", "torture", "assault", "killed"]),
+    ("tragedy", &["died", "dies", "death of", "suicide", "obituary", "passed away", "rip", "funeral", "fatal", "fatalities", "victims", "mourning", "in memoriam"]),
+    ("adult", &["porn", "pornography", "nsfw", "onlyfans", "sexual", "nude", "nudity", "adult content", "explicit", "escort"]),
+];
+// A single title hit flags a story; body and comment mentions need to add up
+const SENSITIVE_MIN_SCORE: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensitivePolicy {
+    Allow,
+    Skip,
+    Approve,
+}
+
+impl SensitivePolicy {
+    pub fn name(self) -> &'static str {
+        match self {
+            SensitivePolicy::Allow => "allow",
+            SensitivePolicy::Skip => "skip",
+            SensitivePolicy::Approve => "approve",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [SensitivePolicy::Allow, SensitivePolicy::Skip, SensitivePolicy::Approve]
+            .into_iter()
+            .find(|policy| policy.name().eq_ignore_ascii_case(value.trim()))
+    }
+}
+
+pub fn sensitive_policy() -> SensitivePolicy {
+    env::var("SENSITIVE_TOPICS").ok().and_then(|v| SensitivePolicy::parse(&v)).unwrap_or(SensitivePolicy::Allow)
+}
+
+// Sensitive categories the story (title, body and comments in `text`) touches
+pub fn sensitive_flags(title: &str, text: &str) -> Vec<&'static str> {
+    let (title, text) = (normalize(title), normalize(text));
+    SENSITIVE
+        .iter()
+        .filter(|(_, keywords)| hits(&title, keywords) * TITLE_WEIGHT + hits(&text, keywords) >= SENSITIVE_MIN_SCORE)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+pub fn names() -> Vec<&'static str> {
+    VOCABULARY.iter().map(|(name, _)| *name).collect()
+}
+
+pub fn is_known(topic: &str) -> bool {
+    VOCABULARY.iter().any(|(name, _)| *name == topic)
+}
+
+fn llm_enabled() -> bool {
+    env::var("TOPIC_CLASSIFIER_LLM").is_ok_and(|v| v == "true" || v == "1")
+}
+
+// Lowercased words separated by single spaces and padded, so " rust " only matches the word
+pub fn normalize(text: &str) -> String {
+    let words: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '+' | '#' | '-') { c } else { ' ' })
+        .collect();
+    format!(" {} ", words.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn hits(normalized: &str, keywords: &[&str]) -> usize {
+    keywords.iter().map(|keyword| normalized.matches(&format!(" {} ", keyword)).count()).sum()
+}
+
+// Best-scoring topics first; empty when nothing clears MIN_SCORE
+pub fn classify_rules(title: &str, text: &str) -> Vec<&'static str> {
+    let (title, text) = (normalize(title), normalize(text));
+    let mut scored: Vec<(usize, &'static str)> = VOCABULARY
+        .iter()
+        .map(|(name, keywords)| (hits(&title, keywords) * TITLE_WEIGHT + hits(&text, keywords), *name))
+        .filter(|(score, _)| *score >= MIN_SCORE)
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().take(MAX_TOPICS).map(|(_, name)| name).collect()
+}
+
+// Rules, then the model when enabled; "rules", "model" or "none" says which one decided
+pub async fn classify(title: &str, text: &str) -> (Vec<String>, &'static str) {
+    let topics = classify_rules(title, text);
+    if !topics.is_empty() {
+        return (topics.into_iter().map(String::from).collect(), "rules");
+    }
+    if !llm_enabled() {
+        return (Vec::new(), "none");
+    }
+    match classify_with_model(title, text).await {
+        Ok(topics) if !topics.is_empty() => (topics, "model"),
+        Ok(_) => (Vec::new(), "none"),
+        Err(e) => {
+            error!("Topic classification failed for \"{}\": {}", title, e);
+            (Vec::new(), "none")
+        }
+    }
+}
+
+async fn classify_with_model(title: &str, text: &str) -> Result<Vec<String>, String> {
+    let api_key = crate::credentials::require("ALCHEMYST_API_KEY")?;
+    let excerpt: String = text.chars().take(CLASSIFIED_CHARS).collect();
+    let no_tools: HashMap<String, bool> = crate::config::GENERATION_TOOLS.iter().map(|(name, _)| (name.to_string(), false)).collect();
+
+    let body = serde_json::json!({
+        "chat_history": [
+            crate::chats::ChatMessage::new("system", format!(
+                "You tag Hacker News stories with topics. Pick at most {} topics from this list and no others: {}. Reply with only a JSON array of topic names, or [] if none fit.",
+                MAX_TOPICS,
+                names().join(", ")
+            )),
+            crate::chats::ChatMessage::new("user", format!("Title: {}\n\n{}", title, excerpt)),
+        ],
+        "persona": crate::config::default_persona(),
+        "scope": "internal",
+        "stream": false,
+        "max_tokens": 64,
+        "tools": crate::config::upstream_tools(&no_tools)?,
+    });
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/v1/chat/generate", crate::config::alchemyst_api_url()))
+        .bearer_auth(api_key)
+        .json(&body)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let value: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+    let text = crate::chats::extract_content(&value).map(crate::chats::content_text).ok_or("response has no content")?;
+
+    let array = text.find('[').zip(text.rfind(']')).map(|(start, end)| &text[start..=end]).ok_or("response has no JSON array")?;
+    let topics: Vec<String> = serde_json::from_str(array).map_err(|e| e.to_string())?;
+    // Anything outside the vocabulary is dropped rather than trusted
+    let mut known: Vec<String> = Vec::new();
+    for topic in topics.into_iter().map(|t| t.trim().to_lowercase()) {
+        if is_known(&topic) && !known.contains(&topic) {
+            known.push(topic);
+        }
+    }
+    known.truncate(MAX_TOPICS);
+    Ok(known)
+}
@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use tracing::{error, info};
+
+// Re-transcription of final episode audio with Whisper, for episodes whose audio no longer matches
+// the script (music beds, human edits). WHISPER_PROVIDER=openai (hosted API, OPENAI_API_KEY) or
+// local (the openai-whisper CLI, WHISPER_BINARY). Both produce the same verbose JSON shape. Word
+// timestamps are requested too; they align rendered episodes with their transcript for
+// click-to-seek and word highlighting in the player.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    // The CLI nests words in their segment; they're moved to Transcript::words
+    #[serde(default, skip_serializing)]
+    pub words: Vec<Word>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Word {
+    pub word: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub text: String,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    #[serde(default)]
+    pub words: Vec<Word>,
+}
+
+pub fn provider_name() -> String {
+    env::var("WHISPER_PROVIDER").unwrap_or_else(|_| "openai".to_string()).to_lowercase()
+}
+
+pub fn is_configured() -> bool {
+    match provider_name().as_str() {
+        "local" => true,
+        _ => crate::credentials::get("OPENAI_API_KEY").is_some(),
+    }
+}
+
+fn extension_for(content_type: &str) -> &'static str {
+    match content_type {
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" => "m4a",
+        "audio/ogg" | "audio/opus" => "ogg",
+        "audio/webm" => "webm",
+        "audio/flac" => "flac",
+        _ => "wav",
+    }
+}
+
+async fn transcribe_openai(audio: Vec<u8>, content_type: &str) -> Result<Transcript, String> {
+    let api_key = crate::credentials::require("OPENAI_API_KEY")?;
+    let model = env::var("WHISPER_MODEL").unwrap_or_else(|_| "whisper-1".to_string());
+
+    let file = reqwest::multipart::Part::bytes(audio)
+        .file_name(format!("episode.{}", extension_for(content_type)))
+        .mime_str(content_type)
+        .map_err(|e| format!("Invalid content type: {}", e))?;
+    let form = reqwest::multipart::Form::new()
+        .part("file", file)
+        .text("model", model)
+        .text("response_format", "verbose_json")
+        .text("timestamp_granularities[]", "segment")
+        .text("timestamp_granularities[]", "word");
+
+    let response = reqwest::Client::new()
+        .post("https://api.openai.com/v1/audio/transcriptions")
+        .bearer_auth(api_key)
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Whisper request failed: {}", e);
+            "Transcription request failed".to_string()
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        error!("Whisper API error {}: {}", status, crate::redact::body(&error_text));
+        return Err(format!("Whisper API error: {}", status));
+    }
+
+    response.json().await.map_err(|e| {
+        error!("Failed to parse Whisper response: {}", e);
+        "Invalid transcription response".to_string()
+    })
+}
+
+async fn transcribe_local(audio: Vec<u8>, content_type: &str) -> Result<Transcript, String> {
+    let binary = env::var("WHISPER_BINARY").unwrap_or_else(|_| "whisper".to_string());
+    let model = env::var("WHISPER_MODEL").unwrap_or_else(|_| "base".to_string());
+
+    let dir = env::temp_dir().join(format!("transcribe-{}", uuid::Uuid::new_v4()));
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| e.to_string())?;
+    let input = dir.join(format!("episode.{}", extension_for(content_type)));
+
+    let result = async {
+        tokio::fs::write(&input, audio).await.map_err(|e| e.to_string())?;
+
+        let output = tokio::process::Command::new(&binary)
+            .arg(&input)
+            .args(["--model", &model, "--word_timestamps", "True", "--output_format", "json", "--output_dir"])
+            .arg(&dir)
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| {
+                error!("Failed to start {}: {}", binary, e);
+                format!("Failed to start Whisper ({})", binary)
+            })?;
+        if !output.status.success() {
+            error!("Whisper exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr));
+            return Err(format!("Whisper exited with {}", output.status));
+        }
+
+        let raw = tokio::fs::read_to_string(dir.join("episode.json")).await.map_err(|e| e.to_string())?;
+        serde_json::from_str(&raw).map_err(|e| format!("Invalid Whisper output: {}", e))
+    }
+    .await;
+
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    result
+}
+
+pub async fn transcribe(audio: Vec<u8>, content_type: &str) -> Result<Transcript, String> {
+    info!("Transcribing {} bytes of {} with {}", audio.len(), content_type, provider_name());
+    let mut transcript = match provider_name().as_str() {
+        "local" => transcribe_local(audio, content_type).await?,
+        _ => transcribe_openai(audio, content_type).await?,
+    };
+    for segment in &mut transcript.segments {
+        segment.text = segment.text.trim().to_string();
+        let words = std::mem::take(&mut segment.words);
+        transcript.words.extend(words);
+    }
+    for word in &mut transcript.words {
+        word.word = word.word.trim().to_string();
+    }
+    transcript.text = transcript.text.trim().to_string();
+    Ok(transcript)
+}
+
+fn vtt_timestamp(secs: f64) -> String {
+    let millis = (secs.max(0.0) * 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02}.{:03}", millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000)
+}
+
+pub fn to_vtt(transcript: &Transcript) -> String {
+    let mut vtt = String::from("WEBVTT\n");
+    for segment in &transcript.segments {
+        vtt.push_str(&format!(
+            "\n{} --> {}\n{}\n",
+            vtt_timestamp(segment.start),
+            vtt_timestamp(segment.end),
+            segment.text
+        ));
+    }
+    vtt
+}
@@ -0,0 +1,401 @@
+use base64::Engine;
+use futures::future::BoxFuture;
+use std::env;
+use std::sync::OnceLock;
+use tokio::io::AsyncWriteExt;
+use tracing::{error, info};
+
+// Pluggable speech synthesis, selected with TTS_PROVIDER=gemini|piper|elevenlabs|openai (default:
+// gemini).
+// Every provider returns raw 16-bit mono PCM at SAMPLE_RATE so the rest of the audio pipeline
+// (announcements, trimming, speed renditions, QA) doesn't care where the audio came from.
+
+pub const SAMPLE_RATE: u32 = 24000;
+
+pub trait AudioSynthesizer: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn synthesize<'a>(&'a self, text: &'a str, voice: &'a str) -> BoxFuture<'a, Result<Vec<u8>, String>>;
+}
+
+pub fn provider_name() -> String {
+    env::var("TTS_PROVIDER").unwrap_or_else(|_| "gemini".to_string()).to_lowercase()
+}
+
+static SYNTHESIZER: OnceLock<Box<dyn AudioSynthesizer>> = OnceLock::new();
+
+pub fn synthesizer() -> &'static dyn AudioSynthesizer {
+    SYNTHESIZER
+        .get_or_init(|| match provider_name().as_str() {
+            "piper" => Box::new(Piper),
+            "elevenlabs" => Box::new(ElevenLabs),
+            "openai" => Box::new(OpenAi),
+            _ => Box::new(Gemini),
+        })
+        .as_ref()
+}
+
+// Voice names of the configured provider beyond the Gemini ones
+pub fn is_provider_voice(voice: &str) -> bool {
+    provider_name() == "openai" && OPENAI_VOICES.contains(&voice.to_lowercase().as_str())
+}
+
+// Custom (cloned) voices always go to the provider they were registered with
+pub fn synthesizer_for(voice: &str) -> &'static dyn AudioSynthesizer {
+    match crate::voices::get(voice) {
+        Some(custom) if custom.provider == "elevenlabs" => &ElevenLabs,
+        _ => synthesizer(),
+    }
+}
+
+// --- Gemini (hosted) ---
+
+struct Gemini;
+
+impl Gemini {
+    async fn synthesize_text(&self, text: &str, voice: &str) -> Result<Vec<u8>, String> {
+        let gemini_api_key = crate::credentials::require("GEMINI_API_KEY")?;
+
+        let client = reqwest::Client::new();
+        let url = "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash-preview-tts:generateContent";
+
+        info!("Generating TTS for text length: {}", text.len());
+
+        let request_body = serde_json::json!({
+            "contents": [{
+                "parts": [{
+                    "text": text
+                }]
+            }],
+            "generationConfig": {
+                "responseModalities": ["AUDIO"],
+                "speechConfig": {
+                    "voiceConfig": {
+                        "prebuiltVoiceConfig": {
+                            "voiceName": voice
+                        }
+                    }
+                }
+            },
+            "model": "gemini-2.5-flash-preview-tts"
+        });
+
+        info!("Request body: {}", crate::redact::json(&request_body));
+
+        let response = client
+            .post(url)
+            .header("x-goog-api-key", &gemini_api_key)
+            .header("Content-Type", "application/json")
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("TTS request failed: {}", e);
+                "Failed to generate TTS".to_string()
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            error!("TTS API error {}: {}", status, crate::redact::body(&error_text));
+            return Err(format!("TTS API error: {}", status));
+        }
+
+        let response_text = response.text().await.map_err(|e| {
+            error!("Failed to get response text: {}", e);
+            "Failed to read response".to_string()
+        })?;
+
+        info!("Got response with length: {}", response_text.len());
+
+        let response_json: serde_json::Value = serde_json::from_str(&response_text).map_err(|e| {
+            error!("Failed to parse TTS response: {} - Response: {}", e, crate::redact::body(&response_text));
+            "Invalid TTS response".to_string()
+        })?;
+
+        info!("Parsed JSON response");
+
+        // Extract the base64 audio data
+        let audio_data = response_json
+            .get("candidates")
+            .and_then(|c| c.get(0))
+            .and_then(|c| c.get("content"))
+            .and_then(|c| c.get("parts"))
+            .and_then(|p| p.get(0))
+            .and_then(|p| p.get("inlineData"))
+            .and_then(|d| d.get("data"))
+            .and_then(|d| d.as_str())
+            .ok_or_else(|| {
+                error!("Failed to extract audio data from response: {}", crate::redact::json(&response_json));
+                "No audio data in response".to_string()
+            })?;
+
+        info!("Extracted base64 audio data, length: {}", audio_data.len());
+
+        // Decode base64 to bytes (this is raw PCM data at 24 kHz)
+        let pcm_bytes = base64::engine::general_purpose::STANDARD.decode(audio_data).map_err(|e| {
+            error!("Failed to decode base64 audio: {}", e);
+            "Invalid audio data".to_string()
+        })?;
+
+        info!("Decoded PCM data length: {}", pcm_bytes.len());
+
+        Ok(pcm_bytes)
+    }
+}
+
+impl AudioSynthesizer for Gemini {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn synthesize<'a>(&'a self, text: &'a str, voice: &'a str) -> BoxFuture<'a, Result<Vec<u8>, String>> {
+        Box::pin(self.synthesize_text(text, voice))
+    }
+}
+
+// --- Piper (local) ---
+
+// Shells out to the piper CLI (PIPER_BINARY, default "piper") with the PIPER_MODEL voice model.
+// Gemini voice names don't apply; PIPER_SPEAKER picks a speaker in multi-speaker models.
+struct Piper;
+
+pub fn piper_model() -> Option<String> {
+    env::var("PIPER_MODEL").ok().filter(|m| !m.is_empty())
+}
+
+// Piper writes the model's native rate, stored next to the model in <model>.json
+fn piper_sample_rate(model: &str) -> u32 {
+    std::fs::read_to_string(format!("{}.json", model))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|config| config.get("audio")?.get("sample_rate")?.as_u64())
+        .map(|rate| rate as u32)
+        .unwrap_or(22050)
+}
+
+impl Piper {
+    async fn synthesize_text(&self, text: &str) -> Result<Vec<u8>, String> {
+        let model = piper_model().ok_or_else(|| "PIPER_MODEL is not configured".to_string())?;
+        let binary = env::var("PIPER_BINARY").unwrap_or_else(|_| "piper".to_string());
+
+        info!("Generating local TTS with Piper for text length: {}", text.len());
+
+        let mut command = tokio::process::Command::new(&binary);
+        command
+            .arg("--model")
+            .arg(&model)
+            .arg("--output_raw")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+        if let Ok(speaker) = env::var("PIPER_SPEAKER") {
+            command.arg("--speaker").arg(speaker);
+        }
+
+        let mut child = command.spawn().map_err(|e| {
+            error!("Failed to start {}: {}", binary, e);
+            format!("Failed to start Piper ({})", binary)
+        })?;
+
+        // Piper synthesizes one utterance per line
+        let mut stdin = child.stdin.take().ok_or_else(|| "Failed to open Piper stdin".to_string())?;
+        let input = text.to_string();
+        let writer = tokio::spawn(async move {
+            let result = stdin.write_all(input.as_bytes()).await;
+            drop(stdin);
+            result
+        });
+
+        let output = child.wait_with_output().await.map_err(|e| {
+            error!("Piper failed: {}", e);
+            "Piper failed".to_string()
+        })?;
+        if let Ok(Err(e)) = writer.await {
+            error!("Failed to write text to Piper: {}", e);
+        }
+
+        if !output.status.success() {
+            error!("Piper exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr));
+            return Err(format!("Piper exited with {}", output.status));
+        }
+
+        info!("Piper produced {} bytes of PCM", output.stdout.len());
+
+        Ok(crate::audio::resample(&output.stdout, piper_sample_rate(&model), SAMPLE_RATE))
+    }
+}
+
+impl AudioSynthesizer for Piper {
+    fn name(&self) -> &'static str {
+        "piper"
+    }
+
+    fn synthesize<'a>(&'a self, text: &'a str, _voice: &'a str) -> BoxFuture<'a, Result<Vec<u8>, String>> {
+        Box::pin(self.synthesize_text(text))
+    }
+}
+
+// --- ElevenLabs (hosted, supports voice cloning) ---
+
+// `voice` is a registered custom voice name, a raw ElevenLabs voice ID, or (for Gemini names such
+// as the TTS_VOICE default) falls back to ELEVENLABS_VOICE_ID
+struct ElevenLabs;
+
+impl ElevenLabs {
+    async fn synthesize_text(&self, text: &str, voice: &str) -> Result<Vec<u8>, String> {
+        let api_key = crate::credentials::require("ELEVENLABS_API_KEY")?;
+        let voice_id = match crate::voices::get(voice) {
+            Some(custom) => custom.provider_voice_id,
+            None if !crate::config::GEMINI_VOICES.contains(&voice) => voice.to_string(),
+            None => env::var("ELEVENLABS_VOICE_ID").map_err(|_| "ELEVENLABS_VOICE_ID is not configured".to_string())?,
+        };
+        let model = env::var("ELEVENLABS_MODEL").unwrap_or_else(|_| "eleven_multilingual_v2".to_string());
+
+        info!("Generating ElevenLabs TTS for text length: {}", text.len());
+
+        // pcm_24000 is raw 16-bit mono PCM at the pipeline rate, so no conversion is needed
+        let response = reqwest::Client::new()
+            .post(format!("https://api.elevenlabs.io/v1/text-to-speech/{}?output_format=pcm_24000", voice_id))
+            .header("xi-api-key", api_key)
+            .json(&serde_json::json!({
+                "text": text,
+                "model_id": model,
+            }))
+            .send()
+            .await
+            .map_err(|e| {
+                error!("ElevenLabs TTS request failed: {}", e);
+                "Failed to generate TTS".to_string()
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            error!("ElevenLabs TTS error {}: {}", status, crate::redact::body(&error_text));
+            return Err(format!("TTS API error: {}", status));
+        }
+
+        let pcm_bytes = response.bytes().await.map_err(|e| {
+            error!("Failed to read ElevenLabs audio: {}", e);
+            "Failed to read response".to_string()
+        })?;
+
+        info!("Received PCM data length: {}", pcm_bytes.len());
+
+        Ok(pcm_bytes.to_vec())
+    }
+}
+
+impl AudioSynthesizer for ElevenLabs {
+    fn name(&self) -> &'static str {
+        "elevenlabs"
+    }
+
+    fn synthesize<'a>(&'a self, text: &'a str, voice: &'a str) -> BoxFuture<'a, Result<Vec<u8>, String>> {
+        Box::pin(self.synthesize_text(text, voice))
+    }
+}
+
+// --- OpenAI (hosted) ---
+
+// OPENAI_TTS_MODEL (default gpt-4o-mini-tts) through OPENAI_BASE_URL. `voice` is an OpenAI voice
+// name; Gemini names such as the TTS_VOICE default fall back to OPENAI_TTS_VOICE (default alloy).
+struct OpenAi;
+
+const OPENAI_VOICES: &[&str] = &["alloy", "ash", "ballad", "coral", "echo", "fable", "nova", "onyx", "sage", "shimmer", "verse"];
+// The speech endpoint takes at most 4096 characters per request
+const OPENAI_MAX_CHARS: usize = 4000;
+
+// Whole paragraphs per piece while they fit the request limit; an overlong paragraph is split
+// between words
+fn text_chunks(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if !current.is_empty() && current.chars().count() + 2 + paragraph.chars().count() <= max_chars {
+            current.push_str("\n\n");
+            current.push_str(paragraph);
+            continue;
+        }
+        if !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+        for word in paragraph.split_whitespace() {
+            if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > max_chars {
+                chunks.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+impl OpenAi {
+    async fn synthesize_text(&self, text: &str, voice: &str) -> Result<Vec<u8>, String> {
+        let api_key = crate::credentials::require("OPENAI_API_KEY")?;
+        let base_url = env::var("OPENAI_BASE_URL").unwrap_or_else(|_| "https://api.openai.com".to_string());
+        let model = env::var("OPENAI_TTS_MODEL").unwrap_or_else(|_| "gpt-4o-mini-tts".to_string());
+        let voice = if OPENAI_VOICES.contains(&voice.to_lowercase().as_str()) {
+            voice.to_lowercase()
+        } else {
+            env::var("OPENAI_TTS_VOICE").unwrap_or_else(|_| "alloy".to_string())
+        };
+
+        info!("Generating OpenAI TTS for text length: {}", text.len());
+
+        let client = reqwest::Client::new();
+        let mut pcm = Vec::new();
+        for chunk in text_chunks(text, OPENAI_MAX_CHARS) {
+            // "pcm" is raw 16-bit mono PCM at 24 kHz, the pipeline rate
+            let response = client
+                .post(format!("{}/v1/audio/speech", base_url.trim_end_matches('/')))
+                .bearer_auth(&api_key)
+                .json(&serde_json::json!({
+                    "model": model,
+                    "voice": voice,
+                    "input": chunk,
+                    "response_format": "pcm",
+                }))
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("OpenAI TTS request failed: {}", e);
+                    "Failed to generate TTS".to_string()
+                })?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await.unwrap_or_default();
+                error!("OpenAI TTS error {}: {}", status, crate::redact::body(&error_text));
+                return Err(format!("TTS API error: {}", status));
+            }
+
+            let bytes = response.bytes().await.map_err(|e| {
+                error!("Failed to read OpenAI audio: {}", e);
+                "Failed to read response".to_string()
+            })?;
+            pcm.extend_from_slice(&bytes);
+        }
+
+        info!("Received PCM data length: {}", pcm.len());
+
+        Ok(pcm)
+    }
+}
+
+impl AudioSynthesizer for OpenAi {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn synthesize<'a>(&'a self, text: &'a str, voice: &'a str) -> BoxFuture<'a, Result<Vec<u8>, String>> {
+        Box::pin(self.synthesize_text(text, voice))
+    }
+}
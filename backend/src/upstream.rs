@@ -0,0 +1,289 @@
+use crate::chats::ChatMessage;
+use crate::config;
+use std::env;
+use std::sync::OnceLock;
+
+// Streaming LLM providers behind /api/v1/chat/generate/stream, selected with
+// LLM_PROVIDER=alchemyst|openai|ollama (default: alchemyst). Each adapter builds its request and
+// turns its wire format into the Alchemyst-style messages ({"type": ..., "content": ...}) the
+// endpoint forwards, so the frontend sees the same events whichever provider is configured.
+
+pub struct StreamRequest<'a> {
+    pub chat_history: &'a [ChatMessage],
+    pub persona: &'a str,
+    pub scope: &'a str,
+    pub max_tokens: u32,
+    // Alchemyst `tools` flags (see config::upstream_tools); other providers don't retrieve
+    pub tools: &'a serde_json::Value,
+}
+
+pub enum StreamEvent {
+    // Normalized message; token-streaming providers send {"type": "delta", "content": "..."}
+    Message(serde_json::Value),
+    Done,
+}
+
+pub trait StreamAdapter: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn request(&self, client: &reqwest::Client, request: &StreamRequest) -> Result<reqwest::RequestBuilder, String>;
+    // One line of the response body; Ok(None) for lines that carry nothing (comments, keep-alives)
+    fn parse_line(&self, line: &str) -> Result<Option<StreamEvent>, String>;
+}
+
+pub fn provider_name() -> String {
+    env::var("LLM_PROVIDER").unwrap_or_else(|_| "alchemyst".to_string()).to_lowercase()
+}
+
+static ADAPTER: OnceLock<Box<dyn StreamAdapter>> = OnceLock::new();
+
+pub fn adapter() -> &'static dyn StreamAdapter {
+    ADAPTER
+        .get_or_init(|| match provider_name().as_str() {
+            "openai" => Box::new(OpenAi),
+            "ollama" => Box::new(Ollama),
+            _ => Box::new(Alchemyst),
+        })
+        .as_ref()
+}
+
+// "data: {...}" SSE lines; None for anything else
+fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(str::trim)
+}
+
+fn delta(content: &str) -> Option<StreamEvent> {
+    (!content.is_empty()).then(|| StreamEvent::Message(serde_json::json!({ "type": "delta", "content": content })))
+}
+
+// Splits the response body into lines as chunks arrive; a line (or a UTF-8 character) may be
+// split across chunks, so the unfinished tail is kept until its newline shows up
+#[derive(Default)]
+pub struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    // Complete lines in this chunk, without their line endings
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let Some(end) = self.pending.iter().rposition(|&b| b == b'\n') else { return Vec::new() };
+        let rest = self.pending.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.pending, rest);
+        String::from_utf8_lossy(&complete).lines().map(String::from).collect()
+    }
+
+    // Whatever is left when the body ends without a trailing newline
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.pending);
+        (!rest.is_empty()).then(|| String::from_utf8_lossy(&rest).into_owned())
+    }
+}
+
+// --- Alchemyst (SSE with typed messages) ---
+
+struct Alchemyst;
+
+impl StreamAdapter for Alchemyst {
+    fn name(&self) -> &'static str {
+        "alchemyst"
+    }
+
+    fn request(&self, client: &reqwest::Client, request: &StreamRequest) -> Result<reqwest::RequestBuilder, String> {
+        let api_key = crate::credentials::require("ALCHEMYST_API_KEY")?;
+
+        let body = serde_json::json!({
+            "chat_history": request.chat_history,
+            "persona": request.persona,
+            "scope": request.scope,
+            "stream": true,
+            "max_tokens": request.max_tokens,
+            "tools": request.tools,
+        });
+
+        Ok(client
+            .post(format!("{}/api/v1/chat/generate/stream", config::alchemyst_api_url()))
+            .header("Authorization", format!("Bearer {}", api_key))
+            .header("Content-Type", "application/json")
+            .json(&body))
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Option<StreamEvent>, String> {
+        let Some(data) = sse_data(line) else { return Ok(None) };
+        if data == "[DONE]" {
+            return Ok(Some(StreamEvent::Done));
+        }
+        serde_json::from_str(data).map(|json| Some(StreamEvent::Message(json))).map_err(|e| e.to_string())
+    }
+}
+
+// --- OpenAI-compatible (SSE chat.completion.chunk) ---
+
+// OPENAI_BASE_URL (default https://api.openai.com) also covers compatible servers such as vLLM
+struct OpenAi;
+
+impl StreamAdapter for OpenAi {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn request(&self, client: &reqwest::Client, request: &StreamRequest) -> Result<reqwest::RequestBuilder, String> {
+        let api_key = crate::credentials::require("OPENAI_API_KEY")?;
+        let base_url = env::var("OPENAI_BASE_URL").unwrap_or_else(|_| "https://api.openai.com".to_string());
+        let model = env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
+
+        Ok(client
+            .post(format!("{}/v1/chat/completions", base_url.trim_end_matches('/')))
+            .bearer_auth(api_key)
+            .json(&serde_json::json!({
+                "model": model,
+                "messages": request.chat_history,
+                "stream": true,
+                "max_tokens": request.max_tokens,
+            })))
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Option<StreamEvent>, String> {
+        let Some(data) = sse_data(line) else { return Ok(None) };
+        if data == "[DONE]" {
+            return Ok(Some(StreamEvent::Done));
+        }
+        let chunk: serde_json::Value = serde_json::from_str(data).map_err(|e| e.to_string())?;
+        if let Some(error) = chunk.get("error") {
+            return Ok(Some(StreamEvent::Message(serde_json::json!({ "type": "error", "content": error }))));
+        }
+        let delta_message = chunk.get("choices").and_then(|c| c.get(0)).and_then(|c| c.get("delta"));
+        // Streamed tool calls arrive in fragments; only the first one carries the name
+        if let Some(event) = tool_call_message(delta_message.and_then(|d| d.get("tool_calls"))) {
+            return Ok(Some(event));
+        }
+        let content = delta_message.and_then(|d| d.get("content")).and_then(|c| c.as_str()).unwrap_or_default();
+        Ok(delta(content))
+    }
+}
+
+// --- Ollama (NDJSON) ---
+
+// OLLAMA_URL (default http://localhost:11434) and OLLAMA_MODEL (default llama3.1)
+struct Ollama;
+
+impl StreamAdapter for Ollama {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn request(&self, client: &reqwest::Client, request: &StreamRequest) -> Result<reqwest::RequestBuilder, String> {
+        let base_url = env::var("OLLAMA_URL").unwrap_or_else(|_| "http://localhost:11434".to_string());
+        let model = env::var("OLLAMA_MODEL").unwrap_or_else(|_| "llama3.1".to_string());
+
+        Ok(client.post(format!("{}/api/chat", base_url.trim_end_matches('/'))).json(&serde_json::json!({
+            "model": model,
+            "messages": request.chat_history,
+            "stream": true,
+            "options": { "num_predict": request.max_tokens },
+        })))
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Option<StreamEvent>, String> {
+        let chunk: serde_json::Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        if let Some(error) = chunk.get("error") {
+            return Ok(Some(StreamEvent::Message(serde_json::json!({ "type": "error", "content": error }))));
+        }
+        if chunk.get("done").and_then(|d| d.as_bool()) == Some(true) {
+            return Ok(Some(StreamEvent::Done));
+        }
+        if let Some(event) = tool_call_message(chunk.get("message").and_then(|m| m.get("tool_calls"))) {
+            return Ok(Some(event));
+        }
+        let content = chunk.get("message").and_then(|m| m.get("content")).and_then(|c| c.as_str()).unwrap_or_default();
+        Ok(delta(content))
+    }
+}
+
+// --- Tool usage ---
+
+// Typed replacements for the providers' tool/retrieval messages, so clients can render a
+// "sources consulted" panel without knowing each provider's shape
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolEvent {
+    ToolCall {
+        tool: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        arguments: Option<serde_json::Value>,
+    },
+    SourceRetrieved {
+        url: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+}
+
+const TOOL_MESSAGE_HINTS: &[&str] = &["tool", "function_call", "search", "source", "retriev", "citation"];
+const SOURCE_LIST_KEYS: &[&str] = &["sources", "results", "documents", "citations", "references", "context"];
+
+fn first_str<'a>(value: &'a serde_json::Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|key| value.get(*key).and_then(|v| v.as_str())).filter(|s| !s.is_empty())
+}
+
+fn collect_sources(value: &serde_json::Value, sources: &mut Vec<ToolEvent>) {
+    match value {
+        serde_json::Value::Array(items) => items.iter().for_each(|item| collect_sources(item, sources)),
+        serde_json::Value::Object(map) => {
+            if let Some(url) = first_str(value, &["url", "link", "source_url", "href"]) {
+                let title = first_str(value, &["title", "name", "pageTitle"]).map(String::from);
+                if !sources.iter().any(|s| matches!(s, ToolEvent::SourceRetrieved { url: u, .. } if u == url)) {
+                    sources.push(ToolEvent::SourceRetrieved { url: url.to_string(), title });
+                }
+                return;
+            }
+            for key in SOURCE_LIST_KEYS {
+                if let Some(nested) = map.get(*key) {
+                    collect_sources(nested, sources);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// None when the message isn't about tool usage (or nothing useful could be extracted from it),
+// in which case it's forwarded unchanged
+pub fn tool_events(message: &serde_json::Value) -> Option<Vec<ToolEvent>> {
+    let kind = message.get("type").and_then(|t| t.as_str())?.to_lowercase();
+    if !TOOL_MESSAGE_HINTS.iter().any(|hint| kind.contains(hint)) {
+        return None;
+    }
+
+    let content = message.get("content").unwrap_or(&serde_json::Value::Null);
+    let mut events = Vec::new();
+    let tool = first_str(content, &["tool", "name", "toolName", "tool_name"])
+        .or_else(|| content.get("function").and_then(|f| first_str(f, &["name"])))
+        .or_else(|| first_str(message, &["tool", "name", "toolName", "tool_name"]));
+    // Results-only messages (search_results, sources) don't name a tool
+    if let Some(tool) = tool {
+        let arguments = ["arguments", "args", "input", "query"]
+            .iter()
+            .find_map(|key| content.get(*key).or_else(|| message.get(*key)))
+            .cloned();
+        events.push(ToolEvent::ToolCall { tool: tool.to_string(), arguments });
+    }
+    collect_sources(content, &mut events);
+    for key in SOURCE_LIST_KEYS {
+        if let Some(nested) = message.get(*key) {
+            collect_sources(nested, &mut events);
+        }
+    }
+
+    (!events.is_empty()).then_some(events)
+}
+
+// OpenAI and Ollama put calls under message/delta.tool_calls[].function
+fn tool_call_message(calls: Option<&serde_json::Value>) -> Option<StreamEvent> {
+    let function = calls?.get(0)?.get("function")?;
+    let name = function.get("name")?.as_str()?;
+    Some(StreamEvent::Message(serde_json::json!({
+        "type": "tool_call",
+        "tool": name,
+        "arguments": function.get("arguments"),
+    })))
+}
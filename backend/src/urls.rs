@@ -0,0 +1,134 @@
+// URL canonicalization so the same article isn't treated as several different URLs: https scheme,
+// lowercase host without www./m. prefixes or default ports, tracking params stripped, remaining
+// params sorted, no fragment or trailing slash, and common mirror domains folded together.
+
+// Click IDs and campaign tokens only: generic names like "ref", "source" or "s" select content on
+// plenty of sites, so stripping them would fold distinct pages together
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "yclid", "_hsenc", "_hsmi", "mkt_tok"];
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+// Drop leading www./m./mobile. labels, including mobile mirrors like en.m.wikipedia.org
+fn normalize_host(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    let mut labels: Vec<&str> = host.split('.').collect();
+    while labels.len() > 2 && matches!(labels[0], "www" | "m" | "mobile" | "amp") {
+        labels.remove(0);
+    }
+    if labels.len() > 2 && labels[1] == "m" {
+        labels.remove(1);
+    }
+    let host = labels.join(".");
+
+    match host.as_str() {
+        "twitter.com" => "x.com".to_string(),
+        "old.reddit.com" | "np.reddit.com" | "new.reddit.com" => "reddit.com".to_string(),
+        _ => host,
+    }
+}
+
+pub fn canonical_host(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(&canonicalize(url)).ok()?;
+    parsed.host_str().map(String::from)
+}
+
+// Canonical form of an http(s) URL; anything unparseable is returned trimmed but otherwise as-is
+pub fn canonicalize(url: &str) -> String {
+    let mut parsed = match url::Url::parse(url.trim()) {
+        Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => parsed,
+        _ => return url.trim().to_string(),
+    };
+    let Some(mut host) = parsed.host_str().map(normalize_host) else {
+        return url.trim().to_string();
+    };
+
+    let mut path = parsed.path().to_string();
+    let mut params: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| !is_tracking_param(name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+
+    // Mirror domains with a well-known canonical page
+    match host.as_str() {
+        "youtu.be" => {
+            host = "youtube.com".to_string();
+            params.push(("v".to_string(), path.trim_start_matches('/').to_string()));
+            path = "/watch".to_string();
+        }
+        "arxiv.org" => {
+            if let Some(id) = path.strip_prefix("/pdf/") {
+                path = format!("/abs/{}", id.trim_end_matches(".pdf"));
+            }
+        }
+        _ => {}
+    }
+
+    params.sort();
+    let path = match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+
+    // Explicit default ports would otherwise survive the switch to https
+    if matches!(parsed.port(), Some(80) | Some(443)) {
+        let _ = parsed.set_port(None);
+    }
+    let _ = parsed.set_scheme("https");
+    let _ = parsed.set_host(Some(&host));
+    parsed.set_path(&path);
+    parsed.set_fragment(None);
+    if params.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(params);
+    }
+    parsed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_tracking_params_and_sorts_the_rest() {
+        assert_eq!(
+            canonicalize("http://www.example.com/post/?utm_source=hn&b=2&fbclid=abc&a=1#comments"),
+            "https://example.com/post?a=1&b=2"
+        );
+    }
+
+    #[test]
+    fn keeps_params_that_select_content() {
+        assert_eq!(canonicalize("https://blog.example.com/?s=rust"), "https://blog.example.com/?s=rust");
+        assert_eq!(canonicalize("https://example.com/page?ref=v2&source=feed"), "https://example.com/page?ref=v2&source=feed");
+    }
+
+    #[test]
+    fn normalizes_hosts_and_ports() {
+        assert_eq!(canonicalize("HTTP://M.Example.COM:80/a/"), "https://example.com/a");
+        assert_eq!(canonicalize("https://en.m.wikipedia.org/wiki/Rust"), "https://en.wikipedia.org/wiki/Rust");
+        assert_eq!(canonicalize("https://mobile.twitter.com/user/status/1"), "https://x.com/user/status/1");
+        assert_eq!(canonicalize("https://old.reddit.com/r/rust/"), "https://reddit.com/r/rust");
+    }
+
+    #[test]
+    fn folds_mirror_domains() {
+        assert_eq!(canonicalize("https://youtu.be/abc123?t=10"), "https://youtube.com/watch?t=10&v=abc123");
+        assert_eq!(canonicalize("https://arxiv.org/pdf/2401.00001.pdf"), "https://arxiv.org/abs/2401.00001");
+    }
+
+    #[test]
+    fn leaves_non_http_urls_alone() {
+        assert_eq!(canonicalize("  mailto:someone@example.com "), "mailto:someone@example.com");
+        assert_eq!(canonicalize("not a url"), "not a url");
+    }
+
+    #[test]
+    fn canonical_host_strips_prefixes() {
+        assert_eq!(canonical_host("https://www.example.com/x").as_deref(), Some("example.com"));
+    }
+}
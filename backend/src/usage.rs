@@ -0,0 +1,315 @@
+use crate::archive::{self, ApiKey};
+use axum::extract::Request;
+use axum::http::{header, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::future::Future;
+use tracing::error;
+
+// API keys and quotas: admins issue keys (POST /admin/keys) with monthly allowances of
+// generations and TTS characters, callers send one in X-API-Key, and every generation or TTS
+// render is added to the key's month before the work starts. A request that would go over is
+// rejected with 429 until the month rolls over (402 when the key has no allowance at all).
+// Without a key requests are unmetered unless API_KEYS_REQUIRED=true. Keys live in the archive.
+// Sandbox keys get the mock pipeline in sandbox.rs instead and are never metered or billed.
+//
+// Alongside the quotas every key's month also records estimated LLM tokens, the bytes of audio it
+// rendered and the bytes of that audio downloaded (whoever fetches it), for the usage report.
+
+pub const HEADER: &str = "x-api-key";
+const SECRET_PREFIX: &str = "hnp_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Generations,
+    // Estimated from prompt and reply length; the upstream doesn't report usage
+    Tokens,
+    TtsCharacters,
+    StorageBytes,
+    BandwidthBytes,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 5] = [Metric::Generations, Metric::Tokens, Metric::TtsCharacters, Metric::StorageBytes, Metric::BandwidthBytes];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Generations => "generations",
+            Metric::Tokens => "tokens",
+            Metric::TtsCharacters => "tts_characters",
+            Metric::StorageBytes => "storage_bytes",
+            Metric::BandwidthBytes => "bandwidth_bytes",
+        }
+    }
+
+    // Only generations and TTS characters have quotas
+    pub fn quota(self, key: &ApiKey) -> Option<u64> {
+        match self {
+            Metric::Generations => key.generation_quota,
+            Metric::TtsCharacters => key.tts_quota,
+            Metric::Tokens | Metric::StorageBytes | Metric::BandwidthBytes => None,
+        }
+    }
+}
+
+tokio::task_local! {
+    static REQUEST: Option<ApiKey>;
+}
+
+pub fn required() -> bool {
+    env::var("API_KEYS_REQUIRED").map(|v| v.trim().eq_ignore_ascii_case("true")).unwrap_or(false)
+}
+
+// The key the current request was made with
+pub fn current() -> Option<ApiKey> {
+    REQUEST.try_with(Option::clone).ok().flatten()
+}
+
+// The request should get the mock pipeline
+pub fn sandbox() -> bool {
+    current().is_some_and(|key| key.sandbox)
+}
+
+// Carries the caller's key into work spawned on its behalf (streams)
+pub async fn scope<F: Future>(key: Option<ApiKey>, f: F) -> F::Output {
+    REQUEST.scope(key, f).await
+}
+
+// Shown once on creation; only the hash is stored
+pub fn new_secret() -> String {
+    format!("{}{}{}", SECRET_PREFIX, uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+pub fn hash(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+pub fn month(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m").to_string()
+}
+
+// When the month's quotas reset
+pub fn month_end(at: DateTime<Utc>) -> DateTime<Utc> {
+    let (year, month) = if at.month() == 12 { (at.year() + 1, 1) } else { (at.year(), at.month() + 1) };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single().unwrap_or(at)
+}
+
+pub fn valid_month(month: &str) -> bool {
+    chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").is_ok() && month.len() == 7
+}
+
+pub async fn layer(request: Request, next: Next) -> Response {
+    let Some(value) = request.headers().get(HEADER) else {
+        return REQUEST.scope(None, next.run(request)).await;
+    };
+    // Never echo the value back
+    let secret = value.to_str().unwrap_or_default().trim();
+    if !secret.starts_with(SECRET_PREFIX) {
+        return reject(StatusCode::UNAUTHORIZED, "Invalid X-API-Key header".to_string());
+    }
+    match archive::api_key_by_secret(&hash(secret)).await {
+        Ok(Some(key)) => REQUEST.scope(Some(key), next.run(request)).await,
+        Ok(None) => reject(StatusCode::UNAUTHORIZED, "Unknown or revoked API key".to_string()),
+        Err(e) => reject(StatusCode::SERVICE_UNAVAILABLE, e),
+    }
+}
+
+fn reject(status: StatusCode, error: String) -> Response {
+    (status, axum::Json(serde_json::json!({ "error": error }))).into_response()
+}
+
+// Adds `amount` to the caller's month. Work done outside a request (jobs, backfills) isn't metered.
+pub async fn charge(metric: Metric, amount: u64) -> Result<(), (StatusCode, String)> {
+    let Ok(key) = REQUEST.try_with(Option::clone) else { return Ok(()) };
+    let Some(key) = key else {
+        return if required() {
+            Err((StatusCode::UNAUTHORIZED, format!("An API key is required ({} header)", HEADER)))
+        } else {
+            Ok(())
+        };
+    };
+    if key.sandbox {
+        return Ok(());
+    }
+    let quota = metric.quota(&key);
+    if quota == Some(0) {
+        return Err((StatusCode::PAYMENT_REQUIRED, format!("API key {} has no {} allowance", key.name, metric.name())));
+    }
+    let now = Utc::now();
+    match archive::add_usage(&key.id, &month(now), metric.name(), amount, quota).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            format!(
+                "API key {} has used its monthly {} quota of {}; it resets at {}",
+                key.name,
+                metric.name(),
+                quota.unwrap_or_default(),
+                month_end(now).to_rfc3339()
+            ),
+        )),
+        Err(e) => Err((StatusCode::SERVICE_UNAVAILABLE, e)),
+    }
+}
+
+// Adds to the caller's month without a quota check, for usage measured after the work is done
+pub async fn record(metric: Metric, amount: u64) {
+    if let Some(key) = current().filter(|key| !key.sandbox) {
+        record_for(&key.id, metric, amount).await;
+    }
+}
+
+pub async fn record_for(key_id: &str, metric: Metric, amount: u64) {
+    if amount == 0 {
+        return;
+    }
+    if let Err(e) = archive::add_usage(key_id, &month(Utc::now()), metric.name(), amount, None).await {
+        error!("Failed to record {} {} for API key {}: {}", amount, metric.name(), key_id, e);
+    }
+}
+
+// Audio downloads count against the key that rendered the episode, whoever fetches them
+pub async fn meter_downloads(request: Request, next: Next) -> Response {
+    let path = request.uri().path().trim_start_matches('/').to_string();
+    let counted = request.method() == Method::GET;
+    let response = next.run(request).await;
+    let bytes = response
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or_default();
+    if counted && response.status().is_success() && bytes > 0 && archive::enabled() {
+        tokio::spawn(async move {
+            match archive::audio_key(&path).await {
+                Ok(Some(key_id)) => record_for(&key_id, Metric::BandwidthBytes, bytes).await,
+                Ok(None) => {}
+                Err(e) => error!("Failed to look up the API key behind {}: {}", path, e),
+            }
+        });
+    }
+    response
+}
+
+// Used, quota and remaining per metric for one key and month
+pub async fn report(key: &ApiKey, month: &str) -> Result<serde_json::Value, String> {
+    let used = archive::usage(&key.id, month).await?;
+    let metrics: serde_json::Map<String, serde_json::Value> = Metric::ALL
+        .iter()
+        .map(|metric| {
+            let used = used.iter().find(|(name, _)| name == metric.name()).map_or(0, |(_, amount)| *amount);
+            let quota = metric.quota(key);
+            (
+                metric.name().to_string(),
+                serde_json::json!({
+                    "used": used,
+                    "quota": quota,
+                    "remaining": quota.map(|quota| quota.saturating_sub(used)),
+                }),
+            )
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "key_id": key.id,
+        "name": key.name,
+        "month": month,
+        "usage": metrics,
+    }))
+}
+
+// Every key's totals for a month, one row per key with usage or not yet revoked; sandbox keys
+// aren't billed
+pub async fn month_report(month: &str) -> Result<Vec<serde_json::Value>, String> {
+    let keys = archive::api_keys().await?;
+    let usage = archive::month_usage(month).await?;
+    Ok(keys
+        .iter()
+        .filter(|key| !key.sandbox)
+        .filter(|key| key.revoked_at.is_none() || usage.iter().any(|(id, _, _)| *id == key.id))
+        .map(|key| {
+            let mut row = serde_json::json!({ "key_id": key.id, "name": key.name });
+            for metric in Metric::ALL {
+                let used: u64 = usage.iter().filter(|(id, name, _)| *id == key.id && name == metric.name()).map(|(_, _, amount)| amount).sum();
+                row[metric.name()] = serde_json::json!(used);
+            }
+            row
+        })
+        .collect())
+}
+
+pub fn csv(rows: &[serde_json::Value]) -> String {
+    let columns: Vec<&str> = ["key_id", "name"].into_iter().chain(Metric::ALL.iter().map(|metric| metric.name())).collect();
+    let mut out = columns.join(",");
+    out.push('\n');
+    for row in rows {
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|column| match &row[*column] {
+                serde_json::Value::String(text) => csv_field(text),
+                value => value.to_string(),
+            })
+            .collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn months_roll_over_at_year_end() {
+        let december = Utc.with_ymd_and_hms(2024, 12, 31, 23, 59, 0).unwrap();
+        assert_eq!(month(december), "2024-12");
+        assert_eq!(month_end(december), Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+        let may = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        assert_eq!(month_end(may), Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn validates_months() {
+        assert!(valid_month("2024-05"));
+        assert!(!valid_month("2024-13"));
+        assert!(!valid_month("2024-5"));
+        assert!(!valid_month("May"));
+    }
+
+    #[test]
+    fn csv_quotes_names() {
+        let rows = vec![serde_json::json!({
+            "key_id": "k1",
+            "name": "Team \"A\", infra",
+            "generations": 3,
+            "tokens": 1200,
+            "tts_characters": 0,
+            "storage_bytes": 10,
+            "bandwidth_bytes": 20,
+        })];
+        assert_eq!(
+            csv(&rows),
+            "key_id,name,generations,tokens,tts_characters,storage_bytes,bandwidth_bytes\nk1,\"Team \"\"A\"\", infra\",3,1200,0,10,20\n"
+        );
+    }
+
+    #[test]
+    fn secrets_are_prefixed_and_hashed() {
+        let secret = new_secret();
+        assert!(secret.starts_with(SECRET_PREFIX));
+        assert_eq!(hash(&secret), hash(&secret));
+        assert_ne!(hash(&secret), hash(&new_secret()));
+        assert_eq!(hash(&secret).len(), 64);
+    }
+}
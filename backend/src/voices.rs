@@ -0,0 +1,156 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use tracing::{error, info};
+
+// Custom (cloned) voices registered with a provider that supports cloning. Only ElevenLabs does
+// today. Registrations are kept in memory and, when VOICES_PATH is set, persisted to that JSON file.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomVoice {
+    pub name: String,
+    pub provider: String,
+    pub provider_voice_id: String,
+    // Persona whose episodes use this voice by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub persona: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct Sample {
+    pub file_name: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+static VOICES: OnceLock<Mutex<HashMap<String, CustomVoice>>> = OnceLock::new();
+
+fn voices_path() -> Option<String> {
+    env::var("VOICES_PATH").ok().filter(|p| !p.is_empty())
+}
+
+fn voices() -> &'static Mutex<HashMap<String, CustomVoice>> {
+    VOICES.get_or_init(|| {
+        let stored: Vec<CustomVoice> = voices_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+        Mutex::new(stored.into_iter().map(|v| (v.name.clone(), v)).collect())
+    })
+}
+
+fn persist(voices: &HashMap<String, CustomVoice>) {
+    let Some(path) = voices_path() else { return };
+    let mut list: Vec<&CustomVoice> = voices.values().collect();
+    list.sort_by_key(|v| v.created_at);
+    let result = serde_json::to_string_pretty(&list)
+        .map_err(anyhow::Error::from)
+        .and_then(|raw| std::fs::write(&path, raw).map_err(anyhow::Error::from));
+    if let Err(e) = result {
+        error!("Failed to save custom voices to {}: {}", path, e);
+    }
+}
+
+pub fn list() -> Vec<CustomVoice> {
+    let mut list: Vec<CustomVoice> = voices().lock().unwrap().values().cloned().collect();
+    list.sort_by_key(|v| v.created_at);
+    list
+}
+
+pub fn get(name: &str) -> Option<CustomVoice> {
+    voices().lock().unwrap().get(name).cloned()
+}
+
+// Most recently registered voice mapped to the persona
+pub fn for_persona(persona: &str) -> Option<CustomVoice> {
+    list().into_iter().rev().find(|v| v.persona.as_deref() == Some(persona))
+}
+
+pub fn cloning_available() -> bool {
+    crate::credentials::get("ELEVENLABS_API_KEY").is_some()
+}
+
+#[derive(Debug)]
+pub enum RegisterError {
+    // A voice is already registered under the name
+    Exists,
+    Provider(String),
+}
+
+impl std::fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegisterError::Exists => write!(f, "a voice with this name already exists"),
+            RegisterError::Provider(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<String> for RegisterError {
+    fn from(e: String) -> Self {
+        RegisterError::Provider(e)
+    }
+}
+
+// Upload the samples to ElevenLabs instant voice cloning and remember the returned voice ID.
+// Names are never overwritten: the check runs before the upload and again before saving.
+pub async fn register(name: &str, persona: Option<String>, samples: Vec<Sample>) -> Result<CustomVoice, RegisterError> {
+    if get(name).is_some() {
+        return Err(RegisterError::Exists);
+    }
+    let api_key = crate::credentials::require("ELEVENLABS_API_KEY")?;
+
+    let mut form = reqwest::multipart::Form::new().text("name", name.to_string());
+    for sample in samples {
+        let part = reqwest::multipart::Part::bytes(sample.data)
+            .file_name(sample.file_name)
+            .mime_str(&sample.content_type)
+            .map_err(|e| format!("Invalid sample content type: {}", e))?;
+        form = form.part("files", part);
+    }
+
+    let response = reqwest::Client::new()
+        .post("https://api.elevenlabs.io/v1/voices/add")
+        .header("xi-api-key", api_key)
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Voice clone request failed: {}", e);
+            "Voice clone request failed".to_string()
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        error!("ElevenLabs voice clone error {}: {}", status, crate::redact::body(&error_text));
+        return Err(format!("Voice clone failed: {}", status).into());
+    }
+
+    let value: serde_json::Value = response.json().await.map_err(|e| format!("Invalid voice clone response: {}", e))?;
+    let provider_voice_id = value
+        .get("voice_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Voice clone response has no voice_id".to_string())?
+        .to_string();
+
+    let voice = CustomVoice {
+        name: name.to_string(),
+        provider: "elevenlabs".to_string(),
+        provider_voice_id,
+        persona,
+        created_at: Utc::now(),
+    };
+    info!("Registered custom voice '{}' ({})", voice.name, voice.provider_voice_id);
+
+    let mut voices = voices().lock().unwrap();
+    if voices.contains_key(&voice.name) {
+        // Registered by a concurrent request while this one was uploading
+        return Err(RegisterError::Exists);
+    }
+    voices.insert(voice.name.clone(), voice.clone());
+    persist(&voices);
+    Ok(voice)
+}
@@ -0,0 +1,40 @@
+use std::env;
+use std::time::Duration;
+use tracing::{error, info};
+
+// WebSub publisher: the feeds advertise the hubs in WEBSUB_HUBS (comma-separated URLs) with
+// rel="hub" links, and when an episode's audio lands in the feeds every hub is pinged with
+// hub.mode=publish for each feed the episode appears in, so directories and subscribers fetch it
+// within seconds instead of on their next poll.
+
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn hubs() -> Vec<String> {
+    env::var("WEBSUB_HUBS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|hub| hub.trim().to_string())
+        .filter(|hub| !hub.is_empty())
+        .collect()
+}
+
+// Pings in the background; failures are only logged, since subscribers still poll
+pub fn spawn_ping(feeds: Vec<String>) {
+    let hubs = hubs();
+    if hubs.is_empty() || feeds.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let client = reqwest::Client::builder().timeout(PING_TIMEOUT).build().unwrap_or_default();
+        for hub in &hubs {
+            // One ping per feed: not every hub accepts several hub.url values in one request
+            for feed in &feeds {
+                match client.post(hub).form(&[("hub.mode", "publish"), ("hub.url", feed.as_str())]).send().await {
+                    Ok(response) if response.status().is_success() => info!("Pinged WebSub hub {} for {}", hub, feed),
+                    Ok(response) => error!("WebSub hub {} answered {} for {}", hub, response.status(), feed),
+                    Err(e) => error!("Failed to ping WebSub hub {} for {}: {}", hub, feed, e),
+                }
+            }
+        }
+    });
+}
@@ -30,7 +30,7 @@ const HomePage = () => {
         const response = await fetch('http://localhost:3001/api/stories');
         const storiesData = await response.json();
         
-        setAllStories(storiesData.filter(story => story && story.title));
+        setAllStories(storiesData.data.filter(story => story && story.title));
       } catch (error) {
         console.error('Error fetching stories:', error);
       } finally {
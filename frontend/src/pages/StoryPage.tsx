@@ -207,6 +207,7 @@ const StoryPage = () => {
         body: JSON.stringify({
           story_id: parseInt(id),
           comments: commentTexts,
+          authors: [story?.by, ...commentsToUse.map(comment => comment.by)].filter(Boolean),
         }),
       });
 